@@ -3,7 +3,7 @@
 //! This type is useful in settings where it is difficult to write code generic in `A: Allocate`,
 //! for example closures whose type arguments must be specified.
 
-use allocator::{Allocate, AllocateBuilder, Message, Thread, Process};
+use allocator::{Allocate, AllocateBuilder, Message, Thread, Process, WorkerIdentity};
 use allocator::zero_copy::allocator_process::{ProcessBuilder, ProcessAllocator};
 use allocator::zero_copy::allocator::{TcpBuilder, TcpAllocator};
 
@@ -41,6 +41,15 @@ impl Generic {
             &Generic::ZeroCopy(ref z) => z.peers(),
         }
     }
+    /// The position of the worker among its process and the computation.
+    pub fn identity(&self) -> WorkerIdentity {
+        match self {
+            &Generic::Thread(ref t) => t.identity(),
+            &Generic::Process(ref p) => p.identity(),
+            &Generic::ProcessBinary(ref pb) => pb.identity(),
+            &Generic::ZeroCopy(ref z) => z.identity(),
+        }
+    }
     /// Constructs several send endpoints and one receive endpoint.
     pub fn allocate<T: Data>(&mut self) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>, Option<usize>) {
         match self {
@@ -73,6 +82,7 @@ impl Generic {
 impl Allocate for Generic {
     fn index(&self) -> usize { self.index() }
     fn peers(&self) -> usize { self.peers() }
+    fn identity(&self) -> WorkerIdentity { self.identity() }
     fn allocate<T: Data>(&mut self) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>, Option<usize>) {
         self.allocate()
     }
@@ -81,6 +91,15 @@ impl Allocate for Generic {
     fn post_work(&mut self) { self.post_work(); }
 }
 
+impl Drop for Generic {
+    fn drop(&mut self) {
+        // raise the failure flag before our channels close, so that peers learn of the failure first.
+        if ::std::thread::panicking() {
+            ::failure::signal_failure();
+        }
+    }
+}
+
 
 /// Enumerations of constructable implementors of `Allocate`.
 ///
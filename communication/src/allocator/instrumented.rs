@@ -0,0 +1,242 @@
+//! An allocator wrapper measuring the messages and bytes sent and received on each channel.
+//!
+//! Wrapping the builders of any allocator, for example with `Instrumented::wrap`, produces builders
+//! whose allocators behave as the originals, but which count the messages, and their lengths in
+//! bytes, pushed into and pulled out of each channel. The counts are shared by the workers built
+//! from the same call to `wrap`, and so reflect a whole process, and may be read from any thread
+//! while the computation runs. The difference between what a channel has sent and received is the
+//! volume queued in it, which makes queues that grow without bound visible without a heap profiler.
+//!
+//! Channels are identified by the order in which they were allocated, which is the same for all
+//! workers. Messages sent to or received from other processes are counted only where they are sent
+//! or received, respectively, and so the queued volume of channels that cross processes is only
+//! meaningful when the measurements of all processes are combined.
+//!
+//! Measuring typed messages in bytes requires computing their serialized lengths, which is not free,
+//! and so bytes are only counted while enabled with `Measurements::measure_bytes`; messages are
+//! always counted.
+//!
+//! #Examples
+//! ```
+//! use timely_communication::{Allocate, Message};
+//! use timely_communication::allocator::Process;
+//! use timely_communication::allocator::instrumented::Instrumented;
+//!
+//! let (builders, measurements) = Instrumented::wrap(Process::new_vector(2));
+//! measurements.measure_bytes(true);
+//!
+//! let guards = timely_communication::initialize_from(builders, Box::new(()), |mut allocator| {
+//!     let (mut senders, mut receiver, _) = allocator.allocate::<u64>();
+//!     for sender in senders.iter_mut() {
+//!         sender.send(Message::from_typed(allocator.index() as u64));
+//!     }
+//!     let mut expecting = 2;
+//!     while expecting > 0 {
+//!         allocator.pre_work();
+//!         if receiver.recv().is_some() { expecting -= 1; }
+//!         allocator.post_work();
+//!     }
+//! }).unwrap();
+//!
+//! guards.join();
+//!
+//! let stats = measurements.channel(0).unwrap();
+//! assert_eq!(stats.sent_messages, 4);
+//! assert_eq!(stats.received_messages, 4);
+//! assert_eq!(stats.queued_messages(), 0);
+//! assert_eq!(stats.sent_bytes, 4 * ::std::mem::size_of::<u64>());
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use allocator::{Allocate, AllocateBuilder, Message, WorkerIdentity};
+use {Data, Push, Pull};
+
+/// Counts of the messages and bytes sent and received on a channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// The number of messages pushed into the channel.
+    pub sent_messages: usize,
+    /// The number of bytes required to serialize the messages pushed into the channel, while bytes
+    /// were measured.
+    pub sent_bytes: usize,
+    /// The number of messages pulled out of the channel.
+    pub received_messages: usize,
+    /// The number of bytes required to serialize the messages pulled out of the channel, while bytes
+    /// were measured.
+    pub received_bytes: usize,
+}
+
+impl ChannelStats {
+    /// The number of messages sent but not yet received.
+    pub fn queued_messages(&self) -> usize { self.sent_messages.saturating_sub(self.received_messages) }
+    /// The number of bytes sent but not yet received.
+    pub fn queued_bytes(&self) -> usize { self.sent_bytes.saturating_sub(self.received_bytes) }
+}
+
+/// Counters shared by the endpoints of a channel.
+#[derive(Default)]
+struct Counters {
+    sent_messages: AtomicUsize,
+    sent_bytes: AtomicUsize,
+    received_messages: AtomicUsize,
+    received_bytes: AtomicUsize,
+}
+
+impl Counters {
+    fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            sent_messages: self.sent_messages.load(Ordering::Relaxed),
+            sent_bytes: self.sent_bytes.load(Ordering::Relaxed),
+            received_messages: self.received_messages.load(Ordering::Relaxed),
+            received_bytes: self.received_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The measurements of the channels of a set of instrumented allocators.
+#[derive(Clone, Default)]
+pub struct Measurements {
+    channels: Arc<Mutex<Vec<Arc<Counters>>>>,
+    bytes: Arc<AtomicBool>,
+}
+
+impl Measurements {
+    /// The counters of channel `index`, allocated if they do not yet exist.
+    fn counters(&self, index: usize) -> Arc<Counters> {
+        let mut channels = self.channels.lock().expect("failed to lock measurements");
+        while channels.len() <= index {
+            channels.push(Arc::new(Counters::default()));
+        }
+        channels[index].clone()
+    }
+    /// Enables or disables measuring the bytes of messages, which is disabled initially.
+    pub fn measure_bytes(&self, enabled: bool) {
+        self.bytes.store(enabled, Ordering::Relaxed);
+    }
+    /// Returns true if the bytes of messages are measured.
+    pub fn measuring_bytes(&self) -> bool {
+        self.bytes.load(Ordering::Relaxed)
+    }
+    /// The counts of channel `index`, if it has been allocated.
+    pub fn channel(&self, index: usize) -> Option<ChannelStats> {
+        self.channels.lock().expect("failed to lock measurements").get(index).map(|c| c.stats())
+    }
+    /// The counts of all allocated channels, in order of allocation.
+    pub fn channels(&self) -> Vec<ChannelStats> {
+        self.channels.lock().expect("failed to lock measurements").iter().map(|c| c.stats()).collect()
+    }
+    /// The counts of all allocated channels, summed.
+    pub fn total(&self) -> ChannelStats {
+        let mut total = ChannelStats::default();
+        for stats in self.channels() {
+            total.sent_messages += stats.sent_messages;
+            total.sent_bytes += stats.sent_bytes;
+            total.received_messages += stats.received_messages;
+            total.received_bytes += stats.received_bytes;
+        }
+        total
+    }
+}
+
+/// A builder of instrumented allocators.
+pub struct InstrumentedBuilder<B: AllocateBuilder> {
+    inner: B,
+    measurements: Measurements,
+}
+
+impl<B: AllocateBuilder> InstrumentedBuilder<B> {
+    /// Wraps `inner`, recording measurements in `measurements`.
+    pub fn new(inner: B, measurements: Measurements) -> Self {
+        InstrumentedBuilder { inner, measurements }
+    }
+}
+
+impl<B: AllocateBuilder> AllocateBuilder for InstrumentedBuilder<B> {
+    type Allocator = Instrumented<B::Allocator>;
+    fn build(self) -> Self::Allocator {
+        Instrumented {
+            inner: self.inner.build(),
+            measurements: self.measurements,
+            allocated: 0,
+        }
+    }
+}
+
+/// An allocator measuring the channels allocated by an inner allocator.
+pub struct Instrumented<A: Allocate> {
+    inner: A,
+    measurements: Measurements,
+    allocated: usize,
+}
+
+impl<A: Allocate> Instrumented<A> {
+    /// Wraps each of `builders`, returning the wrapped builders and their shared measurements.
+    pub fn wrap<B: AllocateBuilder<Allocator=A>>(builders: Vec<B>) -> (Vec<InstrumentedBuilder<B>>, Measurements) {
+        let measurements = Measurements::default();
+        let builders = builders.into_iter().map(|b| InstrumentedBuilder::new(b, measurements.clone())).collect();
+        (builders, measurements)
+    }
+    /// The measurements this allocator contributes to.
+    pub fn measurements(&self) -> &Measurements { &self.measurements }
+    /// The wrapped allocator.
+    pub fn inner(&mut self) -> &mut A { &mut self.inner }
+}
+
+impl<A: Allocate> Allocate for Instrumented<A> {
+    fn index(&self) -> usize { self.inner.index() }
+    fn peers(&self) -> usize { self.inner.peers() }
+    fn identity(&self) -> WorkerIdentity { self.inner.identity() }
+    fn allocate<T: Data>(&mut self) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>, Option<usize>) {
+        let counters = self.measurements.counters(self.allocated);
+        self.allocated += 1;
+        let (pushers, puller, identifier) = self.inner.allocate();
+        let bytes = &self.measurements.bytes;
+        let pushers = pushers.into_iter().map(|pusher| Box::new(Pusher { inner: pusher, counters: counters.clone(), bytes: bytes.clone() }) as Box<Push<Message<T>>>).collect();
+        (pushers, Box::new(Puller { inner: puller, counters, bytes: bytes.clone() }), identifier)
+    }
+    fn pre_work(&mut self) { self.inner.pre_work(); }
+    fn post_work(&mut self) { self.inner.post_work(); }
+}
+
+/// A pusher counting the messages it pushes.
+struct Pusher<T> {
+    inner: Box<Push<Message<T>>>,
+    counters: Arc<Counters>,
+    bytes: Arc<AtomicBool>,
+}
+
+impl<T: Data> Push<Message<T>> for Pusher<T> {
+    #[inline]
+    fn push(&mut self, element: &mut Option<Message<T>>) {
+        if let Some(message) = element.as_ref() {
+            self.counters.sent_messages.fetch_add(1, Ordering::Relaxed);
+            if self.bytes.load(Ordering::Relaxed) {
+                self.counters.sent_bytes.fetch_add(message.length_in_bytes(), Ordering::Relaxed);
+            }
+        }
+        self.inner.push(element);
+    }
+}
+
+/// A puller counting the messages it pulls.
+struct Puller<T> {
+    inner: Box<Pull<Message<T>>>,
+    counters: Arc<Counters>,
+    bytes: Arc<AtomicBool>,
+}
+
+impl<T: Data> Pull<Message<T>> for Puller<T> {
+    #[inline]
+    fn pull(&mut self) -> &mut Option<Message<T>> {
+        let element = self.inner.pull();
+        if let Some(message) = element.as_ref() {
+            self.counters.received_messages.fetch_add(1, Ordering::Relaxed);
+            if self.bytes.load(Ordering::Relaxed) {
+                self.counters.received_bytes.fetch_add(message.length_in_bytes(), Ordering::Relaxed);
+            }
+        }
+        element
+    }
+}
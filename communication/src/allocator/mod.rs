@@ -9,6 +9,7 @@ pub mod thread;
 pub mod process;
 // pub mod binary;
 pub mod generic;
+pub mod instrumented;
 
 pub mod zero_copy;
 
@@ -26,6 +27,72 @@ pub trait AllocateBuilder : Send {
     fn build(self) -> Self::Allocator;
 }
 
+/// The position of a worker among the workers of a computation.
+///
+/// Workers are numbered consecutively within each process, and processes in order, so that the
+/// workers of a process have the indices from its `offset` up to `offset + threads`. Code that needs
+/// to know where a worker runs should consult its identity rather than divide its index by a thread
+/// count, as processes need not all run the same number of workers.
+///
+/// #Examples
+/// ```
+/// use timely_communication::Allocate;
+/// use timely_communication::allocator::Process;
+///
+/// let guards = timely_communication::initialize_from(Process::new_vector(2), Box::new(()), |allocator| {
+///     let identity = allocator.identity();
+///     assert_eq!(identity.process, 0);
+///     assert_eq!(identity.thread, identity.index);
+///     identity.name()
+/// }).unwrap();
+///
+/// let names = guards.join().into_iter().map(|name| name.unwrap()).collect::<Vec<_>>();
+/// assert_eq!(names, vec!["worker 0 (process 0, thread 0)", "worker 1 (process 0, thread 1)"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WorkerIdentity {
+    /// The index of the worker out of `(0..peers)`.
+    pub index: usize,
+    /// The number of workers in the computation.
+    pub peers: usize,
+    /// The index of the worker's process out of `(0..processes)`.
+    pub process: usize,
+    /// The number of processes in the computation.
+    pub processes: usize,
+    /// The index of the worker within its process, out of `(0..threads)`.
+    pub thread: usize,
+    /// The number of workers in the worker's process.
+    pub threads: usize,
+    /// The index of the first worker of the worker's process.
+    pub offset: usize,
+}
+
+impl WorkerIdentity {
+    /// The identity of worker `index` of `peers` workers, all in one process.
+    pub fn single_process(index: usize, peers: usize) -> Self {
+        WorkerIdentity {
+            index,
+            peers,
+            process: 0,
+            processes: 1,
+            thread: index,
+            threads: peers,
+            offset: 0,
+        }
+    }
+
+    /// A name for the worker, for use in logs and thread names.
+    pub fn name(&self) -> String {
+        format!("worker {} (process {}, thread {})", self.index, self.process, self.thread)
+    }
+}
+
+impl ::std::fmt::Display for WorkerIdentity {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// A type capable of allocating channels.
 ///
 /// There is some feature creep, in that this contains several convenience methods about the nature
@@ -35,6 +102,10 @@ pub trait Allocate {
     fn index(&self) -> usize;
     /// The number of workers.
     fn peers(&self) -> usize;
+    /// The position of the worker among its process and the computation.
+    ///
+    /// The default implementation describes a computation with a single process.
+    fn identity(&self) -> WorkerIdentity { WorkerIdentity::single_process(self.index(), self.peers()) }
     /// Constructs several send endpoints and one receive endpoint.
     fn allocate<T: Data>(&mut self) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>, Option<usize>);
     /// Work performed before scheduling dataflows.
@@ -1,19 +1,39 @@
 //! Typed inter-thread, intra-process channels.
 
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::sync::mpsc::{Sender, Receiver, channel};
 
 use allocator::{Allocate, AllocateBuilder, Message, Thread};
 use {Push, Pull};
 
+thread_local!(static CANCELLING: Cell<bool> = Cell::new(false));
+
+/// Runs `logic`, which drops the channels of cancelled dataflows.
+///
+/// Workers may continue to send to the channels that `logic` drops, and their messages are discarded.
+/// Sending to a channel dropped otherwise, for example by a worker that panicked, is an error.
+pub fn cancelling<T, F: FnOnce()->T>(logic: F) -> T {
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) { CANCELLING.with(|cancelling| cancelling.set(false)); }
+    }
+    CANCELLING.with(|cancelling| cancelling.set(true));
+    let _reset = Reset;
+    logic()
+}
+
 /// An allocater for inter-thread, intra-process communication
 pub struct Process {
     inner:      Thread,                         // inner Thread
     index:      usize,                          // number out of peers
     peers:      usize,                          // number of peer allocators (for typed channel allocation).
     allocated:  usize,                          // indicates how many have been allocated (locally).
-    channels:   Arc<Mutex<Vec<Box<Any+Send>>>>, // Box<Any+Send> -> Box<Vec<Option<(Vec<Sender<T>>, Receiver<T>)>>>
+    ordered:    bool,                           // indicates a channel per pair of workers, pulled in order.
+    channels:   Arc<Mutex<Vec<Box<Any+Send>>>>, // Box<Any+Send> -> Box<Vec<Option<(Vec<Pusher<T>>, Puller<T>)>>>
 }
 
 impl Process {
@@ -21,12 +41,27 @@ impl Process {
     pub fn inner<'a>(&'a mut self) -> &'a mut Thread { &mut self.inner }
     /// Allocate a list of connected intra-process allocators.
     pub fn new_vector(count: usize) -> Vec<Process> {
+        Process::new_vector_with(count, false)
+    }
+    /// Allocate a list of connected intra-process allocators, whose channels deliver messages in order of
+    /// their source worker.
+    ///
+    /// Each pair of workers has its own channel. A worker pulling from a channel takes the messages
+    /// available from each source worker in turn, lowest index first, and receives them in that order
+    /// before it takes any messages that arrive later. Messages from each source arrive in the order
+    /// they were sent. Which messages are available depends on the scheduling of threads, and so the
+    /// order is reproducible when workers synchronize before pulling, as tests may arrange.
+    pub fn new_vector_ordered(count: usize) -> Vec<Process> {
+        Process::new_vector_with(count, true)
+    }
+    fn new_vector_with(count: usize, ordered: bool) -> Vec<Process> {
         let channels = Arc::new(Mutex::new(Vec::new()));
         (0 .. count).map(|index| Process {
             inner:      Thread,
             index:      index,
             peers:      count,
             allocated:  0,
+            ordered:    ordered,
             channels:   channels.clone(),
         }).collect()
     }
@@ -42,17 +77,33 @@ impl Allocate for Process {
 
         // we may need to alloc a new channel ...
         if self.allocated == channels.len() {
-            let mut pushers = Vec::new();
-            let mut pullers = Vec::new();
-            for _ in 0..self.peers {
-                let (s, r): (Sender<Message<T>>, Receiver<Message<T>>) = channel();
-                pushers.push(Pusher { target: s });
-                pullers.push(Puller { source: r, current: None });
+            // senders[s][t] sends from worker s to worker t; receivers[t] are the sources of worker t.
+            let mut senders = (0 .. self.peers).map(|_| Vec::new()).collect::<Vec<_>>();
+            let mut receivers = (0 .. self.peers).map(|_| Vec::new()).collect::<Vec<_>>();
+            // cancelled[t] is raised if worker t drops its puller because its dataflow was cancelled.
+            let cancelled = (0 .. self.peers).map(|_| Arc::new(AtomicBool::new(false))).collect::<Vec<_>>();
+            if self.ordered {
+                for source in 0 .. self.peers {
+                    for target in 0 .. self.peers {
+                        let (s, r): (Sender<Message<T>>, Receiver<Message<T>>) = channel();
+                        senders[source].push(Pusher { target: s, cancelled: cancelled[target].clone() });
+                        receivers[target].push(r);
+                    }
+                }
+            }
+            else {
+                for target in 0 .. self.peers {
+                    let (s, r): (Sender<Message<T>>, Receiver<Message<T>>) = channel();
+                    for source in 0 .. self.peers {
+                        senders[source].push(Pusher { target: s.clone(), cancelled: cancelled[target].clone() });
+                    }
+                    receivers[target].push(r);
+                }
             }
 
             let mut to_box = Vec::new();
-            for recv in pullers.into_iter() {
-                to_box.push(Some((pushers.clone(), recv)));
+            for ((send, recv), cancelled) in senders.into_iter().zip(receivers.into_iter()).zip(cancelled.into_iter()) {
+                to_box.push(Some((send, Puller { sources: recv, pending: VecDeque::new(), current: None, cancelled })));
             }
 
             channels.push(Box::new(to_box));
@@ -83,32 +134,53 @@ impl AllocateBuilder for Process {
 /// The push half of an intra-process channel.
 struct Pusher<T> {
     target: Sender<T>,
+    cancelled: Arc<AtomicBool>,
 }
 
 impl<T> Clone for Pusher<T> {
     fn clone(&self) -> Self {
-        Pusher { target: self.target.clone() }
+        Pusher { target: self.target.clone(), cancelled: self.cancelled.clone() }
     }
 }
 
 impl<T> Push<T> for Pusher<T> {
     #[inline] fn push(&mut self, element: &mut Option<T>) {
         if let Some(element) = element.take() {
-            self.target.send(element).unwrap();
+            // messages to the channel of a cancelled dataflow are moot, but are otherwise lost.
+            if self.target.send(element).is_err() && !self.cancelled.load(Ordering::SeqCst) {
+                panic!("intra-process channel closed by its receiving worker");
+            }
         }
     }
 }
 
-/// The pull half of an intra-process channel.
+/// The pull half of an intra-process channel, receiving from its sources in order.
 struct Puller<T> {
     current: Option<T>,
-    source: Receiver<T>,
+    sources: Vec<Receiver<T>>,
+    pending: VecDeque<T>,
+    cancelled: Arc<AtomicBool>,
 }
 
 impl<T> Pull<T> for Puller<T> {
     #[inline]
     fn pull(&mut self) -> &mut Option<T> {
-        self.current = self.source.try_recv().ok();
+        // deliver the messages available from each source in turn, before any that arrive later.
+        if self.pending.is_empty() {
+            for source in self.sources.iter() {
+                self.pending.extend(source.try_iter());
+            }
+        }
+        self.current = self.pending.pop_front();
         &mut self.current
     }
 }
+
+impl<T> Drop for Puller<T> {
+    fn drop(&mut self) {
+        // raised before the receivers drop, so that senders observe it once their sends fail.
+        if CANCELLING.with(|cancelling| cancelling.get()) {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+}
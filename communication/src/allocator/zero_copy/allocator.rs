@@ -1,7 +1,6 @@
 //! Zero-copy allocator based on TCP.
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::VecDeque;
 // use std::sync::mpsc::{channel, Sender, Receiver};
 
 use bytes::arc::Bytes;
@@ -9,10 +8,10 @@ use bytes::arc::Bytes;
 use networking::MessageHeader;
 
 use {Allocate, Data, Push, Pull};
-use allocator::{Message, Process};
+use allocator::{Message, Process, WorkerIdentity};
 
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue, Signal};
-use super::push_pull::{Pusher, PullerInner};
+use super::push_pull::{Pusher, PullerInner, ChannelQueues};
 
 /// Builds an instance of a TcpAllocator.
 ///
@@ -24,6 +23,8 @@ pub struct TcpBuilder<A: Allocate> {
     inner:      A,
     index:      usize,              // number out of peers
     peers:      usize,              // number of peer allocators.
+    process:    usize,              // index of this process.
+    offsets:    Vec<usize>,         // index of the first worker of each process, then the number of workers.
     sends:      Vec<MergeQueue>,    // for pushing bytes at remote processes.
     recvs:      Vec<MergeQueue>,    // for pulling bytes from remote processes.
     signal:     Signal,
@@ -35,22 +36,39 @@ pub fn new_vector(
     threads: usize,
     processes: usize)
 // -> (Vec<TcpBuilder<Process>>, Vec<Receiver<Bytes>>, Vec<Sender<Bytes>>) {
+-> (Vec<TcpBuilder<Process>>, Vec<(Vec<MergeQueue>, Signal)>, Vec<Vec<MergeQueue>>) {
+    new_vector_heterogeneous(my_process, &vec![threads; processes])
+}
+
+/// Creates a vector of builders for processes with the numbers of workers `threads`, sharing appropriate state.
+///
+/// Workers are numbered consecutively within each process, and processes in order, so that the
+/// workers of process `p` follow the `threads[0] + .. + threads[p-1]` workers of prior processes.
+pub fn new_vector_heterogeneous(
+    my_process: usize,
+    threads: &[usize])
 -> (Vec<TcpBuilder<Process>>, Vec<(Vec<MergeQueue>, Signal)>, Vec<Vec<MergeQueue>>) {
 
+    assert!(threads.iter().all(|&count| count > 0), "each process must have at least one worker");
+
+    let processes = threads.len();
+    let offsets = worker_offsets(threads);
+    let local = threads[my_process];
+
     // The results are a vector of builders, as well as the necessary shared state to build each
     // of the send and receive communication threads, respectively.
 
-    let worker_signals: Vec<Signal> = (0 .. threads).map(|_| Signal::new()).collect();
+    let worker_signals: Vec<Signal> = (0 .. local).map(|_| Signal::new()).collect();
     let network_signals: Vec<Signal> = (0 .. processes-1).map(|_| Signal::new()).collect();
 
-    let worker_to_network: Vec<Vec<_>> = (0 .. threads).map(|_| (0 .. processes-1).map(|p| MergeQueue::new(network_signals[p].clone())).collect()).collect();
-    let network_to_worker: Vec<Vec<_>> = (0 .. processes-1).map(|_| (0 .. threads).map(|t| MergeQueue::new(worker_signals[t].clone())).collect()).collect();
+    let worker_to_network: Vec<Vec<_>> = (0 .. local).map(|_| (0 .. processes-1).map(|p| MergeQueue::new(network_signals[p].clone())).collect()).collect();
+    let network_to_worker: Vec<Vec<_>> = (0 .. processes-1).map(|_| (0 .. local).map(|t| MergeQueue::new(worker_signals[t].clone())).collect()).collect();
 
-    let worker_from_network: Vec<Vec<_>> = (0 .. threads).map(|t| (0 .. processes-1).map(|p| network_to_worker[p][t].clone()).collect()).collect();
-    let network_from_worker: Vec<Vec<_>> = (0 .. processes-1).map(|p| (0 .. threads).map(|t| worker_to_network[t][p].clone()).collect()).collect();
+    let worker_from_network: Vec<Vec<_>> = (0 .. local).map(|t| (0 .. processes-1).map(|p| network_to_worker[p][t].clone()).collect()).collect();
+    let network_from_worker: Vec<Vec<_>> = (0 .. processes-1).map(|p| (0 .. local).map(|t| worker_to_network[t][p].clone()).collect()).collect();
 
     let builders =
-    Process::new_vector(threads)
+    Process::new_vector(local)
         .into_iter()
         .zip(worker_signals)
         .zip(worker_to_network)
@@ -59,8 +77,10 @@ pub fn new_vector(
         .map(|(index, (((inner, signal), sends), recvs))| {
             TcpBuilder {
                 inner,
-                index: my_process * threads + index,
-                peers: threads * processes,
+                index: offsets[my_process] + index,
+                peers: offsets[processes],
+                process: my_process,
+                offsets: offsets.clone(),
                 sends,
                 recvs,
                 signal,
@@ -72,6 +92,23 @@ pub fn new_vector(
     (builders, sends, network_to_worker)
 }
 
+/// The index of the first worker of each process, followed by the total number of workers.
+pub fn worker_offsets(threads: &[usize]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(threads.len() + 1);
+    let mut total = 0;
+    offsets.push(total);
+    for &count in threads.iter() {
+        total += count;
+        offsets.push(total);
+    }
+    offsets
+}
+
+/// The process hosting `worker`, given the offsets reported by `worker_offsets`.
+fn process_of(offsets: &[usize], worker: usize) -> usize {
+    offsets[1..].iter().position(|&end| worker < end).expect("worker index out of range")
+}
+
 impl<A: Allocate> TcpBuilder<A> {
 
     /// Builds a `TcpAllocator`, instantiating `Rc<RefCell<_>>` elements.
@@ -87,12 +124,14 @@ impl<A: Allocate> TcpBuilder<A> {
             inner: self.inner,
             index: self.index,
             peers: self.peers,
+            process: self.process,
+            offsets: self.offsets,
             allocated: 0,
             _signal: self.signal,
             staged: Vec::new(),
             sends,
             recvs: self.recvs,
-            to_local: Vec::new(),
+            to_local: ChannelQueues::new(),
         }
     }
 }
@@ -104,6 +143,8 @@ pub struct TcpAllocator<A: Allocate> {
 
     index:      usize,                              // number out of peers
     peers:      usize,                              // number of peer allocators (for typed channel allocation).
+    process:    usize,                              // index of this process.
+    offsets:    Vec<usize>,                         // index of the first worker of each process, then the number of workers.
     allocated:  usize,                              // indicates how many channels have been allocated (locally).
 
     _signal:     Signal,
@@ -113,12 +154,23 @@ pub struct TcpAllocator<A: Allocate> {
     // sending, receiving, and responding to binary buffers.
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>,         // sends[x] -> goes to process x.
     recvs:      Vec<MergeQueue>,                    // recvs[x] <- from process x?.
-    to_local:   Vec<Rc<RefCell<VecDeque<Bytes>>>>,  // to worker-local typed pullers.
+    to_local:   ChannelQueues,                      // to worker-local typed pullers, by channel.
 }
 
 impl<A: Allocate> Allocate for TcpAllocator<A> {
     fn index(&self) -> usize { self.index }
     fn peers(&self) -> usize { self.peers }
+    fn identity(&self) -> WorkerIdentity {
+        WorkerIdentity {
+            index: self.index,
+            peers: self.peers,
+            process: self.process,
+            processes: self.offsets.len() - 1,
+            thread: self.inner.index(),
+            threads: self.inner.peers(),
+            offset: self.offsets[self.process],
+        }
+    }
     fn allocate<T: Data>(&mut self) -> (Vec<Box<Push<Message<T>>>>, Box<Pull<Message<T>>>, Option<usize>) {
 
         let channel_id = self.allocated;
@@ -128,15 +180,13 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
         let mut pushes = Vec::<Box<Push<Message<T>>>>::new();
 
         // Inner exchange allocations.
-        let inner_peers = self.inner.peers();
         let (mut inner_sends, inner_recv, _) = self.inner.allocate();
 
         for target_index in 0 .. self.peers() {
 
-            // TODO: crappy place to hardcode this rule.
-            let mut process_id = target_index / inner_peers;
+            let mut process_id = process_of(&self.offsets, target_index);
 
-            if process_id == self.index / inner_peers {
+            if process_id == self.process {
                 pushes.push(inner_sends.remove(0));
             }
             else {
@@ -150,18 +200,14 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
                 };
 
                 // create, box, and stash new process_binary pusher.
-                if process_id > self.index / inner_peers { process_id -= 1; }
+                if process_id > self.process { process_id -= 1; }
                 pushes.push(Box::new(Pusher::new(header, self.sends[process_id].clone())));
             }
         }
 
-        while self.to_local.len() <= channel_id {
-            self.to_local.push(Rc::new(RefCell::new(VecDeque::new())));
-        }
-
-        let puller = Box::new(PullerInner::new(inner_recv, self.to_local[channel_id].clone()));
+        let puller = Box::new(PullerInner::new(inner_recv, channel_id, self.to_local.clone()));
 
-        (pushes, puller, None)
+        (pushes, puller, Some(channel_id))
     }
 
     // Perform preparatory work, most likely reading binary buffers from self.recv.
@@ -184,14 +230,9 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
                     let mut peel = bytes.extract_to(header.required_bytes());
                     let _ = peel.extract_to(40);
 
-                    // Ensure that a queue exists.
-                    // We may receive data before allocating, and shouldn't block.
-                    while self.to_local.len() <= header.channel {
-                        self.to_local.push(Rc::new(RefCell::new(VecDeque::new())));
-                    }
-
-                    // Introduce the binary slice into the operator input queue.
-                    self.to_local[header.channel].borrow_mut().push_back(peel);
+                    // Introduce the binary slice into the operator input queue, which is created
+                    // on the channel's first message, perhaps before the channel is allocated.
+                    self.to_local.push(header.channel, peel);
                 }
                 else {
                     println!("failed to read full header!");
@@ -209,8 +250,8 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
 
         // OPTIONAL: Tattle on channels sitting on borrowed data.
         // OPTIONAL: Perhaps copy borrowed data into owned allocation.
-        // for index in 0 .. self.to_local.len() {
-        //     let len = self.to_local[index].borrow_mut().len();
+        // for index in 0 .. self.allocated {
+        //     let len = self.to_local.pending(index);
         //     if len > 0 {
         //         eprintln!("Warning: worker {}, undrained channel[{}].len() = {}", self.index, index, len);
         //     }
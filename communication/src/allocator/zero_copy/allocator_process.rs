@@ -2,7 +2,6 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::VecDeque;
 
 use bytes::arc::Bytes;
 
@@ -13,7 +12,7 @@ use allocator::Message;
 
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue, Signal};
 
-use super::push_pull::{Pusher, Puller};
+use super::push_pull::{Pusher, Puller, ChannelQueues};
 
 /// Builds an instance of a ProcessAllocator.
 ///
@@ -84,7 +83,7 @@ impl ProcessBuilder {
             staged: Vec::new(),
             sends,
             recvs: self.recvs,
-            to_local: Vec::new(),
+            to_local: ChannelQueues::new(),
             _signal: self.signal,
         }
     }
@@ -102,7 +101,7 @@ pub struct ProcessAllocator {
     staged:     Vec<Bytes>,
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>, // sends[x] -> goes to process x.
     recvs:      Vec<MergeQueue>,                            // recvs[x] <- from process x?.
-    to_local:   Vec<Rc<RefCell<VecDeque<Bytes>>>>,          // to worker-local typed pullers.
+    to_local:   ChannelQueues,                              // to worker-local typed pullers, by channel.
 }
 
 impl Allocate for ProcessAllocator {
@@ -130,13 +129,9 @@ impl Allocate for ProcessAllocator {
             pushes.push(Box::new(Pusher::new(header, self.sends[target_index].clone())));
         }
 
-        while self.to_local.len() <= channel_id {
-            self.to_local.push(Rc::new(RefCell::new(VecDeque::new())));
-        }
-
-        let puller = Box::new(Puller::new(self.to_local[channel_id].clone()));
+        let puller = Box::new(Puller::new(channel_id, self.to_local.clone()));
 
-        (pushes, puller, None)
+        (pushes, puller, Some(channel_id))
     }
 
     // Perform preparatory work, most likely reading binary buffers from self.recv.
@@ -159,14 +154,9 @@ impl Allocate for ProcessAllocator {
                     let mut peel = bytes.extract_to(header.required_bytes());
                     let _ = peel.extract_to(40);
 
-                    // Ensure that a queue exists.
-                    // We may receive data before allocating, and shouldn't block.
-                    while self.to_local.len() <= header.channel {
-                        self.to_local.push(Rc::new(RefCell::new(VecDeque::new())));
-                    }
-
-                    // Introduce the binary slice into the operator input queue.
-                    self.to_local[header.channel].borrow_mut().push_back(peel);
+                    // Introduce the binary slice into the operator input queue, which is created
+                    // on the channel's first message, perhaps before the channel is allocated.
+                    self.to_local.push(header.channel, peel);
                 }
                 else {
                     println!("failed to read full header!");
@@ -184,8 +174,8 @@ impl Allocate for ProcessAllocator {
 
         // OPTIONAL: Tattle on channels sitting on borrowed data.
         // OPTIONAL: Perhaps copy borrowed data into owned allocation.
-        // for index in 0 .. self.to_local.len() {
-        //     let len = self.to_local[index].borrow_mut().len();
+        // for index in 0 .. self.allocated {
+        //     let len = self.to_local.pending(index);
         //     if len > 0 {
         //         eprintln!("Warning: worker {}, undrained channel[{}].len() = {}", self.index, index, len);
         //     }
@@ -1,11 +1,43 @@
 //! Types and traits for sharing `Bytes`.
+//!
+//! These types move binary data between threads without copying it, and are used by timely's own
+//! transport, but are equally suited to user code that ships bytes between threads, for example a
+//! sink that serializes records on worker threads and writes them out from another thread.
+//!
+//! A `SendEndpoint` stages writes into large shared allocations, and a `MergeQueue` carries the
+//! written `Bytes` to a reader, merging contiguous writes to the same allocation as it goes.
+//!
+//! #Examples
+//! ```
+//! use timely_communication::allocator::zero_copy::bytes_exchange::{MergeQueue, Signal, SendEndpoint, BytesPull};
+//! use timely_communication::allocator::zero_copy::bytes_slab::GrowthPolicy;
+//!
+//! let mut queue = MergeQueue::new(Signal::new());
+//! let mut endpoint = SendEndpoint::with_policy(queue.clone(), GrowthPolicy::default().initial_shift(10));
+//!
+//! endpoint.reserve(5)[..5].copy_from_slice(b"hello");
+//! endpoint.make_valid(5);
+//! endpoint.reserve(6)[..6].copy_from_slice(b" world");
+//! endpoint.make_valid(6);
+//!
+//! // the two writes share an allocation and arrive merged.
+//! let mut received = Vec::new();
+//! queue.drain_into(&mut received);
+//! assert_eq!(received.len(), 1);
+//! assert_eq!(&received[0][..], b"hello world");
+//!
+//! // the queue is complete once all other handles to it have dropped.
+//! assert!(!queue.is_complete());
+//! drop(endpoint);
+//! assert!(queue.is_complete());
+//! ```
 
 use std::thread::Thread;
 use std::sync::{Arc, Mutex, RwLock};
 use std::collections::VecDeque;
 
 use bytes::arc::Bytes;
-use super::bytes_slab::BytesSlab;
+use super::bytes_slab::{BytesSlab, GrowthPolicy};
 
 /// A target for `Bytes`.
 pub trait BytesPush {
@@ -58,7 +90,14 @@ impl Signal {
 }
 
 use std::sync::atomic::{AtomicBool, Ordering};
-/// Who knows.
+/// A shared queue of `Bytes`, written to by any number of clones and read by one.
+///
+/// Writes merge each `Bytes` with the last enqueued `Bytes` when they are contiguous regions of the
+/// same allocation, which keeps the queue short when data are written in many small pieces. The
+/// queue pings its signal when it transitions from empty to non-empty, and when any handle drops.
+///
+/// A panic on a thread holding a handle poisons the queue, after which operations on the queue's
+/// other handles panic, to distinguish an unclean shutdown from a clean one.
 #[derive(Clone)]
 pub struct MergeQueue {
     queue: Arc<Mutex<VecDeque<Bytes>>>, // queue of bytes.
@@ -80,6 +119,11 @@ impl MergeQueue {
         if self.panic.load(Ordering::SeqCst) { panic!("MergeQueue poisoned."); }
         Arc::strong_count(&self.queue) == 1
     }
+    /// Indicates that the queue currently holds no `Bytes`.
+    pub fn is_empty(&self) -> bool {
+        if self.panic.load(Ordering::SeqCst) { panic!("MergeQueue poisoned."); }
+        self.queue.lock().expect("unable to lock mutex").is_empty()
+    }
 }
 
 impl BytesPush for MergeQueue {
@@ -157,9 +201,13 @@ impl<P: BytesPush> SendEndpoint<P> {
 
     /// Allocates a new `BytesSendEndpoint` from a shared queue.
     pub fn new(queue: P) -> Self {
+        SendEndpoint::with_policy(queue, GrowthPolicy::default())
+    }
+    /// Allocates a new `BytesSendEndpoint` from a shared queue, staging writes in buffers sized by `policy`.
+    pub fn with_policy(queue: P, policy: GrowthPolicy) -> Self {
         SendEndpoint {
             send: queue,
-            buffer: BytesSlab::new(20),
+            buffer: BytesSlab::with_policy(policy),
         }
     }
     /// Makes the next `bytes` bytes valid.
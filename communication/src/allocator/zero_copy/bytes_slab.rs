@@ -2,6 +2,58 @@
 
 use bytes::arc::Bytes;
 
+/// Configures the sizes of the buffers a `BytesSlab` allocates, and how many it retains for reuse.
+///
+/// A slab starts with a buffer of `1 << initial_shift` bytes. When asked for more space than its
+/// buffer can provide, the slab increases its shift by `growth_shift` until the request fits, and
+/// discards retained buffers of the old size. At most `stash_limit` reclaimed buffers are retained.
+///
+/// #Examples
+/// ```
+/// use timely_communication::allocator::zero_copy::bytes_slab::{BytesSlab, GrowthPolicy};
+///
+/// let policy = GrowthPolicy::default().initial_shift(4).growth_shift(2);
+/// let mut slab = BytesSlab::with_policy(policy);
+/// assert_eq!(slab.empty().len(), 16);
+///
+/// // 17 bytes do not fit in 16, and so the buffer grows to 64 bytes.
+/// slab.ensure_capacity(17);
+/// assert_eq!(slab.empty().len(), 64);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrowthPolicy {
+    /// The initial buffer size is `1 << initial_shift` bytes.
+    pub initial_shift: usize,
+    /// The amount by which the shift increases each time the buffer must grow.
+    pub growth_shift: usize,
+    /// The maximum number of reclaimed buffers retained for reuse.
+    pub stash_limit: usize,
+}
+
+impl Default for GrowthPolicy {
+    /// Starts at one megabyte, doubles when growing, and retains any number of reclaimed buffers.
+    fn default() -> Self {
+        GrowthPolicy {
+            initial_shift: 20,
+            growth_shift: 1,
+            stash_limit: usize::max_value(),
+        }
+    }
+}
+
+impl GrowthPolicy {
+    /// Sets the initial buffer size to `1 << shift` bytes.
+    pub fn initial_shift(mut self, shift: usize) -> Self { self.initial_shift = shift; self }
+    /// Sets the amount by which the shift increases when growing, which must be positive.
+    pub fn growth_shift(mut self, shift: usize) -> Self {
+        assert!(shift > 0, "growth_shift must be positive");
+        self.growth_shift = shift;
+        self
+    }
+    /// Sets the maximum number of reclaimed buffers retained for reuse.
+    pub fn stash_limit(mut self, limit: usize) -> Self { self.stash_limit = limit; self }
+}
+
 /// A large binary allocation for writing and sharing.
 ///
 /// A bytes slab wraps a `Bytes` and maintains a valid (written) length, and supports writing after
@@ -13,19 +65,27 @@ pub struct BytesSlab {
     stash:          Vec<Bytes>,                 // reclaimed and resuable buffers.
     shift:          usize,                      // current buffer allocation size.
     valid:          usize,                      // buffer[..valid] are valid bytes.
+    policy:         GrowthPolicy,               // buffer sizing and retention policy.
 }
 
 impl BytesSlab {
     /// Allocates a new `BytesSlab` with an initial size determined by a shift.
     pub fn new(shift: usize) -> Self {
+        BytesSlab::with_policy(GrowthPolicy::default().initial_shift(shift))
+    }
+    /// Allocates a new `BytesSlab` whose buffers are sized and retained according to `policy`.
+    pub fn with_policy(policy: GrowthPolicy) -> Self {
         BytesSlab {
-            buffer: Bytes::from(vec![0u8; 1 << shift].into_boxed_slice()),
+            buffer: Bytes::from(vec![0u8; 1 << policy.initial_shift].into_boxed_slice()),
             in_progress: Vec::new(),
             stash: Vec::new(),
-            shift,
+            shift: policy.initial_shift,
             valid: 0,
+            policy,
         }
     }
+    /// The policy by which the slab sizes and retains buffers.
+    pub fn policy(&self) -> GrowthPolicy { self.policy }
     /// The empty region of the slab.
     pub fn empty(&mut self) -> &mut [u8] {
         &mut self.buffer[self.valid..]
@@ -56,7 +116,7 @@ impl BytesSlab {
 
             // Increase allocation if copy would be insufficient.
             while self.valid + capacity > (1 << self.shift) {
-                self.shift += 1;
+                self.shift += self.policy.growth_shift;
                 self.stash.clear();         // clear wrongly sized buffers.
                 self.in_progress.clear();   // clear wrongly sized buffers.
             }
@@ -66,7 +126,10 @@ impl BytesSlab {
                 for shared in self.in_progress.iter_mut() {
                     if let Some(mut bytes) = shared.take() {
                         if bytes.try_regenerate::<Box<[u8]>>() {
-                            self.stash.push(bytes);
+                            // retain reclaimed buffers up to the limit, and release the rest.
+                            if self.stash.len() < self.policy.stash_limit {
+                                self.stash.push(bytes);
+                            }
                         }
                         else {
                             *shared = Some(bytes);
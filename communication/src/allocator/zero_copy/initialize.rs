@@ -1,10 +1,18 @@
 //! Network initialization.
 
+use std::io::{Read, Write, BufReader, BufWriter};
+use std::fs::File;
+use std::net::TcpStream;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use allocator::Process;
-use networking::create_sockets;
+use networking::{create_sockets_namespaced, Namespace};
+use super::bytes_exchange::{MergeQueue, Signal};
 use super::tcp::{send_loop, recv_loop};
-use super::allocator::{TcpBuilder, new_vector};
+use super::replay::{Recorder, Replayer, recording_path};
+use super::simulated::{pipe, LinkConfig};
+use super::allocator::{TcpBuilder, new_vector, new_vector_heterogeneous, worker_offsets};
 
 /// Join handles for send and receive threads.
 ///
@@ -34,19 +42,56 @@ pub fn initialize_networking(
     my_index: usize,
     threads: usize,
     noisy: bool,
-    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>)
+    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+    failed: Arc<AtomicBool>)
 -> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)> {
+    initialize_networking_recorded(addresses, my_index, threads, noisy, None, Namespace::default(), log_sender, failed)
+}
+
+/// Initializes network connections, recording the bytes received from each process into `record`.
+///
+/// If `record` is a directory, the bytes received from process `index` are recorded into the file
+/// `recording_path(record, my_index, index)`, from which `initialize_replay` can replay them. Only
+/// processes in `namespace` are connected to.
+pub fn initialize_networking_recorded(
+    addresses: Vec<String>,
+    my_index: usize,
+    threads: usize,
+    noisy: bool,
+    record: Option<PathBuf>,
+    namespace: Namespace,
+    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+    failed: Arc<AtomicBool>)
+-> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)> {
+    let threads = vec![threads; addresses.len()];
+    initialize_networking_heterogeneous(addresses, my_index, threads, noisy, record, namespace, log_sender, failed)
+}
 
-    let processes = addresses.len();
+/// Initializes network connections between processes running the numbers of workers `threads`.
+///
+/// Process `index` runs `threads[index]` workers, and all processes must be initialized with the
+/// same `threads`. Otherwise, as `initialize_networking_recorded`.
+pub fn initialize_networking_heterogeneous(
+    addresses: Vec<String>,
+    my_index: usize,
+    threads: Vec<usize>,
+    noisy: bool,
+    record: Option<PathBuf>,
+    namespace: Namespace,
+    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+    failed: Arc<AtomicBool>)
+-> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)> {
 
-    let mut results = create_sockets(addresses, my_index, noisy)?;
+    assert_eq!(addresses.len(), threads.len(), "each process requires a number of workers");
+    let offset = worker_offsets(&threads)[my_index];
 
-    let (builders, remote_recvs, remote_sends) = new_vector(my_index, threads, processes);
+    let mut results = create_sockets_namespaced(addresses, my_index, &namespace, noisy)?;
+
+    let (builders, remote_recvs, remote_sends) = new_vector_heterogeneous(my_index, &threads);
     let mut remote_recv_iter = remote_recvs.into_iter();
     let mut remote_send_iter = remote_sends.into_iter();
 
-    let mut send_guards = Vec::new();
-    let mut recv_guards = Vec::new();
+    let mut guard = CommsGuard { send_guards: Vec::new(), recv_guards: Vec::new() };
 
     // for each process, if a stream exists (i.e. not local) ...
     for index in 0..results.len() {
@@ -54,50 +99,186 @@ pub fn initialize_networking(
         if let Some(stream) = results[index].take() {
 
             let (remote_recv, signal) = remote_recv_iter.next().unwrap();
+            let remote_send = remote_send_iter.next().unwrap();
 
-            {
-                let log_sender = log_sender.clone();
-                let stream = stream.try_clone()?;
-                let join_guard =
-                ::std::thread::Builder::new()
-                    .name(format!("send thread {}", index))
-                    .spawn(move || {
+            let writer = stream.try_clone()?;
+            let shutdown = |stream: TcpStream| stream.shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
+            let connection = Connection { index, my_index, offset, remote_recv, signal, remote_send, namespace: namespace.clone() };
 
-                        let log_sender = log_sender(::logging::CommsSetup {
-                            process: my_index,
-                            sender: true,
-                            remote: Some(index),
-                        });
+            if let Some(directory) = record.as_ref() {
+                let recording = BufWriter::new(File::create(recording_path(directory, my_index, index))?);
+                let reader = Recorder::new(stream, recording);
+                connection.spawn(reader, writer, shutdown, &log_sender, &failed, &mut guard)?;
+            }
+            else {
+                connection.spawn(stream, writer, shutdown, &log_sender, &failed, &mut guard)?;
+            }
+        }
+    }
 
-                        send_loop(stream, remote_recv, signal, log_sender);
-                    })?;
+    Ok((builders, guard))
+}
 
-                send_guards.push(join_guard);
-            }
+/// Initializes a process which replays the bytes it received in a recorded run, in place of connections.
+///
+/// The process must have the index `my_index`, and the same number of processes and threads, as the
+/// process whose recordings are in `directory`. Bytes the process sends are discarded. If `paced`
+/// is set, the recorded bytes are delivered no sooner than they were received in the recorded run.
+pub fn initialize_replay(
+    directory: PathBuf,
+    my_index: usize,
+    processes: usize,
+    threads: usize,
+    paced: bool,
+    namespace: Namespace,
+    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+    failed: Arc<AtomicBool>)
+-> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)> {
 
-            let remote_send = remote_send_iter.next().unwrap();
+    let (builders, remote_recvs, remote_sends) = new_vector(my_index, threads, processes);
+    let mut remote_recv_iter = remote_recvs.into_iter();
+    let mut remote_send_iter = remote_sends.into_iter();
 
-            {
-                // let remote_sends = remote_sends.clone();
-                let log_sender = log_sender.clone();
-                let stream = stream.try_clone()?;
-                let join_guard =
-                ::std::thread::Builder::new()
-                    .name(format!("recv thread {}", index))
-                    .spawn(move || {
-                        let log_sender = log_sender(::logging::CommsSetup {
-                            process: my_index,
-                            sender: false,
-                            remote: Some(index),
-                        });
-                        recv_loop(stream, remote_send, threads * my_index, log_sender);
-                    })?;
-
-                recv_guards.push(join_guard);
-            }
+    let mut guard = CommsGuard { send_guards: Vec::new(), recv_guards: Vec::new() };
+
+    for index in (0..processes).filter(|&index| index != my_index) {
 
+        let (remote_recv, signal) = remote_recv_iter.next().unwrap();
+        let remote_send = remote_send_iter.next().unwrap();
+
+        let recording = BufReader::new(File::open(recording_path(&directory, my_index, index))?);
+        let reader = Replayer::new(recording).paced(paced);
+        let connection = Connection { index, my_index, offset: threads * my_index, remote_recv, signal, remote_send, namespace: namespace.clone() };
+        connection.spawn(reader, ::std::io::sink(), |_| { }, &log_sender, &failed, &mut guard)?;
+    }
+
+    Ok((builders, guard))
+}
+
+/// Initializes all processes of a cluster in this process, connected by simulated links.
+///
+/// The result contains builders for the `threads` workers of each of the `processes` processes, in
+/// order of worker index. Each pair of processes is connected by a pair of links configured by `config`.
+pub fn initialize_simulated(
+    processes: usize,
+    threads: usize,
+    config: LinkConfig,
+    namespace: Namespace,
+    log_sender: Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+    failed: Arc<AtomicBool>)
+-> ::std::io::Result<(Vec<TcpBuilder<Process>>, CommsGuard)> {
+
+    // links[source][target] carries the bytes process `source` sends to process `target`.
+    let mut writers = Vec::new();
+    let mut readers = Vec::new();
+    for source in 0 .. processes {
+        let mut source_writers = Vec::new();
+        let mut source_readers = Vec::new();
+        for target in 0 .. processes {
+            let (writer, reader) = pipe(&config, (source * processes + target) as u64);
+            source_writers.push(Some(writer));
+            source_readers.push(Some(reader));
+        }
+        writers.push(source_writers);
+        readers.push(source_readers);
+    }
+
+    let mut all_builders = Vec::new();
+    let mut guard = CommsGuard { send_guards: Vec::new(), recv_guards: Vec::new() };
+
+    for my_index in 0 .. processes {
+
+        let (builders, remote_recvs, remote_sends) = new_vector(my_index, threads, processes);
+        let mut remote_recv_iter = remote_recvs.into_iter();
+        let mut remote_send_iter = remote_sends.into_iter();
+
+        for index in (0..processes).filter(|&index| index != my_index) {
+
+            let (remote_recv, signal) = remote_recv_iter.next().unwrap();
+            let remote_send = remote_send_iter.next().unwrap();
+
+            let writer = writers[my_index][index].take().unwrap();
+            let reader = readers[index][my_index].take().unwrap();
+            let connection = Connection { index, my_index, offset: threads * my_index, remote_recv, signal, remote_send, namespace: namespace.clone() };
+            // dropping the writer closes the link.
+            connection.spawn(reader, writer, |_| { }, &log_sender, &failed, &mut guard)?;
         }
+
+        all_builders.extend(builders);
     }
 
-    Ok((builders, CommsGuard { send_guards, recv_guards }))
-}
\ No newline at end of file
+    Ok((all_builders, guard))
+}
+
+/// The queues connecting the workers of this process to process `index`.
+struct Connection {
+    index: usize,
+    my_index: usize,
+    offset: usize,      // the index of the first worker of this process.
+    remote_recv: Vec<MergeQueue>,
+    signal: Signal,
+    remote_send: Vec<MergeQueue>,
+    namespace: Namespace,
+}
+
+impl Connection {
+    /// Spawns send and receive threads moving bytes between the queues and `writer` and `reader`.
+    ///
+    /// Once the send thread has written the end of its stream, it calls `shutdown` with the writer.
+    fn spawn<R, W, S>(
+        self,
+        reader: R,
+        writer: W,
+        shutdown: S,
+        log_sender: &Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>,
+        failed: &Arc<AtomicBool>,
+        guard: &mut CommsGuard)
+    -> ::std::io::Result<()>
+    where
+        R: Read+Send+'static,
+        W: Write+Send+'static,
+        S: FnOnce(W)+Send+'static,
+    {
+        let Connection { index, my_index, offset, remote_recv, signal, remote_send, namespace } = self;
+
+        {
+            let log_sender = log_sender.clone();
+            let failed = failed.clone();
+            let join_guard =
+            ::std::thread::Builder::new()
+                .name(namespace.thread_name(&format!("send thread {}", index)))
+                .spawn(move || {
+
+                    let log_sender = log_sender(::logging::CommsSetup {
+                        process: my_index,
+                        sender: true,
+                        remote: Some(index),
+                    });
+
+                    shutdown(send_loop(writer, remote_recv, signal, failed, log_sender));
+                })?;
+
+            guard.send_guards.push(join_guard);
+        }
+
+        {
+            let log_sender = log_sender.clone();
+            let failed = failed.clone();
+            let join_guard =
+            ::std::thread::Builder::new()
+                .name(namespace.thread_name(&format!("recv thread {}", index)))
+                .spawn(move || {
+                    let log_sender = log_sender(::logging::CommsSetup {
+                        process: my_index,
+                        sender: false,
+                        remote: Some(index),
+                    });
+                    recv_loop(reader, remote_send, offset, failed, log_sender);
+                })?;
+
+            guard.recv_guards.push(join_guard);
+        }
+
+        Ok(())
+    }
+}
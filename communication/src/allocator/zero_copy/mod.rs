@@ -3,11 +3,20 @@
 //! These allocators are based on `Abomonation` serialization, and its ability to deserialized
 //! typed Rust data in-place. They surface references to data, often ultimately referencing the
 //! raw binary data they initial received.
+//!
+//! Connecting processes over TCP requires the `networking` feature.
+//!
+//! The `bytes_slab` and `bytes_exchange` modules do not depend on the allocators, and may be used
+//! on their own to move serialized data between threads without copies.
 
 pub mod bytes_slab;
 pub mod bytes_exchange;
+#[cfg(feature = "networking")]
 pub mod tcp;
 pub mod allocator;
 pub mod allocator_process;
+#[cfg(feature = "networking")]
 pub mod initialize;
-pub mod push_pull;
\ No newline at end of file
+pub mod push_pull;
+pub mod replay;
+pub mod simulated;
\ No newline at end of file
@@ -2,7 +2,7 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use bytes::arc::Bytes;
 
@@ -59,6 +59,67 @@ impl<T:Data, P: BytesPush> Push<Message<T>> for Pusher<T, P> {
     }
 }
 
+/// Queues of received serialized messages, by channel, shared by an allocator and its pullers.
+///
+/// A channel's queue is materialized when the first message for the channel arrives, rather than
+/// when the channel is allocated, so that channels that never receive serialized messages, for
+/// example those only used within a worker, cost nothing here. Messages may arrive before their
+/// channel is allocated.
+///
+/// Once the puller of a channel is dropped, for example because its dataflow was cancelled, the
+/// channel is closed: its queue is discarded, and messages that arrive for it later are dropped.
+/// Only the `CLOSED_CAPACITY` most recently closed channels are remembered, as nothing indicates
+/// that no further messages will arrive for a channel; a message arriving for a channel closed
+/// longer ago than that is queued, and held until the queues are dropped.
+#[derive(Clone, Default)]
+pub struct ChannelQueues {
+    queues: Rc<RefCell<HashMap<usize, VecDeque<Bytes>>>>,
+    closed: Rc<RefCell<(HashSet<usize>, VecDeque<usize>)>>,
+}
+
+/// The number of closed channels a `ChannelQueues` remembers.
+pub const CLOSED_CAPACITY: usize = 1 << 12;
+
+impl ChannelQueues {
+    /// Allocates an empty set of queues.
+    pub fn new() -> Self { Default::default() }
+    /// Enqueues `bytes` for `channel`, materializing its queue if necessary.
+    #[inline]
+    pub fn push(&self, channel: usize, bytes: Bytes) {
+        if self.closed.borrow().0.contains(&channel) { return; }
+        self.queues.borrow_mut().entry(channel).or_insert_with(VecDeque::new).push_back(bytes);
+    }
+    /// Dequeues the next message for `channel`, if any.
+    #[inline]
+    pub fn pop(&self, channel: usize) -> Option<Bytes> {
+        self.queues.borrow_mut().get_mut(&channel).and_then(|queue| queue.pop_front())
+    }
+    /// The number of messages queued for `channel`.
+    pub fn pending(&self, channel: usize) -> usize {
+        self.queues.borrow().get(&channel).map(|queue| queue.len()).unwrap_or(0)
+    }
+    /// The number of channels whose queues have been materialized.
+    pub fn materialized(&self) -> usize {
+        self.queues.borrow().len()
+    }
+    /// Discards the queue of `channel`, and any messages that arrive for it subsequently.
+    pub fn close(&self, channel: usize) {
+        let mut closed = self.closed.borrow_mut();
+        if closed.0.insert(channel) {
+            closed.1.push_back(channel);
+            if closed.1.len() > CLOSED_CAPACITY {
+                let oldest = closed.1.pop_front().expect("closed channels vanished");
+                closed.0.remove(&oldest);
+            }
+        }
+        self.queues.borrow_mut().remove(&channel);
+    }
+    /// Returns true if `channel` is among the most recently closed channels.
+    pub fn is_closed(&self, channel: usize) -> bool {
+        self.closed.borrow().0.contains(&channel)
+    }
+}
+
 /// An adapter from which one can pull elements of type `T`.
 ///
 /// This type is very simple, and just consumes owned `Vec<u8>` allocations. It is
@@ -67,14 +128,16 @@ impl<T:Data, P: BytesPush> Push<Message<T>> for Pusher<T, P> {
 /// allocation.
 pub struct Puller<T> {
     current: Option<Message<T>>,
-    receiver: Rc<RefCell<VecDeque<Bytes>>>,    // source of serialized buffers
+    channel: usize,
+    receiver: ChannelQueues,    // source of serialized buffers
 }
 
 impl<T:Data> Puller<T> {
-    /// Creates a new `Puller` instance from a shared queue.
-    pub fn new(receiver: Rc<RefCell<VecDeque<Bytes>>>) -> Puller<T> {
+    /// Creates a new `Puller` instance for `channel` from shared queues.
+    pub fn new(channel: usize, receiver: ChannelQueues) -> Puller<T> {
         Puller {
             current: None,
+            channel,
             receiver,
         }
     }
@@ -85,14 +148,19 @@ impl<T:Data> Pull<Message<T>> for Puller<T> {
     fn pull(&mut self) -> &mut Option<Message<T>> {
         self.current =
         self.receiver
-            .borrow_mut()
-            .pop_front()
+            .pop(self.channel)
             .map(|bytes| unsafe { Message::from_bytes(bytes) });
 
         &mut self.current
     }
 }
 
+impl<T> Drop for Puller<T> {
+    fn drop(&mut self) {
+        self.receiver.close(self.channel);
+    }
+}
+
 /// An adapter from which one can pull elements of type `T`.
 ///
 /// This type is very simple, and just consumes owned `Vec<u8>` allocations. It is
@@ -102,15 +170,17 @@ impl<T:Data> Pull<Message<T>> for Puller<T> {
 pub struct PullerInner<T> {
     inner: Box<Pull<Message<T>>>,            // inner pullable (e.g. intra-process typed queue)
     current: Option<Message<T>>,
-    receiver: Rc<RefCell<VecDeque<Bytes>>>,    // source of serialized buffers
+    channel: usize,
+    receiver: ChannelQueues,                 // source of serialized buffers
 }
 
 impl<T:Data> PullerInner<T> {
-    /// Creates a new `PullerInner` instance from a shared queue.
-    pub fn new(inner: Box<Pull<Message<T>>>, receiver: Rc<RefCell<VecDeque<Bytes>>>) -> Self {
+    /// Creates a new `PullerInner` instance for `channel` from shared queues.
+    pub fn new(inner: Box<Pull<Message<T>>>, channel: usize, receiver: ChannelQueues) -> Self {
         PullerInner {
             inner,
             current: None,
+            channel,
             receiver,
         }
     }
@@ -127,11 +197,16 @@ impl<T:Data> Pull<Message<T>> for PullerInner<T> {
         else {
             self.current =
             self.receiver
-                .borrow_mut()
-                .pop_front()
+                .pop(self.channel)
                 .map(|bytes| unsafe { Message::from_bytes(bytes) });
 
             &mut self.current
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T> Drop for PullerInner<T> {
+    fn drop(&mut self) {
+        self.receiver.close(self.channel);
+    }
+}
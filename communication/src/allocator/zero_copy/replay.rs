@@ -0,0 +1,149 @@
+//! Recording and replaying the byte streams a process receives from its peers.
+//!
+//! A process of a cluster receives from each other process a stream of bytes, which the receive
+//! loops carve into messages for its workers. A `Recorder` wraps the reader of such a stream and
+//! writes each chunk it reads, with the time it was read, to a recording. A `Replayer` reads those
+//! chunks back, optionally at their recorded pace, and may stand in for the connection.
+//!
+//! Replaying the recordings a process made, in place of its connections, reproduces what the
+//! process received without its peers, and so a bug observed in a distributed run can be debugged
+//! in a single local process. The interleaving of the process's own worker threads is not recorded,
+//! and so replay reproduces the inputs of the process, but not necessarily its schedule.
+//!
+//! A recording is a sequence of frames, each an eight byte little-endian count of nanoseconds since
+//! the recording started, an eight byte little-endian length, and that many bytes.
+//!
+//! #Examples
+//! ```
+//! use std::io::Read;
+//! use timely_communication::allocator::zero_copy::replay::{Recorder, Replayer};
+//!
+//! let mut recording = Vec::new();
+//! {
+//!     let mut recorder = Recorder::new(&b"hello world"[..], &mut recording);
+//!     let mut received = Vec::new();
+//!     recorder.read_to_end(&mut received).unwrap();
+//!     assert_eq!(&received[..], b"hello world");
+//! }
+//!
+//! let mut replayed = Vec::new();
+//! Replayer::new(&recording[..]).read_to_end(&mut replayed).unwrap();
+//! assert_eq!(&replayed[..], b"hello world");
+//! ```
+
+use std::io::{Read, Write, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The file to which process `process` records the bytes it receives from process `remote`.
+pub fn recording_path(directory: &Path, process: usize, remote: usize) -> PathBuf {
+    directory.join(format!("recv-{}-from-{}.timely", process, remote))
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<()> {
+    let mut bytes = [0u8; 8];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (8 * index)) as u8;
+    }
+    writer.write_all(&bytes)
+}
+
+/// Reads a `u64`, or returns `None` if the reader is exhausted before its first byte.
+fn read_u64<R: Read>(reader: &mut R) -> Result<Option<u64>> {
+    let mut bytes = [0u8; 8];
+    let mut read = 0;
+    while read < 8 {
+        match reader.read(&mut bytes[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(::std::io::Error::new(::std::io::ErrorKind::UnexpectedEof, "truncated recording")),
+            count => read += count,
+        }
+    }
+    Ok(Some(bytes.iter().enumerate().fold(0, |value, (index, byte)| value | ((*byte as u64) << (8 * index)))))
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+/// A reader that records each chunk it reads from an inner reader.
+pub struct Recorder<R: Read, W: Write> {
+    reader: R,
+    recording: W,
+    start: Instant,
+}
+
+impl<R: Read, W: Write> Recorder<R, W> {
+    /// Wraps `reader`, recording what is read from it into `recording`.
+    pub fn new(reader: R, recording: W) -> Self {
+        Recorder { reader, recording, start: Instant::now() }
+    }
+    /// The wrapped reader.
+    pub fn inner(&mut self) -> &mut R { &mut self.reader }
+}
+
+impl<R: Read, W: Write> Read for Recorder<R, W> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let read = self.reader.read(buffer)?;
+        if read > 0 {
+            write_u64(&mut self.recording, nanos(self.start.elapsed()))?;
+            write_u64(&mut self.recording, read as u64)?;
+            self.recording.write_all(&buffer[..read])?;
+            // flush each chunk, so that the recording survives a failure of the process.
+            self.recording.flush()?;
+        }
+        Ok(read)
+    }
+}
+
+/// A reader producing the bytes of a recording made by a `Recorder`.
+pub struct Replayer<R: Read> {
+    recording: R,
+    paced: bool,
+    start: Instant,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl<R: Read> Replayer<R> {
+    /// Replays `recording` as fast as it is read.
+    pub fn new(recording: R) -> Self {
+        Replayer { recording, paced: false, start: Instant::now(), chunk: Vec::new(), offset: 0 }
+    }
+    /// Sets whether chunks are withheld until the time they were recorded, relative to the construction of the replayer.
+    pub fn paced(mut self, paced: bool) -> Self {
+        self.paced = paced;
+        self
+    }
+    /// Loads the next chunk, returning false if the recording is exhausted.
+    fn next_chunk(&mut self) -> Result<bool> {
+        let elapsed = match read_u64(&mut self.recording)? {
+            Some(elapsed) => elapsed,
+            None => return Ok(false),
+        };
+        let length = read_u64(&mut self.recording)?.ok_or_else(|| ::std::io::Error::new(::std::io::ErrorKind::UnexpectedEof, "truncated recording"))?;
+        self.chunk.resize(length as usize, 0);
+        self.recording.read_exact(&mut self.chunk[..])?;
+        self.offset = 0;
+        if self.paced {
+            let due = Duration::new(elapsed / 1_000_000_000, (elapsed % 1_000_000_000) as u32);
+            let now = self.start.elapsed();
+            if due > now {
+                ::std::thread::sleep(due - now);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Replayer<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if self.offset == self.chunk.len() && !self.next_chunk()? {
+            return Ok(0);
+        }
+        let count = ::std::cmp::min(buffer.len(), self.chunk.len() - self.offset);
+        buffer[..count].copy_from_slice(&self.chunk[self.offset .. self.offset + count]);
+        self.offset += count;
+        Ok(count)
+    }
+}
@@ -0,0 +1,219 @@
+//! In-memory links between simulated processes, with configurable delays.
+//!
+//! A `pipe` is a one-directional byte stream standing in for a network connection. Each write is
+//! delivered to the reader as a chunk, no sooner than its transmission time under the link's
+//! bandwidth cap, plus the link's latency and a random jitter. A write may also be "lost", which as
+//! on a reliable transport results in its delivery after an additional retransmission delay. Bytes
+//! are always delivered in the order they were written, as timely requires of its connections, and
+//! so reordering only occurs between different links.
+//!
+//! Connecting the processes of a cluster with pipes, for example with `Configuration::Simulated`,
+//! runs a whole cluster in one process, with the delays of a network one would like to model.
+//!
+//! #Examples
+//! ```
+//! use std::io::{Read, Write};
+//! use std::time::{Duration, Instant};
+//! use timely_communication::allocator::zero_copy::simulated::{pipe, LinkConfig};
+//!
+//! let config = LinkConfig::default().latency(Duration::from_millis(20));
+//! let (mut writer, mut reader) = pipe(&config, 0);
+//!
+//! let start = Instant::now();
+//! writer.write_all(b"hello").unwrap();
+//! drop(writer);
+//!
+//! let mut received = Vec::new();
+//! reader.read_to_end(&mut received).unwrap();
+//! assert_eq!(&received[..], b"hello");
+//! assert!(start.elapsed() >= Duration::from_millis(20));
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{Read, Write, Result};
+use std::sync::{Arc, Mutex, Condvar};
+use std::time::{Duration, Instant};
+
+/// The delays a simulated link imposes on the bytes written to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkConfig {
+    /// The delay between transmitting and delivering each write.
+    pub latency: Duration,
+    /// The bound on an additional delay, drawn uniformly at random for each write.
+    pub jitter: Duration,
+    /// The number of bytes the link transmits per second, if limited.
+    pub bandwidth: Option<u64>,
+    /// The probability that a write is lost and must be retransmitted.
+    pub loss: f64,
+    /// The additional delay of a lost write.
+    pub retransmit: Duration,
+    /// The seed for the random choices of jitter and loss.
+    pub seed: u64,
+}
+
+impl Default for LinkConfig {
+    /// A link without delays or losses.
+    fn default() -> Self {
+        LinkConfig {
+            latency: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            bandwidth: None,
+            loss: 0.0,
+            retransmit: Duration::from_millis(200),
+            seed: 0,
+        }
+    }
+}
+
+impl LinkConfig {
+    /// Sets the delay between transmitting and delivering each write.
+    pub fn latency(mut self, latency: Duration) -> Self { self.latency = latency; self }
+    /// Sets the bound on the random additional delay of each write.
+    pub fn jitter(mut self, jitter: Duration) -> Self { self.jitter = jitter; self }
+    /// Limits the link to transmitting `bytes_per_second` bytes per second.
+    pub fn bandwidth(mut self, bytes_per_second: u64) -> Self {
+        assert!(bytes_per_second > 0, "bandwidth must be positive");
+        self.bandwidth = Some(bytes_per_second);
+        self
+    }
+    /// Sets the probability that a write is lost, and the additional delay of its retransmission.
+    pub fn loss(mut self, probability: f64, retransmit: Duration) -> Self {
+        assert!(probability >= 0.0 && probability < 1.0, "loss probability must be in [0, 1)");
+        self.loss = probability;
+        self.retransmit = retransmit;
+        self
+    }
+    /// Sets the seed for the random choices of jitter and loss.
+    pub fn seed(mut self, seed: u64) -> Self { self.seed = seed; self }
+}
+
+/// The state shared by the ends of a pipe.
+struct Link {
+    config: LinkConfig,
+    chunks: VecDeque<(Instant, Vec<u8>)>,   // written chunks and their delivery times.
+    transmitted: Instant,                   // the time the link finishes transmitting prior writes.
+    delivered: Instant,                     // the delivery time of the most recent write.
+    random: u64,                            // xorshift state.
+    closed: bool,                           // indicates that the writer has dropped.
+}
+
+impl Link {
+    /// A random number in `[0, 1)`.
+    fn random(&mut self) -> f64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        (self.random >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Scales a duration by a fraction.
+fn scale(duration: Duration, fraction: f64) -> Duration {
+    let nanos = (duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64) * fraction;
+    Duration::new((nanos / 1e9) as u64, (nanos % 1e9) as u32)
+}
+
+/// Creates a simulated link, returning its write and read ends.
+///
+/// The `stream` argument distinguishes the random choices of links created from the same config.
+pub fn pipe(config: &LinkConfig, stream: u64) -> (PipeWriter, PipeReader) {
+    let now = Instant::now();
+    let link = Link {
+        config: *config,
+        chunks: VecDeque::new(),
+        transmitted: now,
+        delivered: now,
+        // xorshift requires a non-zero state.
+        random: (config.seed ^ stream.wrapping_mul(0x9E3779B97F4A7C15)) | 1,
+        closed: false,
+    };
+    let shared = Arc::new((Mutex::new(link), Condvar::new()));
+    (PipeWriter { shared: shared.clone() }, PipeReader { shared, chunk: Vec::new(), offset: 0 })
+}
+
+/// The write end of a simulated link.
+///
+/// Dropping the writer closes the link, once the bytes written to it are delivered.
+pub struct PipeWriter {
+    shared: Arc<(Mutex<Link>, Condvar)>,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        if !buffer.is_empty() {
+            let &(ref lock, ref condvar) = &*self.shared;
+            let mut link = lock.lock().expect("failed to lock link");
+            let now = Instant::now();
+            let start = if link.transmitted > now { link.transmitted } else { now };
+            let transmission = match link.config.bandwidth {
+                Some(bandwidth) => scale(Duration::from_secs(1), buffer.len() as f64 / bandwidth as f64),
+                None => Duration::from_millis(0),
+            };
+            link.transmitted = start + transmission;
+            let jitter = link.random();
+            let mut delivery = link.transmitted + link.config.latency + scale(link.config.jitter, jitter);
+            if link.random() < link.config.loss {
+                delivery += link.config.retransmit;
+            }
+            // bytes are delivered in order, and so no sooner than those written before them.
+            if delivery < link.delivered {
+                delivery = link.delivered;
+            }
+            link.delivered = delivery;
+            link.chunks.push_back((delivery, buffer.to_vec()));
+            condvar.notify_all();
+        }
+        Ok(buffer.len())
+    }
+    fn flush(&mut self) -> Result<()> { Ok(()) }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        let &(ref lock, ref condvar) = &*self.shared;
+        if let Ok(mut link) = lock.lock() {
+            link.closed = true;
+        }
+        condvar.notify_all();
+    }
+}
+
+/// The read end of a simulated link.
+///
+/// Reads block until bytes are delivered, and return zero once the link is closed and empty.
+pub struct PipeReader {
+    shared: Arc<(Mutex<Link>, Condvar)>,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if self.offset == self.chunk.len() {
+            let &(ref lock, ref condvar) = &*self.shared;
+            let mut link = lock.lock().expect("failed to lock link");
+            loop {
+                let now = Instant::now();
+                let due = link.chunks.front().map(|&(due, _)| due);
+                match due {
+                    Some(due) if due <= now => {
+                        self.chunk = link.chunks.pop_front().expect("chunk vanished").1;
+                        self.offset = 0;
+                        break;
+                    },
+                    Some(due) => {
+                        link = condvar.wait_timeout(link, due - now).expect("failed to wait on link").0;
+                    },
+                    None if link.closed => return Ok(0),
+                    None => {
+                        link = condvar.wait(link).expect("failed to wait on link");
+                    },
+                }
+            }
+        }
+        let count = ::std::cmp::min(buffer.len(), self.chunk.len() - self.offset);
+        buffer[..count].copy_from_slice(&self.chunk[self.offset .. self.offset + count]);
+        self.offset += count;
+        Ok(count)
+    }
+}
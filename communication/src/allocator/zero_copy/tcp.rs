@@ -1,23 +1,26 @@
 //!
 
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use networking::MessageHeader;
+use networking::{MessageHeader, FAILURE_CHANNEL};
 
 use super::bytes_slab::BytesSlab;
 use super::bytes_exchange::{MergeQueue, Signal};
 
-/// Repeatedly reads from a TcpStream and carves out messages.
+/// Repeatedly reads from a TcpStream, or any other reader, and carves out messages.
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
 /// If the stream ends without being shut down, the receive thread panics in an attempt to
-/// take down the computation and cause the failures to cascade.
-pub fn recv_loop(
-    mut reader: TcpStream,
+/// take down the computation and cause the failures to cascade. If the stream ends with a
+/// header on `FAILURE_CHANNEL`, the remote process has had a worker fail, and `failed` is raised.
+pub fn recv_loop<R: Read>(
+    mut reader: R,
     mut targets: Vec<MergeQueue>,
     worker_offset: usize,
+    failed: Arc<AtomicBool>,
     _log_sender: ::logging::CommsLogger)
 {
     let mut buffer = BytesSlab::new(20);
@@ -62,7 +65,13 @@ pub fn recv_loop(
             let peeled_bytes = header.required_bytes();
             let bytes = buffer.extract(peeled_bytes);
 
-            if header.length > 0 {
+            if header.channel == FAILURE_CHANNEL {
+                // A remote worker has failed; nothing further will be sent.
+                failed.store(true, Ordering::SeqCst);
+                active = false;
+                break;
+            }
+            else if header.length > 0 {
                 stageds[header.target - worker_offset].push(bytes);
             }
             else {
@@ -87,22 +96,25 @@ pub fn recv_loop(
     // println!("RECVER EXITING");
 }
 
-/// Repeatedly sends messages into a TcpStream.
+/// Repeatedly sends messages into a TcpStream, or any other writer.
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
-pub fn send_loop(
+/// The writer is flushed and returned once the stream ends, so that it may be shut down.
+pub fn send_loop<W: Write>(
     // TODO: Maybe we don't need BufWriter with consolidation in writes.
-    writer: TcpStream,
+    writer: W,
     mut sources: Vec<MergeQueue>,
     signal: Signal,
-    _log_sender: ::logging::CommsLogger)
+    failed: Arc<AtomicBool>,
+    _log_sender: ::logging::CommsLogger) -> W
 {
 
     let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
     let mut stash = Vec::new();
 
-    while !sources.is_empty() {
+    // Stop sending if a worker has failed; the remote process need only learn of the failure.
+    while !sources.is_empty() && !failed.load(Ordering::SeqCst) {
 
         // TODO: Round-robin better, to release resources fairly when overloaded.
         for source in sources.iter_mut() {
@@ -126,17 +138,17 @@ pub fn send_loop(
         }
     }
 
-    // Write final zero-length header.
+    // Write final zero-length header, on `FAILURE_CHANNEL` if a worker has failed.
     // Would be better with meaningful metadata, but as this stream merges many
     // workers it isn't clear that there is anything specific to write here.
+    let failure = failed.load(Ordering::SeqCst);
     let header = MessageHeader {
-        channel:    0,
+        channel:    if failure { FAILURE_CHANNEL } else { 0 },
         source:     0,
         target:     0,
         length:     0,
         seqno:      0,
     };
     header.write_to(&mut writer).expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
-    writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
+    writer.into_inner().ok().expect("Failed to flush writer.")
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+//! Containment and propagation of worker failures.
+//!
+//! Each worker thread runs its logic under `catch_unwind`, so that a panic is reported with its
+//! payload when the worker is joined, rather than as an opaque error. A panicking worker also raises
+//! a flag shared by all workers and networking threads of its process: local workers observe the flag
+//! through `peer_failed`, and networking threads forward it to remote processes, whose workers then
+//! observe it in turn. Workers that observe the flag are expected to stop, so that the failure of one
+//! worker shuts down the whole computation rather than leaving its peers waiting indefinitely. The
+//! failure is reported only by the failed worker's result, rather than by panics of its peers.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+thread_local!(static FAILED: RefCell<Option<Arc<AtomicBool>>> = RefCell::new(None));
+
+/// The failure of a worker thread, with the message of its panic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WorkerFailure {
+    /// The index of the worker thread within its process.
+    pub index: usize,
+    /// The message the worker panicked with.
+    pub message: String,
+}
+
+impl fmt::Display for WorkerFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "worker thread {} panicked: {}", self.index, self.message)
+    }
+}
+
+/// Returns true iff some worker of the computation running on this thread has failed.
+///
+/// This includes the workers of other processes, once their failure has been received. Workers are
+/// expected to check this regularly and to stop once it returns true, returning from their logic.
+pub fn peer_failed() -> bool {
+    FAILED.with(|failed| failed.borrow().as_ref().map(|f| f.load(Ordering::SeqCst)).unwrap_or(false))
+}
+
+/// Raises the failure flag of the computation running on this thread, if any.
+///
+/// The `Generic` allocator calls this when dropped during a panic, which happens before its channels
+/// close, so that networking threads observe the failure before any consequence of the closed channels.
+pub fn signal_failure() {
+    FAILED.with(|failed| {
+        if let Some(ref failed) = *failed.borrow() {
+            failed.store(true, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Runs the logic of worker `index`, reporting a panic as a `WorkerFailure` and raising `failed`.
+pub fn run_worker<T, F: FnOnce()->T>(index: usize, failed: Arc<AtomicBool>, logic: F) -> Result<T, WorkerFailure> {
+    FAILED.with(|f| *f.borrow_mut() = Some(failed.clone()));
+    let result = panic::catch_unwind(AssertUnwindSafe(logic));
+    FAILED.with(|f| *f.borrow_mut() = None);
+    result.map_err(|payload| {
+        failed.store(true, Ordering::SeqCst);
+        WorkerFailure {
+            index,
+            message: panic_message(&*payload),
+        }
+    })
+}
+
+/// Extracts the message from a panic payload, if it is a string.
+///
+/// #Examples
+/// ```
+/// use timely_communication::panic_message;
+///
+/// let payload = ::std::panic::catch_unwind(|| panic!("failed at {}", 17)).unwrap_err();
+/// assert_eq!(panic_message(&*payload), "failed at 17");
+/// ```
+pub fn panic_message(payload: &(Any+Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    }
+    else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    }
+    else {
+        "(non-string panic payload)".to_owned()
+    }
+}
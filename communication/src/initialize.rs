@@ -1,17 +1,24 @@
 //! Initialization logic for a generic instance of the `Allocate` channel allocation trait.
 
 use std::thread;
+use std::time::{Duration, Instant};
 #[cfg(feature = "arg_parse")]
 use std::io::BufRead;
 #[cfg(feature = "arg_parse")]
 use getopts;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::AtomicBool;
 
 use std::any::Any;
+use std::path::PathBuf;
 
 use allocator::{AllocateBuilder, Thread, Process, Generic, GenericBuilder};
 // use allocator::zero_copy::allocator_process::ProcessBuilder;
-use allocator::zero_copy::initialize::initialize_networking;
+#[cfg(feature = "networking")]
+use allocator::zero_copy::initialize::{initialize_networking_recorded, initialize_networking_heterogeneous, initialize_replay, initialize_simulated};
+use allocator::zero_copy::simulated::LinkConfig;
+use failure::{run_worker, WorkerFailure};
+use networking::Namespace;
 
 /// Possible configurations for the communication infrastructure.
 pub enum Configuration {
@@ -19,8 +26,60 @@ pub enum Configuration {
     Thread,
     /// Use one process with an indicated number of threads.
     Process(usize),
+    /// Use one process with an indicated number of threads, whose channels deliver the messages
+    /// available to a worker in order of their source worker, for reproducible executions in tests.
+    Ordered(usize),
     /// Expect multiple processes indicated by `(threads, process, host_list, report)`.
-    Cluster(usize, usize, Vec<String>, bool)
+    Cluster(usize, usize, Vec<String>, bool),
+    /// As `Cluster`, but with a number of threads for each process, as `(threads, process, host_list, report)`.
+    ///
+    /// Process `p` runs `threads[p]` workers, and all processes must be configured with the same
+    /// `threads`. Workers are numbered consecutively within each process, and processes in order.
+    Heterogeneous(Vec<usize>, usize, Vec<String>, bool),
+    /// As `Cluster`, but recording the bytes received from other processes into a directory, as
+    /// `(threads, process, host_list, report, directory)`.
+    Record(usize, usize, Vec<String>, bool, PathBuf),
+    /// Replays the bytes process `process` received in a recorded run of `processes` processes, in
+    /// place of connecting to them, as `(threads, process, processes, directory)`.
+    ///
+    /// Recorded bytes are delivered no sooner than they were received in the recorded run, and the
+    /// bytes the process sends are discarded.
+    Replay(usize, usize, usize, PathBuf),
+    /// Runs all processes of a cluster in this process, connected by simulated links, as
+    /// `(threads, processes, link)`.
+    ///
+    /// Each process has `threads` workers, and each pair of processes is connected by links which
+    /// delay the bytes sent between them as `link` indicates.
+    Simulated(usize, usize, LinkConfig),
+    /// Runs the indicated configuration in a namespace, which prefixes the names of its threads and
+    /// keeps its processes from connecting to those of computations in other namespaces.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely_communication::Configuration;
+    ///
+    /// let config = Configuration::Namespaced("tests".to_owned(), Box::new(Configuration::Process(2)));
+    /// let logger = ::std::sync::Arc::new(|_| timely_communication::logging::BufferingLogger::new_inactive());
+    /// let names = timely_communication::initialize_scoped(config, logger, |_allocator| {
+    ///     ::std::thread::current().name().unwrap().to_owned()
+    /// }).unwrap();
+    ///
+    /// assert_eq!(names[0], Ok("tests: worker thread 0".to_owned()));
+    /// ```
+    Namespaced(String, Box<Configuration>),
+}
+
+impl Configuration {
+    /// Separates the outermost namespace, if any, from the configuration it applies to.
+    fn split_namespace(self) -> (Namespace, Configuration) {
+        match self {
+            Configuration::Namespaced(name, config) => {
+                let (_inner, config) = config.split_namespace();
+                (Namespace::new(name), config)
+            },
+            config => (Namespace::default(), config),
+        }
+    }
 }
 
 #[cfg(feature = "arg_parse")]
@@ -30,32 +89,63 @@ impl Configuration {
     ///
     /// Most commonly, this uses `std::env::Args()` as the supplied iterator.
     pub fn from_args<I: Iterator<Item=String>>(args: I) -> Result<Configuration,String> {
+        Configuration::from_args_with_free(args).map(|(config, _free)| config)
+    }
+
+    /// Constructs a new configuration by parsing supplied text arguments, also returning the
+    /// arguments not understood as configuration.
+    ///
+    /// The first argument is taken to be the program name, and is not returned. Arguments following
+    /// `--` are returned without interpretation, which allows applications to pass options of their own.
+    pub fn from_args_with_free<I: Iterator<Item=String>>(args: I) -> Result<(Configuration, Vec<String>),String> {
 
         let mut opts = getopts::Options::new();
         opts.optopt("w", "threads", "number of per-process worker threads", "NUM");
         opts.optopt("p", "process", "identity of this process", "IDX");
         opts.optopt("n", "processes", "number of processes", "NUM");
-        opts.optopt("h", "hostfile", "text file whose lines are process addresses", "FILE");
+        opts.optopt("h", "hostfile", "text file whose lines are process addresses, each optionally followed by a number of threads", "FILE");
         opts.optflag("r", "report", "reports connection progress");
+        opts.optopt("", "record", "records bytes received from other processes into a directory", "DIR");
+        opts.optopt("", "replay", "replays bytes recorded into a directory, in place of other processes", "DIR");
+        opts.optflag("o", "ordered", "delivers messages between threads in order of their source");
+        opts.optopt("", "namespace", "distinguishes this computation from others on the same hosts", "NAME");
 
         opts.parse(args)
             .map_err(|e| format!("{:?}", e))
-            .map(|matches| {
+            .and_then(|matches| {
 
             // let mut config = Configuration::new(1, 0, Vec::new());
+            let free = matches.free.iter().skip(1).cloned().collect::<Vec<_>>();
             let threads = matches.opt_str("w").map(|x| x.parse().unwrap_or(1)).unwrap_or(1);
             let process = matches.opt_str("p").map(|x| x.parse().unwrap_or(0)).unwrap_or(0);
             let processes = matches.opt_str("n").map(|x| x.parse().unwrap_or(1)).unwrap_or(1);
             let report = matches.opt_present("report");
+            let ordered = matches.opt_present("ordered");
+
+            let namespace = matches.opt_str("namespace");
 
             assert!(process < processes);
 
-            if processes > 1 {
+            let (config, free) =
+            if let Some(directory) = matches.opt_str("replay") {
+                assert!(processes > 1, "replay requires -n greater than one");
+                (Configuration::Replay(threads, process, processes, PathBuf::from(directory)), free)
+            }
+            else if processes > 1 {
                 let mut addresses = Vec::new();
+                let mut counts = Vec::new();
                 if let Some(hosts) = matches.opt_str("h") {
-                    let reader = ::std::io::BufReader::new(::std::fs::File::open(hosts.clone()).unwrap());
-                    for x in reader.lines().take(processes) {
-                        addresses.push(x.unwrap());
+                    let file = ::std::fs::File::open(hosts.clone()).map_err(|e| format!("failed to open {}: {}", hosts, e))?;
+                    let reader = ::std::io::BufReader::new(file);
+                    for (number, x) in reader.lines().take(processes).enumerate() {
+                        // each line holds an address, optionally followed by its number of threads.
+                        let line = x.map_err(|e| format!("failed to read {}: {}", hosts, e))?;
+                        let mut fields = line.split_whitespace();
+                        addresses.push(fields.next().unwrap_or("").to_owned());
+                        counts.push(match fields.next() {
+                            Some(count) => Some(count.parse::<usize>().map_err(|_| format!("{} line {}: malformed thread count {:?}", hosts, number + 1, count))?),
+                            None => None,
+                        });
                     }
                     if addresses.len() < processes {
                         panic!("could only read {} addresses from {}, but -n: {}", addresses.len(), hosts, processes);
@@ -64,21 +154,39 @@ impl Configuration {
                 else {
                     for index in 0..processes {
                         addresses.push(format!("localhost:{}", 2101 + index));
+                        counts.push(None);
                     }
                 }
 
                 assert!(processes == addresses.len());
-                Configuration::Cluster(threads, process, addresses, report)
+                let counts = counts.into_iter().map(|count| count.unwrap_or(threads)).collect::<Vec<_>>();
+                if counts.iter().any(|&count| count != threads) {
+                    assert!(matches.opt_str("record").is_none(), "recording requires the same number of threads in each process");
+                    (Configuration::Heterogeneous(counts, process, addresses, report), free)
+                }
+                else if let Some(directory) = matches.opt_str("record") {
+                    (Configuration::Record(threads, process, addresses, report, PathBuf::from(directory)), free)
+                }
+                else {
+                    (Configuration::Cluster(threads, process, addresses, report), free)
+                }
             }
-            else if threads > 1 { Configuration::Process(threads) }
-            else { Configuration::Thread }
+            else if threads > 1 && ordered { (Configuration::Ordered(threads), free) }
+            else if threads > 1 { (Configuration::Process(threads), free) }
+            else { (Configuration::Thread, free) };
+
+            Ok(match namespace {
+                Some(name) => (Configuration::Namespaced(name, Box::new(config)), free),
+                None => (config, free),
+            })
         })
     }
 }
 
 type LogBuilder = Arc<Fn(::logging::CommsSetup)->::logging::CommsLogger+Send+Sync>;
 
-fn create_allocators(config: Configuration, logger: LogBuilder) -> Result<(Vec<GenericBuilder>, Box<Any>),String> {
+fn create_allocators(config: Configuration, namespace: &Namespace, logger: LogBuilder, failed: Arc<AtomicBool>) -> Result<(Vec<GenericBuilder>, Box<Any>),String> {
+    let namespace = namespace.clone();
     match config {
         Configuration::Thread => {
             Ok((vec![GenericBuilder::Thread(Thread)], Box::new(())))
@@ -88,14 +196,63 @@ fn create_allocators(config: Configuration, logger: LogBuilder) -> Result<(Vec<G
             Ok((Process::new_vector(threads).into_iter().map(|x| GenericBuilder::Process(x)).collect(), Box::new(())))
             // Ok((ProcessBuilder::new_vector(threads).into_iter().map(|x| GenericBuilder::ProcessBinary(x)).collect(), Box::new(())))
         },
+        Configuration::Ordered(threads) => {
+            Ok((Process::new_vector_ordered(threads).into_iter().map(|x| GenericBuilder::Process(x)).collect(), Box::new(())))
+        },
+        #[cfg(feature = "networking")]
         Configuration::Cluster(threads, process, addresses, report) => {
-            if let Ok((stuff, guard)) = initialize_networking(addresses, process, threads, report, logger) {
+            if let Ok((stuff, guard)) = initialize_networking_recorded(addresses, process, threads, report, None, namespace, logger, failed) {
+                Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
+            }
+            else {
+                Err("failed to initialize networking".to_owned())
+            }
+        },
+        #[cfg(feature = "networking")]
+        Configuration::Heterogeneous(threads, process, addresses, report) => {
+            if let Ok((stuff, guard)) = initialize_networking_heterogeneous(addresses, process, threads, report, None, namespace, logger, failed) {
                 Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
             }
             else {
                 Err("failed to initialize networking".to_owned())
             }
         },
+        #[cfg(feature = "networking")]
+        Configuration::Record(threads, process, addresses, report, directory) => {
+            if let Ok((stuff, guard)) = initialize_networking_recorded(addresses, process, threads, report, Some(directory), namespace, logger, failed) {
+                Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
+            }
+            else {
+                Err("failed to initialize networking".to_owned())
+            }
+        },
+        #[cfg(feature = "networking")]
+        Configuration::Replay(threads, process, processes, directory) => {
+            if let Ok((stuff, guard)) = initialize_replay(directory, process, processes, threads, true, namespace, logger, failed) {
+                Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
+            }
+            else {
+                Err("failed to open recordings for replay".to_owned())
+            }
+        },
+        #[cfg(feature = "networking")]
+        Configuration::Simulated(threads, processes, link) => {
+            if let Ok((stuff, guard)) = initialize_simulated(processes, threads, link, namespace, logger, failed) {
+                Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
+            }
+            else {
+                Err("failed to initialize simulated network".to_owned())
+            }
+        },
+        Configuration::Namespaced(_, config) => {
+            // the outermost namespace applies.
+            create_allocators(*config, &namespace, logger, failed)
+        },
+        #[cfg(not(feature = "networking"))]
+        Configuration::Cluster(..) | Configuration::Heterogeneous(..) | Configuration::Record(..) | Configuration::Replay(..) | Configuration::Simulated(..) => {
+            let _ = (logger, failed, namespace);
+            Err("multiple processes require the `networking` feature".to_owned())
+        },
     }
 }
 
@@ -170,8 +327,10 @@ pub fn initialize<T:Send+'static, F: Fn(Generic)->T+Send+Sync+'static>(
     log_sender: LogBuilder,
     func: F,
 ) -> Result<WorkerGuards<T>,String> {
-    let (allocators, others) = try!(create_allocators(config, log_sender));
-    initialize_from(allocators, others, func)
+    let failed = Arc::new(AtomicBool::new(false));
+    let (namespace, config) = config.split_namespace();
+    let (allocators, others) = try!(create_allocators(config, &namespace, log_sender, failed.clone()));
+    spawn_workers(allocators, others, &namespace, failed, func)
 }
 
 /// Initializes computation and runs a distributed computation.
@@ -227,46 +386,225 @@ pub fn initialize_from<A, T, F>(
     others: Box<Any>,
     func: F,
 ) -> Result<WorkerGuards<T>,String>
+where
+    A: AllocateBuilder+'static,
+    T: Send+'static,
+    F: Fn(<A as AllocateBuilder>::Allocator)->T+Send+Sync+'static
+{
+    spawn_workers(builders, others, &Namespace::default(), Arc::new(AtomicBool::new(false)), func)
+}
+
+/// Spawns a worker thread for each builder, named in `namespace` and sharing the failure flag `failed`.
+fn spawn_workers<A, T, F>(
+    builders: Vec<A>,
+    others: Box<Any>,
+    namespace: &Namespace,
+    failed: Arc<AtomicBool>,
+    func: F,
+) -> Result<WorkerGuards<T>,String>
 where
     A: AllocateBuilder+'static,
     T: Send+'static,
     F: Fn(<A as AllocateBuilder>::Allocator)->T+Send+Sync+'static
 {
     let logic = Arc::new(func);
+    let exited = Arc::new((Mutex::new(0), Condvar::new()));
     let mut guards = Vec::new();
     for (index, builder) in builders.into_iter().enumerate() {
         let clone = logic.clone();
+        let failed = failed.clone();
+        let exited = ExitSignal(exited.clone());
         guards.push(try!(thread::Builder::new()
-                            .name(format!("worker thread {}", index))
+                            .name(namespace.thread_name(&format!("worker thread {}", index)))
                             .spawn(move || {
-                                let communicator = builder.build();
-                                (*clone)(communicator)
+                                let _exited = exited;
+                                run_worker(index, failed, move || {
+                                    let communicator = builder.build();
+                                    (*clone)(communicator)
+                                })
                             })
                             .map_err(|e| format!("{:?}", e))));
     }
 
-    Ok(WorkerGuards { guards, others })
+    Ok(WorkerGuards { guards, others, exited })
+}
+
+/// Counts a worker thread as exited when dropped, however the thread exits, and wakes any waiters.
+struct ExitSignal(Arc<(Mutex<usize>, Condvar)>);
+
+impl Drop for ExitSignal {
+    fn drop(&mut self) {
+        let (ref count, ref condvar) = *self.0;
+        if let Ok(mut count) = count.lock() {
+            *count += 1;
+        }
+        condvar.notify_all();
+    }
+}
+
+/// Initializes communication and executes a distributed computation, borrowing from the caller.
+///
+/// Unlike `initialize`, this method waits for all worker threads to complete before returning, and
+/// so the supplied function need not be `'static`: it may borrow from the caller's stack, for example
+/// to share read-only configuration or lookup tables among workers without wrapping them in `Arc`.
+/// The method returns the results of the workers, with an error for any worker that panicked.
+///
+/// #Examples
+/// ```
+/// // data borrowed by each worker.
+/// let greeting = String::from("hello");
+///
+/// let config = timely_communication::Configuration::Process(2);
+/// let logger = ::std::sync::Arc::new(|_| timely_communication::logging::BufferingLogger::new_inactive());
+/// let results = timely_communication::initialize_scoped(config, logger, |allocator| {
+///     use timely_communication::Allocate;
+///     format!("{}, {}", greeting, allocator.index())
+/// }).unwrap();
+///
+/// assert_eq!(results.len(), 2);
+/// ```
+pub fn initialize_scoped<'a, T: Send+'a, F: Fn(Generic)->T+Send+Sync+'a>(
+    config: Configuration,
+    log_sender: LogBuilder,
+    func: F,
+) -> Result<Vec<Result<T,String>>,String> {
+    let failed = Arc::new(AtomicBool::new(false));
+    let (namespace, config) = config.split_namespace();
+    let (builders, others) = try!(create_allocators(config, &namespace, log_sender, failed.clone()));
+    let func = &func;
+    let results = thread::scope(|scope| {
+        let mut guards = Vec::new();
+        for (index, builder) in builders.into_iter().enumerate() {
+            let failed = failed.clone();
+            guards.push(thread::Builder::new()
+                            .name(namespace.thread_name(&format!("worker thread {}", index)))
+                            .spawn_scoped(scope, move || run_worker(index, failed, move || func(builder.build())))
+                            .map_err(|e| format!("{:?}", e)));
+        }
+        guards.into_iter()
+              .map(|guard| guard.and_then(|guard| {
+                  guard.join()
+                       .map_err(|e| format!("{:?}", e))
+                       .and_then(|result| result.map_err(|failure| failure.to_string()))
+              }))
+              .collect::<Vec<_>>()
+    });
+    // networking threads must outlive the workers.
+    drop(others);
+    Ok(results)
 }
 
 /// Maintains `JoinHandle`s for worker threads.
 pub struct WorkerGuards<T:Send+'static> {
-    guards: Vec<::std::thread::JoinHandle<T>>,
+    guards: Vec<::std::thread::JoinHandle<Result<T, WorkerFailure>>>,
     others: Box<Any>,
+    /// The number of worker threads that have exited, signalled as each exits.
+    exited: Arc<(Mutex<usize>, Condvar)>,
 }
 
 impl<T:Send+'static> WorkerGuards<T> {
     /// Waits on the worker threads and returns the results they produce.
-    pub fn join(mut self) -> Vec<Result<T,String>> {
+    ///
+    /// A worker that panicked is reported by its index and panic message.
+    pub fn join(self) -> Vec<Result<T,String>> {
+        self.join_failures()
+            .into_iter()
+            .map(|result| result.map_err(|failure| failure.to_string()))
+            .collect()
+    }
+
+    /// Waits on the worker threads and returns the results they produce, or their failures.
+    ///
+    /// #Examples
+    /// ```
+    /// let config = timely_communication::Configuration::Process(2);
+    /// let logger = ::std::sync::Arc::new(|_| timely_communication::logging::BufferingLogger::new_inactive());
+    /// let guards = timely_communication::initialize(config, logger, |allocator| {
+    ///     use timely_communication::Allocate;
+    ///     if allocator.index() == 1 { panic!("worker one failed"); }
+    ///     allocator.index()
+    /// }).unwrap();
+    ///
+    /// let results = guards.join_failures();
+    /// assert_eq!(results[0], Ok(0));
+    /// assert_eq!(results[1].as_ref().unwrap_err().message, "worker one failed");
+    /// ```
+    pub fn join_failures(mut self) -> Vec<Result<T, WorkerFailure>> {
+        self.join_all()
+    }
+
+    /// Waits at most `timeout` for the worker threads to complete, and returns the results they produce.
+    ///
+    /// If some worker has not completed once `timeout` has elapsed, the guards are returned as an
+    /// error, so that the caller may report diagnostics and wait again.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let config = timely_communication::Configuration::Process(2);
+    /// let logger = ::std::sync::Arc::new(|_| timely_communication::logging::BufferingLogger::new_inactive());
+    /// let mut guards = timely_communication::initialize(config, logger, |allocator| {
+    ///     use timely_communication::Allocate;
+    ///     ::std::thread::sleep(Duration::from_millis(100));
+    ///     allocator.index()
+    /// }).unwrap();
+    ///
+    /// let results = loop {
+    ///     match guards.join_timeout(Duration::from_millis(10)) {
+    ///         Ok(results) => break results,
+    ///         Err(unfinished) => { println!("still waiting"); guards = unfinished; },
+    ///     }
+    /// };
+    /// assert_eq!(results.len(), 2);
+    /// ```
+    pub fn join_timeout(self, timeout: Duration) -> Result<Vec<Result<T,String>>, WorkerGuards<T>> {
+        let deadline = Instant::now() + timeout;
+        {
+            let (ref count, ref condvar) = *self.exited;
+            let mut exited = count.lock().expect("failed to lock exit count");
+            while *exited < self.guards.len() {
+                let now = Instant::now();
+                if now >= deadline {
+                    drop(exited);
+                    return Err(self);
+                }
+                exited = condvar.wait_timeout(exited, deadline - now).expect("failed to lock exit count").0;
+            }
+        }
+        Ok(self.join())
+    }
+
+    /// Returns true iff every worker thread has completed, without blocking.
+    pub fn is_finished(&self) -> bool {
+        self.guards.iter().all(|guard| guard.is_finished())
+    }
+
+    /// Reports for each worker thread whether it has completed, without blocking.
+    pub fn finished(&self) -> Vec<bool> {
+        self.guards.iter().map(|guard| guard.is_finished()).collect()
+    }
+
+    /// Joins each worker thread, leaving `self.guards` empty.
+    fn join_all(&mut self) -> Vec<Result<T, WorkerFailure>> {
         self.guards.drain(..)
-                   .map(|guard| guard.join().map_err(|e| format!("{:?}", e)))
+                   .enumerate()
+                   .map(|(index, guard)| {
+                       guard.join()
+                            .unwrap_or_else(|e| Err(WorkerFailure { index, message: format!("{:?}", e) }))
+                   })
                    .collect()
     }
 }
 
 impl<T:Send+'static> Drop for WorkerGuards<T> {
+    /// Joins the worker threads, and panics if any failed and their results were not taken with
+    /// `join` or `join_failures`.
     fn drop(&mut self) {
-        for guard in self.guards.drain(..) {
-            guard.join().expect("Worker panic");
+        if let Some(failure) = self.join_all().into_iter().filter_map(|result| result.err()).next() {
+            if !thread::panicking() {
+                panic!("{}", failure);
+            }
         }
         // println!("WORKER THREADS JOINED");
     }
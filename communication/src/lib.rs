@@ -87,6 +87,7 @@ extern crate bytes;
 pub mod allocator;
 pub mod networking;
 pub mod initialize;
+pub mod failure;
 pub mod logging;
 pub mod message;
 
@@ -95,9 +96,10 @@ use std::any::Any;
 use abomonation::Abomonation;
 
 pub use allocator::Generic as Allocator;
-pub use allocator::Allocate;
-pub use initialize::{initialize, initialize_from, Configuration, WorkerGuards};
+pub use allocator::{Allocate, WorkerIdentity};
+pub use initialize::{initialize, initialize_from, initialize_scoped, Configuration, WorkerGuards};
 pub use message::Message;
+pub use failure::{peer_failed, panic_message, WorkerFailure};
 
 /// A composite trait for types that may be used with channels.
 pub trait Data : Send+Any+Abomonation+'static { }
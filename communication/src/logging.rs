@@ -3,12 +3,17 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
+#[cfg(not(target_arch = "wasm32"))]
 static mut PRECISE_TIME_NS_DELTA: Option<i64> = None;
 
 /// Returns the value of an high resolution performance counter, in nanoseconds, rebased to be
 /// roughly comparable to an unix timestamp.
 /// Useful for comparing and merging logs from different machines (precision is limited by the
 /// precision of the wall clock base; clock skew effects should be taken into consideration).
+///
+/// On `wasm32` targets, which need not have a clock, timestamps instead count the calls to this
+/// function, and so order events but do not measure time.
+#[cfg(not(target_arch = "wasm32"))]
 #[inline(always)]
 pub fn get_precise_time_ns() -> u64 {
     let delta = unsafe {
@@ -21,6 +26,15 @@ pub fn get_precise_time_ns() -> u64 {
     (::time::precise_time_ns() as i64 - delta) as u64
 }
 
+/// Returns the number of previous calls to this function, in place of a timestamp.
+#[cfg(target_arch = "wasm32")]
+#[inline(always)]
+pub fn get_precise_time_ns() -> u64 {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    CALLS.fetch_add(1, Ordering::SeqCst) as u64
+}
+
 const BUFFERING_LOGGER_CAPACITY: usize = 1024;
 
 /// Either log data or and end marker.
@@ -30,6 +30,13 @@ impl<'a, T: 'a> ::std::ops::Deref for RefOrMut<'a, T> {
 }
 
 impl<'a, T: Clone+'a> RefOrMut<'a, T> {
+    /// Returns true iff `self` is a mutable reference, whose contents can be taken without a copy.
+    pub fn is_mut(&self) -> bool {
+        match self {
+            RefOrMut::Ref(_) => false,
+            RefOrMut::Mut(_) => true,
+        }
+    }
     /// Extracts the contents of `self`, either by cloning or swapping.
     ///
     /// This consumes `self` because its contents are now in an unknown state.
@@ -72,6 +79,20 @@ impl<T> Message<T> {
             TypedOrBinary::Typed(typed) => Some(typed),
         }
     }
+    /// Returns an immutable reference, if typed.
+    pub fn if_ref(&self) -> Option<&T> {
+        match &self.payload {
+            TypedOrBinary::Binary(_) => None,
+            TypedOrBinary::Typed(typed) => Some(typed),
+        }
+    }
+    /// Returns true iff the message holds a typed Rust instance rather than binary data.
+    pub fn is_typed(&self) -> bool {
+        match &self.payload {
+            TypedOrBinary::Binary(_) => false,
+            TypedOrBinary::Typed(_) => true,
+        }
+    }
 }
 
 impl<T: Abomonation> Message<T> {
@@ -99,6 +120,26 @@ impl<T: Abomonation> Message<T> {
         }
     }
 
+    /// Returns the serialized representation, if binary.
+    pub fn if_bytes(&self) -> Option<&[u8]> {
+        match &self.payload {
+            TypedOrBinary::Binary(bytes) => Some(bytes.as_bytes()),
+            TypedOrBinary::Typed(_) => None,
+        }
+    }
+
+    /// Returns an immutable typed reference, decoding binary data in place.
+    ///
+    /// Binary data are read where they lie, without promoting them to an owned typed instance,
+    /// which makes this the cheapest way for a receiver that only reads the data to access them.
+    /// Use `to_typed` or `as_mut` only when the data must be owned or mutated.
+    pub fn as_ref_or_decode(&self) -> &T {
+        match &self.payload {
+            TypedOrBinary::Binary(bytes) => { bytes },
+            TypedOrBinary::Typed(typed) => { typed },
+        }
+    }
+
     /// The number of bytes required to serialize the data.
     pub fn length_in_bytes(&self) -> usize {
         match &self.payload {
@@ -131,6 +172,13 @@ impl<T> ::std::ops::Deref for Message<T> {
 }
 
 impl<T: Abomonation+Clone> Message<T> {
+    /// Produces an owned typed instance of the wrapped element, leaving the message unchanged.
+    ///
+    /// This clones typed data and decodes binary data, and is only needed when the receiver must own
+    /// the data; readers should prefer `as_ref_or_decode`.
+    pub fn to_typed(&self) -> T {
+        self.as_ref_or_decode().clone()
+    }
     /// Produces a typed instance of the wrapped element.
     pub fn into_typed(self) -> T {
         match self.payload {
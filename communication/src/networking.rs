@@ -1,15 +1,29 @@
 //! Networking code for sending and receiving fixed size `Vec<u8>` between machines.
+//!
+//! The framing of messages is always available, as serializing allocators use it within a process.
+//! The establishment of socket connections requires the `networking` feature.
 
-use std::io::{Read, Result};
+#[cfg(feature = "networking")]
+use std::io::{Read, Write, Result};
+#[cfg(feature = "networking")]
 use std::net::{TcpListener, TcpStream};
+#[cfg(feature = "networking")]
 use std::sync::Arc;
 // use std::sync::mpsc::{Sender, Receiver, channel};
+#[cfg(feature = "networking")]
 use std::thread;
+#[cfg(feature = "networking")]
 use std::thread::sleep;
+#[cfg(feature = "networking")]
 use std::time::Duration;
 
 use abomonation::{encode, decode};
 
+/// The channel of a zero-length header announcing the failure of a worker in the sending process.
+///
+/// The header takes the place of the zero-length header ending the stream.
+pub const FAILURE_CHANNEL: usize = ::std::usize::MAX;
+
 /// Framing data for each `Vec<u8>` transmission, indicating a typed channel, the source and
 /// destination workers, and the length in bytes.
 #[derive(Copy, Clone, Abomonation)]
@@ -54,14 +68,60 @@ impl MessageHeader {
     }
 }
 
+/// Distinguishes the connections and threads of computations sharing a process or a network.
+///
+/// Processes of one computation only connect to each other if their namespaces match, which keeps a
+/// computation from adopting connections meant for another, for example one previously or
+/// concurrently using the same addresses. Thread names are prefixed with a non-empty namespace, so
+/// that the threads of concurrent computations in one process can be told apart.
+///
+/// #Examples
+/// ```
+/// use timely_communication::networking::Namespace;
+///
+/// assert_eq!(Namespace::default().identifier(), 0);
+/// assert!(Namespace::new("tests").identifier() != Namespace::new("server").identifier());
+/// assert_eq!(Namespace::new("tests").thread_name("worker thread 0"), "tests: worker thread 0");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespace {
+    name: String,
+}
+
+impl Namespace {
+    /// Creates a namespace with the supplied name.
+    pub fn new<S: Into<String>>(name: S) -> Self { Namespace { name: name.into() } }
+    /// The name of the namespace, empty for the default namespace.
+    pub fn name(&self) -> &str { &self.name }
+    /// An identifier for the namespace exchanged when connecting, zero for the default namespace.
+    pub fn identifier(&self) -> u64 {
+        if self.name.is_empty() { return 0; }
+        // FNV-1a, which is stable across processes and builds.
+        self.name.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    }
+    /// The name for a thread of a computation in this namespace.
+    pub fn thread_name(&self, name: &str) -> String {
+        if self.name.is_empty() { name.to_owned() }
+        else { format!("{}: {}", self.name, name) }
+    }
+}
+
+#[cfg(feature = "networking")]
 /// Creates socket connections from a list of host addresses.
 pub fn create_sockets(addresses: Vec<String>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+    create_sockets_namespaced(addresses, my_index, &Namespace::default(), noisy)
+}
+
+#[cfg(feature = "networking")]
+/// Creates socket connections from a list of host addresses, only to processes in the same namespace.
+pub fn create_sockets_namespaced(addresses: Vec<String>, my_index: usize, namespace: &Namespace, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
 
     let hosts1 = Arc::new(addresses);
     let hosts2 = hosts1.clone();
+    let namespace = namespace.identifier();
 
-    let start_task = thread::spawn(move || start_connections(hosts1, my_index, noisy));
-    let await_task = thread::spawn(move || await_connections(hosts2, my_index, noisy));
+    let start_task = thread::spawn(move || start_connections(hosts1, my_index, namespace, noisy));
+    let await_task = thread::spawn(move || await_connections(hosts2, my_index, namespace, noisy));
 
     let mut results = start_task.join().unwrap()?;
     results.push(None);
@@ -74,20 +134,26 @@ pub fn create_sockets(addresses: Vec<String>, my_index: usize, noisy: bool) -> R
 }
 
 
+#[cfg(feature = "networking")]
 /// Result contains connections [0, my_index - 1].
-pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+///
+/// Each connection announces the namespace identifier and index of this process, and is retried
+/// until it succeeds, unless the remote process replies with another namespace identifier, in which
+/// case this returns an error.
+pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, namespace: u64, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
     let mut results: Vec<_> = (0..my_index).map(|_| None).collect();
     for index in 0..my_index {
         let mut connected = false;
         while !connected {
-            match TcpStream::connect(&addresses[index][..]) {
-                Ok(mut stream) => {
-                    stream.set_nodelay(true).expect("set_nodelay call failed");
-                    unsafe { encode(&(my_index as u64), &mut stream) }.expect("failed to encode/send worker index");
+            match TcpStream::connect(&addresses[index][..]).and_then(|stream| handshake(stream, my_index, namespace)) {
+                Ok(stream) => {
                     results[index as usize] = Some(stream);
                     if noisy { println!("worker {}:\tconnection to worker {}", my_index, index); }
                     connected = true;
                 },
+                Err(ref error) if error.kind() == ::std::io::ErrorKind::InvalidData => {
+                    return Err(::std::io::Error::new(error.kind(), format!("worker {}: connecting to worker {}: {}", my_index, index, error)));
+                },
                 Err(error) => {
                     println!("worker {}:\terror connecting to worker {}: {}; retrying", my_index, index, error);
                     sleep(Duration::from_secs(1));
@@ -99,18 +165,51 @@ pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bo
     Ok(results)
 }
 
+#[cfg(feature = "networking")]
+/// Announces the namespace and index of this process on `stream`, and awaits confirmation of the namespace.
+fn handshake(mut stream: TcpStream, my_index: usize, namespace: u64) -> Result<TcpStream> {
+    stream.set_nodelay(true).expect("set_nodelay call failed");
+    unsafe { encode(&namespace, &mut stream) }?;
+    unsafe { encode(&(my_index as u64), &mut stream) }?;
+    stream.flush()?;
+    let mut buffer = [0u8;8];
+    stream.read_exact(&mut buffer)?;
+    let confirmed = unsafe { decode::<u64>(&mut buffer) }.expect("failed to decode namespace").0.clone();
+    if confirmed != namespace {
+        return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidData, format!("remote process is in namespace {:x}", confirmed)));
+    }
+    Ok(stream)
+}
+
+#[cfg(feature = "networking")]
 /// Result contains connections [my_index + 1, addresses.len() - 1].
-pub fn await_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+///
+/// Connections announcing a namespace identifier other than `namespace` are told this process's
+/// namespace identifier, which the connecting process reports as an error, and are dropped.
+pub fn await_connections(addresses: Arc<Vec<String>>, my_index: usize, namespace: u64, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
     let mut results: Vec<_> = (0..(addresses.len() - my_index - 1)).map(|_| None).collect();
     let listener = try!(TcpListener::bind(&addresses[my_index][..]));
 
-    for _ in (my_index + 1) .. addresses.len() {
+    let mut accepted = 0;
+    while accepted < addresses.len() - my_index - 1 {
         let mut stream = listener.accept()?.0;
         stream.set_nodelay(true).expect("set_nodelay call failed");
-        let mut buffer = [0u8;8];
-        stream.read_exact(&mut buffer).expect("failed to read worker index");
-        let identifier = unsafe { decode::<u64>(&mut buffer) }.expect("failed to decode worker index").0.clone() as usize;
+        let mut buffer = [0u8;16];
+        if stream.read_exact(&mut buffer).is_err() {
+            if noisy { println!("worker {}:\tdropping connection which did not identify itself", my_index); }
+            continue;
+        }
+        let remote = unsafe { decode::<u64>(&mut buffer[..8]) }.expect("failed to decode namespace").0.clone();
+        if remote != namespace {
+            // reply with our namespace, so that the connecting process can report the mismatch.
+            let _ = unsafe { encode(&namespace, &mut stream) }.and_then(|_| stream.flush());
+            if noisy { println!("worker {}:\tdropping connection from namespace {:x}", my_index, remote); }
+            continue;
+        }
+        let identifier = unsafe { decode::<u64>(&mut buffer[8..]) }.expect("failed to decode worker index").0.clone() as usize;
+        unsafe { encode(&namespace, &mut stream) }.expect("failed to confirm namespace");
         results[identifier - my_index - 1] = Some(stream);
+        accepted += 1;
         if noisy { println!("worker {}:\tconnection from worker {}", my_index, identifier); }
     }
 
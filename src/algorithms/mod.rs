@@ -0,0 +1,28 @@
+//! Building blocks for graph computations.
+//!
+//! Graph computations on timely dataflow tend to repeat a few patterns: distributing edges among
+//! workers so that high-degree vertices do not overload one worker, propagating labels along edges
+//! in an iterative scope until no label improves, and detecting when such an iteration has converged.
+//! The modules here provide these patterns for streams of edges `(source, target)`.
+//!
+//! `partition` routes edges by their source, except for the edges of high-degree sources, which are
+//! routed by their target. `propagate` computes, for each vertex, the least label reachable from an
+//! initial labelling, where labels are advanced along each edge they cross; this covers connected
+//! components (the least vertex identifier reachable) and breadth-first search (the least number of
+//! hops from a root). Its `Convergence` reports whether the iteration for an epoch has completed.
+
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+pub mod partition;
+pub mod propagate;
+
+pub use self::partition::PartitionEdges;
+pub use self::propagate::{Propagate, Convergence};
+
+/// Hashes `key` to route it among workers.
+fn hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
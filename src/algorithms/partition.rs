@@ -0,0 +1,72 @@
+//! Degree-aware partitioning of edges among workers.
+//!
+//! Routing edges by their source keeps the edges of each vertex at one worker, which suits computations
+//! that process each vertex's edges together, but leaves the worker holding a high-degree vertex with a
+//! disproportionate share of the edges. Routing the edges of high-degree vertices by their target
+//! instead spreads them among workers, at the cost of each such vertex having edges at many workers.
+
+use std::hash::Hash;
+use std::collections::HashMap;
+
+use ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Exchange as ExchangePact;
+use dataflow::operators::{Exchange, Map};
+use dataflow::operators::generic::Operator;
+
+use super::hash;
+
+/// Methods to partition edges among workers.
+pub trait PartitionEdges<G: Scope, N: ExchangeData+Hash+Eq> {
+    /// Routes each edge to a worker by its source, unless the source has more than `threshold` edges at
+    /// the edge's timestamp, in which case the edge is routed by its target.
+    ///
+    /// Degrees are counted within each timestamp, and so edges are produced once their timestamp is
+    /// complete.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::algorithms::PartitionEdges;
+    ///
+    /// timely::example(|scope| {
+    ///     // vertex 0 has 100 edges, and vertices 1 through 9 have one each.
+    ///     (1..101u64).map(|x| (0, x))
+    ///                .chain((1..10u64).map(|x| (x, x + 1)))
+    ///                .to_stream(scope)
+    ///                .partition_edges(10)
+    ///                .inspect(|edge| println!("edge: {:?}", edge));
+    /// });
+    /// ```
+    fn partition_edges(&self, threshold: usize) -> Stream<G, (N, N)>;
+}
+
+impl<G: Scope, N: ExchangeData+Hash+Eq> PartitionEdges<G, N> for Stream<G, (N, N)> {
+    fn partition_edges(&self, threshold: usize) -> Stream<G, (N, N)> {
+
+        let mut stash: HashMap<G::Timestamp, Vec<(N, N)>> = HashMap::new();
+
+        // count the degree of each source, and mark the edges of high-degree sources.
+        self.unary_notify(ExchangePact::new(|edge: &(N, N)| hash(&edge.0)), "DegreeCount", vec![], move |input, output, notificator| {
+            input.for_each(|time, data| {
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(data.replace(Vec::new()));
+                notificator.notify_at(time.retain());
+            });
+            notificator.for_each(|time, _, _| {
+                if let Some(edges) = stash.remove(time.time()) {
+                    let mut degrees = HashMap::new();
+                    for &(ref source, _) in edges.iter() {
+                        *degrees.entry(source.clone()).or_insert(0) += 1;
+                    }
+                    let mut session = output.session(&time);
+                    for edge in edges {
+                        let high = degrees[&edge.0] > threshold;
+                        session.give((high, edge));
+                    }
+                }
+            });
+        })
+        .exchange(|&(high, ref edge)| if high { hash(&edge.1) } else { hash(&edge.0) })
+        .map(|(_, edge)| edge)
+    }
+}
@@ -0,0 +1,196 @@
+//! Propagation of labels along edges in an iterative scope.
+//!
+//! Each vertex holds the least label it has received. A vertex whose label improves sends its label,
+//! advanced by a step function, along each of its edges in the next iteration, and the iteration for
+//! an epoch ends once no label improves. Each epoch is computed independently, from the edges and
+//! initial labels at that epoch, and its state is discarded once its iteration has converged.
+
+use std::hash::Hash;
+use std::collections::HashMap;
+
+use ExchangeData;
+use order::PartialOrder;
+use progress::Timestamp;
+use progress::nested::product::Product;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::{Exchange, Pipeline};
+use dataflow::operators::{Enter, Leave, Concat, LoopVariable, ConnectLoop, Probe};
+use dataflow::operators::probe::Handle as ProbeHandle;
+use dataflow::operators::generic::Operator;
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+use super::hash;
+
+/// Reports whether the iterations of epochs of a `propagate` computation have completed.
+///
+/// An epoch has converged once the frontier of the iteration holds no times of the epoch or earlier,
+/// meaning that no labels of the epoch remain to be propagated.
+pub struct Convergence<T: Timestamp> {
+    probe: ProbeHandle<Product<T, u64>>,
+}
+
+impl<T: Timestamp> Convergence<T> {
+    /// Returns true if the iterations of all epochs less or equal to `time` have completed.
+    pub fn converged(&self, time: &T) -> bool {
+        self.probe.with_frontier(|frontier| !frontier.iter().any(|x| x.outer.less_equal(time)))
+    }
+
+    /// The least iteration in progress for epochs less or equal to `time`, if they have not converged.
+    pub fn iteration(&self, time: &T) -> Option<u64> {
+        self.probe.with_frontier(|frontier| {
+            frontier.iter().filter(|x| x.outer.less_equal(time)).map(|x| x.inner).min()
+        })
+    }
+}
+
+/// Methods to propagate labels along edges.
+pub trait Propagate<G: Scope, N: ExchangeData+Hash+Eq> {
+    /// Produces, for each vertex whose label improves at an epoch, the least label it receives at the
+    /// epoch, starting from the initial `labels` and advancing labels by `step` along each edge.
+    ///
+    /// Labels must not improve by crossing an edge, meaning that `step(label)` is not less than `label`,
+    /// or the iteration may not end. Labels are produced once the epoch's iteration has converged.
+    ///
+    /// #Examples
+    ///
+    /// Breadth-first search from vertex 0, labelling each reachable vertex with its number of hops:
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::algorithms::Propagate;
+    ///
+    /// timely::example(|scope| {
+    ///     let edges = vec![(0u64, 1u64), (1, 2), (0, 2), (2, 3), (5, 6)].to_stream(scope);
+    ///     let roots = vec![(0u64, 0u64)].to_stream(scope);
+    ///     let (distances, _convergence) = edges.propagate(&roots, |hops| hops + 1);
+    ///     distances.inspect(|&(vertex, hops)| {
+    ///         let expected = match vertex { 0 => 0, 1 => 1, 2 => 1, 3 => 2, _ => panic!("unreachable vertex") };
+    ///         assert_eq!(hops, expected);
+    ///     });
+    /// });
+    /// ```
+    ///
+    /// Connected components, labelling each vertex with the least vertex connected to it, and
+    /// stepping the worker until the iteration converges:
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{Input, Map, Concat, Inspect};
+    /// use timely::algorithms::Propagate;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///     let (mut input, convergence) = worker.dataflow::<u64,_,_>(|scope| {
+    ///         let (input, edges) = scope.new_input::<(u64, u64)>();
+    ///         let both = edges.map(|(x, y)| (y, x)).concat(&edges);
+    ///         let labels = both.map(|(x, _)| (x, x));
+    ///         let (components, convergence) = both.propagate(&labels, |label| *label);
+    ///         components.inspect(|x| println!("component: {:?}", x));
+    ///         (input, convergence)
+    ///     });
+    ///     for edge in vec![(1, 2), (2, 3), (4, 5)] {
+    ///         input.send(edge);
+    ///     }
+    ///     input.advance_to(1);
+    ///     worker.step_while(|| !convergence.converged(&RootTimestamp::new(0)));
+    /// }).unwrap();
+    /// ```
+    fn propagate<L, F>(&self, labels: &Stream<G, (N, L)>, step: F) -> (Stream<G, (N, L)>, Convergence<G::Timestamp>)
+    where
+        L: ExchangeData+Ord,
+        F: Fn(&L)->L+'static;
+}
+
+impl<G: Scope, N: ExchangeData+Hash+Eq> Propagate<G, N> for Stream<G, (N, N)> {
+    fn propagate<L, F>(&self, labels: &Stream<G, (N, L)>, step: F) -> (Stream<G, (N, L)>, Convergence<G::Timestamp>)
+    where
+        L: ExchangeData+Ord,
+        F: Fn(&L)->L+'static {
+
+        let mut probe = ProbeHandle::new();
+
+        let improved = self.scope().scoped::<u64,_,_>(|inner| {
+
+            let edges = self.enter(inner);
+            let (handle, cycle) = inner.loop_variable(u64::max_value(), 1);
+            let candidates = labels.enter(inner).concat(&cycle);
+
+            let mut builder = OperatorBuilder::new("Propagate".to_owned(), inner.clone());
+            let mut edge_input = builder.new_input(&edges, Exchange::new(|edge: &(N, N)| hash(&edge.0)));
+            let mut label_input = builder.new_input(&candidates, Exchange::new(|label: &(N, L)| hash(&label.0)));
+            let (mut next_output, next) = builder.new_output();
+            let (mut improved_output, improved) = builder.new_output();
+
+            builder.build(move |_| {
+
+                // the edges and least labels of each vertex, for each epoch.
+                let mut states: HashMap<G::Timestamp, (HashMap<N, Vec<N>>, HashMap<N, L>)> = HashMap::new();
+
+                move |frontiers| {
+
+                    let mut next_handle = next_output.activate();
+                    let mut improved_handle = improved_output.activate();
+
+                    // send the labels of known vertices along new edges.
+                    edge_input.for_each(|time, data| {
+                        let &mut (ref mut neighbors, ref best) = states.entry(time.time().outer.clone()).or_insert_with(Default::default);
+                        let mut session = next_handle.session(&time);
+                        for (source, target) in data.replace(Vec::new()) {
+                            if let Some(label) = best.get(&source) {
+                                session.give((target.clone(), step(label)));
+                            }
+                            neighbors.entry(source).or_insert_with(Vec::new).push(target);
+                        }
+                    });
+
+                    // record improved labels, and send them along known edges.
+                    label_input.for_each(|time, data| {
+                        let &mut (ref neighbors, ref mut best) = states.entry(time.time().outer.clone()).or_insert_with(Default::default);
+                        let mut next_session = next_handle.session(&time);
+                        let mut improved_session = improved_handle.session(&time);
+                        for (vertex, label) in data.replace(Vec::new()) {
+                            if best.get(&vertex).map(|current| label < *current).unwrap_or(true) {
+                                if let Some(targets) = neighbors.get(&vertex) {
+                                    let next = step(&label);
+                                    for target in targets.iter() {
+                                        next_session.give((target.clone(), next.clone()));
+                                    }
+                                }
+                                improved_session.give((vertex.clone(), label.clone()));
+                                best.insert(vertex, label);
+                            }
+                        }
+                    });
+
+                    // discard the state of epochs whose iterations have converged.
+                    states.retain(|epoch, _| {
+                        frontiers.iter().any(|frontier| frontier.frontier().iter().any(|time| time.outer.less_equal(epoch)))
+                    });
+                }
+            });
+
+            next.probe_with(&mut probe).connect_loop(handle);
+            improved.leave()
+        });
+
+        // produce the least improved label of each vertex, once the epoch is complete.
+        let mut least: HashMap<G::Timestamp, HashMap<N, L>> = HashMap::new();
+        let result = improved.unary_notify(Pipeline, "PropagateLeast", vec![], move |input, output, notificator| {
+            input.for_each(|time, data| {
+                let least = least.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (vertex, label) in data.replace(Vec::new()) {
+                    if least.get(&vertex).map(|current| label < *current).unwrap_or(true) {
+                        least.insert(vertex, label);
+                    }
+                }
+                notificator.notify_at(time.retain());
+            });
+            notificator.for_each(|time, _, _| {
+                if let Some(labels) = least.remove(time.time()) {
+                    output.session(&time).give_iterator(labels.into_iter());
+                }
+            });
+        });
+
+        (result, Convergence { probe })
+    }
+}
@@ -0,0 +1,99 @@
+//! Application-specific configuration made available to each worker.
+//!
+//! A `WorkerConfig` is a read-only store of key-value pairs and positional arguments, shared by all
+//! workers of a process and available through `worker.config()`. It is populated from the command
+//! line arguments not understood by timely itself when using `execute_from_args`, or may be built
+//! programmatically and supplied to `execute_configured`.
+//!
+//! Command line arguments of the form `key=value` or `--key=value` become key-value pairs, arguments
+//! of the form `--key` become keys with empty values, and other arguments are positional. Arguments
+//! beginning with `-` must follow a `--` argument, so that they are not taken as timely's own options.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Application-specific key-value pairs and positional arguments, shared by workers.
+///
+/// #Examples
+/// ```
+/// use timely::config::WorkerConfig;
+///
+/// let args = vec!["input.txt".to_owned(), "--rounds=10".to_owned(), "verbose".to_owned(), "--trace".to_owned()];
+/// let config = WorkerConfig::from_args(args);
+///
+/// assert_eq!(config.get("rounds"), Some("10"));
+/// assert_eq!(config.parse::<usize>("rounds"), Some(Ok(10)));
+/// assert!(config.contains("trace"));
+/// assert_eq!(config.positional(), &["input.txt".to_owned(), "verbose".to_owned()]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WorkerConfig {
+    values: Arc<HashMap<String, String>>,
+    positional: Arc<Vec<String>>,
+}
+
+impl WorkerConfig {
+
+    /// Allocates a new empty configuration.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allocates a configuration from arguments not understood by timely.
+    pub fn from_args<I: IntoIterator<Item=String>>(args: I) -> Self {
+        let mut config = WorkerConfig::new();
+        for arg in args {
+            if let Some(index) = arg.find('=') {
+                let key = arg[..index].trim_start_matches('-');
+                config = config.set(key, &arg[index+1 ..]);
+            }
+            else if arg.starts_with("--") {
+                config = config.set(&arg[2..], "");
+            }
+            else {
+                Arc::make_mut(&mut config.positional).push(arg);
+            }
+        }
+        config
+    }
+
+    /// Sets the value of `key` to `value`, replacing any existing value.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::config::WorkerConfig;
+    ///
+    /// let config = WorkerConfig::new().set("rounds", "10")
+    ///                                 .set("batch", "1000");
+    ///
+    /// timely::execute_configured(timely::Configuration::Thread, config, |worker| {
+    ///     let rounds = worker.config().parse::<usize>("rounds").unwrap().unwrap();
+    ///     assert_eq!(rounds, 10);
+    /// }).unwrap();
+    /// ```
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        Arc::make_mut(&mut self.values).insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// The value associated with `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|value| &value[..])
+    }
+
+    /// The value associated with `key` parsed as a `T`, if any.
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<Result<T, T::Err>> {
+        self.get(key).map(|value| value.parse())
+    }
+
+    /// Returns true iff a value is associated with `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Positional arguments, in the order supplied.
+    pub fn positional(&self) -> &[String] {
+        &self.positional[..]
+    }
+}
@@ -0,0 +1,117 @@
+//! Hash functions for routing records in `Exchange` pacts.
+//!
+//! An `Exchange` pact routes each record to the worker indicated by the `u64` its function returns,
+//! modulo the number of workers. Functions returning raw keys route well only when the keys are
+//! uniformly distributed in their low bits; the helpers here instead hash keys, or remix the values
+//! of existing routing functions, optionally with a seed so that different channels or different
+//! runs route the same keys differently.
+//!
+//! #Examples
+//! ```
+//! use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+//! use timely::dataflow::channels::hashing::{hash_by, seeded, FnvBuildHasher};
+//!
+//! timely::example(|scope| {
+//!     (0..10u64).to_stream(scope)
+//!               // route by an FNV-1a hash of the record.
+//!               .exchange(hash_by(|x: &u64| *x, FnvBuildHasher::with_seed(7)))
+//!               // route by a seeded remix of the record.
+//!               .exchange(seeded(|x: &u64| *x, 13))
+//!               .inspect(|x| println!("seen: {:?}", x));
+//! });
+//! ```
+
+use std::hash::{Hash, Hasher, BuildHasher};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+const XX_PRIME_1: u64 = 0x9E3779B185EBCA87;
+const XX_PRIME_2: u64 = 0xC2B2AE3D27D4EB4F;
+const XX_PRIME_3: u64 = 0x165667B19E3779F9;
+
+/// A 64-bit FNV-1a hasher, whose initial state may be perturbed by a seed.
+///
+/// FNV-1a is fast for the short keys typical of routing, but is not resistant to adversarial keys.
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher {
+    state: u64,
+}
+
+impl FnvHasher {
+    /// Creates a hasher with the standard FNV-1a offset basis.
+    pub fn new() -> Self { FnvHasher::with_seed(0) }
+    /// Creates a hasher whose offset basis is perturbed by `seed`.
+    pub fn with_seed(seed: u64) -> Self { FnvHasher { state: FNV_OFFSET ^ seed } }
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self { FnvHasher::new() }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+    fn finish(&self) -> u64 { self.state }
+}
+
+/// Builds `FnvHasher`s with a common seed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FnvBuildHasher {
+    seed: u64,
+}
+
+impl FnvBuildHasher {
+    /// Creates a builder of hashers seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self { FnvBuildHasher { seed } }
+}
+
+impl BuildHasher for FnvBuildHasher {
+    type Hasher = FnvHasher;
+    fn build_hasher(&self) -> FnvHasher { FnvHasher::with_seed(self.seed) }
+}
+
+/// Mixes a `u64` with a seed, using the avalanche step of xxHash64.
+///
+/// Each bit of the result depends on every bit of `value` and `seed`, and so values that differ only
+/// in their high bits, or that share a stride with the number of workers, are spread across workers.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::channels::hashing::mix;
+///
+/// assert_eq!(mix(5, 0), mix(5, 0));
+/// assert!(mix(5, 0) != mix(5, 1));
+/// assert!(mix(0, 0) != mix(1 << 32, 0));
+/// ```
+pub fn mix(value: u64, seed: u64) -> u64 {
+    let mut hash = seed.wrapping_add(XX_PRIME_3) ^ value.wrapping_mul(XX_PRIME_2).rotate_left(31).wrapping_mul(XX_PRIME_1);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XX_PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XX_PRIME_3);
+    hash ^= hash >> 32;
+    hash
+}
+
+/// Produces a routing function hashing the key `key` extracts from each record with hashers from `build`.
+///
+/// The result may be passed to `Exchange::new`, or to the `exchange` operator.
+pub fn hash_by<D, K: Hash, B: BuildHasher>(key: impl Fn(&D)->K, build: B) -> impl Fn(&D)->u64 {
+    move |datum| {
+        let mut hasher = build.build_hasher();
+        key(datum).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Produces a routing function remixing the values of `func` with `seed`, as by `mix`.
+///
+/// The result may be passed to `Exchange::new`, or to the `exchange` operator.
+pub fn seeded<D>(func: impl Fn(&D)->u64, seed: u64) -> impl Fn(&D)->u64 {
+    move |datum| mix(func(datum), seed)
+}
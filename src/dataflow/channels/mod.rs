@@ -8,6 +8,10 @@ pub mod pushers;
 pub mod pullers;
 /// Parallelization contracts, describing how data must be exchanged between operators.
 pub mod pact;
+/// Hash functions for routing records in exchange pacts.
+pub mod hashing;
+/// A registry of the channels a worker has connected.
+pub mod registry;
 
 /// The input to and output from timely dataflow communication channels.
 pub type Bundle<T, D> = ::communication::Message<Message<T, D>>;
@@ -7,6 +7,8 @@
 //! The only requirement of a pact is that it not alter the number of `D` records at each time `T`.
 //! The progress tracking logic assumes that this number is independent of the pact used.
 
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::marker::PhantomData;
 
 use communication::{Allocate, Push, Pull, Data};
@@ -15,12 +17,20 @@ use communication::allocator::thread::Pusher as ThreadPusher;
 use communication::allocator::thread::Puller as ThreadPuller;
 
 use dataflow::channels::pushers::Exchange as ExchangePusher;
+use dataflow::channels::pushers::BatchExchange as BatchExchangePusher;
+use dataflow::channels::pushers::exchange::SkewMonitor;
 use super::{Bundle, Message};
 
+use order::PartialOrder;
+use progress::ChangeBatch;
+
 use logging::Logger;
 
 use abomonation::Abomonation;
 
+/// A function called with each change to the frontier of an input.
+pub type FrontierObserver<T> = Box<FnMut(&mut ChangeBatch<T>)>;
+
 /// A `ParallelizationContract` allocates paired `Push` and `Pull` implementors.
 pub trait ParallelizationContract<T: 'static, D: 'static> {
     /// Type implementing `Push` produced by this pact.
@@ -29,6 +39,17 @@ pub trait ParallelizationContract<T: 'static, D: 'static> {
     type Puller: Pull<Bundle<T, D>>+'static;
     /// Allocates a matched pair of push and pull endpoints implementing the pact.
     fn connect<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller);
+    /// Allocates push and pull endpoints as `connect` does, also returning a function to be called with
+    /// each change to the frontier of the connected input, if the pact needs to observe it.
+    ///
+    /// An operator that connects an input with an observer receives frontier changes for that input even
+    /// if it would not otherwise.
+    fn connect_observed<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller, Option<FrontierObserver<T>>) where Self: Sized {
+        let (pusher, puller) = self.connect(allocator, identifier, logging);
+        (pusher, puller, None)
+    }
+    /// A name for the pact, recorded in the worker's `ChannelRegistry` and in logged `ChannelsEvent`s.
+    fn name(&self) -> &str { "Unnamed" }
 }
 
 /// A direct connection
@@ -36,6 +57,7 @@ pub struct Pipeline;
 impl<T: 'static, D: 'static> ParallelizationContract<T, D> for Pipeline {
     type Pusher = LogPusher<T, D, ThreadPusher<Bundle<T, D>>>;
     type Puller = LogPuller<T, D, ThreadPuller<Bundle<T, D>>>;
+    fn name(&self) -> &str { "Pipeline" }
     fn connect<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller) {
         // ignore `&mut A` and use thread allocator
         let (pusher, puller) = Thread::new::<Bundle<T, D>>();
@@ -45,27 +67,229 @@ impl<T: 'static, D: 'static> ParallelizationContract<T, D> for Pipeline {
 }
 
 /// An exchange between multiple observers by data
-pub struct Exchange<D, F: Fn(&D)->u64+'static> { hash_func: F, phantom: PhantomData<D>, }
+pub struct Exchange<D, F: Fn(&D)->u64+'static> { hash_func: F, skew_threshold: Option<f64>, phantom: PhantomData<D>, }
 impl<D, F: Fn(&D)->u64> Exchange<D, F> {
     /// Allocates a new `Exchange` pact from a distribution function.
+    ///
+    /// Records are routed to the worker indexed by the function's value modulo the number of workers.
+    /// The `hashing` module provides functions hashing keys, optionally with a seed, for use here.
     pub fn new(func: F) -> Exchange<D, F> {
         Exchange {
             hash_func:  func,
+            skew_threshold: None,
             phantom:    PhantomData,
         }
     }
+    /// Counts the records routed to each worker at each timestamp, and once the timestamp is complete
+    /// logs a `SkewEvent` if the ratio of the busiest worker's records to the mean exceeds `threshold`.
+    ///
+    /// Counting costs a little for each record, and so is off unless requested. The module
+    /// `pushers::exchange` defines `DEFAULT_SKEW_THRESHOLD` as a reasonable threshold.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::channels::pact::Exchange;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::generic::Operator;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10u64).to_stream(scope)
+    ///               .unary(Exchange::new(|x: &u64| *x).with_skew_threshold(4.0), "Exchanged", |_,_| |input, output| {
+    ///                   input.for_each(|time, data| output.session(&time).forward(data));
+    ///               })
+    ///               .inspect(|x: &u64| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    pub fn with_skew_threshold(mut self, threshold: f64) -> Self {
+        self.skew_threshold = Some(threshold);
+        self
+    }
 }
 
 // Exchange uses a `Box<Pushable>` because it cannot know what type of pushable will return from the allocator.
-impl<T: Eq+Data+Abomonation+Clone, D: Data+Abomonation+Clone, F: Fn(&D)->u64+'static> ParallelizationContract<T, D> for Exchange<D, F> {
+impl<T: PartialOrder+Ord+Data+Abomonation+Clone, D: Data+Abomonation+Clone, F: Fn(&D)->u64+'static> ParallelizationContract<T, D> for Exchange<D, F> {
     // TODO: The closure in the type prevents us from naming it.
     //       Could specialize `ExchangePusher` to a time-free version.
     type Pusher = Box<Push<Bundle<T, D>>>;
     type Puller = Box<Pull<Bundle<T, D>>>;
+    fn name(&self) -> &str { "Exchange" }
     fn connect<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller) {
         let (senders, receiver, channel_id) = allocator.allocate::<Message<T, D>>();
         let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, channel_id, logging.clone())).collect::<Vec<_>>();
-        (Box::new(ExchangePusher::new(senders, move |_, d| (self.hash_func)(d))), Box::new(LogPuller::new(receiver, allocator.index(), identifier, channel_id, logging.clone())))
+        let hash_func = self.hash_func;
+        (Box::new(ExchangePusher::new(senders, move |_, d| (hash_func)(d))), Box::new(LogPuller::new(receiver, allocator.index(), identifier, channel_id, logging.clone())))
+    }
+    fn connect_observed<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller, Option<FrontierObserver<T>>) {
+        let threshold = match self.skew_threshold {
+            Some(threshold) if allocator.peers() > 1 => threshold,
+            _ => { let (pusher, puller) = self.connect(allocator, identifier, logging); return (pusher, puller, None); }
+        };
+        let (senders, receiver, channel_id) = allocator.allocate::<Message<T, D>>();
+        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, channel_id, logging.clone())).collect::<Vec<_>>();
+        let monitor = Rc::new(RefCell::new(SkewMonitor::new(identifier, senders.len(), threshold, logging.clone())));
+        let hash_func = self.hash_func;
+        let pusher = ExchangePusher::new(senders, move |_, d| (hash_func)(d)).with_skew_monitor(monitor.clone());
+        let observer = Box::new(move |changes: &mut ChangeBatch<T>| monitor.borrow_mut().observe(changes));
+        (Box::new(pusher), Box::new(LogPuller::new(receiver, allocator.index(), identifier, channel_id, logging.clone())), Some(observer))
+    }
+}
+
+/// An exchange between multiple observers by batch, sending each batch whole to one observer.
+///
+/// Batches are routed by a function of their time and records, evaluated once per batch, which suits
+/// streams whose batches are already partitioned, for example by the source that produced them.
+pub struct ExchangeBatched<D, F: Fn(&[D])->u64+'static> { hash_func: F, phantom: PhantomData<D>, }
+impl<D, F: Fn(&[D])->u64> ExchangeBatched<D, F> {
+    /// Allocates a new `ExchangeBatched` pact from a distribution function.
+    ///
+    /// Batches are routed to the worker indexed by the function's value modulo the number of workers.
+    pub fn new(func: F) -> ExchangeBatched<D, F> {
+        ExchangeBatched {
+            hash_func:  func,
+            phantom:    PhantomData,
+        }
+    }
+}
+
+impl<T: Eq+Data+Abomonation+Clone, D: Data+Abomonation+Clone, F: Fn(&[D])->u64+'static> ParallelizationContract<T, D> for ExchangeBatched<D, F> {
+    type Pusher = Box<Push<Bundle<T, D>>>;
+    type Puller = Box<Pull<Bundle<T, D>>>;
+    fn name(&self) -> &str { "ExchangeBatched" }
+    fn connect<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller) {
+        let (senders, receiver, channel_id) = allocator.allocate::<Message<T, D>>();
+        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, channel_id, logging.clone())).collect::<Vec<_>>();
+        let pusher = BatchExchangePusher::new(senders, move |_, d: &[D]| (self.hash_func)(d));
+        (Box::new(pusher), Box::new(LogPuller::new(receiver, allocator.index(), identifier, channel_id, logging.clone())))
+    }
+}
+
+/// Custom serialization of batches of records, for use by `ExchangeWith`.
+///
+/// A codec replaces the default `Abomonation`-based serialization for a single channel, for example to
+/// dictionary encode strings on a hot channel, or to use a format readable by non-Rust peers.
+pub trait Codec<D>: 'static {
+    /// Appends an encoding of `data` to `bytes`.
+    fn encode(&self, data: &[D], bytes: &mut Vec<u8>);
+    /// Appends the records encoded in `bytes` to `data`.
+    fn decode(&self, bytes: &[u8], data: &mut Vec<D>);
+}
+
+/// An exchange between multiple observers by data, serializing batches with a custom `Codec`.
+///
+/// Batches are encoded before they are sent, including to workers in the same process, and decoded
+/// by the receiving worker; the channel itself carries only bytes.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::operators::generic::Operator;
+/// use timely::dataflow::channels::pact::{Codec, ExchangeWith};
+///
+/// // encodes each record as eight little-endian bytes.
+/// struct LittleEndian;
+/// impl Codec<u64> for LittleEndian {
+///     fn encode(&self, data: &[u64], bytes: &mut Vec<u8>) {
+///         for &x in data.iter() {
+///             for i in 0 .. 8 { bytes.push((x >> (8 * i)) as u8); }
+///         }
+///     }
+///     fn decode(&self, bytes: &[u8], data: &mut Vec<u64>) {
+///         for chunk in bytes.chunks(8) {
+///             data.push(chunk.iter().enumerate().fold(0, |x, (i, &b)| x | ((b as u64) << (8 * i))));
+///         }
+///     }
+/// }
+///
+/// timely::example(|scope| {
+///     (0..10u64).to_stream(scope)
+///               .unary(ExchangeWith::new(|x: &u64| *x, LittleEndian), "Exchanged", |_,_| |input, output| {
+///                   input.for_each(|time, data| output.session(&time).forward(data));
+///               })
+///               .inspect(|x: &u64| println!("seen: {:?}", x));
+/// });
+/// ```
+pub struct ExchangeWith<D, F: Fn(&D)->u64+'static, C: Codec<D>> { hash_func: F, codec: C, phantom: PhantomData<D>, }
+impl<D, F: Fn(&D)->u64, C: Codec<D>> ExchangeWith<D, F, C> {
+    /// Allocates a new `ExchangeWith` pact from a distribution function and a codec.
+    pub fn new(func: F, codec: C) -> ExchangeWith<D, F, C> {
+        ExchangeWith {
+            hash_func:  func,
+            codec,
+            phantom:    PhantomData,
+        }
+    }
+}
+
+impl<T: PartialOrder+Ord+Data+Abomonation+Clone, D: ::Data, F: Fn(&D)->u64+'static, C: Codec<D>> ParallelizationContract<T, D> for ExchangeWith<D, F, C> {
+    type Pusher = Box<Push<Bundle<T, D>>>;
+    type Puller = Box<Pull<Bundle<T, D>>>;
+    fn name(&self) -> &str { "ExchangeWith" }
+    fn connect<A: Allocate>(self, allocator: &mut A, identifier: usize, logging: Logger) -> (Self::Pusher, Self::Puller) {
+        let codec = Rc::new(self.codec);
+        let (senders, receiver, channel_id) = allocator.allocate::<Message<T, u8>>();
+        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(EncodePusher::new(x, codec.clone()), allocator.index(), i, identifier, channel_id, logging.clone())).collect::<Vec<_>>();
+        let receiver = DecodePuller::new(receiver, codec);
+        let hash_func = self.hash_func;
+        (Box::new(ExchangePusher::new(senders, move |_, d| (hash_func)(d))), Box::new(LogPuller::new(receiver, allocator.index(), identifier, channel_id, logging.clone())))
+    }
+}
+
+/// Encodes the data of messages with a `Codec`, and pushes them as bytes.
+pub struct EncodePusher<T, D, C: Codec<D>, P: Push<Bundle<T, u8>>> {
+    pusher: P,
+    codec: Rc<C>,
+    phantom: PhantomData<(T, D)>,
+}
+impl<T, D, C: Codec<D>, P: Push<Bundle<T, u8>>> EncodePusher<T, D, C, P> {
+    /// Allocates a new pusher.
+    pub fn new(pusher: P, codec: Rc<C>) -> Self {
+        EncodePusher {
+            pusher,
+            codec,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone, D, C: Codec<D>, P: Push<Bundle<T, u8>>> Push<Bundle<T, D>> for EncodePusher<T, D, C, P> {
+    fn push(&mut self, element: &mut Option<Bundle<T, D>>) {
+        if let Some(bundle) = element.take() {
+            let mut bytes = Vec::new();
+            self.codec.encode(&bundle.data[..], &mut bytes);
+            let encoded = Message::new(bundle.time.clone(), bytes, bundle.from, bundle.seq);
+            self.pusher.push(&mut Some(Bundle::from_typed(encoded)));
+        }
+        else {
+            self.pusher.push(&mut None);
+        }
+    }
+}
+
+/// Pulls messages of bytes, and decodes their data with a `Codec`.
+pub struct DecodePuller<T, D, C: Codec<D>, P: Pull<Bundle<T, u8>>> {
+    puller: P,
+    codec: Rc<C>,
+    current: Option<Bundle<T, D>>,
+}
+impl<T, D, C: Codec<D>, P: Pull<Bundle<T, u8>>> DecodePuller<T, D, C, P> {
+    /// Allocates a new puller.
+    pub fn new(puller: P, codec: Rc<C>) -> Self {
+        DecodePuller {
+            puller,
+            codec,
+            current: None,
+        }
+    }
+}
+
+impl<T: Clone, D, C: Codec<D>, P: Pull<Bundle<T, u8>>> Pull<Bundle<T, D>> for DecodePuller<T, D, C, P> {
+    fn pull(&mut self) -> &mut Option<Bundle<T, D>> {
+        self.current = self.puller.recv().map(|encoded| {
+            let mut data = Vec::new();
+            self.codec.decode(&encoded.data[..], &mut data);
+            Bundle::from_typed(Message::new(encoded.time.clone(), data, encoded.from, encoded.seq))
+        });
+        &mut self.current
     }
 }
 
@@ -5,6 +5,7 @@ use dataflow::channels::{Bundle, Message};
 use progress::Timestamp;
 use dataflow::operators::Capability;
 use communication::Push;
+use communication::message::RefOrMut;
 
 /// Buffers data sent at the same time, for efficient communication.
 ///
@@ -14,6 +15,9 @@ pub struct Buffer<T, D, P: Push<Bundle<T, D>>> {
     time: Option<T>,  // the currently open time, if it is open
     buffer: Vec<D>,   // a buffer for records, to send at self.time
     pusher: P,
+    limit: usize,                   // the number of records at which the buffer is flushed
+    bounds: Option<(usize, usize)>, // bounds on `limit`, if it adapts to the records given
+    given: usize,                   // records given since the last `cease`
 }
 
 impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
@@ -24,9 +28,40 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
             time: None,
             buffer: Vec::with_capacity(Message::<T, D>::default_length()),
             pusher,
+            limit: Message::<T, D>::default_length(),
+            bounds: None,
+            given: 0,
         }
     }
 
+    /// Adapts the number of records at which the buffer is flushed, between `min` and `max`.
+    ///
+    /// The limit starts at `min`, so that sparse outputs are sent promptly, and doubles each time
+    /// the buffer fills, so that activations producing many records send them in large batches.
+    /// When an activation produces fewer than half the limit, the limit halves. Setting the same
+    /// bounds again has no effect, and so this may be called on each activation.
+    pub fn adaptive(&mut self, min: usize, max: usize) {
+        assert!(0 < min && min <= max, "adaptive bounds must satisfy 0 < min <= max");
+        if self.bounds != Some((min, max)) {
+            self.bounds = Some((min, max));
+            self.limit = min;
+        }
+    }
+
+    /// Informs an adaptive buffer whether its downstream consumers lag behind.
+    ///
+    /// A lagging consumer, for example one a probe shows to be behind the input, benefits more from
+    /// throughput than from latency, and so the limit grows towards its maximum; otherwise it shrinks
+    /// towards its minimum. This has no effect on buffers without adaptive bounds.
+    pub fn observe_lag(&mut self, lagging: bool) {
+        if let Some((min, max)) = self.bounds {
+            self.limit = if lagging { ::std::cmp::min(2 * self.limit, max) } else { ::std::cmp::max(self.limit / 2, min) };
+        }
+    }
+
+    /// The number of records at which the buffer is currently flushed.
+    pub fn limit(&self) -> usize { self.limit }
+
     /// Returns a `Session`, which accepts data to send at the associated time
     pub fn session(&mut self, time: &T) -> Session<T, D, P> {
         if let Some(true) = self.time.as_ref().map(|x| x != time) { self.flush(); }
@@ -52,6 +87,12 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
     pub fn cease(&mut self) {
         self.flush();
         self.pusher.push(&mut None);
+        if let Some((min, _max)) = self.bounds {
+            if 2 * self.given < self.limit {
+                self.limit = ::std::cmp::max(self.limit / 2, min);
+            }
+        }
+        self.given = 0;
     }
 
     /// moves the contents of
@@ -65,9 +106,12 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
     // internal method for use by `Session`.
     fn give(&mut self, data: D) {
         self.buffer.push(data);
-        // assert!(self.buffer.capacity() == Message::<O::Data>::default_length());
-        if self.buffer.len() == self.buffer.capacity() {
+        self.given += 1;
+        if self.buffer.len() >= self.limit {
             self.flush();
+            if let Some((_min, max)) = self.bounds {
+                self.limit = ::std::cmp::min(2 * self.limit, max);
+            }
         }
     }
 
@@ -78,6 +122,7 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
             self.flush();
         }
 
+        self.given += vector.len();
         let time = self.time.as_ref().expect("Buffer::give_vec(): time is None.").clone();
         Message::push_at(vector, time, &mut self.pusher);
     }
@@ -117,6 +162,36 @@ impl<'a, T, D, P: Push<Bundle<T, D>>+'a> Session<'a, T, D, P>  where T: Eq+Clone
             self.buffer.give_vec(message);
         }
     }
+    /// Forwards a batch of records received from an input, at the time specified by the `Session`.
+    ///
+    /// A batch of typed records is handed on whole without copying the records, leaving `batch` with a
+    /// spare allocation. Batches received along a `Pipeline` channel are always typed. A batch borrowed
+    /// from serialized data, as may be received along other channels from another process, is cloned
+    /// record by record, exactly as `give_iterator` would, and so forwarding saves nothing there.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::generic::Operator;
+    /// use timely::dataflow::channels::pact::Pipeline;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .unary(Pipeline, "Forward", |_,_| |input, output| {
+    ///                input.for_each(|time, data| {
+    ///                    output.session(&time).forward(data);
+    ///                });
+    ///            })
+    ///            .inspect(|x: &u64| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    #[inline]
+    pub fn forward(&mut self, batch: RefOrMut<Vec<D>>) where D: Clone {
+        match batch {
+            RefOrMut::Mut(vector) => self.give_vec(vector),
+            RefOrMut::Ref(vector) => self.give_iterator(vector.iter().cloned()),
+        }
+    }
 }
 
 /// A session which will flush itself when dropped.
@@ -1,19 +1,104 @@
 //! The exchange pattern distributes pushed data between many target pushees.
 
+use std::rc::Rc;
+use std::cell::RefCell;
+
 use Data;
 use communication::Push;
+use order::PartialOrder;
+use progress::ChangeBatch;
+use progress::frontier::MutableAntichain;
 use dataflow::channels::{Bundle, Message};
+use logging::{Logger, SkewEvent};
+
+/// The default ratio of the busiest destination's records to the mean above which routing is skewed.
+pub const DEFAULT_SKEW_THRESHOLD: f64 = 2.0;
+
+/// Counts the records an exchange sends to each destination at each timestamp, and logs a `SkewEvent`
+/// once a timestamp is complete if its busiest destination received more than `threshold` times the mean.
+///
+/// A timestamp is complete once the frontier reported to `observe` no longer includes it. Timestamps
+/// with fewer records than `minimum` are not reported, as their distributions are noisy.
+pub struct SkewMonitor<T: PartialOrder+Ord> {
+    channel: usize,
+    peers: usize,
+    threshold: f64,
+    minimum: u64,
+    counts: Vec<(T, Vec<u64>)>,
+    frontier: MutableAntichain<T>,
+    logging: Logger,
+}
+
+impl<T: PartialOrder+Ord+Clone> SkewMonitor<T> {
+    /// Allocates a monitor for channel `channel` with `peers` destinations.
+    pub fn new(channel: usize, peers: usize, threshold: f64, logging: Logger) -> Self {
+        SkewMonitor {
+            channel,
+            peers,
+            threshold,
+            minimum: 16 * peers as u64,
+            counts: Vec::new(),
+            frontier: MutableAntichain::new(),
+            logging,
+        }
+    }
+    /// Sets the fewest records a timestamp must have to be reported.
+    pub fn minimum(mut self, minimum: u64) -> Self {
+        self.minimum = minimum;
+        self
+    }
+    /// The records counted for each destination at `time`, if any.
+    pub fn counts(&self, time: &T) -> Option<&[u64]> {
+        self.counts.iter().find(|x| &x.0 == time).map(|x| &x.1[..])
+    }
+    /// Adds `counts`, the records sent to each destination, to those counted at `time`.
+    pub fn count(&mut self, time: &T, counts: &[u64]) {
+        let position = match self.counts.iter().position(|x| &x.0 == time) {
+            Some(position) => position,
+            None => {
+                self.counts.push((time.clone(), vec![0; self.peers]));
+                self.counts.len() - 1
+            }
+        };
+        for (total, count) in self.counts[position].1.iter_mut().zip(counts.iter()) {
+            *total += *count;
+        }
+    }
+    /// Applies `changes` to the frontier of the channel's timestamps, and reports the timestamps it
+    /// has passed.
+    pub fn observe(&mut self, changes: &mut ChangeBatch<T>) {
+        self.frontier.update_iter(changes.iter().cloned());
+        let frontier = &self.frontier;
+        let (channel, threshold, minimum, logging) = (self.channel, self.threshold, self.minimum, &self.logging);
+        self.counts.retain(|&(ref time, ref counts)| {
+            if frontier.less_equal(time) { return true; }
+            let records = counts.iter().sum::<u64>();
+            if records > 0 && records >= minimum {
+                let max = counts.iter().cloned().max().unwrap_or(0);
+                let ratio = max as f64 * counts.len() as f64 / records as f64;
+                if ratio > threshold {
+                    let mut top = counts.iter().cloned().enumerate().filter(|x| x.1 > 0).collect::<Vec<_>>();
+                    top.sort_by(|x, y| y.1.cmp(&x.1));
+                    top.truncate(3);
+                    logging.when_enabled(|l| l.log(SkewEvent { channel, records, ratio, top }.into()));
+                }
+            }
+            false
+        });
+    }
+}
 
 // TODO : Software write combining
 /// Distributes records among target pushees according to a distribution function.
-pub struct Exchange<T, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D) -> u64> {
+pub struct Exchange<T: PartialOrder+Ord, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D) -> u64> {
     pushers: Vec<P>,
     buffers: Vec<Vec<D>>,
     current: Option<T>,
     hash_func: H,
+    skew: Option<(Rc<RefCell<SkewMonitor<T>>>, Vec<u64>)>,
 }
 
-impl<T: Clone, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64>  Exchange<T, D, P, H> {
+impl<T: PartialOrder+Ord+Clone, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64>  Exchange<T, D, P, H> {
     /// Allocates a new `Exchange` from a supplied set of pushers and a distribution function.
     pub fn new(pushers: Vec<P>, key: H) -> Exchange<T, D, P, H> {
         let mut buffers = vec![];
@@ -25,8 +110,15 @@ impl<T: Clone, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64>  Exchange<T, D, P,
             hash_func: key,
             buffers,
             current: None,
+            skew: None,
         }
     }
+    /// Counts the records sent to each destination with `monitor`, which reports skewed timestamps.
+    pub fn with_skew_monitor(mut self, monitor: Rc<RefCell<SkewMonitor<T>>>) -> Self {
+        let peers = self.pushers.len();
+        self.skew = Some((monitor, vec![0; peers]));
+        self
+    }
     #[inline]
     fn flush(&mut self, index: usize) {
         if !self.buffers[index].is_empty() {
@@ -37,7 +129,7 @@ impl<T: Clone, D, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64>  Exchange<T, D, P,
     }
 }
 
-impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle<T, D>> for Exchange<T, D, P, H> {
+impl<T: PartialOrder+Ord+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle<T, D>> for Exchange<T, D, P, H> {
     #[inline(never)]
     fn push(&mut self, message: &mut Option<Bundle<T, D>>) {
         // if only one pusher, no exchange
@@ -63,6 +155,7 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle
                 let mask = (self.pushers.len() - 1) as u64;
                 for datum in data.drain(..) {
                     let index = (((self.hash_func)(time, &datum)) & mask) as usize;
+                    if let Some((_, ref mut counts)) = self.skew { counts[index] += 1; }
 
                     self.buffers[index].push(datum);
                     if self.buffers[index].len() == self.buffers[index].capacity() {
@@ -82,6 +175,7 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle
             else {
                 for datum in data.drain(..) {
                     let index = (((self.hash_func)(time, &datum)) % self.pushers.len() as u64) as usize;
+                    if let Some((_, ref mut counts)) = self.skew { counts[index] += 1; }
                     self.buffers[index].push(datum);
                     if self.buffers[index].len() == self.buffers[index].capacity() {
                         self.flush(index);
@@ -89,6 +183,10 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle
                 }
             }
 
+            if let Some((ref monitor, ref mut counts)) = self.skew {
+                monitor.borrow_mut().count(time, &counts[..]);
+                for count in counts.iter_mut() { *count = 0; }
+            }
         }
         else {
             // flush
@@ -99,3 +197,47 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: Fn(&T, &D)->u64> Push<Bundle
         }
     }
 }
+
+/// Distributes entire batches of records among target pushees according to a function of the batch.
+///
+/// Each batch is sent whole to one pushee, so that batches arrive with the boundaries they were sent
+/// with, and the function is evaluated once per batch rather than once per record.
+pub struct BatchExchange<T, D, P: Push<Bundle<T, D>>, H: Fn(&T, &[D]) -> u64> {
+    pushers: Vec<P>,
+    hash_func: H,
+    phantom: ::std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, D, P: Push<Bundle<T, D>>, H: Fn(&T, &[D])->u64> BatchExchange<T, D, P, H> {
+    /// Allocates a new `BatchExchange` from a supplied set of pushers and a distribution function.
+    pub fn new(pushers: Vec<P>, key: H) -> BatchExchange<T, D, P, H> {
+        BatchExchange {
+            pushers,
+            hash_func: key,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, D, P: Push<Bundle<T, D>>, H: Fn(&T, &[D])->u64> Push<Bundle<T, D>> for BatchExchange<T, D, P, H> {
+    #[inline]
+    fn push(&mut self, message: &mut Option<Bundle<T, D>>) {
+        // if only one pusher, no exchange
+        if self.pushers.len() == 1 {
+            self.pushers[0].push(message);
+        }
+        else if message.is_some() {
+            let index = {
+                let batch = message.as_ref().expect("message vanished");
+                ((self.hash_func)(&batch.time, &batch.data[..]) % self.pushers.len() as u64) as usize
+            };
+            self.pushers[index].push(message);
+        }
+        else {
+            // flush
+            for pusher in self.pushers.iter_mut() {
+                pusher.push(&mut None);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 pub use self::tee::{Tee, TeeHelper};
-pub use self::exchange::Exchange;
+pub use self::exchange::{Exchange, BatchExchange};
 pub use self::counter::Counter;
 
 pub mod tee;
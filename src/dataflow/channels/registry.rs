@@ -0,0 +1,100 @@
+//! A registry of the channels a worker has connected, for attributing observations to dataflow edges.
+//!
+//! Network-level tools observe communication channels only by the identifiers their allocator
+//! assigns them. Each worker registers every channel it connects, along with the communication
+//! channel allocated for it (if any), the scope containing it, the operator ports it connects, and
+//! the name of its parallelization contract, so that such observations can be traced back to edges.
+//! The same information is logged as a `ChannelsEvent` when the channel is connected.
+//!
+//! #Examples
+//! ```
+//! use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+//!
+//! timely::execute(timely::Configuration::Thread, |worker| {
+//!
+//!     worker.dataflow::<u64,_,_>(|scope| {
+//!         (0..10u64).to_stream(scope)
+//!                   .exchange(|x| *x)
+//!                   .inspect(|x| println!("seen: {:?}", x));
+//!     });
+//!
+//!     let channels = worker.channels();
+//!     let exchanges = channels.records().into_iter().filter(|r| r.pact == "Exchange").count();
+//!     assert_eq!(exchanges, 1);
+//! }).unwrap();
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+/// The description of one connected channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelRecord {
+    /// Worker-unique identifier for the channel, as reported in logging events.
+    pub id: usize,
+    /// Communication channel identifier, for channels allocated by the communication layer.
+    pub comm_channel: Option<usize>,
+    /// Sequence of nested scope identifiers indicating the path from the root to the channel's scope.
+    ///
+    /// The first identifier is the index of the dataflow containing the channel.
+    pub scope_addr: Vec<usize>,
+    /// Source descriptor, indicating operator index and output port.
+    pub source: (usize, usize),
+    /// Target descriptor, indicating operator index and input port.
+    pub target: (usize, usize),
+    /// The name of the channel's parallelization contract.
+    pub pact: String,
+}
+
+impl ChannelRecord {
+    /// The index of the dataflow containing the channel.
+    pub fn dataflow(&self) -> Option<usize> { self.scope_addr.first().cloned() }
+}
+
+/// The channels connected by a worker, registered in its resources.
+#[derive(Default)]
+pub struct ChannelRegistry {
+    records: RefCell<BTreeMap<usize, ChannelRecord>>,
+    allocated: Cell<Option<usize>>,
+}
+
+impl ChannelRegistry {
+    /// Allocates a new empty registry.
+    pub fn new() -> Self { Default::default() }
+
+    /// Notes the communication channel most recently allocated by the worker.
+    pub fn note_allocation(&self, comm_channel: Option<usize>) {
+        if comm_channel.is_some() {
+            self.allocated.set(comm_channel);
+        }
+    }
+
+    /// Returns and clears the communication channel most recently allocated by the worker.
+    ///
+    /// Channel constructors call this once before connecting a parallelization contract, to discard
+    /// allocations made for other purposes, and once after, to learn the channel's allocation.
+    pub fn take_allocation(&self) -> Option<usize> { self.allocated.take() }
+
+    /// Registers a connected channel, replacing any record with the same identifier.
+    pub fn register(&self, record: ChannelRecord) {
+        self.records.borrow_mut().insert(record.id, record);
+    }
+
+    /// The record of the channel with identifier `id`, if any.
+    pub fn get(&self, id: usize) -> Option<ChannelRecord> {
+        self.records.borrow().get(&id).cloned()
+    }
+
+    /// The records of channels carried by communication channel `comm_channel`.
+    ///
+    /// A communication channel may carry several channels, for example the per-worker edges of a
+    /// broadcast.
+    pub fn by_comm_channel(&self, comm_channel: usize) -> Vec<ChannelRecord> {
+        self.records.borrow().values().filter(|r| r.comm_channel == Some(comm_channel)).cloned().collect()
+    }
+
+    /// The records of all registered channels, in order of identifier.
+    pub fn records(&self) -> Vec<ChannelRecord> {
+        self.records.borrow().values().cloned().collect()
+    }
+}
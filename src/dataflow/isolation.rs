@@ -0,0 +1,118 @@
+//! Detection of operator state shared across dataflows.
+//!
+//! Timely dataflow assumes that the state of an operator is private to its dataflow: each dataflow
+//! is scheduled independently, and the order in which the operators of different dataflows run is
+//! not deterministic. State captured by the closures of several dataflows, for example through a
+//! shared `Rc<RefCell<_>>`, is updated in an order that may differ from run to run and from worker
+//! to worker, which produces bugs that are subtle and hard to reproduce.
+//!
+//! The compiler already prevents non-`Send` state from crossing between workers, but it cannot see
+//! state shared between the dataflows of one worker. An `Isolated` wraps such state, and records the
+//! dataflow in which it is first bound to operators. In debug builds, binding it into any other
+//! dataflow panics at construction time with a diagnostic naming the state and both dataflows. In
+//! release builds the check is skipped, and `check` may be called to perform it explicitly.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use dataflow::Scope;
+
+/// State intended for the operators of a single dataflow.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::isolation::Isolated;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let count = Isolated::new("count", 0);
+///
+///     worker.dataflow::<u64,_,_>(|scope| {
+///         let count1 = count.bind(scope);
+///         let count2 = count.bind(scope);
+///         (0..10).to_stream(scope).inspect(move |_| *count1.borrow_mut() += 1);
+///         (0..10).to_stream(scope).inspect(move |_| *count2.borrow_mut() += 1);
+///     });
+///
+///     // using `count` in a second dataflow would be reported.
+///     worker.dataflow::<u64,_,_>(|scope| {
+///         assert!(count.check(scope).is_err());
+///     });
+/// }).unwrap();
+/// ```
+pub struct Isolated<T> {
+    name: String,
+    value: Rc<RefCell<T>>,
+    owner: Rc<RefCell<Option<Vec<usize>>>>,
+}
+
+impl<T> Isolated<T> {
+    /// Wraps `value` as state for a single dataflow, described by `name` in diagnostics.
+    pub fn new(name: &str, value: T) -> Self {
+        Isolated {
+            name: name.to_owned(),
+            value: Rc::new(RefCell::new(value)),
+            owner: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Returns the state for use by the operators of the dataflow of `scope`.
+    ///
+    /// The first call binds the state to the dataflow of `scope`.
+    ///
+    /// #Panics
+    ///
+    /// In debug builds, panics if the state is bound to another dataflow.
+    pub fn bind<G: Scope>(&self, scope: &G) -> Rc<RefCell<T>> {
+        if cfg!(debug_assertions) {
+            if let Err(diagnostic) = self.check(scope) {
+                panic!("{}", diagnostic);
+            }
+        }
+        let dataflow = dataflow_of(scope);
+        self.owner.borrow_mut().get_or_insert(dataflow);
+        self.value.clone()
+    }
+
+    /// Reports whether the state may be used by the dataflow of `scope`, without binding it.
+    ///
+    /// The result is a diagnostic if the state is bound to another dataflow.
+    pub fn check<G: Scope>(&self, scope: &G) -> Result<(), String> {
+        let dataflow = dataflow_of(scope);
+        match *self.owner.borrow() {
+            Some(ref owner) if owner != &dataflow => {
+                Err(format!("state `{}` bound to dataflow {:?} is also used by dataflow {:?} (scope `{}`); \
+                             dataflows are scheduled independently, and updates to shared state from \
+                             different dataflows occur in a non-deterministic order",
+                            self.name, owner, dataflow, scope.name()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// The address of the dataflow the state is bound to, if any.
+    pub fn owner(&self) -> Option<Vec<usize>> {
+        self.owner.borrow().clone()
+    }
+
+    /// The name of the state.
+    pub fn name(&self) -> &str { &self.name }
+}
+
+impl<T> Clone for Isolated<T> {
+    fn clone(&self) -> Self {
+        Isolated {
+            name: self.name.clone(),
+            value: self.value.clone(),
+            owner: self.owner.clone(),
+        }
+    }
+}
+
+/// The address of the dataflow containing `scope`: the worker index and the dataflow index.
+fn dataflow_of<G: Scope>(scope: &G) -> Vec<usize> {
+    let mut addr = scope.addr();
+    addr.truncate(2);
+    addr
+}
@@ -23,3 +23,6 @@ pub mod operators;
 pub mod channels;
 pub mod scopes;
 pub mod stream;
+pub mod plan;
+pub mod schema;
+pub mod isolation;
@@ -0,0 +1,94 @@
+//! Two-stage aggregation, combining records locally before exchanging partial aggregates.
+use std::hash::Hash;
+use std::collections::HashMap;
+
+use ::{Data, ExchangeData};
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::operator::Operator;
+use dataflow::channels::pact::Pipeline;
+
+use super::Aggregate;
+
+/// Generic intra-timestamp aggregation with local pre-aggregation.
+///
+/// Extension method supporting aggregation of keyed data within timestamp, where each worker
+/// first folds its own records into partial aggregates, and only the partial aggregates are
+/// exchanged. This can substantially reduce the volume of data exchanged when there are many
+/// more records than distinct keys.
+pub trait AggregateGlobal<S: Scope, K: ExchangeData+Hash, V: Data> {
+    /// Aggregates data of the form `(key, val)`, first locally and then globally.
+    ///
+    /// The `aggregate_global` method takes functions `fold`, `merge`, `emit`, and `hash`; used
+    /// to combine new `V` data with a local `A` partial aggregate, to combine partial aggregates
+    /// received from each worker, to produce `R` output from the final `A` aggregate, and to route
+    /// `K` keys to the worker that computes their final aggregate, respectively. To compute all
+    /// aggregates at a single worker, use a `hash` function that returns a constant.
+    ///
+    /// Aggregation happens within each time, and results are produced once the time is complete.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::AggregateGlobal;
+    ///
+    /// timely::example(|scope| {
+    ///
+    ///     (0..10).to_stream(scope)
+    ///         .map(|x| (x % 2, x))
+    ///         .aggregate_global(
+    ///             |_key, val, agg| { *agg += val; },
+    ///             |_key, part, agg| { *agg += part; },
+    ///             |key, agg: i32| (key, agg),
+    ///             |key| *key as u64
+    ///         )
+    ///         .inspect(|x| assert!(*x == (0, 20) || *x == (1, 25)));
+    /// });
+    /// ```
+    fn aggregate_global<R, A, F, M, E, H>(&self, fold: F, merge: M, emit: E, hash: H) -> Stream<S, R>
+    where
+        R: Data,
+        A: ExchangeData+Default,
+        F: Fn(&K, V, &mut A)+'static,
+        M: Fn(&K, A, &mut A)+'static,
+        E: Fn(K, A)->R+'static,
+        H: Fn(&K)->u64+'static,
+        S::Timestamp: Eq;
+}
+
+impl<S: Scope, K: ExchangeData+Hash+Eq, V: Data> AggregateGlobal<S, K, V> for Stream<S, (K, V)> {
+
+    fn aggregate_global<R, A, F, M, E, H>(&self, fold: F, merge: M, emit: E, hash: H) -> Stream<S, R>
+    where
+        R: Data,
+        A: ExchangeData+Default,
+        F: Fn(&K, V, &mut A)+'static,
+        M: Fn(&K, A, &mut A)+'static,
+        E: Fn(K, A)->R+'static,
+        H: Fn(&K)->u64+'static,
+        S::Timestamp: Eq {
+
+        let mut partials = HashMap::new();
+        let mut vector = Vec::new();
+        self.unary_notify(Pipeline, "AggregateLocal", vec![], move |input, output, notificator| {
+
+            // read each input, fold into local partial aggregates
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let agg_time = partials.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (key, val) in vector.drain(..) {
+                    let agg = agg_time.entry(key.clone()).or_insert_with(Default::default);
+                    fold(&key, val, agg);
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            // release completed partial aggregates for exchange
+            notificator.for_each(|time,_,_| {
+                if let Some(aggs) = partials.remove(time.time()) {
+                    output.session(&time).give_iterator(aggs.into_iter());
+                }
+            });
+        })
+        .aggregate(merge, emit, hash)
+    }
+}
@@ -0,0 +1,74 @@
+//! Consolidation of `(data, diff)` streams within timestamps.
+use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+
+use ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::operator::Operator;
+use dataflow::channels::pact::Exchange;
+
+/// Accumulates the differences of equal records within each timestamp.
+pub trait Consolidate<S: Scope, D: ExchangeData+Hash+Eq> {
+    /// Accumulates the differences of equal records within each timestamp, and once the timestamp
+    /// is complete produces each record whose accumulated difference is non-zero, with that difference.
+    ///
+    /// Records are exchanged by their hash, so that each record is produced at most once per timestamp,
+    /// by one worker.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Capture};
+    /// use timely::dataflow::operators::aggregation::Consolidate;
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     vec![(0u64, 1), (1, 1), (0, 2), (1, -1), (2, 0)]
+    ///         .to_stream(scope)
+    ///         .consolidate()
+    ///         .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(RootTimestamp::new(0), vec![(0, 3)])]);
+    /// ```
+    fn consolidate(&self) -> Stream<S, (D, i64)>;
+}
+
+impl<S: Scope, D: ExchangeData+Hash+Eq> Consolidate<S, D> for Stream<S, (D, i64)> {
+    fn consolidate(&self) -> Stream<S, (D, i64)> {
+
+        let mut totals = HashMap::new();
+        let mut vector = Vec::new();
+        let exchange = Exchange::new(|&(ref data, _): &(D, i64)| {
+            let mut hasher = DefaultHasher::new();
+            data.hash(&mut hasher);
+            hasher.finish()
+        });
+
+        self.unary_notify(exchange, "Consolidate", vec![], move |input, output, notificator| {
+
+            // accumulate differences for each record at each time.
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let totals = totals.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (data, diff) in vector.drain(..) {
+                    *totals.entry(data).or_insert(0) += diff;
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            // produce non-zero totals for completed times.
+            notificator.for_each(|time,_,_| {
+                if let Some(totals) = totals.remove(time.time()) {
+                    let mut session = output.session(&time);
+                    for (data, diff) in totals {
+                        if diff != 0 {
+                            session.give((data, diff));
+                        }
+                    }
+                }
+            });
+        })
+    }
+}
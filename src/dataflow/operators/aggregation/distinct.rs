@@ -0,0 +1,174 @@
+//! Approximate counts of the distinct values of each key within timestamps.
+//!
+//! Counting distinct values exactly requires holding every distinct value. A `HyperLogLog` sketch
+//! instead holds a fixed number of small registers, from which it estimates the number of distinct
+//! values inserted with a relative standard error of about `1.04 / sqrt(registers)`. Sketches with the
+//! same number of registers merge exactly, by taking the maximum of each register, and so each worker
+//! may sketch its own values and exchange only the sketches, as `AggregateGlobal` does.
+
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+use ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::operators::Map;
+
+use super::AggregateGlobal;
+
+/// The precision of a `HyperLogLog` created by `default`, with 4096 registers.
+pub const DEFAULT_PRECISION: u8 = 12;
+
+/// A sketch estimating the number of distinct values inserted into it.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::aggregation::distinct::HyperLogLog;
+///
+/// let mut sketch = HyperLogLog::new(12);
+/// for value in 0 .. 100000 {
+///     sketch.insert(&(value % 10000));
+/// }
+///
+/// let estimate = sketch.estimate();
+/// assert!(estimate > 9500.0 && estimate < 10500.0);
+/// ```
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    /// The base-two logarithm of the number of registers.
+    precision: u8,
+    /// The greatest number of leading zeros, plus one, of hashes routed to each register.
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Allocates an empty sketch with `1 << precision` registers, for `precision` from 4 to 18.
+    pub fn new(precision: u8) -> Self {
+        assert!(precision >= 4 && precision <= 18, "precision must be between 4 and 18");
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// The base-two logarithm of the number of registers.
+    pub fn precision(&self) -> u8 { self.precision }
+
+    /// Returns true if no values have been inserted.
+    pub fn is_empty(&self) -> bool { self.registers.iter().all(|register| *register == 0) }
+
+    /// Inserts `value`, identified by its hash.
+    pub fn insert<V: Hash>(&mut self, value: &V) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        self.insert_hash(hasher.finish());
+    }
+
+    /// Inserts a value identified by the 64-bit hash `hash`, whose bits should be uniformly distributed.
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        // the low bit ensures the remaining bits have at most `64 - precision` leading zeros.
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Merges the values inserted into `other` into this sketch.
+    ///
+    /// Panics if the sketches have different precisions, unless either is empty.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        if other.is_empty() { return; }
+        if self.is_empty() {
+            *self = other.clone();
+            return;
+        }
+        assert_eq!(self.precision, other.precision, "merged sketches must have the same precision");
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            if *other > *register {
+                *register = *other;
+            }
+        }
+    }
+
+    /// Estimates the number of distinct values inserted.
+    pub fn estimate(&self) -> f64 {
+        let registers = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / registers),
+        };
+        let sum = self.registers.iter().map(|register| 2.0f64.powi(-(*register as i32))).sum::<f64>();
+        let estimate = alpha * registers * registers / sum;
+        let zeros = self.registers.iter().filter(|register| **register == 0).count();
+        // small cardinalities are estimated more accurately by the fraction of empty registers.
+        if estimate <= 2.5 * registers && zeros > 0 {
+            registers * (registers / zeros as f64).ln()
+        }
+        else {
+            estimate
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self { HyperLogLog::new(DEFAULT_PRECISION) }
+}
+
+/// Estimates the number of distinct values of each key within timestamps.
+pub trait ApproxDistinct<S: Scope, K: ExchangeData+Hash> {
+    /// Produces, once each timestamp is complete, a sketch of the distinct values of each key at the
+    /// timestamp, merged from the sketches of each worker at the worker `hash` routes the key to.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::ApproxDistinct;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10000).to_stream(scope)
+    ///         .map(|x| (x % 2, x % 100))
+    ///         .sketch_distinct(10, |key| *key as u64)
+    ///         .inspect(|&(_, ref sketch)| assert_eq!(sketch.precision(), 10));
+    /// });
+    /// ```
+    fn sketch_distinct<H: Fn(&K)->u64+'static>(&self, precision: u8, hash: H) -> Stream<S, (K, HyperLogLog)>;
+
+    /// Produces, once each timestamp is complete, an estimate of the number of distinct values of each
+    /// key at the timestamp, from sketches with `1 << precision` registers.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::ApproxDistinct;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10000).to_stream(scope)
+    ///         .map(|x| (x % 2, x % 1000))
+    ///         .approx_distinct(12, |key| *key as u64)
+    ///         .inspect(|&(_, count)| assert!(count > 450 && count < 550));
+    /// });
+    /// ```
+    fn approx_distinct<H: Fn(&K)->u64+'static>(&self, precision: u8, hash: H) -> Stream<S, (K, u64)>;
+}
+
+impl<S: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData+Hash> ApproxDistinct<S, K> for Stream<S, (K, V)> {
+    fn sketch_distinct<H: Fn(&K)->u64+'static>(&self, precision: u8, hash: H) -> Stream<S, (K, HyperLogLog)> {
+        assert!(precision >= 4 && precision <= 18, "precision must be between 4 and 18");
+        self.aggregate_global(
+            move |_key, value, sketch: &mut HyperLogLog| {
+                if sketch.precision() != precision { *sketch = HyperLogLog::new(precision); }
+                sketch.insert(&value);
+            },
+            |_key, part, sketch| sketch.merge(&part),
+            |key, sketch| (key, sketch),
+            hash
+        )
+    }
+
+    fn approx_distinct<H: Fn(&K)->u64+'static>(&self, precision: u8, hash: H) -> Stream<S, (K, u64)> {
+        self.sketch_distinct(precision, hash)
+            .map(|(key, sketch)| (key, sketch.estimate().round() as u64))
+    }
+}
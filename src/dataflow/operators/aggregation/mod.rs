@@ -10,9 +10,34 @@
 //!
 //! The two methods are often combined, using first `Aggregate` to reduce the volume of information, and then
 //! `StateMachine` to track an accumulation across timestamps.
+//!
+//! `AggregateGlobal` is a variant of `Aggregate` which first accumulates records at each worker, and
+//! only then exchanges the partial accumulations to be combined.
+//!
+//! `OrderedStateMachine` is a variant of `StateMachine` which buffers events until their time is complete,
+//! and applies the events for each key in the order of a user-supplied sequence number.
+//!
+//! `Consolidate` accumulates the differences of `(data, diff)` records within times, and releases the
+//! records with non-zero accumulated differences once the time is complete.
+//!
+//! `Quantiles` sketches the values of each key within times, merging the sketches of each worker once
+//! the time is complete, to estimate quantiles such as latency percentiles.
+//!
+//! `ApproxDistinct` similarly merges `HyperLogLog` sketches of the values of each key within times, to
+//! estimate the number of distinct values without holding them.
 
 pub use self::aggregate::Aggregate;
+pub use self::aggregate_global::AggregateGlobal;
 pub use self::state_machine::StateMachine;
+pub use self::ordered_state_machine::OrderedStateMachine;
+pub use self::consolidate::Consolidate;
+pub use self::quantiles::{Quantiles, QuantileSketch};
+pub use self::distinct::{ApproxDistinct, HyperLogLog};
 
 pub mod state_machine;
+pub mod ordered_state_machine;
 pub mod aggregate;
+pub mod aggregate_global;
+pub mod consolidate;
+pub mod quantiles;
+pub mod distinct;
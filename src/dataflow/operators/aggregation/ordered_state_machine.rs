@@ -0,0 +1,103 @@
+//! State transition operator applying events in a user-specified order.
+use std::hash::Hash;
+use std::collections::HashMap;
+
+use ::{Data, ExchangeData};
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::operator::Operator;
+use dataflow::channels::pact::Exchange;
+
+/// Provides the `state_machine_ordered` method.
+///
+/// Where `state_machine` applies events as soon as their time is the current time, and so may apply
+/// events within a time in whichever order they arrive, `state_machine_ordered` buffers all events
+/// until their time is complete, and then applies the events for each key in the order of a
+/// user-supplied sequence number. This is appropriate when events are produced in a known order
+/// but may be delivered out of order, for example when they arrive from multiple network sources.
+pub trait OrderedStateMachine<S: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData> {
+    /// Tracks a state for each presented key, applying events in order of `sequence` within each time.
+    ///
+    /// The transition logic `fold` may mutate the state, and produce both output records and
+    /// a `bool` indicating that it is appropriate to deregister the state. Times are processed in
+    /// the order they complete, and within a time the events for each key are presented to `fold`
+    /// in increasing order of `sequence`; events with equal sequence numbers are presented in the
+    /// order they were received.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::OrderedStateMachine;
+    ///
+    /// timely::example(|scope| {
+    ///
+    ///     // events arrive in reverse order, but are applied by sequence number.
+    ///     (0..10).rev()
+    ///            .to_stream(scope)
+    ///            .map(|x| (x % 2, x))
+    ///            .state_machine_ordered(
+    ///                |_key, val, last: &mut Option<i32>| {
+    ///                    assert!(last.map(|l| l < val).unwrap_or(true));
+    ///                    *last = Some(val);
+    ///                    (false, Some(val))
+    ///                },
+    ///                |val| *val,
+    ///                |key| *key as u64
+    ///            )
+    ///            .inspect(|x| println!("applied: {:?}", x));
+    /// });
+    /// ```
+    fn state_machine_ordered<
+        R: Data,                                    // output type
+        D: Default+'static,                         // per-key state (data)
+        I: IntoIterator<Item=R>,                    // type of output iterator
+        Q: Ord,                                     // sequence number type
+        F: Fn(&K, V, &mut D)->(bool, I)+'static,    // state update logic
+        O: Fn(&V)->Q+'static,                       // sequence number of an event
+        H: Fn(&K)->u64+'static,                     // "hash" function for keys
+    >(&self, fold: F, sequence: O, hash: H) -> Stream<S, R> where S::Timestamp : Hash+Eq ;
+}
+
+impl<S: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData> OrderedStateMachine<S, K, V> for Stream<S, (K, V)> {
+    fn state_machine_ordered<
+            R: Data,                                    // output type
+            D: Default+'static,                         // per-key state (data)
+            I: IntoIterator<Item=R>,                    // type of output iterator
+            Q: Ord,                                     // sequence number type
+            F: Fn(&K, V, &mut D)->(bool, I)+'static,    // state update logic
+            O: Fn(&V)->Q+'static,                       // sequence number of an event
+            H: Fn(&K)->u64+'static,                     // "hash" function for keys
+        >(&self, fold: F, sequence: O, hash: H) -> Stream<S, R> where S::Timestamp : Hash+Eq {
+
+        let mut pending: HashMap<_, Vec<(K, V)>> = HashMap::new();   // times -> events
+        let mut states = HashMap::new();    // keys -> state
+
+        let mut vector = Vec::new();
+
+        self.unary_notify(Exchange::new(move |&(ref k, _)| hash(k)), "OrderedStateMachine", vec![], move |input, output, notificator| {
+
+            // stash each input and request a notification, as later batches may hold earlier events.
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                pending.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                notificator.notify_at(time.retain());
+            });
+
+            // for each complete time, apply events in sequence order.
+            notificator.for_each(|time,_,_| {
+                if let Some(mut pend) = pending.remove(time.time()) {
+                    // a stable sort keeps the arrival order of events with equal sequence numbers.
+                    pend.sort_by(|x,y| sequence(&x.1).cmp(&sequence(&y.1)));
+                    let mut session = output.session(&time);
+                    for (key, val) in pend {
+                        let (remove, output) = {
+                            let state = states.entry(key.clone()).or_insert_with(Default::default);
+                            fold(&key, val, state)
+                        };
+                        if remove { states.remove(&key); }
+                        session.give_iterator(output.into_iter());
+                    }
+                }
+            });
+        })
+    }
+}
@@ -0,0 +1,214 @@
+//! Mergeable sketches estimating the quantiles of keyed values within timestamps.
+//!
+//! A `QuantileSketch` counts values in buckets whose bounds grow geometrically, so that each bucket
+//! spans values within a fixed relative error of each other. Quantiles estimated from a sketch are
+//! within that relative error of the exact quantiles, and sketches with the same accuracy merge
+//! exactly by adding the counts of their buckets, which makes them suitable for latencies and other
+//! positive values spanning many orders of magnitude.
+//!
+//! The `Quantiles` operators sketch the values of each key at each worker, and once a timestamp is
+//! complete exchange the partial sketches by key and merge them, as `AggregateGlobal` does.
+
+use std::hash::Hash;
+
+use ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::operators::Map;
+
+use super::AggregateGlobal;
+
+/// The relative accuracy of a `QuantileSketch` created by `default`.
+pub const DEFAULT_ACCURACY: f64 = 0.01;
+
+/// Counts of non-negative values in geometrically growing buckets.
+///
+/// Values not greater than zero are counted as zero.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::aggregation::quantiles::QuantileSketch;
+///
+/// let mut sketch = QuantileSketch::new(0.01);
+/// for value in 1 .. 1001 {
+///     sketch.insert(value as f64);
+/// }
+///
+/// let median = sketch.quantile(0.5);
+/// assert!((median - 500.0).abs() <= 10.0);
+/// assert_eq!(sketch.quantile(0.0), 1.0);
+/// assert_eq!(sketch.quantile(1.0), 1000.0);
+/// ```
+#[derive(Abomonation, Debug, Clone, PartialEq)]
+pub struct QuantileSketch {
+    /// The ratio of the bounds of each bucket.
+    gamma: f64,
+    /// The counts of non-empty buckets, in order of their indices.
+    bins: Vec<(i32, u64)>,
+    /// The count of values not greater than zero.
+    zeros: u64,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl QuantileSketch {
+    /// Allocates an empty sketch whose quantiles are within relative error `accuracy`.
+    pub fn new(accuracy: f64) -> Self {
+        assert!(accuracy > 0.0 && accuracy < 1.0, "accuracy must be between zero and one");
+        QuantileSketch {
+            gamma: (1.0 + accuracy) / (1.0 - accuracy),
+            bins: Vec::new(),
+            zeros: 0,
+            count: 0,
+            min: 0.0,
+            max: 0.0,
+        }
+    }
+
+    /// The relative error of the sketch's quantiles.
+    pub fn accuracy(&self) -> f64 { (self.gamma - 1.0) / (self.gamma + 1.0) }
+
+    /// The number of values counted.
+    pub fn count(&self) -> u64 { self.count }
+
+    /// Returns true if the sketch has counted no values.
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+
+    /// Counts `value`.
+    pub fn insert(&mut self, value: f64) {
+        self.insert_many(value, 1);
+    }
+
+    /// Counts `value` `count` times.
+    pub fn insert_many(&mut self, value: f64, count: u64) {
+        if count == 0 { return; }
+        let value = if value > 0.0 { value } else { 0.0 };
+        if self.count == 0 || value < self.min { self.min = value; }
+        if self.count == 0 || value > self.max { self.max = value; }
+        self.count += count;
+        if value > 0.0 {
+            let index = (value.ln() / self.gamma.ln()).ceil() as i32;
+            match self.bins.binary_search_by_key(&index, |&(index, _)| index) {
+                Ok(position) => self.bins[position].1 += count,
+                Err(position) => self.bins.insert(position, (index, count)),
+            }
+        }
+        else {
+            self.zeros += count;
+        }
+    }
+
+    /// Adds the counts of `other` to this sketch.
+    ///
+    /// Panics if the sketches have different accuracies, unless either is empty.
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        if other.is_empty() { return; }
+        if self.is_empty() {
+            *self = other.clone();
+            return;
+        }
+        assert_eq!(self.gamma, other.gamma, "merged sketches must have the same accuracy");
+        let mut bins = Vec::with_capacity(self.bins.len() + other.bins.len());
+        {
+            let mut mine = self.bins.iter().cloned().peekable();
+            let mut theirs = other.bins.iter().cloned().peekable();
+            loop {
+                let next = match (mine.peek().cloned(), theirs.peek().cloned()) {
+                    (Some(x), Some(y)) if x.0 == y.0 => { mine.next(); theirs.next(); (x.0, x.1 + y.1) },
+                    (Some(x), Some(y)) if x.0 < y.0 => { mine.next(); x },
+                    (_, Some(y)) => { theirs.next(); y },
+                    (Some(x), None) => { mine.next(); x },
+                    (None, None) => break,
+                };
+                bins.push(next);
+            }
+        }
+        self.bins = bins;
+        self.zeros += other.zeros;
+        self.count += other.count;
+        if other.min < self.min { self.min = other.min; }
+        if other.max > self.max { self.max = other.max; }
+    }
+
+    /// Estimates the `q` quantile of the counted values, for `q` between zero and one.
+    ///
+    /// The least and greatest values are reported exactly. Returns zero if the sketch is empty.
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 { return 0.0; }
+        let q = if q < 0.0 { 0.0 } else if q > 1.0 { 1.0 } else { q };
+        let rank = (q * (self.count - 1) as f64).round() as u64;
+        if rank == 0 { return self.min; }
+        if rank == self.count - 1 { return self.max; }
+        let mut seen = self.zeros;
+        if rank < seen { return 0.0; }
+        for &(index, count) in self.bins.iter() {
+            seen += count;
+            if rank < seen {
+                let estimate = 2.0 * self.gamma.powi(index) / (self.gamma + 1.0);
+                return estimate.max(self.min).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self { QuantileSketch::new(DEFAULT_ACCURACY) }
+}
+
+/// Estimates the quantiles of keyed values within timestamps.
+pub trait Quantiles<S: Scope, K: ExchangeData+Hash> {
+    /// Produces, once each timestamp is complete, a sketch of the values of each key at the timestamp,
+    /// merged from the sketches of each worker at the worker `hash` routes the key to.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::Quantiles;
+    ///
+    /// timely::example(|scope| {
+    ///     (1..1001).to_stream(scope)
+    ///         .map(|x| (x % 2, x as f64))
+    ///         .sketch_quantiles(0.01, |key| *key as u64)
+    ///         .inspect(|&(_, ref sketch)| assert_eq!(sketch.count(), 500));
+    /// });
+    /// ```
+    fn sketch_quantiles<H: Fn(&K)->u64+'static>(&self, accuracy: f64, hash: H) -> Stream<S, (K, QuantileSketch)>;
+
+    /// Produces, once each timestamp is complete, estimates of the `quantiles` of the values of each
+    /// key at the timestamp, in the order of `quantiles`.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    /// use timely::dataflow::operators::aggregation::Quantiles;
+    ///
+    /// timely::example(|scope| {
+    ///     (1..1001).to_stream(scope)
+    ///         .map(|x| (0, x as f64))
+    ///         .quantiles(0.01, vec![0.5, 0.99], |_key| 0)
+    ///         .inspect(|x| println!("p50 and p99: {:?}", x));
+    /// });
+    /// ```
+    fn quantiles<H: Fn(&K)->u64+'static>(&self, accuracy: f64, quantiles: Vec<f64>, hash: H) -> Stream<S, (K, Vec<f64>)>;
+}
+
+impl<S: Scope, K: ExchangeData+Hash+Eq> Quantiles<S, K> for Stream<S, (K, f64)> {
+    fn sketch_quantiles<H: Fn(&K)->u64+'static>(&self, accuracy: f64, hash: H) -> Stream<S, (K, QuantileSketch)> {
+        assert!(accuracy > 0.0 && accuracy < 1.0, "accuracy must be between zero and one");
+        self.aggregate_global(
+            move |_key, value, sketch: &mut QuantileSketch| {
+                if sketch.is_empty() { *sketch = QuantileSketch::new(accuracy); }
+                sketch.insert(value);
+            },
+            |_key, part, sketch| sketch.merge(&part),
+            |key, sketch| (key, sketch),
+            hash
+        )
+    }
+
+    fn quantiles<H: Fn(&K)->u64+'static>(&self, accuracy: f64, quantiles: Vec<f64>, hash: H) -> Stream<S, (K, Vec<f64>)> {
+        self.sketch_quantiles(accuracy, hash)
+            .map(move |(key, sketch)| (key, quantiles.iter().map(|q| sketch.quantile(*q)).collect()))
+    }
+}
@@ -0,0 +1,207 @@
+//! Operators that assert properties of the progress of a stream, for testing dataflows.
+//!
+//! Each operator passes its input through unchanged, and reports a violation of its assertion either
+//! by panicking or, for the `check_` variants, as an `OperatorError` on a separate error stream. They
+//! are intended for integration tests of the progress behavior of complex dataflows, where an error
+//! would otherwise show up only as a stalled or incorrect computation.
+
+use order::PartialOrder;
+use progress::frontier::Antichain;
+use Data;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::Inspect;
+use dataflow::operators::catch::OperatorError;
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+/// Methods to assert properties of the progress of a stream.
+pub trait Assertions<G: Scope, D: Data> {
+    /// Asserts that the frontier of the stream reaches `target` before the stream completes, meaning
+    /// that while the stream is incomplete each of its frontier's times is eventually greater or equal
+    /// to some element of `target`.
+    ///
+    /// The assertion is checked once the stream completes, and the operator panics if the frontier was
+    /// never observed to reach `target` before then. A dataflow dropped before its stream completes,
+    /// for example because it is cancelled, is not checked. The frontier is observed each time the
+    /// operator is scheduled, and so a frontier that reaches `target` and completes within one step
+    /// of the worker may not be observed to.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::product::Product;
+    /// use timely::progress::timestamp::RootTimestamp;
+    /// use timely::dataflow::operators::{Input, Inspect};
+    /// use timely::dataflow::operators::assertions::Assertions;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///     let mut input = worker.dataflow::<u64,_,_>(|scope| {
+    ///         let (input, stream) = scope.new_input();
+    ///         stream.assert_frontier_eventually(Antichain::from_elem(Product::new(RootTimestamp, 5)))
+    ///               .inspect(|x: &u64| println!("seen: {:?}", x));
+    ///         input
+    ///     });
+    ///     for round in 0 .. 10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    fn assert_frontier_eventually(&self, target: Antichain<G::Timestamp>) -> Stream<G, D>;
+
+    /// As `assert_frontier_eventually`, but produces an error on a separate stream rather than panicking.
+    ///
+    /// The error stream's frontier advances with the stream's frontier while it has a single element,
+    /// and completes once the frontier reaches `target`, or once the stream completes.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::product::Product;
+    /// use timely::progress::timestamp::RootTimestamp;
+    /// use timely::dataflow::operators::{Input, Inspect};
+    /// use timely::dataflow::operators::assertions::Assertions;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///     let mut input = worker.dataflow::<u64,_,_>(|scope| {
+    ///         let (input, stream) = scope.new_input::<u64>();
+    ///         let (_stream, errors) = stream.check_frontier_eventually(Antichain::from_elem(Product::new(RootTimestamp, 5)));
+    ///         errors.inspect(|error| println!("violation: {}", error.message));
+    ///         input
+    ///     });
+    ///     // the input closes without advancing to 5, which the error stream reports.
+    ///     input.advance_to(2);
+    /// }).unwrap();
+    /// ```
+    fn check_frontier_eventually(&self, target: Antichain<G::Timestamp>) -> (Stream<G, D>, Stream<G, OperatorError<G::Timestamp>>);
+
+    /// Asserts that the stream carries no records at times not greater or equal to `time`.
+    ///
+    /// The operator panics when it receives such a record.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::progress::nested::product::Product;
+    /// use timely::progress::timestamp::RootTimestamp;
+    /// use timely::dataflow::operators::{ToStream, Delay, Inspect};
+    /// use timely::dataflow::operators::assertions::Assertions;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10u64).to_stream(scope)
+    ///               .delay(|_x, time| Product::new(RootTimestamp, time.inner + 3))
+    ///               .assert_no_data_before(Product::new(RootTimestamp, 3))
+    ///               .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn assert_no_data_before(&self, time: G::Timestamp) -> Stream<G, D>;
+
+    /// As `assert_no_data_before`, but produces an error at the time of each violating batch on a
+    /// separate stream rather than panicking. Violating records are passed through unchanged.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::progress::nested::product::Product;
+    /// use timely::progress::timestamp::RootTimestamp;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::assertions::Assertions;
+    ///
+    /// timely::example(|scope| {
+    ///     let (_stream, errors) = (0..10u64).to_stream(scope)
+    ///                                       .check_no_data_before(Product::new(RootTimestamp, 3));
+    ///     errors.inspect(|error| assert_eq!(error.time, Product::new(RootTimestamp, 0)));
+    /// });
+    /// ```
+    fn check_no_data_before(&self, time: G::Timestamp) -> (Stream<G, D>, Stream<G, OperatorError<G::Timestamp>>);
+}
+
+impl<G: Scope, D: Data> Assertions<G, D> for Stream<G, D> {
+    fn assert_frontier_eventually(&self, target: Antichain<G::Timestamp>) -> Stream<G, D> {
+        let (stream, errors) = self.check_frontier_eventually(target);
+        errors.inspect(|error| panic!("{}: {}", error.operator, error.message));
+        stream
+    }
+
+    fn check_frontier_eventually(&self, target: Antichain<G::Timestamp>) -> (Stream<G, D>, Stream<G, OperatorError<G::Timestamp>>) {
+
+        let name = "AssertFrontierEventually";
+        let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut output, stream) = builder.new_output();
+        let (mut error_output, errors) = builder.new_output();
+
+        builder.build(move |mut capabilities| {
+
+            // the capability of the error output, held until the assertion is decided.
+            let mut capability = capabilities.pop();
+            let mut vector = Vec::new();
+
+            move |frontiers| {
+
+                let mut output_handle = output.activate();
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    output_handle.session(&time).give_vec(&mut vector);
+                });
+
+                let frontier = frontiers[0].frontier();
+                if frontier.is_empty() {
+                    if let Some(capability) = capability.take() {
+                        error_output.activate().session(&capability).give(OperatorError {
+                            time: capability.time().clone(),
+                            operator: name.to_owned(),
+                            message: format!("frontier completed without reaching {:?}", target.elements()),
+                        });
+                    }
+                }
+                else if frontier.iter().all(|time| target.less_equal(time)) {
+                    capability = None;
+                }
+                else if let Some(ref mut capability) = capability {
+                    if frontier.len() == 1 && capability.time().less_equal(&frontier[0]) {
+                        capability.downgrade(&frontier[0]);
+                    }
+                }
+            }
+        });
+
+        (stream, errors)
+    }
+
+    fn assert_no_data_before(&self, time: G::Timestamp) -> Stream<G, D> {
+        let (stream, errors) = self.check_no_data_before(time);
+        errors.inspect(|error| panic!("{}: {}", error.operator, error.message));
+        stream
+    }
+
+    fn check_no_data_before(&self, time: G::Timestamp) -> (Stream<G, D>, Stream<G, OperatorError<G::Timestamp>>) {
+
+        let name = "AssertNoDataBefore";
+        let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut output, stream) = builder.new_output();
+        let (mut error_output, errors) = builder.new_output();
+        builder.set_notify(false);
+
+        builder.build(move |_| {
+            let mut vector = Vec::new();
+            move |_frontiers| {
+                let mut output_handle = output.activate();
+                let mut error_handle = error_output.activate();
+                input.for_each(|record_time, data| {
+                    data.swap(&mut vector);
+                    if !time.less_equal(record_time.time()) {
+                        error_handle.session(&record_time).give(OperatorError {
+                            time: record_time.time().clone(),
+                            operator: name.to_owned(),
+                            message: format!("{} records at {:?}, which is not greater or equal to {:?}", vector.len(), record_time.time(), time),
+                        });
+                    }
+                    output_handle.session(&record_time).give_vec(&mut vector);
+                });
+            }
+        });
+
+        (stream, errors)
+    }
+}
@@ -0,0 +1,310 @@
+//! An audit of the capabilities held by the operators of a worker.
+//!
+//! A dataflow whose frontier fails to advance usually has an operator holding a capability it no
+//! longer needs. When auditing is enabled, each capability minted, cloned, delayed, downgraded, or
+//! dropped by the worker's thread is recorded as a `CapabilityEvent`, with the address of the
+//! operator, and the output port the capability is for. Each capability is assigned an identifier,
+//! and capabilities derived from others record the identifier they derive from, so that the chain
+//! back to the capability the system minted can be followed.
+//!
+//! With the `backtrace` feature, the backtrace of each action that creates a capability is captured,
+//! and events record its identifier, whose text `backtrace` returns. Downgrades and drops record the
+//! backtrace of the capability's creation rather than capturing their own.
+//!
+//! Generic operators also report whether all of their inputs have closed. The `suspects` function
+//! reports the capabilities held by such operators, which can no longer receive the data their
+//! capabilities might respond to.
+//!
+//! Auditing is enabled for a worker's thread by `enable`, or by the worker configuration key
+//! `capability-audit` (for example, the command line argument `--capability-audit`). It retains
+//! every event in memory, and is intended for debugging only. When no thread has enabled auditing,
+//! capabilities skip it after reading a single global counter, and no backtraces are captured.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An action applied to a capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapabilityAction {
+    /// Minted by the system, or retained from a capability reference.
+    Mint,
+    /// Cloned from another capability.
+    Clone,
+    /// Created from another capability for a later time.
+    Delay,
+    /// Downgraded in place to a later time.
+    Downgrade,
+    /// Dropped, releasing its time.
+    Drop,
+}
+
+/// An action applied to a capability, and where the capability is held.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityEvent {
+    /// The identifier of the capability.
+    pub id: usize,
+    /// The identifier of the capability this one derives from, if any.
+    pub parent: Option<usize>,
+    /// The action applied.
+    pub action: CapabilityAction,
+    /// The time of the capability after the action, formatted with `Debug`.
+    pub time: String,
+    /// The address of the operator, if known.
+    pub operator: Option<Vec<usize>>,
+    /// The output port of the operator the capability is for.
+    pub output: usize,
+    /// The identifier of the backtrace of the capability's creation, whose text `backtrace` returns,
+    /// if backtraces are captured.
+    pub backtrace: Option<usize>,
+}
+
+/// A capability held by an operator whose inputs have all closed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Suspect {
+    /// The identifier of the capability.
+    pub id: usize,
+    /// The time of the capability, formatted with `Debug`.
+    pub time: String,
+    /// The name of the operator.
+    pub name: String,
+    /// The address of the operator.
+    pub operator: Vec<usize>,
+    /// The output port of the operator the capability is for.
+    pub output: usize,
+    /// The identifier of the backtrace of the capability's creation, if backtraces are captured.
+    pub backtrace: Option<usize>,
+}
+
+/// An operator's output, as registered by its builder.
+struct Output {
+    name: String,
+    operator: Vec<usize>,
+    port: usize,
+}
+
+#[derive(Default)]
+struct Audit {
+    /// Operator outputs, by the address of the shared state their capabilities update.
+    outputs: HashMap<usize, Output>,
+    /// Operators whose inputs have all closed, by address.
+    closed: HashMap<Vec<usize>, bool>,
+    /// Live capabilities: their times, the keys of their outputs, and their creation backtraces.
+    live: BTreeMap<usize, (String, usize, Option<usize>)>,
+    events: Vec<CapabilityEvent>,
+    next_id: usize,
+    /// Distinct backtraces, and their identifiers.
+    backtraces: Vec<String>,
+    backtrace_ids: HashMap<String, usize>,
+}
+
+impl Audit {
+    /// Captures the current backtrace, returning its identifier.
+    #[cfg(feature = "backtrace")]
+    fn backtrace(&mut self) -> Option<usize> {
+        let text = format!("{:?}", ::backtrace::Backtrace::new());
+        if let Some(id) = self.backtrace_ids.get(&text) {
+            return Some(*id);
+        }
+        self.backtraces.push(text.clone());
+        self.backtrace_ids.insert(text, self.backtraces.len() - 1);
+        Some(self.backtraces.len() - 1)
+    }
+    /// Backtraces are captured only with the `backtrace` feature.
+    #[cfg(not(feature = "backtrace"))]
+    fn backtrace(&mut self) -> Option<usize> { None }
+}
+
+thread_local! {
+    static AUDIT: RefCell<Option<Audit>> = RefCell::new(None);
+}
+
+/// The number of threads with auditing enabled.
+static ENABLED: AtomicUsize = AtomicUsize::new(0);
+
+/// Enables auditing for capabilities of this thread, if not already enabled.
+///
+/// Only operators constructed after auditing is enabled are identified in events.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::operators::generic::Operator;
+/// use timely::dataflow::channels::pact::Pipeline;
+/// use timely::dataflow::operators::audit;
+///
+/// audit::enable();
+/// let mut worker = timely::thread_worker();
+/// worker.dataflow::<u64,_,_>(|scope| {
+///     // an operator that retains a capability it never releases.
+///     let mut held = None;
+///     (0..10).to_stream(scope)
+///            .unary(Pipeline, "Hoarder", |_capability, _info| move |input, output| {
+///                input.for_each(|time, data| {
+///                    output.session(&time).give_vec(&mut data.replace(Vec::new()));
+///                    held = Some(time.retain());
+///                });
+///            })
+///            .inspect(|x: &u64| println!("seen: {:?}", x));
+/// });
+///
+/// for _ in 0 .. 10 { worker.step(); }
+///
+/// let suspects = audit::suspects();
+/// assert_eq!(suspects.len(), 1);
+/// assert_eq!(suspects[0].name, "Hoarder");
+/// println!("{}", audit::report());
+/// audit::disable();
+/// ```
+pub fn enable() {
+    AUDIT.with(|audit| {
+        let mut audit = audit.borrow_mut();
+        if audit.is_none() {
+            *audit = Some(Audit { next_id: 1, ..Default::default() });
+            ENABLED.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Disables auditing for this thread, discarding its events.
+pub fn disable() {
+    AUDIT.with(|audit| {
+        if audit.borrow_mut().take().is_some() {
+            ENABLED.fetch_sub(1, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Returns true if auditing is enabled for this thread.
+///
+/// This reads only a global counter unless some thread has enabled auditing.
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) > 0 && AUDIT.with(|audit| audit.borrow().is_some())
+}
+
+/// The text of the backtrace with identifier `id`, as recorded in events.
+pub fn backtrace(id: usize) -> Option<String> {
+    AUDIT.with(|audit| audit.borrow().as_ref().and_then(|audit| audit.backtraces.get(id).cloned()))
+}
+
+/// The events recorded by this thread, in order.
+pub fn events() -> Vec<CapabilityEvent> {
+    AUDIT.with(|audit| audit.borrow().as_ref().map(|audit| audit.events.clone()).unwrap_or(Vec::new()))
+}
+
+/// The events recorded by this thread for the capability `id` and those derived from it, in order.
+pub fn lineage(id: usize) -> Vec<CapabilityEvent> {
+    let mut ids = vec![id];
+    let mut result = Vec::new();
+    for event in events() {
+        if ids.contains(&event.id) {
+            result.push(event);
+        }
+        else if event.parent.map(|parent| ids.contains(&parent)).unwrap_or(false) {
+            ids.push(event.id);
+            result.push(event);
+        }
+    }
+    result
+}
+
+/// The identifiers and times of capabilities held by this thread, in order of identifier.
+pub fn live() -> Vec<(usize, String)> {
+    AUDIT.with(|audit| {
+        audit.borrow().as_ref().map(|audit| {
+            audit.live.iter().map(|(id, &(ref time, _, _))| (*id, time.clone())).collect()
+        }).unwrap_or(Vec::new())
+    })
+}
+
+/// Capabilities held by operators of this thread whose inputs have all closed.
+///
+/// Operators without inputs, and operators that are not generic operators, are never reported.
+pub fn suspects() -> Vec<Suspect> {
+    AUDIT.with(|audit| {
+        let audit = audit.borrow();
+        let audit = match audit.as_ref() { Some(audit) => audit, None => return Vec::new() };
+        audit.live.iter().filter_map(|(id, &(ref time, key, backtrace))| {
+            audit.outputs.get(&key).and_then(|output| {
+                if audit.closed.get(&output.operator).cloned().unwrap_or(false) {
+                    Some(Suspect {
+                        id: *id,
+                        time: time.clone(),
+                        name: output.name.clone(),
+                        operator: output.operator.clone(),
+                        output: output.port,
+                        backtrace,
+                    })
+                }
+                else { None }
+            })
+        }).collect()
+    })
+}
+
+/// Formats the capabilities reported by `suspects`, each followed by its creation backtrace, if any.
+pub fn report() -> String {
+    let mut report = String::new();
+    for suspect in suspects() {
+        report.push_str(&format!("capability {} at {} held by {} {:?} output {} with all inputs closed\n",
+            suspect.id, suspect.time, suspect.name, suspect.operator, suspect.output));
+        if let Some(backtrace) = suspect.backtrace.and_then(backtrace) {
+            report.push_str(&backtrace);
+            report.push('\n');
+        }
+    }
+    report
+}
+
+/// Registers the output whose capabilities update the shared state at `key`.
+pub fn register_output(key: usize, name: &str, operator: &[usize], port: usize) {
+    AUDIT.with(|audit| {
+        if let Some(ref mut audit) = *audit.borrow_mut() {
+            audit.outputs.insert(key, Output { name: name.to_owned(), operator: operator.to_vec(), port });
+        }
+    });
+}
+
+/// Notes whether the inputs of the operator at `operator` have all closed.
+pub fn note_inputs_closed(operator: &[usize], closed: bool) {
+    AUDIT.with(|audit| {
+        if let Some(ref mut audit) = *audit.borrow_mut() {
+            audit.closed.insert(operator.to_vec(), closed);
+        }
+    });
+}
+
+/// Records `action` applied to the capability `id` for the output at `key`, returning its identifier.
+///
+/// Actions that create capabilities (`Mint`, `Clone`, and `Delay`) assign a new identifier, and
+/// record `id` as the parent if it is non-zero. Returns zero if auditing is not enabled.
+pub fn record<T: Debug>(id: usize, action: CapabilityAction, time: &T, key: usize) -> usize {
+    AUDIT.with(|audit| {
+        if let Some(ref mut audit) = *audit.borrow_mut() {
+            let time = format!("{:?}", time);
+            let (id, parent) = match action {
+                CapabilityAction::Mint | CapabilityAction::Clone | CapabilityAction::Delay => {
+                    audit.next_id += 1;
+                    (audit.next_id - 1, if id == 0 { None } else { Some(id) })
+                },
+                CapabilityAction::Downgrade | CapabilityAction::Drop => (id, None),
+            };
+            // capabilities minted before auditing was enabled have identifier zero, and are not tracked.
+            if id != 0 {
+                let backtrace = match action {
+                    CapabilityAction::Mint | CapabilityAction::Clone | CapabilityAction::Delay => audit.backtrace(),
+                    CapabilityAction::Downgrade | CapabilityAction::Drop => audit.live.get(&id).and_then(|live| live.2),
+                };
+                if action == CapabilityAction::Drop { audit.live.remove(&id); }
+                else { audit.live.insert(id, (time.clone(), key, backtrace)); }
+                let operator = audit.outputs.get(&key).map(|output| output.operator.clone());
+                let output = audit.outputs.get(&key).map(|output| output.port).unwrap_or(0);
+                audit.events.push(CapabilityEvent { id, parent, action, time, operator, output, backtrace });
+            }
+            id
+        }
+        else { 0 }
+    })
+}
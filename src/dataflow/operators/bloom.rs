@@ -0,0 +1,233 @@
+//! Approximate semi-joins and anti-joins of streams against Bloom filters of keys.
+//!
+//! Joining a large stream against a smaller set of keys usually exchanges the large stream so that
+//! its records meet the keys. The operators here instead build a `BloomFilter` of each worker's keys
+//! at each timestamp, broadcast the filters to all workers, and filter the large stream where it is,
+//! against the union of the filters for the records' timestamps. A semi-join keeps the records whose
+//! keys may be present, including some whose keys are absent (false positives), and so is suitable for
+//! discarding most records before an exact join. An anti-join keeps the records whose keys are certainly
+//! absent, and so discards some records whose keys are absent too.
+//!
+//! Records are held until the filters of their timestamp are complete, meaning that the frontier of
+//! the stream of keys has passed the timestamp.
+
+use std::hash::{Hash, Hasher};
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::{DefaultHasher, Entry};
+
+use {Data, ExchangeData};
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::Broadcast;
+use dataflow::operators::generic::Operator;
+
+/// A set of hashes, which may report values as present that were never inserted.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::bloom::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_rate(1000, 0.01);
+/// for key in 0 .. 1000 {
+///     filter.insert(&key);
+/// }
+///
+/// assert!((0 .. 1000).all(|key| filter.contains(&key)));
+/// assert!((1000 .. 11000).filter(|key| filter.contains(key)).count() < 200);
+/// ```
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Allocates an empty filter of at least `bits` bits, setting `hashes` bits for each value.
+    pub fn new(bits: usize, hashes: u32) -> Self {
+        assert!(bits > 0 && hashes > 0, "a filter must have bits and hashes");
+        BloomFilter {
+            bits: vec![0; (bits + 63) / 64],
+            hashes,
+        }
+    }
+
+    /// Allocates an empty filter which, holding `expected` values, reports absent values as present with
+    /// probability about `rate`.
+    pub fn with_rate(expected: usize, rate: f64) -> Self {
+        assert!(rate > 0.0 && rate < 1.0, "rate must be between zero and one");
+        let ln2 = 2.0f64.ln();
+        let expected = if expected > 0 { expected as f64 } else { 1.0 };
+        let bits = (-expected * rate.ln() / (ln2 * ln2)).ceil();
+        let hashes = (bits / expected * ln2).round();
+        BloomFilter::new(bits as usize, if hashes < 1.0 { 1 } else { hashes as u32 })
+    }
+
+    /// Inserts `value`.
+    pub fn insert<V: Hash>(&mut self, value: &V) {
+        let bits = (self.bits.len() * 64) as u64;
+        let (first, second) = hashes(value);
+        for index in 0 .. self.hashes as u64 {
+            let bit = first.wrapping_add(index.wrapping_mul(second)) % bits;
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns false if `value` was certainly not inserted, and true if it may have been.
+    pub fn contains<V: Hash>(&self, value: &V) -> bool {
+        let bits = (self.bits.len() * 64) as u64;
+        let (first, second) = hashes(value);
+        (0 .. self.hashes as u64).all(|index| {
+            let bit = first.wrapping_add(index.wrapping_mul(second)) % bits;
+            self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    /// Inserts the values inserted into `other`.
+    ///
+    /// Panics if the filters have different sizes or numbers of hashes.
+    pub fn merge(&mut self, other: &BloomFilter) {
+        assert!(self.bits.len() == other.bits.len() && self.hashes == other.hashes, "merged filters must have the same shape");
+        for (bits, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *bits |= *other;
+        }
+    }
+}
+
+/// The two hashes of `value` from which a filter derives the bits it sets.
+fn hashes<V: Hash>(value: &V) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    let hash = hasher.finish();
+    // an odd second hash visits distinct bits for each of the filter's hashes.
+    (hash & 0xFFFF_FFFF, (hash >> 32) | 1)
+}
+
+/// Methods to filter a stream against Bloom filters of the keys of another stream.
+pub trait BloomJoin<G: Scope, D: Data> {
+    /// Produces the records of this stream whose keys, as extracted by `key`, may be among the records of
+    /// `keys` at the same timestamp.
+    ///
+    /// The filters of each timestamp are sized to hold `expected` keys at each worker, with a false
+    /// positive rate of about `rate`.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::bloom::BloomJoin;
+    ///
+    /// timely::example(|scope| {
+    ///     let keys = (0..10u64).to_stream(scope);
+    ///     (0..1000u64).to_stream(scope)
+    ///                 .bloom_semijoin(&keys, |x| x % 100, 10, 0.01)
+    ///                 .inspect(|x| println!("may match: {:?}", x));
+    /// });
+    /// ```
+    fn bloom_semijoin<K, F>(&self, keys: &Stream<G, K>, key: F, expected: usize, rate: f64) -> Stream<G, D>
+    where
+        K: ExchangeData+Hash,
+        F: Fn(&D)->K+'static;
+
+    /// Produces the records of this stream whose keys, as extracted by `key`, are certainly not among the
+    /// records of `keys` at the same timestamp.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::bloom::BloomJoin;
+    ///
+    /// timely::example(|scope| {
+    ///     let keys = (0..10u64).to_stream(scope);
+    ///     (0..1000u64).to_stream(scope)
+    ///                 .bloom_antijoin(&keys, |x| x % 100, 10, 0.01)
+    ///                 .inspect(|x| assert!(x % 100 >= 10));
+    /// });
+    /// ```
+    fn bloom_antijoin<K, F>(&self, keys: &Stream<G, K>, key: F, expected: usize, rate: f64) -> Stream<G, D>
+    where
+        K: ExchangeData+Hash,
+        F: Fn(&D)->K+'static;
+}
+
+impl<G: Scope, D: Data> BloomJoin<G, D> for Stream<G, D> {
+    fn bloom_semijoin<K, F>(&self, keys: &Stream<G, K>, key: F, expected: usize, rate: f64) -> Stream<G, D>
+    where
+        K: ExchangeData+Hash,
+        F: Fn(&D)->K+'static {
+        bloom_filter(self, keys, key, expected, rate, true)
+    }
+
+    fn bloom_antijoin<K, F>(&self, keys: &Stream<G, K>, key: F, expected: usize, rate: f64) -> Stream<G, D>
+    where
+        K: ExchangeData+Hash,
+        F: Fn(&D)->K+'static {
+        bloom_filter(self, keys, key, expected, rate, false)
+    }
+}
+
+/// Produces the records of `stream` whose keys the filters of `keys` report as present, or if not
+/// `present`, as absent.
+fn bloom_filter<G, D, K, F>(stream: &Stream<G, D>, keys: &Stream<G, K>, key: F, expected: usize, rate: f64, present: bool) -> Stream<G, D>
+where
+    G: Scope,
+    D: Data,
+    K: ExchangeData+Hash,
+    F: Fn(&D)->K+'static {
+
+    // build a filter of each worker's keys at each timestamp, and share it with all workers.
+    let mut building: HashMap<G::Timestamp, _> = HashMap::new();
+    let filters = keys.unary_frontier(Pipeline, "BloomBuild", move |_capability, _info| move |input, output| {
+        input.for_each(|time, data| {
+            let entry = building.entry(time.time().clone()).or_insert_with(|| (time.retain(), BloomFilter::with_rate(expected, rate)));
+            for key in data.iter() {
+                entry.1.insert(key);
+            }
+        });
+        let frontier = input.frontier();
+        building.retain(|time, &mut (ref capability, ref filter)| {
+            if frontier.less_equal(time) { true }
+            else {
+                output.session(capability).give(filter.clone());
+                false
+            }
+        });
+    })
+    .broadcast();
+
+    let mut merged: HashMap<G::Timestamp, BloomFilter> = HashMap::new();
+    let mut pending = BTreeMap::new();
+
+    stream.binary_frontier(&filters, Pipeline, Pipeline, "BloomFilter", move |_capability, _info| move |input1, input2, output| {
+
+        input1.for_each(|time, data| {
+            let entry = pending.entry(time.time().clone()).or_insert_with(|| (time.retain(), Vec::new()));
+            entry.1.extend(data.replace(Vec::new()));
+        });
+        input2.for_each(|time, data| {
+            for filter in data.replace(Vec::new()) {
+                match merged.entry(time.time().clone()) {
+                    Entry::Occupied(mut existing) => existing.get_mut().merge(&filter),
+                    Entry::Vacant(vacant) => { vacant.insert(filter); },
+                }
+            }
+        });
+
+        // filter records of times whose filters are complete.
+        let frontier1 = input1.frontier();
+        let frontier2 = input2.frontier();
+        let ready = pending.keys().filter(|time| !frontier2.less_equal(time)).cloned().collect::<Vec<_>>();
+        for time in ready {
+            let (capability, records) = pending.remove(&time).expect("missing records");
+            let filter = merged.get(&time);
+            let mut session = output.session(&capability);
+            for record in records {
+                let contained = filter.map(|filter| filter.contains(&key(&record))).unwrap_or(false);
+                if contained == present {
+                    session.give(record);
+                }
+            }
+        }
+
+        // discard filters once no more records can arrive at their times.
+        merged.retain(|time, _| frontier1.less_equal(time) || frontier2.less_equal(time));
+    })
+}
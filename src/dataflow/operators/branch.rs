@@ -40,6 +40,7 @@ impl<S: Scope, D: Data> Branch<S, D> for Stream<S, D> {
         condition: impl Fn(&S::Timestamp, &D) -> bool + 'static,
     ) -> (Stream<S, D>, Stream<S, D>) {
         let mut builder = OperatorBuilder::new("Branch".to_owned(), self.scope());
+        builder.set_notify(false);
 
         let mut input = builder.new_input(self, Pipeline);
         let (mut output1, stream1) = builder.new_output();
@@ -77,6 +78,13 @@ pub trait BranchWhen<S: Scope, D: Data> {
     /// the records for that will be sent to the second returned stream, otherwise
     /// they will be sent to the first.
     ///
+    /// The closure is called once for each received batch rather than for each
+    /// record, before the batch's records are examined, and batches are forwarded
+    /// whole to the selected output with `Session::forward`. The records are
+    /// neither inspected, copied, nor deserialized: typed batches are handed on
+    /// as they are, and only a batch that arrives serialized is decoded, as it
+    /// must be to produce owned records for the output.
+    ///
     /// #Examples
     /// ```
     /// use timely::dataflow::operators::{ToStream, BranchWhen, Inspect, Delay};
@@ -103,27 +111,25 @@ impl<S: Scope, D: Data> BranchWhen<S, D> for Stream<S, D> {
         &self,
         condition: impl Fn(&S::Timestamp) -> bool + 'static,
     ) -> (Stream<S, D>, Stream<S, D>) {
-        let mut builder = OperatorBuilder::new("Branch".to_owned(), self.scope());
+        let mut builder = OperatorBuilder::new("BranchWhen".to_owned(), self.scope());
+        builder.set_notify(false);
 
         let mut input = builder.new_input(self, Pipeline);
         let (mut output1, stream1) = builder.new_output();
         let (mut output2, stream2) = builder.new_output();
 
         builder.build(move |_| {
-
-            let mut vector = Vec::new();
             move |_frontiers| {
                 let mut output1_handle = output1.activate();
                 let mut output2_handle = output2.activate();
 
                 input.for_each(|time, data| {
-                    data.swap(&mut vector);
                     let mut out = if condition(&time.time()) {
                         output2_handle.session(&time)
                     } else {
                         output1_handle.session(&time)
                     };
-                    out.give_vec(&mut vector);
+                    out.forward(data);
                 });
             }
         });
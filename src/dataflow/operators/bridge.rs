@@ -0,0 +1,161 @@
+//! A bounded channel bridging external threads to a worker's input.
+//!
+//! External threads produce records through a `BridgeSender`, which blocks (or reports failure, with
+//! `try_send`) once the channel holds `capacity` records. The worker moves records from the paired
+//! `BridgeReceiver` into an `InputHandle` between calls to `step`, advancing the input's epoch as
+//! directed by an `AdvancePolicy`. Because the channel is bounded, producers are slowed to the rate
+//! at which the worker drains the channel.
+
+use std::sync::mpsc::{sync_channel, SyncSender, Receiver, SendError, TrySendError, TryRecvError};
+use std::time::{Duration, Instant};
+
+use Data;
+use dataflow::operators::input::Handle as InputHandle;
+
+/// Describes when a `BridgeReceiver` advances the epoch of the input it drains into.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AdvancePolicy {
+    /// Never advance the epoch; the worker is responsible for advancing the input.
+    Manual,
+    /// Advance the epoch after each call to `drain_into` that moved any records.
+    Drain,
+    /// Advance the epoch each time the given number of records have been moved.
+    Records(usize),
+    /// Advance the epoch once the given duration has elapsed since the previous advance.
+    Interval(Duration),
+}
+
+/// Creates a bounded bridge holding at most `capacity` records, and advancing epochs by `policy`.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{Input, Inspect, Probe};
+/// use timely::dataflow::operators::bridge::{bridge, AdvancePolicy};
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let (mut input, probe) = worker.dataflow(|scope| {
+///         let (input, stream) = scope.new_input();
+///         let probe = stream.inspect(|x: &u64| println!("received: {:?}", x)).probe();
+///         (input, probe)
+///     });
+///
+///     let (sender, mut receiver) = bridge(16, AdvancePolicy::Records(10));
+///     let producer = ::std::thread::spawn(move || {
+///         for record in 0..100 {
+///             sender.send(record).unwrap();
+///         }
+///     });
+///
+///     while !receiver.is_disconnected() {
+///         receiver.drain_into(&mut input);
+///         worker.step();
+///     }
+///     producer.join().unwrap();
+///
+///     assert_eq!(input.epoch(), &10);
+///     input.close();
+///     worker.step_while(|| !probe.done());
+/// }).unwrap();
+/// ```
+pub fn bridge<D: Data>(capacity: usize, policy: AdvancePolicy) -> (BridgeSender<D>, BridgeReceiver<D>) {
+    let (sender, receiver) = sync_channel(capacity);
+    let sender = BridgeSender { sender };
+    let receiver = BridgeReceiver {
+        receiver,
+        capacity,
+        policy,
+        records: 0,
+        advanced: Instant::now(),
+        disconnected: false,
+    };
+    (sender, receiver)
+}
+
+/// The sending half of a bridge, which may be cloned and moved to other threads.
+pub struct BridgeSender<D> {
+    sender: SyncSender<D>,
+}
+
+impl<D> Clone for BridgeSender<D> {
+    fn clone(&self) -> Self {
+        BridgeSender { sender: self.sender.clone() }
+    }
+}
+
+impl<D> BridgeSender<D> {
+    /// Sends a record, blocking while the bridge is full.
+    ///
+    /// An error is returned, with the record, if the receiving half has been dropped.
+    pub fn send(&self, data: D) -> Result<(), SendError<D>> {
+        self.sender.send(data)
+    }
+
+    /// Sends a record if the bridge is not full, without blocking.
+    pub fn try_send(&self, data: D) -> Result<(), TrySendError<D>> {
+        self.sender.try_send(data)
+    }
+}
+
+/// The receiving half of a bridge, held by a worker.
+pub struct BridgeReceiver<D> {
+    receiver: Receiver<D>,
+    capacity: usize,
+    policy: AdvancePolicy,
+    /// Records moved since the epoch last advanced.
+    records: usize,
+    /// The instant the epoch last advanced.
+    advanced: Instant,
+    disconnected: bool,
+}
+
+impl<D: Data> BridgeReceiver<D> {
+
+    /// Moves available records into `input`, advancing its epoch according to the bridge's policy.
+    ///
+    /// At most `capacity` records are moved per call, so that a worker is not held indefinitely by
+    /// fast producers. Returns the number of records moved.
+    pub fn drain_into(&mut self, input: &mut InputHandle<u64, D>) -> usize {
+        let mut moved = 0;
+        while moved < self.capacity.max(1) {
+            match self.receiver.try_recv() {
+                Ok(data) => {
+                    input.send(data);
+                    moved += 1;
+                    self.records += 1;
+                    if let AdvancePolicy::Records(records) = self.policy {
+                        if self.records >= records {
+                            self.advance(input);
+                        }
+                    }
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => { self.disconnected = true; break; },
+            }
+        }
+
+        match self.policy {
+            AdvancePolicy::Drain if moved > 0 => self.advance(input),
+            AdvancePolicy::Interval(interval) if self.advanced.elapsed() >= interval => self.advance(input),
+            _ => { },
+        }
+
+        moved
+    }
+
+    /// Returns true once all senders have been dropped and every record has been received.
+    ///
+    /// This is only updated by `drain_into`; once it returns true, no further records will arrive
+    /// and the worker may close its input.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Advances the epoch of `input` by one, resetting the policy's counters.
+    fn advance(&mut self, input: &mut InputHandle<u64, D>) {
+        let next = *input.epoch() + 1;
+        input.advance_to(next);
+        self.records = 0;
+        self.advanced = Instant::now();
+    }
+}
@@ -55,7 +55,7 @@ impl<G: Scope, D: ExchangeData> Broadcast<D> for Stream<G, D> {
 
         for (i, pusher) in pushers.into_iter().enumerate() {
             let sender = LogPusher::new(pusher, scope.index(), i, channel_id, comm_chan, scope.logging());
-            self.connect_to(Target { index: operator_index, port: i }, sender, channel_id);
+            self.connect_to_via(Target { index: operator_index, port: i }, sender, channel_id, "Broadcast", comm_chan);
         }
 
         Stream::new(Source { index: operator_index, port: 0 }, registrar, scope)
@@ -29,6 +29,7 @@ use std::fmt::{self, Debug};
 use order::PartialOrder;
 use progress::Timestamp;
 use progress::ChangeBatch;
+use dataflow::operators::audit::{self, CapabilityAction};
 
 /// An internal trait expressing the capability to send messages with a given timestamp.
 pub trait CapabilityTrait<T: Timestamp> {
@@ -59,6 +60,7 @@ impl<'a, T: Timestamp, C: CapabilityTrait<T>> CapabilityTrait<T> for &'a mut C {
 pub struct Capability<T: Timestamp> {
     time: T,
     internal: Rc<RefCell<ChangeBatch<T>>>,
+    audit: usize,
 }
 
 impl<T: Timestamp> CapabilityTrait<T> for Capability<T> {
@@ -84,7 +86,7 @@ impl<T: Timestamp> Capability<T> {
         if !self.time.less_equal(new_time) {
             panic!("Attempted to delay {:?} to {:?}, which is not `less_equal` the capability's time.", self, new_time);
         }
-        mint(new_time.clone(), self.internal.clone())
+        mint_audited(new_time.clone(), self.internal.clone(), self.audit, CapabilityAction::Delay)
     }
 
     /// Downgrades the capability to one corresponding to `new_time`.
@@ -92,8 +94,18 @@ impl<T: Timestamp> Capability<T> {
     /// This method panics if `self.time` is not less or equal to `new_time`.
     #[inline(always)]
     pub fn downgrade(&mut self, new_time: &T) {
-        let new_cap = self.delayed(new_time);
-        *self = new_cap;
+        if !self.time.less_equal(new_time) {
+            panic!("Attempted to downgrade {:?} to {:?}, which is not `less_equal` the capability's time.", self, new_time);
+        }
+        {
+            let mut borrow = self.internal.borrow_mut();
+            borrow.update(new_time.clone(), 1);
+            borrow.update(self.time.clone(), -1);
+        }
+        self.time = new_time.clone();
+        if self.audit != 0 {
+            audit::record(self.audit, CapabilityAction::Downgrade, &self.time, self.internal.as_ptr() as usize);
+        }
     }
 }
 
@@ -102,10 +114,18 @@ impl<T: Timestamp> Capability<T> {
 /// Declared separately so that it can be kept private when `Capability` is re-exported.
 #[inline(always)]
 pub fn mint<T: Timestamp>(time: T, internal: Rc<RefCell<ChangeBatch<T>>>) -> Capability<T> {
+    mint_audited(time, internal, 0, CapabilityAction::Mint)
+}
+
+/// Creates a new capability at `t`, recording its creation from the capability `parent` if auditing.
+#[inline(always)]
+fn mint_audited<T: Timestamp>(time: T, internal: Rc<RefCell<ChangeBatch<T>>>, parent: usize, action: CapabilityAction) -> Capability<T> {
     internal.borrow_mut().update(time.clone(), 1);
+    let audit = if audit::enabled() { audit::record(parent, action, &time, internal.as_ptr() as usize) } else { 0 };
     Capability {
         time,
         internal,
+        audit,
     }
 }
 
@@ -116,13 +136,16 @@ impl<T: Timestamp> Drop for Capability<T> {
     #[inline]
     fn drop(&mut self) {
         self.internal.borrow_mut().update(self.time.clone(), -1);
+        if self.audit != 0 {
+            audit::record(self.audit, CapabilityAction::Drop, &self.time, self.internal.as_ptr() as usize);
+        }
     }
 }
 
 impl<T: Timestamp> Clone for Capability<T> {
     #[inline]
     fn clone(&self) -> Capability<T> {
-        mint(self.time.clone(), self.internal.clone())
+        mint_audited(self.time.clone(), self.internal.clone(), self.audit, CapabilityAction::Clone)
     }
 }
 
@@ -0,0 +1,130 @@
+//! Operators that catch the panics of their logic, and report them as timestamped errors.
+//!
+//! A panic in operator logic ordinarily unwinds the worker, and so stops the computation. Long-running
+//! computations may prefer to continue without the records whose processing failed. The operators in
+//! this module run their logic on each batch of records under `catch_unwind`, and convert a panic
+//! into an `OperatorError` at the batch's time, on a separate error stream. An `ErrorPolicy` decides
+//! whether the operator continues with subsequent batches, or discards them once poisoned.
+//!
+//! Outputs produced by a batch whose logic panics are discarded. State the logic mutates may be left
+//! inconsistent by a panic, and logic should only be run this way if that is acceptable.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use Data;
+use communication::panic_message;
+use dataflow::channels::pact::Pipeline;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+/// The failure of operator logic at a time.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OperatorError<T> {
+    /// The time of the batch whose processing failed.
+    pub time: T,
+    /// The name of the operator.
+    pub operator: String,
+    /// The message of the panic.
+    pub message: String,
+}
+
+/// Describes what an operator does after its logic panics.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ErrorPolicy {
+    /// Continue processing subsequent batches.
+    Continue,
+    /// Discard all subsequent batches, reporting each as an error.
+    Poison,
+}
+
+/// Methods to apply logic to batches of records, catching its panics.
+pub trait Catch<G: Scope, D: Data> {
+    /// Applies `logic` to the time and records of each batch, producing the records it pushes at the
+    /// batch's time, and an error at the batch's time for each batch whose logic panics.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::catch::{Catch, ErrorPolicy};
+    ///
+    /// timely::example(|scope| {
+    ///     let (parsed, errors) =
+    ///     vec!["1", "2", "three"].into_iter()
+    ///         .map(|x| x.to_owned())
+    ///         .to_stream(scope)
+    ///         .unary_catch("Parse", ErrorPolicy::Continue, |_time, data, output| {
+    ///             for text in data {
+    ///                 output.push(text.parse::<u64>().expect("malformed number"));
+    ///             }
+    ///         });
+    ///
+    ///     parsed.inspect(|x| println!("parsed: {:?}", x));
+    ///     errors.inspect(|e| assert!(e.message.starts_with("malformed number")));
+    /// });
+    /// ```
+    fn unary_catch<D2, L>(&self, name: &str, policy: ErrorPolicy, logic: L) -> (Stream<G, D2>, Stream<G, OperatorError<G::Timestamp>>)
+    where
+        D2: Data,
+        L: FnMut(&G::Timestamp, Vec<D>, &mut Vec<D2>)+'static;
+}
+
+impl<G: Scope, D: Data> Catch<G, D> for Stream<G, D> {
+    fn unary_catch<D2, L>(&self, name: &str, policy: ErrorPolicy, mut logic: L) -> (Stream<G, D2>, Stream<G, OperatorError<G::Timestamp>>)
+    where
+        D2: Data,
+        L: FnMut(&G::Timestamp, Vec<D>, &mut Vec<D2>)+'static {
+
+        let mut builder = OperatorBuilder::new(name.to_owned(), self.scope());
+
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut output, stream) = builder.new_output();
+        let (mut error_output, error_stream) = builder.new_output();
+
+        let name = name.to_owned();
+
+        builder.build(move |_| {
+
+            let mut poisoned = None;
+            let mut produced = Vec::new();
+
+            move |_frontiers| {
+
+                let mut output_handle = output.activate();
+                let mut error_handle = error_output.activate();
+
+                input.for_each(|time, data| {
+                    let data = data.replace(Vec::new());
+                    let message = match poisoned {
+                        Some(ref message) => Some(format!("operator poisoned: {}", message)),
+                        None => {
+                            let result = catch_unwind(AssertUnwindSafe(|| logic(time.time(), data, &mut produced)));
+                            match result {
+                                Ok(()) => {
+                                    output_handle.session(&time).give_vec(&mut produced);
+                                    None
+                                },
+                                Err(payload) => {
+                                    produced.clear();
+                                    let message = panic_message(&*payload);
+                                    if policy == ErrorPolicy::Poison {
+                                        poisoned = Some(message.clone());
+                                    }
+                                    Some(message)
+                                },
+                            }
+                        },
+                    };
+                    if let Some(message) = message {
+                        error_handle.session(&time).give(OperatorError {
+                            time: time.time().clone(),
+                            operator: name.clone(),
+                            message,
+                        });
+                    }
+                });
+            }
+        });
+
+        (stream, error_stream)
+    }
+}
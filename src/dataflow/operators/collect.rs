@@ -0,0 +1,159 @@
+//! Collect the contents of a stream for inspection outside the dataflow.
+//!
+//! The `collect` operator captures a stream into a channel, and returns a `Handle` the worker can
+//! drain between calls to `step`. Each batch is tagged with its timestamp, and the handle tracks the
+//! frontier of the stream so that the worker can determine which times are complete.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+
+use Data;
+use progress::Timestamp;
+use progress::frontier::{AntichainRef, MutableAntichain};
+use dataflow::{Stream, Scope};
+use dataflow::operators::capture::{Capture, Event};
+
+/// Collects the records of a stream into a `Handle`.
+pub trait Collect<G: Scope, D: Data> {
+    /// Collects the records of the stream, for retrieval through the returned `Handle`.
+    ///
+    /// The handle only receives records at the local worker; use `exchange` beforehand to
+    /// route the records of interest to a particular worker.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{Input, Collect};
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, mut output) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input();
+    ///         (input, stream.collect())
+    ///     });
+    ///
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| output.less_equal(&RootTimestamp::new(round)));
+    ///         assert_eq!(output.drain_complete(), vec![(RootTimestamp::new(round), vec![round])]);
+    ///     }
+    /// }).unwrap();
+    /// ```
+    fn collect(&self) -> Handle<G::Timestamp, D>;
+}
+
+impl<G: Scope, D: Data> Collect<G, D> for Stream<G, D> {
+    fn collect(&self) -> Handle<G::Timestamp, D> {
+        let (send, recv) = ::std::sync::mpsc::channel();
+        self.capture_into(send);
+        Handle::new(recv)
+    }
+}
+
+/// Receives the timestamped batches and frontier of a collected stream.
+pub struct Handle<T: Timestamp, D> {
+    receiver: Receiver<Event<T, D>>,
+    frontier: MutableAntichain<T>,
+    stash: VecDeque<(T, Vec<D>)>,
+}
+
+impl<T: Timestamp, D> Handle<T, D> {
+
+    fn new(receiver: Receiver<Event<T, D>>) -> Self {
+        Handle {
+            receiver,
+            frontier: MutableAntichain::new_bottom(Default::default()),
+            stash: VecDeque::new(),
+        }
+    }
+
+    /// Moves all received events into the handle, updating the frontier.
+    fn pull(&mut self) {
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                Event::Progress(updates) => self.frontier.update_iter(updates),
+                Event::Messages(time, data) => self.stash.push_back((time, data)),
+            }
+        }
+    }
+
+    /// Returns the next received batch, if one exists.
+    ///
+    /// Batches are returned in the order they were received, which may not be the order of their
+    /// timestamps. Their times may still be incomplete; consult `frontier` or use `drain_complete`.
+    pub fn next(&mut self) -> Option<(T, Vec<D>)> {
+        self.pull();
+        self.stash.pop_front()
+    }
+
+    /// Returns all received batches, in the order they were received.
+    pub fn drain(&mut self) -> Vec<(T, Vec<D>)> {
+        self.pull();
+        self.stash.drain(..).collect()
+    }
+
+    /// Returns received batches at times the frontier has passed, sorted by time.
+    ///
+    /// Batches at the same time are merged into one batch. Batches at times that may still receive
+    /// records are retained in the handle.
+    pub fn drain_complete(&mut self) -> Vec<(T, Vec<D>)> {
+        self.pull();
+        let mut complete = Vec::new();
+        let mut retained = VecDeque::new();
+        for (time, data) in self.stash.drain(..) {
+            if self.frontier.less_equal(&time) {
+                retained.push_back((time, data));
+            }
+            else {
+                complete.push((time, data));
+            }
+        }
+        self.stash = retained;
+
+        complete.sort_by(|x,y| x.0.cmp(&y.0));
+        let mut result: Vec<(T, Vec<D>)> = Vec::new();
+        for (time, mut data) in complete {
+            let merge = result.last().map(|x| x.0 == time).unwrap_or(false);
+            if merge {
+                result.last_mut().unwrap().1.append(&mut data);
+            }
+            else {
+                result.push((time, data));
+            }
+        }
+        result
+    }
+
+    /// Reveals the current frontier of the collected stream.
+    pub fn frontier(&mut self) -> AntichainRef<T> {
+        self.pull();
+        self.frontier.frontier()
+    }
+
+    /// Returns true iff the frontier is strictly less than `time`.
+    pub fn less_than(&mut self, time: &T) -> bool {
+        self.pull();
+        self.frontier.less_than(time)
+    }
+
+    /// Returns true iff the frontier is less than or equal to `time`.
+    pub fn less_equal(&mut self, time: &T) -> bool {
+        self.pull();
+        self.frontier.less_equal(time)
+    }
+
+    /// Returns true iff the collected stream is complete, meaning its frontier is empty.
+    ///
+    /// Received batches may remain in the handle even once the stream is complete.
+    pub fn done(&mut self) -> bool {
+        self.pull();
+        self.frontier.is_empty()
+    }
+
+    /// Returns true iff no batches are currently held by the handle.
+    pub fn is_empty(&mut self) -> bool {
+        self.pull();
+        self.stash.is_empty()
+    }
+}
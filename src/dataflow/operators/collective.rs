@@ -0,0 +1,162 @@
+//! Collective operators on streams of vectors, in the style of MPI.
+//!
+//! Each worker contributes some number of vectors at each time, and the vectors are combined
+//! position by position using a supplied operator. Vectors of differing lengths are permitted;
+//! positions present in only some vectors take the combination of only those values.
+//!
+//! The combination is distributed: consecutive blocks of positions are assigned to workers, so that
+//! each worker performs a fraction of the work and only receives the values for its blocks.
+
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use ::ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::{Pipeline, Exchange};
+use dataflow::operators::broadcast::Broadcast;
+use dataflow::operators::generic::operator::Operator;
+
+/// Number of consecutive vector positions reduced together at the same worker.
+const BLOCK_LENGTH: usize = 1 << 10;
+
+/// Collective operators on streams of vectors.
+pub trait Collective<G: Scope, D: ExchangeData> {
+    /// Combines the vectors at each time, delivering each worker a disjoint part of the result.
+    ///
+    /// The output records are pairs `(offset, values)` where `values` are the combined values
+    /// of the positions starting at `offset`. Across all workers, each position of the combined
+    /// vector is produced exactly once, and the records are produced once their time is complete.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::collective::Collective;
+    ///
+    /// timely::example(|scope| {
+    ///     vec![vec![1, 2, 3], vec![4, 5, 6]]
+    ///         .to_stream(scope)
+    ///         .reduce_scatter(|x, y| *x += y)
+    ///         .inspect(|x| assert_eq!(x, &(0, vec![5, 7, 9])));
+    /// });
+    /// ```
+    fn reduce_scatter(&self, op: impl Fn(&mut D, D)+'static) -> Stream<G, (usize, Vec<D>)>;
+
+    /// Combines the vectors at each time, delivering the complete result to every worker.
+    ///
+    /// Each worker produces one vector at each time for which any worker supplied input, once
+    /// that time is complete.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::collective::Collective;
+    ///
+    /// timely::execute(timely::Configuration::Process(3), |worker| {
+    ///     let index = worker.index();
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         vec![vec![index; 4]]
+    ///             .to_stream(scope)
+    ///             .allreduce(|x, y| *x += y)
+    ///             .inspect(|x| assert_eq!(x, &vec![3; 4]));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    fn allreduce(&self, op: impl Fn(&mut D, D)+'static) -> Stream<G, Vec<D>>;
+}
+
+impl<G: Scope, D: ExchangeData> Collective<G, D> for Stream<G, Vec<D>> {
+
+    fn reduce_scatter(&self, op: impl Fn(&mut D, D)+'static) -> Stream<G, (usize, Vec<D>)> {
+
+        let op1 = Rc::new(op);
+        let op2 = op1.clone();
+
+        // combine vectors locally, and cut the result into blocks once the time is complete.
+        let mut locals = HashMap::new();
+        let mut vector = Vec::new();
+        let blocks = self.unary_notify(Pipeline, "ReduceLocal", vec![], move |input, output, notificator| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let local = locals.entry(time.time().clone()).or_insert_with(Vec::new);
+                for values in vector.drain(..) {
+                    combine(local, values, &*op1);
+                }
+                notificator.notify_at(time.retain());
+            });
+            notificator.for_each(|time,_,_| {
+                if let Some(local) = locals.remove(time.time()) {
+                    let mut session = output.session(&time);
+                    let mut offset = 0;
+                    let mut block = Vec::with_capacity(BLOCK_LENGTH);
+                    for value in local.into_iter() {
+                        block.push(value);
+                        if block.len() == BLOCK_LENGTH {
+                            session.give((offset, ::std::mem::replace(&mut block, Vec::with_capacity(BLOCK_LENGTH))));
+                            offset += BLOCK_LENGTH;
+                        }
+                    }
+                    if !block.is_empty() {
+                        session.give((offset, block));
+                    }
+                }
+            });
+        });
+
+        // route each block to its worker, and combine the blocks once the time is complete.
+        let mut pending = HashMap::new();
+        let mut vector = Vec::new();
+        let exchange = Exchange::new(|x: &(usize, Vec<D>)| (x.0 / BLOCK_LENGTH) as u64);
+        blocks.unary_notify(exchange, "ReduceScatter", vec![], move |input, output, notificator| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let blocks = pending.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (offset, values) in vector.drain(..) {
+                    combine(blocks.entry(offset).or_insert_with(Vec::new), values, &*op2);
+                }
+                notificator.notify_at(time.retain());
+            });
+            notificator.for_each(|time,_,_| {
+                if let Some(blocks) = pending.remove(time.time()) {
+                    let mut blocks = blocks.into_iter().collect::<Vec<_>>();
+                    blocks.sort_by(|x,y| x.0.cmp(&y.0));
+                    output.session(&time).give_iterator(blocks.into_iter());
+                }
+            });
+        })
+    }
+
+    fn allreduce(&self, op: impl Fn(&mut D, D)+'static) -> Stream<G, Vec<D>> {
+
+        // assemble the broadcast blocks in order once the time is complete.
+        let mut pending = HashMap::new();
+        let mut vector = Vec::new();
+        self.reduce_scatter(op)
+            .broadcast()
+            .unary_notify(Pipeline, "Allreduce", vec![], move |input, output, notificator| {
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    pending.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                    notificator.notify_at(time.retain());
+                });
+                notificator.for_each(|time,_,_| {
+                    if let Some(mut blocks) = pending.remove(time.time()) {
+                        blocks.sort_by(|x: &(usize, Vec<D>), y| x.0.cmp(&y.0));
+                        let mut result = Vec::new();
+                        for (_offset, mut values) in blocks.into_iter() {
+                            result.append(&mut values);
+                        }
+                        output.session(&time).give(result);
+                    }
+                });
+            })
+    }
+}
+
+/// Combines `from` into `into` position by position, appending any positions `into` lacks.
+fn combine<D, F: Fn(&mut D, D)>(into: &mut Vec<D>, from: Vec<D>, op: &F) {
+    let mut from = from.into_iter();
+    for (x, y) in into.iter_mut().zip(from.by_ref()) {
+        op(x, y);
+    }
+    into.extend(from);
+}
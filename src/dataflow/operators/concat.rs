@@ -66,13 +66,11 @@ impl<G: Scope, D: Data> Concatenate<G, D> for G {
         // build an operator that plays out all input data.
         builder.build(move |_capability| {
 
-            let mut vector = Vec::new();
             move |_frontier| {
                 let mut output = output.activate();
                 for handle in handles.iter_mut() {
                     handle.for_each(|time, data| {
-                        data.swap(&mut vector);
-                        output.session(&time).give_vec(&mut vector);
+                        output.session(&time).forward(data);
                     })
                 }
             }
@@ -0,0 +1,101 @@
+//! Suppression of duplicate records within a horizon of time.
+//!
+//! Removing all duplicates from an unbounded stream requires remembering every record ever seen.
+//! Many streams only repeat records within a bounded span of time, for example retries of a message,
+//! and the operator here suppresses duplicates only within a horizon after a record is produced. It
+//! forgets a record once the input frontier passes the record's time advanced by the horizon, and so
+//! holds state only for records produced within the horizon of the frontier.
+
+use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+
+use ExchangeData;
+use order::PartialOrder;
+use progress::{Timestamp, PathSummary};
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Exchange;
+use dataflow::operators::generic::{Operator, FrontierNotificator};
+
+/// Methods to suppress duplicate records.
+pub trait DedupWithin<G: Scope, D: ExchangeData+Hash+Eq> {
+    /// Produces each record unless an equal record was produced at a time `t` such that the record's
+    /// time is not greater or equal to `t` advanced by `horizon`.
+    ///
+    /// Records are produced once their time is complete, and the records of complete times are
+    /// considered in order of their times; suppressed records do not extend the horizon of the record
+    /// that suppressed them. Records are exchanged by their hash, so that equal records meet at one
+    /// worker. A record whose time advanced by `horizon` is not a timestamp is never forgotten.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Delay, Map, Inspect};
+    /// use timely::dataflow::operators::dedup::DedupWithin;
+    /// use timely::progress::nested::Summary;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::example(|scope| {
+    ///     // the record 0 at times 0, 3, and 10, and the record 1 at times 1 through 9.
+    ///     vec![(0, 0), (0, 3), (0, 10), (1, 1), (1, 2), (1, 3), (1, 4), (1, 5), (1, 6), (1, 7), (1, 8), (1, 9)]
+    ///         .to_stream(scope)
+    ///         .delay(|&(_, time), _| RootTimestamp::new(time))
+    ///         .map(|(record, _)| record)
+    ///         .dedup_within(Summary::Local(5))
+    ///         // produces 0 at times 0 and 10, and 1 at times 1 and 6.
+    ///         .inspect_time(|time, record| println!("{:?} at {:?}", record, time));
+    /// });
+    /// ```
+    fn dedup_within(&self, horizon: <G::Timestamp as Timestamp>::Summary) -> Stream<G, D>;
+}
+
+impl<G: Scope, D: ExchangeData+Hash+Eq> DedupWithin<G, D> for Stream<G, D> {
+    fn dedup_within(&self, horizon: <G::Timestamp as Timestamp>::Summary) -> Stream<G, D> {
+
+        let exchange = Exchange::new(|data: &D| {
+            let mut hasher = DefaultHasher::new();
+            data.hash(&mut hasher);
+            hasher.finish()
+        });
+
+        let mut stash: HashMap<G::Timestamp, Vec<D>> = HashMap::new();
+        // records produced within the horizon, and the times at which they expire, if any.
+        let mut produced: HashMap<D, Option<G::Timestamp>> = HashMap::new();
+
+        self.unary_frontier(exchange, "DedupWithin", move |_capability, _info| {
+
+            let mut notificator = FrontierNotificator::new();
+
+            move |input, output| {
+
+                input.for_each(|time, data| {
+                    stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(data.replace(Vec::new()));
+                    notificator.notify_at(time.retain());
+                });
+
+                notificator.for_each(&[input.frontier()], |time, _| {
+                    if let Some(records) = stash.remove(time.time()) {
+                        let mut session = output.session(&time);
+                        for record in records {
+                            let fresh = match produced.get(&record) {
+                                Some(&Some(ref expiry)) => expiry.less_equal(time.time()),
+                                Some(&None) => false,
+                                None => true,
+                            };
+                            if fresh {
+                                produced.insert(record.clone(), horizon.results_in(time.time()));
+                                session.give(record);
+                            }
+                        }
+                    }
+                });
+
+                // forget records whose expiry every future time is greater or equal to.
+                let frontier = input.frontier().frontier();
+                produced.retain(|_, expiry| match *expiry {
+                    Some(ref expiry) => frontier.iter().any(|time| !expiry.less_equal(time)),
+                    None => true,
+                });
+            }
+        })
+    }
+}
@@ -2,6 +2,7 @@
 
 use ::ExchangeData;
 use dataflow::channels::pact::Exchange as ExchangePact;
+use dataflow::channels::pact::ExchangeBatched as ExchangeBatchedPact;
 // use dataflow::channels::pact::TimeExchange as TimeExchangePact;
 use dataflow::{Stream, Scope};
 use dataflow::operators::generic::operator::Operator;
@@ -22,6 +23,24 @@ pub trait Exchange<T, D: ExchangeData> {
     /// });
     /// ```
     fn exchange(&self, route: impl Fn(&D)->u64+'static) -> Self;
+
+    /// Exchange batches of records whole, so that all batches with the same `route` are at the same worker.
+    ///
+    /// The route is computed once per batch rather than once per record, and each batch arrives with
+    /// the records it was sent with. This suits streams whose batches are already partitioned, where
+    /// any record of a batch determines its destination.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .exchange_batched(|batch: &[u64]| batch.first().cloned().unwrap_or(0))
+    ///            .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn exchange_batched(&self, route: impl Fn(&[D])->u64+'static) -> Self;
 }
 
 impl<T: Timestamp, G: Scope<Timestamp=T>, D: ExchangeData> Exchange<T, D> for Stream<G, D> {
@@ -34,4 +53,14 @@ impl<T: Timestamp, G: Scope<Timestamp=T>, D: ExchangeData> Exchange<T, D> for St
             });
         })
     }
+
+    fn exchange_batched(&self, route: impl Fn(&[D])->u64+'static) -> Stream<G, D> {
+        let mut vector = Vec::new();
+        self.unary(ExchangeBatchedPact::new(route), "ExchangeBatched", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                output.session(&time).give_vec(&mut vector);
+            });
+        })
+    }
 }
@@ -0,0 +1,68 @@
+//! Connections of streams between dataflows of the same worker.
+//!
+//! A stream exported from one dataflow may be imported into another dataflow constructed later by
+//! the same worker, for example to feed the output of a control dataflow into a data dataflow. The
+//! connection buffers the data and progress information of the exported stream, so the importing
+//! dataflow observes the stream's frontier as well as its data, and it is released as the importing
+//! dataflow consumes it. Both dataflows must use the same timestamp.
+//!
+//! The connection is a captured stream, as described in the `capture` module, specialized to a
+//! single importing dataflow on the same worker.
+
+use std::rc::Rc;
+
+use Data;
+use progress::Timestamp;
+use dataflow::{Stream, Scope};
+use dataflow::operators::capture::{Capture, EventLink, Replay};
+
+/// Methods to export a stream to another dataflow.
+pub trait Export<G: Scope, D: Data> {
+    /// Exports the stream, returning a handle by which another dataflow of the same worker may import it.
+    ///
+    /// Until the handle is imported, the stream's data are buffered in the handle, and if the handle
+    /// is dropped they are discarded.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect, Probe};
+    /// use timely::dataflow::operators::export::Export;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let exported = worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .export()
+    ///     });
+    ///
+    ///     let probe = worker.dataflow(|scope| {
+    ///         exported.import_into(scope)
+    ///                 .inspect(|x| println!("imported: {:?}", x))
+    ///                 .probe()
+    ///     });
+    ///
+    ///     worker.step_while(|| !probe.done());
+    /// }).unwrap();
+    /// ```
+    fn export(&self) -> Exported<G::Timestamp, D>;
+}
+
+impl<G: Scope, D: Data> Export<G, D> for Stream<G, D> {
+    fn export(&self) -> Exported<G::Timestamp, D> {
+        let link = Rc::new(EventLink::new());
+        self.capture_into(link.clone());
+        Exported { link }
+    }
+}
+
+/// A stream exported from a dataflow, which may be imported into another dataflow of the same worker.
+pub struct Exported<T: Timestamp, D: Data> {
+    link: Rc<EventLink<T, D>>,
+}
+
+impl<T: Timestamp, D: Data> Exported<T, D> {
+    /// Imports the stream into `scope`, which must have the same timestamp as the exporting scope.
+    pub fn import_into<S: Scope<Timestamp=T>>(self, scope: &mut S) -> Stream<S, D> {
+        Some(self.link).replay_into(scope)
+    }
+}
@@ -0,0 +1,287 @@
+//! A framed wire protocol by which non-Rust processes produce and consume timely streams.
+//!
+//! An external process participates in a computation as the source or the sink of specific streams,
+//! rather than as a worker. It exchanges frames with one worker over any byte stream, typically a TCP
+//! connection, and the worker presents these as a stream of `Vec<u8>` records with `u64` timestamps.
+//! The records are opaque to timely, and the two parties agree on their encoding.
+//!
+//! #Protocol
+//!
+//! Each frame is a one-byte tag, a `u64` time as eight little-endian bytes, a `u32` payload length as
+//! four little-endian bytes, and then the payload.
+//!
+//! * tag `0`, **data**: one record, the payload, at the frame's time.
+//! * tag `1`, **frontier**: a statement that no further data will be sent at times less than the
+//!   frame's time. Frontier statements must not decrease. The payload is empty.
+//! * tag `2`, **close**: a statement that no further data will be sent. The time is ignored and the
+//!   payload is empty.
+//!
+//! Before any frontier statement the frontier is `0`. Data must not be sent at times less than the
+//! most recent frontier statement, and the stream is incomplete until it is closed.
+//!
+//! A `FrameReader` reads frames from an external producer, and presents them as the events of a
+//! captured stream, which `Replay` introduces into a dataflow. A `FrameWriter` receives the events of
+//! a stream from `Capture`, and writes them as frames for an external consumer; it writes a frontier
+//! statement whenever the stream's frontier advances, and closes once it is empty. An external
+//! producer typically connects to a single worker, and the other workers replay no readers.
+//!
+//! This module is only available with the `external` feature.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use progress::frontier::MutableAntichain;
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+use dataflow::operators::capture::{Event, EventPusher};
+use dataflow::operators::capture::event::EventIterator;
+
+const TAG_DATA: u8 = 0;
+const TAG_FRONTIER: u8 = 1;
+const TAG_CLOSE: u8 = 2;
+const HEADER_LENGTH: usize = 13;
+
+/// A frame of the external protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// A record at a time.
+    Data(u64, Vec<u8>),
+    /// No further data will be sent at times less than this time.
+    Frontier(u64),
+    /// No further data will be sent.
+    Close,
+}
+
+impl Frame {
+    /// Writes the encoding of the frame to `writer`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (tag, time, payload) = match *self {
+            Frame::Data(time, ref payload) => (TAG_DATA, time, &payload[..]),
+            Frame::Frontier(time) => (TAG_FRONTIER, time, &[][..]),
+            Frame::Close => (TAG_CLOSE, 0, &[][..]),
+        };
+        let mut header = [0u8; HEADER_LENGTH];
+        header[0] = tag;
+        for i in 0 .. 8 { header[1 + i] = (time >> (8 * i)) as u8; }
+        for i in 0 .. 4 { header[9 + i] = ((payload.len() as u32) >> (8 * i)) as u8; }
+        writer.write_all(&header)?;
+        writer.write_all(payload)
+    }
+
+    /// Decodes a frame from the front of `bytes`, returning it and the number of bytes it occupies.
+    ///
+    /// Returns `Ok(None)` if `bytes` do not yet contain a complete frame, and an error if the frame
+    /// has an unknown tag.
+    pub fn read_from(bytes: &[u8]) -> Result<Option<(Frame, usize)>, String> {
+        if bytes.len() < HEADER_LENGTH {
+            return Ok(None);
+        }
+        let time = (0 .. 8).fold(0u64, |time, i| time | ((bytes[1 + i] as u64) << (8 * i)));
+        let length = (0 .. 4).fold(0u32, |length, i| length | ((bytes[9 + i] as u32) << (8 * i))) as usize;
+        if bytes.len() < HEADER_LENGTH + length {
+            return Ok(None);
+        }
+        let frame = match bytes[0] {
+            TAG_DATA => Frame::Data(time, bytes[HEADER_LENGTH .. HEADER_LENGTH + length].to_vec()),
+            TAG_FRONTIER => Frame::Frontier(time),
+            TAG_CLOSE => Frame::Close,
+            tag => return Err(format!("unknown frame tag: {}", tag)),
+        };
+        Ok(Some((frame, HEADER_LENGTH + length)))
+    }
+}
+
+/// Reads frames from an external producer, and presents them as events.
+///
+/// Reads should not block indefinitely, as the worker reads when the replaying operator is scheduled;
+/// a `TcpStream` should be set to non-blocking mode.
+///
+/// #Panics
+///
+/// Panics if the producer violates the protocol, or disconnects before closing its stream.
+///
+/// #Examples
+/// ```
+/// use std::io::Cursor;
+/// use timely::dataflow::operators::{Replay, Capture, Map};
+/// use timely::dataflow::operators::capture::Extract;
+/// use timely::dataflow::operators::external::{Frame, FrameReader};
+/// use timely::progress::timestamp::RootTimestamp;
+///
+/// // frames as an external producer would send them.
+/// let mut bytes = Vec::new();
+/// Frame::Data(0, b"hello".to_vec()).write_to(&mut bytes).unwrap();
+/// Frame::Frontier(1).write_to(&mut bytes).unwrap();
+/// Frame::Data(1, b"world".to_vec()).write_to(&mut bytes).unwrap();
+/// Frame::Close.write_to(&mut bytes).unwrap();
+///
+/// let captured = timely::example(move |scope| {
+///     Some(FrameReader::new(Cursor::new(bytes.clone())))
+///         .replay_into(scope)
+///         .map(|record| String::from_utf8(record).unwrap())
+///         .capture()
+/// });
+///
+/// assert_eq!(captured.extract(), vec![
+///     (RootTimestamp::new(0), vec!["hello".to_owned()]),
+///     (RootTimestamp::new(1), vec!["world".to_owned()]),
+/// ]);
+/// ```
+pub struct FrameReader<R: Read> {
+    reader: R,
+    bytes: Vec<u8>,
+    buffer: Vec<u8>,
+    pending: VecDeque<Event<Product<RootTimestamp, u64>, Vec<u8>>>,
+    current: Option<Event<Product<RootTimestamp, u64>, Vec<u8>>>,
+    frontier: Option<u64>,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Allocates a new `FrameReader` wrapping a supplied reader.
+    pub fn new(reader: R) -> Self {
+        FrameReader {
+            reader,
+            bytes: vec![0u8; 1 << 16],
+            buffer: Vec::new(),
+            pending: VecDeque::new(),
+            current: None,
+            frontier: Some(0),
+        }
+    }
+
+    /// Translates complete frames in `self.buffer` into pending events.
+    fn decode(&mut self) {
+        let mut consumed = 0;
+        while let Some((frame, length)) = Frame::read_from(&self.buffer[consumed..]).unwrap_or_else(|error| panic!("external producer: {}", error)) {
+            consumed += length;
+            let frontier = self.frontier.expect("external producer: frame received after close");
+            match frame {
+                Frame::Data(time, record) => {
+                    assert!(time >= frontier, "external producer: data at time {} sent after frontier {}", time, frontier);
+                    // extend the most recent batch, if it has the same time.
+                    if let Some(&mut Event::Messages(ref last, ref mut data)) = self.pending.back_mut() {
+                        if last.inner == time {
+                            data.push(record);
+                            continue;
+                        }
+                    }
+                    self.pending.push_back(Event::Messages(RootTimestamp::new(time), vec![record]));
+                },
+                Frame::Frontier(time) => {
+                    assert!(time >= frontier, "external producer: frontier {} decreases from {}", time, frontier);
+                    if time > frontier {
+                        self.pending.push_back(Event::Progress(vec![(RootTimestamp::new(time), 1), (RootTimestamp::new(frontier), -1)]));
+                        self.frontier = Some(time);
+                    }
+                },
+                Frame::Close => {
+                    self.pending.push_back(Event::Progress(vec![(RootTimestamp::new(frontier), -1)]));
+                    self.frontier = None;
+                },
+            }
+        }
+        self.buffer.drain(.. consumed);
+    }
+}
+
+impl<R: Read> EventIterator<Product<RootTimestamp, u64>, Vec<u8>> for FrameReader<R> {
+    fn next(&mut self) -> Option<&Event<Product<RootTimestamp, u64>, Vec<u8>>> {
+        if self.pending.is_empty() && self.frontier.is_some() {
+            match self.reader.read(&mut self.bytes[..]) {
+                Ok(0) => panic!("external producer: disconnected before closing its stream"),
+                Ok(length) => {
+                    self.buffer.extend_from_slice(&self.bytes[.. length]);
+                    self.decode();
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => { },
+                Err(error) => panic!("external producer: read failed: {}", error),
+            }
+        }
+        self.current = self.pending.pop_front();
+        self.current.as_ref()
+    }
+}
+
+/// Writes the events of a captured stream as frames, for an external consumer.
+///
+/// The first error writing to the consumer is recorded in the handle `error` returns, and the writer
+/// writes nothing further.
+///
+/// #Examples
+/// ```
+/// use std::net::TcpStream;
+/// use timely::dataflow::operators::{ToStream, Map, Capture};
+/// use timely::dataflow::operators::external::FrameWriter;
+///
+/// timely::example(|scope| {
+///     let stream = (0..10).to_stream(scope)
+///                         .map(|x: u64| x.to_string().into_bytes());
+///     // connect to an external consumer, if one is listening.
+///     if let Ok(connection) = TcpStream::connect("127.0.0.1:8000") {
+///         let writer = FrameWriter::new(connection);
+///         let error = writer.error();
+///         stream.capture_into(writer);
+///         assert!(error.borrow().is_none());
+///     }
+/// });
+/// ```
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    frontier: MutableAntichain<Product<RootTimestamp, u64>>,
+    reported: Option<u64>,
+    error: Rc<RefCell<Option<io::Error>>>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Allocates a new `FrameWriter` wrapping a supplied writer.
+    pub fn new(writer: W) -> Self {
+        FrameWriter {
+            writer,
+            frontier: MutableAntichain::new_bottom(Default::default()),
+            reported: Some(0),
+            error: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// A handle to the first error writing to the consumer, if any.
+    pub fn error(&self) -> Rc<RefCell<Option<io::Error>>> {
+        self.error.clone()
+    }
+
+    /// Writes the frames of `event`.
+    fn write(&mut self, event: Event<Product<RootTimestamp, u64>, Vec<u8>>) -> io::Result<()> {
+        match event {
+            Event::Messages(time, data) => {
+                for record in data {
+                    Frame::Data(time.inner, record).write_to(&mut self.writer)?;
+                }
+            },
+            Event::Progress(updates) => {
+                self.frontier.update_iter(updates.into_iter());
+                let frontier = self.frontier.frontier().iter().map(|time| time.inner).min();
+                if frontier != self.reported {
+                    let frame = match frontier {
+                        Some(time) => Frame::Frontier(time),
+                        None => Frame::Close,
+                    };
+                    frame.write_to(&mut self.writer)?;
+                    self.writer.flush()?;
+                    self.reported = frontier;
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> EventPusher<Product<RootTimestamp, u64>, Vec<u8>> for FrameWriter<W> {
+    fn push(&mut self, event: Event<Product<RootTimestamp, u64>, Vec<u8>>) {
+        // once a write has failed, the consumer's view of the stream cannot be repaired.
+        if self.error.borrow().is_some() { return; }
+        if let Err(error) = self.write(event) {
+            *self.error.borrow_mut() = Some(error);
+        }
+    }
+}
@@ -42,10 +42,45 @@ pub trait LoopVariable<'a, G: ScopeParent, T: Timestamp> {
     /// });
     /// ```
     fn loop_variable<D: Data>(&mut self, limit: T, summary: T::Summary) -> (Handle<G::Timestamp, T, D>, Stream<Child<'a, G, T>, D>);
+
+    /// As `loop_variable`, but applying `transform` to each batch of records crossing the loop boundary.
+    ///
+    /// The transform is called with the advanced time and the records of each batch that remains
+    /// within `limit`, and may modify, remove, or add records, for example to consolidate the loop
+    /// variable or to discard converged records. Batches left empty are dropped. The transform runs
+    /// within the feedback edge, rather than as an additional operator, and sees one batch at a time.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{LoopVariable, ConnectLoop, ToStream, Concat, Map, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     // halve 0..10 until each reaches zero, which is discarded at the loop boundary.
+    ///     let (handle, cycle) = scope.loop_variable_with(100, 1, |_time, data: &mut Vec<u64>| {
+    ///         data.retain(|x| *x > 0);
+    ///     });
+    ///     (0..10).to_stream(scope)
+    ///            .concat(&cycle)
+    ///            .inspect(|x| println!("seen: {:?}", x))
+    ///            .map(|x| x / 2)
+    ///            .connect_loop(handle);
+    /// });
+    /// ```
+    fn loop_variable_with<D: Data, F: FnMut(&T, &mut Vec<D>)+'static>(&mut self, limit: T, summary: T::Summary, transform: F) -> (Handle<G::Timestamp, T, D>, Stream<Child<'a, G, T>, D>);
 }
 
 impl<'a, G: ScopeParent, T: Timestamp> LoopVariable<'a, G, T> for Child<'a, G, T> {
     fn loop_variable<D: Data>(&mut self, limit: T, summary: T::Summary) -> (Handle<G::Timestamp, T, D>, Stream<Child<'a, G, T>, D>) {
+        self.loop_variable_inner(limit, summary, None)
+    }
+
+    fn loop_variable_with<D: Data, F: FnMut(&T, &mut Vec<D>)+'static>(&mut self, limit: T, summary: T::Summary, transform: F) -> (Handle<G::Timestamp, T, D>, Stream<Child<'a, G, T>, D>) {
+        self.loop_variable_inner(limit, summary, Some(Box::new(transform)))
+    }
+}
+
+impl<'a, G: ScopeParent, T: Timestamp> Child<'a, G, T> {
+    fn loop_variable_inner<D: Data>(&mut self, limit: T, summary: T::Summary, transform: Option<Box<FnMut(&T, &mut Vec<D>)>>) -> (Handle<G::Timestamp, T, D>, Stream<Child<'a, G, T>, D>) {
 
         let (targets, registrar) = Tee::<Product<G::Timestamp, T>, D>::new();
 
@@ -53,7 +88,7 @@ impl<'a, G: ScopeParent, T: Timestamp> LoopVariable<'a, G, T> for Child<'a, G, T
         let produced = feedback_output.produced().clone();
 
         let feedback_input =  Counter::new(Observer {
-            limit, summary: summary.clone(), targets: feedback_output
+            limit, summary: summary.clone(), targets: feedback_output, transform
         });
         let consumed = feedback_input.produced().clone();
 
@@ -77,6 +112,7 @@ struct Observer<TOuter: Timestamp, TInner: Timestamp, D:Data> {
     limit:      TInner,
     summary:    TInner::Summary,
     targets:    Counter<Product<TOuter, TInner>, D, Tee<Product<TOuter, TInner>, D>>,
+    transform:  Option<Box<FnMut(&TInner, &mut Vec<D>)>>,
 }
 
 impl<TOuter: Timestamp, TInner: Timestamp, D: Data> Push<Bundle<Product<TOuter, TInner>, D>> for Observer<TOuter, TInner, D> {
@@ -86,7 +122,15 @@ impl<TOuter: Timestamp, TInner: Timestamp, D: Data> Push<Bundle<Product<TOuter,
             let message = message.as_mut();
             if let Some(new_time) = self.summary.results_in(&message.time.inner) {
                 message.time.inner = new_time;
-                message.time.inner.less_equal(&self.limit)
+                let within = message.time.inner.less_equal(&self.limit);
+                if within {
+                    if let Some(ref mut transform) = self.transform {
+                        transform(&message.time.inner, &mut message.data);
+                        !message.data.is_empty()
+                    }
+                    else { true }
+                }
+                else { false }
             }
             else {
                 false
@@ -0,0 +1,359 @@
+//! Operators reading records from files, and writing records to files.
+//!
+//! Two record formats are supported: comma-separated values, where each line is a record of string
+//! fields, and length-delimited binary, where each record is a sequence of bytes preceded by its length
+//! as a little-endian `u64`.
+//!
+//! The sources distribute the supplied files among workers, and read records from their files in
+//! order, placing each consecutive `records_per_epoch` records at the next epoch. The sinks write each
+//! worker's records to a file of its own, writing the records of each time only once the time is
+//! complete, in order of time, and flushing the file after each time.
+//!
+//! Failures to open, read, create, or write files do not panic, but are reported as `OperatorError`s on
+//! an error stream returned by each operator. A source skips the remainder of a file it fails to read,
+//! and a sink reports each time whose records it fails to write.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use Data;
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::catch::OperatorError;
+use dataflow::operators::generic::operator::Operator;
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+use dataflow::operators::generic::FrontierNotificator;
+
+/// Reads comma-separated records from `paths`, distributed among workers.
+///
+/// Each line is parsed into its fields. Fields may be enclosed in double quotes, in which case they
+/// may contain commas, and doubled double quotes stand for one double quote; fields may not contain
+/// line breaks.
+///
+/// Returns the stream of records, and a stream of errors opening or reading files.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::file::read_csv;
+///
+/// let path = ::std::env::temp_dir().join("timely_read_csv_example.csv");
+/// ::std::fs::write(&path, "a,b\n\"c,d\",e\n").unwrap();
+///
+/// timely::example(move |scope| {
+///     let (records, _errors) = read_csv(scope, vec![path], 1);
+///     records.inspect(|x| assert_eq!(x.len(), 2));
+/// });
+/// ```
+pub fn read_csv<G>(scope: &G, paths: Vec<PathBuf>, records_per_epoch: usize) -> (Stream<G, Vec<String>>, Stream<G, OperatorError<G::Timestamp>>)
+where G: Scope<Timestamp=Product<RootTimestamp, u64>> {
+    let mut line = String::new();
+    read_records(scope, "ReadCsv", paths, records_per_epoch, move |reader| {
+        line.clear();
+        if reader.read_line(&mut line)? > 0 {
+            let trimmed = line.trim_end_matches(|c| c == '\n' || c == '\r');
+            Ok(Some(parse_csv(trimmed)))
+        }
+        else {
+            Ok(None)
+        }
+    })
+}
+
+/// Reads length-delimited binary records from `paths`, distributed among workers.
+///
+/// Returns the stream of records, and a stream of errors opening or reading files, including files
+/// ending in a truncated record.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::file::read_length_delimited;
+///
+/// let path = ::std::env::temp_dir().join("timely_read_length_delimited_example.bin");
+/// ::std::fs::write(&path, &[3, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]).unwrap();
+///
+/// timely::example(move |scope| {
+///     let (records, _errors) = read_length_delimited(scope, vec![path], 1);
+///     records.inspect(|x| assert_eq!(x, &vec![1, 2, 3]));
+/// });
+/// ```
+pub fn read_length_delimited<G>(scope: &G, paths: Vec<PathBuf>, records_per_epoch: usize) -> (Stream<G, Vec<u8>>, Stream<G, OperatorError<G::Timestamp>>)
+where G: Scope<Timestamp=Product<RootTimestamp, u64>> {
+    read_records(scope, "ReadLengthDelimited", paths, records_per_epoch, |reader| {
+        let mut length = [0u8; 8];
+        match reader.read_exact(&mut length) {
+            Ok(()) => {
+                let length = length.iter().rev().fold(0u64, |acc, &byte| (acc << 8) | (byte as u64));
+                let mut record = vec![0u8; length as usize];
+                reader.read_exact(&mut record)?;
+                Ok(Some(record))
+            },
+            Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    })
+}
+
+/// Writes comma-separated records to a file per worker.
+pub trait WriteCsv<G: Scope> {
+    /// Writes the records at each worker to the file named by `path` applied to the worker index.
+    ///
+    /// Fields containing commas, double quotes, or line breaks are enclosed in double quotes. Returns
+    /// a stream of errors creating or writing the file, one for each time whose records were not written.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map};
+    /// use timely::dataflow::operators::file::WriteCsv;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .map(|x| vec![x.to_string(), (x * x).to_string()])
+    ///            .write_csv(|index| ::std::env::temp_dir().join(format!("timely_write_csv_example-{}.csv", index)));
+    /// });
+    /// ```
+    fn write_csv(&self, path: impl Fn(usize)->PathBuf) -> Stream<G, OperatorError<G::Timestamp>>;
+}
+
+impl<G: Scope> WriteCsv<G> for Stream<G, Vec<String>> {
+    fn write_csv(&self, path: impl Fn(usize)->PathBuf) -> Stream<G, OperatorError<G::Timestamp>> {
+        let mut line = String::new();
+        write_records(self, "WriteCsv", path, move |writer, record| {
+            line.clear();
+            format_csv(record, &mut line);
+            line.push('\n');
+            writer.write_all(line.as_bytes())
+        })
+    }
+}
+
+/// Writes length-delimited binary records to a file per worker.
+pub trait WriteLengthDelimited<G: Scope> {
+    /// Writes the records at each worker to the file named by `path` applied to the worker index.
+    ///
+    /// Returns a stream of errors creating or writing the file, one for each time whose records were
+    /// not written.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map};
+    /// use timely::dataflow::operators::file::WriteLengthDelimited;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10u8).to_stream(scope)
+    ///              .map(|x| vec![x; x as usize])
+    ///              .write_length_delimited(|index| ::std::env::temp_dir().join(format!("timely_write_bin_example-{}.bin", index)));
+    /// });
+    /// ```
+    fn write_length_delimited(&self, path: impl Fn(usize)->PathBuf) -> Stream<G, OperatorError<G::Timestamp>>;
+}
+
+impl<G: Scope> WriteLengthDelimited<G> for Stream<G, Vec<u8>> {
+    fn write_length_delimited(&self, path: impl Fn(usize)->PathBuf) -> Stream<G, OperatorError<G::Timestamp>> {
+        write_records(self, "WriteLengthDelimited", path, |writer, record| {
+            let mut length = [0u8; 8];
+            for (index, byte) in length.iter_mut().enumerate() {
+                *byte = ((record.len() as u64) >> (8 * index)) as u8;
+            }
+            writer.write_all(&length)?;
+            writer.write_all(&record[..])
+        })
+    }
+}
+
+/// Reads records from this worker's share of `paths`, using `read` until it returns `None` for each file.
+///
+/// A file that cannot be opened, or whose `read` returns an error, is reported on the error stream at
+/// the current epoch, and the source moves on to the next file.
+fn read_records<G, D, R>(scope: &G, name: &str, paths: Vec<PathBuf>, records_per_epoch: usize, mut read: R) -> (Stream<G, D>, Stream<G, OperatorError<G::Timestamp>>)
+where
+    G: Scope<Timestamp=Product<RootTimestamp, u64>>,
+    D: Data,
+    R: FnMut(&mut BufReader<File>)->io::Result<Option<D>>+'static {
+
+    assert!(records_per_epoch > 0, "records_per_epoch must be positive");
+
+    let index = scope.index();
+    let peers = scope.peers();
+
+    let mut builder = OperatorBuilder::new(name.to_owned(), scope.clone());
+    let (mut output, stream) = builder.new_output();
+    let (mut error_output, errors) = builder.new_output();
+    builder.set_notify(false);
+
+    let name = name.to_owned();
+    builder.build(move |mut capabilities| {
+
+        let error_capability = capabilities.pop().unwrap();
+        let capability = capabilities.pop().unwrap();
+
+        let mut paths = paths.into_iter()
+                             .enumerate()
+                             .filter(|&(i, _)| i % peers == index)
+                             .map(|(_, path)| path)
+                             .collect::<VecDeque<_>>();
+
+        let mut reader: Option<(PathBuf, BufReader<File>)> = None;
+        let mut caps = Some((capability, error_capability));
+        let mut in_epoch = 0;
+        let mut messages = Vec::new();
+
+        move |_frontiers| {
+            let mut done = false;
+            if let Some((ref mut cap, ref mut error_cap)) = caps {
+                {
+                    let mut output_handle = output.activate();
+                    let mut session = output_handle.session(&*cap);
+                    while in_epoch < records_per_epoch && !done {
+                        if reader.is_none() {
+                            match paths.pop_front() {
+                                Some(path) => match File::open(&path) {
+                                    Ok(file) => { reader = Some((path, BufReader::new(file))); },
+                                    Err(error) => { messages.push(format!("failed to open {:?}: {}", path, error)); },
+                                },
+                                None => { done = true; },
+                            }
+                        }
+                        else {
+                            let result = read(&mut reader.as_mut().unwrap().1);
+                            match result {
+                                Ok(Some(record)) => {
+                                    session.give(record);
+                                    in_epoch += 1;
+                                },
+                                Ok(None) => { reader = None; },
+                                Err(error) => {
+                                    let (path, _) = reader.take().unwrap();
+                                    messages.push(format!("failed to read {:?}: {}", path, error));
+                                },
+                            }
+                        }
+                    }
+                }
+
+                if !messages.is_empty() {
+                    let mut error_handle = error_output.activate();
+                    let mut session = error_handle.session(&*error_cap);
+                    for message in messages.drain(..) {
+                        session.give(OperatorError {
+                            time: error_cap.time().clone(),
+                            operator: name.clone(),
+                            message: message,
+                        });
+                    }
+                }
+
+                // advance to the next epoch once this epoch is full.
+                if in_epoch == records_per_epoch {
+                    let mut time = cap.time().clone();
+                    time.inner += 1;
+                    *cap = cap.delayed(&time);
+                    *error_cap = error_cap.delayed(&time);
+                    in_epoch = 0;
+                }
+            }
+
+            if done { caps = None; }
+        }
+    });
+
+    (stream, errors)
+}
+
+/// Writes the records of each complete time using `write`, and then flushes the file.
+///
+/// A failure to create the file, or to write or flush the records of a time, is reported on the
+/// returned stream at that time.
+fn write_records<G, D, P, W>(stream: &Stream<G, D>, name: &str, path: P, mut write: W) -> Stream<G, OperatorError<G::Timestamp>>
+where
+    G: Scope,
+    D: Data,
+    P: Fn(usize)->PathBuf,
+    W: FnMut(&mut BufWriter<File>, &D)->io::Result<()>+'static {
+
+    let path = path(stream.scope().index());
+    let mut writer = File::create(&path)
+                         .map(BufWriter::new)
+                         .map_err(|error| format!("failed to create {:?}: {}", path, error));
+
+    let operator = name.to_owned();
+    stream.unary_frontier(Pipeline, name, move |_capability, _info| {
+
+        let mut notificator = FrontierNotificator::new();
+        let mut stash = HashMap::new();
+        let mut vector = Vec::new();
+
+        move |input, output| {
+
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone())
+                     .or_insert_with(|| { notificator.notify_at(time.retain()); Vec::new() })
+                     .extend(vector.drain(..));
+            }
+
+            notificator.for_each(&[input.frontier()], |time, _| {
+                if let Some(records) = stash.remove(time.time()) {
+                    let result = match writer {
+                        Ok(ref mut writer) => {
+                            records.iter()
+                                   .map(|record| write(writer, record))
+                                   .collect::<io::Result<()>>()
+                                   .and_then(|()| writer.flush())
+                                   .map_err(|error| format!("failed to write {:?}: {}", path, error))
+                        },
+                        Err(ref message) => Err(message.clone()),
+                    };
+                    if let Err(message) = result {
+                        output.session(&time).give(OperatorError {
+                            time: time.time().clone(),
+                            operator: operator.clone(),
+                            message: message,
+                        });
+                    }
+                }
+            });
+        }
+    })
+}
+
+/// Parses a line of comma-separated values into its fields.
+fn parse_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') { chars.next(); field.push('"'); }
+                else { quoted = false; }
+            },
+            '"' if field.is_empty() => { quoted = true; },
+            ',' if !quoted => { fields.push(::std::mem::replace(&mut field, String::new())); },
+            _ => { field.push(c); },
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Formats fields as a line of comma-separated values, without a line break.
+fn format_csv(fields: &[String], line: &mut String) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 { line.push(','); }
+        if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        }
+        else {
+            line.push_str(field);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Fusion of chains of record-at-a-time operators into a single operator.
+//!
+//! Each operator in a dataflow has a cost beyond its logic: its input and output channels, and its
+//! participation in progress tracking. A long chain of `map` and `filter` operators pays this cost
+//! for each stage. The `Fused` builder collects a chain of such transformations and renders them as
+//! one operator, applying all stages to each record in turn.
+
+use Data;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::generic::operator::Operator;
+
+/// Begins a chain of fused record-at-a-time transformations.
+pub trait Fuse<G: Scope, D: Data> {
+    /// Starts a chain of transformations to be rendered as a single operator.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::fuse::Fuse;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .fuse()
+    ///            .map(|x| x + 1)
+    ///            .filter(|x| x % 2 == 0)
+    ///            .flat_map(|x| 0..x)
+    ///            .build()
+    ///            .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn fuse(&self) -> Fused<G, D, D>;
+}
+
+impl<G: Scope, D: Data> Fuse<G, D> for Stream<G, D> {
+    fn fuse(&self) -> Fused<G, D, D> {
+        Fused {
+            stream: self.clone(),
+            logic: Box::new(|datum: D, emit: &mut FnMut(D)| emit(datum)),
+        }
+    }
+}
+
+/// A chain of transformations from records of type `D1` to records of type `D2`, not yet rendered.
+pub struct Fused<G: Scope, D1: Data, D2: Data> {
+    stream: Stream<G, D1>,
+    logic: Box<FnMut(D1, &mut FnMut(D2))>,
+}
+
+impl<G: Scope, D1: Data, D2: Data> Fused<G, D1, D2> {
+
+    /// Appends a transformation of each record to the chain.
+    pub fn map<D3: Data>(self, mut logic: impl FnMut(D2)->D3+'static) -> Fused<G, D1, D3> {
+        let mut prior = self.logic;
+        Fused {
+            stream: self.stream,
+            logic: Box::new(move |datum: D1, emit: &mut FnMut(D3)| prior(datum, &mut |x| emit(logic(x)))),
+        }
+    }
+
+    /// Appends a predicate to the chain, retaining only records satisfying it.
+    pub fn filter(self, mut predicate: impl FnMut(&D2)->bool+'static) -> Fused<G, D1, D2> {
+        let mut prior = self.logic;
+        Fused {
+            stream: self.stream,
+            logic: Box::new(move |datum: D1, emit: &mut FnMut(D2)| prior(datum, &mut |x| if predicate(&x) { emit(x) })),
+        }
+    }
+
+    /// Appends a transformation of each record into any number of records to the chain.
+    pub fn flat_map<I: IntoIterator>(self, mut logic: impl FnMut(D2)->I+'static) -> Fused<G, D1, I::Item> where I::Item: Data {
+        let mut prior = self.logic;
+        Fused {
+            stream: self.stream,
+            logic: Box::new(move |datum: D1, emit: &mut FnMut(I::Item)| prior(datum, &mut |x| for y in logic(x) { emit(y) })),
+        }
+    }
+
+    /// Appends an observation of each record to the chain.
+    pub fn inspect(self, mut func: impl FnMut(&D2)+'static) -> Fused<G, D1, D2> {
+        let mut prior = self.logic;
+        Fused {
+            stream: self.stream,
+            logic: Box::new(move |datum: D1, emit: &mut FnMut(D2)| prior(datum, &mut |x| { func(&x); emit(x) })),
+        }
+    }
+
+    /// Renders the chain as a single operator, returning its output stream.
+    pub fn build(self) -> Stream<G, D2> {
+        let mut logic = self.logic;
+        let mut vector = Vec::new();
+        self.stream.unary(Pipeline, "Fused", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let mut session = output.session(&time);
+                for datum in vector.drain(..) {
+                    logic(datum, &mut |x| session.give(x));
+                }
+            });
+        })
+    }
+}
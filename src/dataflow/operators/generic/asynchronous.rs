@@ -0,0 +1,212 @@
+//! Operators whose logic is a future, awaiting the arrival of input and the advance of its frontier.
+//!
+//! Stateful operators written as closures must stash their state and capabilities between
+//! invocations, and rediscover on each invocation what they were waiting for. An asynchronous
+//! operator instead runs a single future for its lifetime, which awaits the next batch of its input
+//! with `AsyncInput::next`, or the input frontier passing a time with `AsyncInput::frontier_passes`,
+//! and keeps its state in local variables across these points.
+//!
+//! The future is polled each time the operator is scheduled, after the batches received in that
+//! invocation are made available to it and the frontier it observes is updated. Its wakers are
+//! ignored, as the worker schedules operators whether or not they have been woken. Once the future
+//! completes, the operator discards its input.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker, RawWaker, RawWakerVTable};
+
+use Data;
+use order::PartialOrder;
+use progress::Timestamp;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::ParallelizationContract;
+use dataflow::operators::Capability;
+use dataflow::operators::generic::Operator;
+
+/// The input state shared between the operator and its future.
+struct InputState<T: Timestamp, D> {
+    batches: VecDeque<(Capability<T>, Vec<D>)>,
+    frontier: Vec<T>,
+}
+
+/// The input of an asynchronous operator.
+pub struct AsyncInput<T: Timestamp, D> {
+    state: Rc<RefCell<InputState<T, D>>>,
+}
+
+impl<T: Timestamp, D> AsyncInput<T, D> {
+    /// A future resolving to the next batch of input and a capability for its time, or to `None`
+    /// once the input is complete.
+    pub fn next(&self) -> NextBatch<T, D> {
+        NextBatch { state: self.state.clone() }
+    }
+    /// A future resolving once the input frontier has passed `time`, meaning that no further input
+    /// will arrive at times less or equal to `time`.
+    pub fn frontier_passes(&self, time: &T) -> FrontierPasses<T, D> {
+        FrontierPasses { state: self.state.clone(), time: time.clone() }
+    }
+    /// The input frontier, as of the most recent invocation of the operator.
+    pub fn frontier(&self) -> Vec<T> {
+        self.state.borrow().frontier.clone()
+    }
+}
+
+/// A future resolving to the next batch of an asynchronous operator's input.
+pub struct NextBatch<T: Timestamp, D> {
+    state: Rc<RefCell<InputState<T, D>>>,
+}
+
+impl<T: Timestamp, D> Future for NextBatch<T, D> {
+    type Output = Option<(Capability<T>, Vec<D>)>;
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if let Some(batch) = state.batches.pop_front() {
+            Poll::Ready(Some(batch))
+        }
+        else if state.frontier.is_empty() {
+            Poll::Ready(None)
+        }
+        else {
+            Poll::Pending
+        }
+    }
+}
+
+/// A future resolving once the input frontier of an asynchronous operator passes a time.
+pub struct FrontierPasses<T: Timestamp, D> {
+    state: Rc<RefCell<InputState<T, D>>>,
+    time: T,
+}
+
+impl<T: Timestamp, D> Future for FrontierPasses<T, D> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        if self.state.borrow().frontier.iter().any(|t| t.less_equal(&self.time)) {
+            Poll::Pending
+        }
+        else {
+            Poll::Ready(())
+        }
+    }
+}
+
+/// The output of an asynchronous operator.
+///
+/// Records are buffered along with a capability for their time, and sent once the future yields.
+pub struct AsyncOutput<T: Timestamp, D> {
+    buffer: Rc<RefCell<Vec<(Capability<T>, Vec<D>)>>>,
+}
+
+impl<T: Timestamp, D> AsyncOutput<T, D> {
+    /// Sends `record` at the time of `capability`.
+    pub fn give(&self, capability: &Capability<T>, record: D) {
+        self.give_vec(capability, &mut vec![record]);
+    }
+    /// Sends the contents of `records` at the time of `capability`, leaving `records` empty.
+    pub fn give_vec(&self, capability: &Capability<T>, records: &mut Vec<D>) {
+        if !records.is_empty() {
+            let records = ::std::mem::replace(records, Vec::new());
+            self.buffer.borrow_mut().push((capability.clone(), records));
+        }
+    }
+}
+
+/// A waker that does nothing, as operators are scheduled whether or not they are woken.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker { RawWaker::new(::std::ptr::null(), &VTABLE) }
+    fn noop(_: *const ()) { }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(::std::ptr::null(), &VTABLE)) }
+}
+
+/// Methods to construct operators whose logic is a future.
+pub trait AsyncOperator<G: Scope, D1: Data> {
+    /// Creates a new dataflow operator that partitions its input stream by a parallelization
+    /// strategy `pact`, and runs the future returned by `constructor` until it completes.
+    ///
+    /// The constructor receives a capability for the initial time, the operator's input, and its
+    /// output. The future holds capabilities for the times it may yet send records at, and should
+    /// drop them once it no longer needs them, so that downstream frontiers may advance.
+    ///
+    /// #Examples
+    /// ```edition2018
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::generic::AsyncOperator;
+    /// use timely::dataflow::channels::pact::Pipeline;
+    ///
+    /// timely::example(|scope| {
+    ///     (0u64..10).to_stream(scope)
+    ///               .unary_frontier_async(Pipeline, "Sums", |capability, input, output| async move {
+    ///                   drop(capability);
+    ///                   // sums each batch once no more input can arrive at its time.
+    ///                   while let Some((time, data)) = input.next().await {
+    ///                       input.frontier_passes(time.time()).await;
+    ///                       output.give(&time, data.iter().sum::<u64>());
+    ///                   }
+    ///               })
+    ///               .inspect(|x| println!("sum: {:?}", x));
+    /// });
+    /// ```
+    fn unary_frontier_async<D2, B, F, P>(&self, pact: P, name: &str, constructor: B) -> Stream<G, D2>
+    where
+        D2: Data,
+        B: FnOnce(Capability<G::Timestamp>, AsyncInput<G::Timestamp, D1>, AsyncOutput<G::Timestamp, D2>) -> F,
+        F: Future<Output=()>+'static,
+        P: ParallelizationContract<G::Timestamp, D1>;
+}
+
+impl<G: Scope, D1: Data> AsyncOperator<G, D1> for Stream<G, D1> {
+    fn unary_frontier_async<D2, B, F, P>(&self, pact: P, name: &str, constructor: B) -> Stream<G, D2>
+    where
+        D2: Data,
+        B: FnOnce(Capability<G::Timestamp>, AsyncInput<G::Timestamp, D1>, AsyncOutput<G::Timestamp, D2>) -> F,
+        F: Future<Output=()>+'static,
+        P: ParallelizationContract<G::Timestamp, D1> {
+
+        self.unary_frontier(pact, name, move |capability, _info| {
+
+            let state = Rc::new(RefCell::new(InputState {
+                batches: VecDeque::new(),
+                frontier: vec![capability.time().clone()],
+            }));
+            let buffer = Rc::new(RefCell::new(Vec::new()));
+
+            let input = AsyncInput { state: state.clone() };
+            let output = AsyncOutput { buffer: buffer.clone() };
+            let mut future = Some(Box::pin(constructor(capability, input, output)));
+
+            let waker = noop_waker();
+
+            move |input, output| {
+
+                {
+                    let mut state = state.borrow_mut();
+                    let running = future.is_some();
+                    input.for_each(|time, data| {
+                        // input arriving after the future completes is discarded.
+                        if running {
+                            state.batches.push_back((time.retain(), data.replace(Vec::new())));
+                        }
+                    });
+                    state.frontier = input.frontier().frontier().iter().cloned().collect();
+                }
+
+                let complete = match future {
+                    Some(ref mut future) => future.as_mut().poll(&mut Context::from_waker(&waker)).is_ready(),
+                    None => false,
+                };
+                if complete {
+                    future = None;
+                    state.borrow_mut().batches.clear();
+                }
+
+                for (capability, mut records) in buffer.borrow_mut().drain(..) {
+                    output.session(&capability).give_vec(&mut records);
+                }
+            }
+        })
+    }
+}
@@ -5,6 +5,7 @@
 //! an interface that is intentionally harder to mis-use.
 
 use std::default::Default;
+use std::time::Duration;
 
 use ::Data;
 
@@ -14,7 +15,8 @@ use progress::{Timestamp, Operate, Antichain};
 
 use dataflow::{Stream, Scope};
 use dataflow::channels::pushers::Tee;
-use dataflow::channels::pact::ParallelizationContract;
+use dataflow::channels::pact::{ParallelizationContract, FrontierObserver};
+use dataflow::channels::registry::ChannelRegistry;
 
 /// Contains type-free information about the operator properties.
 pub struct OperatorShape {
@@ -23,6 +25,7 @@ pub struct OperatorShape {
     peers: usize,   // The total number of workers in the computation.
     inputs: usize,  // The number of input ports.
     outputs: usize, // The number of output ports.
+    budget: Option<Duration>,   // The expected maximum duration of an invocation.
 }
 
 /// Core data for the structure of an operator, minus scope and logic.
@@ -34,6 +37,7 @@ impl OperatorShape {
             peers,
             inputs: 0,
             outputs: 0,
+            budget: None,
         }
     }
 
@@ -54,6 +58,7 @@ pub struct OperatorBuilder<G: Scope> {
     index: usize,
     shape: OperatorShape,
     summary: Vec<Vec<Antichain<<G::Timestamp as Timestamp>::Summary>>>,
+    observers: Vec<(usize, FrontierObserver<G::Timestamp>)>,
 }
 
 impl<G: Scope> OperatorBuilder<G> {
@@ -69,6 +74,7 @@ impl<G: Scope> OperatorBuilder<G> {
             index,
             shape: OperatorShape::new(name, peers),
             summary: vec![],
+            observers: Vec::new(),
         }
     }
 
@@ -77,6 +83,13 @@ impl<G: Scope> OperatorBuilder<G> {
         self.index
     }
 
+    /// The operator's address, the path of scope indices leading to it.
+    pub fn address(&self) -> Vec<usize> {
+        let mut address = self.scope.addr();
+        address.push(self.index);
+        address
+    }
+
     /// Return a reference to the operator's shape
     pub fn shape(&self) -> &OperatorShape {
         &self.shape
@@ -87,6 +100,14 @@ impl<G: Scope> OperatorBuilder<G> {
         self.shape.notify = notify;
     }
 
+    /// Sets the time an invocation of the operator is expected to take at most.
+    ///
+    /// Invocations exceeding the budget are logged as `BudgetEvent`s. Without a budget, the operator
+    /// uses that of its scope, if any.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.shape.budget = budget;
+    }
+
     /// Adds a new input to a generic operator builder, returning the `Pull` implementor to use.
     pub fn new_input<D: Data, P>(&mut self, stream: &Stream<G, D>, pact: P) -> P::Puller
         where
@@ -102,9 +123,17 @@ impl<G: Scope> OperatorBuilder<G> {
 
         let channel_id = self.scope.new_identifier();
         let logging = self.scope.logging();
-        let (sender, receiver) = pact.connect(&mut self.scope, channel_id, logging);
+        let registry = self.scope.resources().get::<ChannelRegistry>();
+        // discard allocations made other than for this channel.
+        if let Some(ref registry) = registry { registry.take_allocation(); }
+        let name = pact.name().to_owned();
+        let (sender, receiver, observer) = pact.connect_observed(&mut self.scope, channel_id, logging);
+        if let Some(observer) = observer {
+            self.observers.push((self.shape.inputs, observer));
+        }
+        let comm_channel = registry.and_then(|r| r.take_allocation());
         let target = Target { index: self.index, port: self.shape.inputs };
-        stream.connect_to(target, sender, channel_id);
+        stream.connect_to_via(target, sender, channel_id, &name, comm_channel);
 
         self.shape.inputs += 1;
         assert_eq!(self.shape.outputs, connection.len());
@@ -146,6 +175,17 @@ impl<G: Scope> OperatorBuilder<G> {
             &mut [ChangeBatch<G::Timestamp>],
         )->bool+'static
     {
+        // inputs whose pacts observe their frontiers require frontier changes.
+        let mut observers = self.observers;
+        if !observers.is_empty() { self.shape.notify = true; }
+        let mut push_external = push_external;
+        let push_external = move |changes: &mut [ChangeBatch<G::Timestamp>]| {
+            for &mut (port, ref mut observer) in observers.iter_mut() {
+                observer(&mut changes[port]);
+            }
+            push_external(changes);
+        };
+
         let operator = OperatorCore {
             shape: self.shape,
             push_external,
@@ -213,4 +253,5 @@ impl<T, PEP, PIP> Operate<T> for OperatorCore<T, PEP, PIP>
 
     fn name(&self) -> String { self.shape.name.clone() }
     fn notify_me(&self) -> bool { self.shape.notify }
+    fn budget(&self) -> Option<Duration> { self.shape.budget }
 }
@@ -3,6 +3,7 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::default::Default;
+use std::time::Duration;
 
 use ::Data;
 
@@ -17,6 +18,7 @@ use dataflow::channels::pact::ParallelizationContract;
 use dataflow::channels::pullers::Counter as PullCounter;
 use dataflow::operators::capability::Capability;
 use dataflow::operators::capability::mint as mint_capability;
+use dataflow::operators::audit;
 
 use dataflow::operators::generic::handles::{InputHandle, new_input_handle, OutputWrapper};
 
@@ -32,6 +34,7 @@ pub struct OperatorBuilder<G: Scope> {
     internal: Rc<RefCell<Vec<Rc<RefCell<ChangeBatch<G::Timestamp>>>>>>,
     produced: Vec<Rc<RefCell<ChangeBatch<G::Timestamp>>>>,
     logging: Logger,
+    name: String,
 }
 
 impl<G: Scope> OperatorBuilder<G> {
@@ -40,12 +43,13 @@ impl<G: Scope> OperatorBuilder<G> {
     pub fn new(name: String, scope: G) -> Self {
         let logging = scope.logging();
         OperatorBuilder {
-            builder: OperatorBuilderRaw::new(name, scope),
+            builder: OperatorBuilderRaw::new(name.clone(), scope),
             frontier: Vec::new(),
             consumed: Vec::new(),
             internal: Rc::new(RefCell::new(Vec::new())),
             produced: Vec::new(),
             logging,
+            name,
         }
     }
 
@@ -54,6 +58,11 @@ impl<G: Scope> OperatorBuilder<G> {
         self.builder.set_notify(notify);
     }
 
+    /// Sets the time an invocation of the operator is expected to take at most.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.builder.set_budget(budget);
+    }
+
     /// Adds a new input to a generic operator builder, returning the `Pull` implementor to use.
     pub fn new_input<D: Data, P>(&mut self, stream: &Stream<G, D>, pact: P) -> InputHandle<G::Timestamp, D, P::Puller>
     where
@@ -89,6 +98,10 @@ impl<G: Scope> OperatorBuilder<G> {
         let (tee, stream) = self.builder.new_output_connection(connection);
 
         let internal = Rc::new(RefCell::new(ChangeBatch::new()));
+        let port = self.internal.borrow().len();
+        if audit::enabled() {
+            audit::register_output(internal.as_ptr() as usize, &self.name, &self.builder.address(), port);
+        }
         self.internal.borrow_mut().push(internal.clone());
 
         let mut buffer = PushBuffer::new(PushCounter::new(tee));
@@ -119,6 +132,7 @@ impl<G: Scope> OperatorBuilder<G> {
         let self_consumed = self.consumed;
         let self_internal = self.internal;
         let self_produced = self.produced;
+        let address = self.builder.address();
 
         let pep = move |changes: &mut [ChangeBatch<G::Timestamp>]| {
             let mut borrow = self_frontier1.borrow_mut();
@@ -135,6 +149,10 @@ impl<G: Scope> OperatorBuilder<G> {
             let borrow = self_frontier2.borrow();
             logic(&*borrow);
 
+            if audit::enabled() && !borrow.is_empty() {
+                audit::note_inputs_closed(&address, borrow.iter().all(|frontier| frontier.is_empty()));
+            }
+
             // move batches of consumed changes.
             for index in 0 .. consumed.len() {
                 self_consumed[index].borrow_mut().drain_into(&mut consumed[index]);
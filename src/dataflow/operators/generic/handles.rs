@@ -215,6 +215,36 @@ impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>> OutputHandle<'a, T, D, P> {
 
         self.push_buffer.session(cap.time())
     }
+
+    /// Adapts the size of the batches the output sends, between `min` and `max` records.
+    ///
+    /// Batches start small, for latency, and grow towards `max` when activations produce many
+    /// records, for throughput. See `Buffer::adaptive` for details.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::ToStream;
+    /// use timely::dataflow::operators::generic::Operator;
+    /// use timely::dataflow::channels::pact::Pipeline;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .unary(Pipeline, "example", |_,_| |input, output| {
+    ///                output.adaptive_batching(16, 4096);
+    ///                input.for_each(|cap, data| {
+    ///                    output.session(&cap).give_iterator(data.iter().cloned());
+    ///                });
+    ///            });
+    /// });
+    /// ```
+    pub fn adaptive_batching(&mut self, min: usize, max: usize) {
+        self.push_buffer.adaptive(min, max);
+    }
+
+    /// Informs adaptive batching whether downstream consumers lag, for example as observed by a probe.
+    pub fn observe_lag(&mut self, lagging: bool) {
+        self.push_buffer.observe_lag(lagging);
+    }
 }
 
 impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>> Drop for OutputHandle<'a, T, D, P> {
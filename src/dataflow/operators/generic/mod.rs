@@ -5,6 +5,9 @@ pub mod binary;
 pub mod operator;
 pub mod builder_rc;
 pub mod builder_raw;
+pub mod pacer;
+pub mod spill;
+pub mod asynchronous;
 // pub mod builder_ref;
 mod handles;
 mod notificator;
@@ -15,5 +18,8 @@ pub use self::notificator::{Notificator, FrontierNotificator};
 
 pub use self::unary::Unary;
 pub use self::binary::Binary;
-pub use self::operator::{Operator, source};
+pub use self::operator::{Operator, source, source_every, source_from_iterator};
 pub use self::operator_info::OperatorInfo;
+pub use self::pacer::Pacer;
+pub use self::spill::EpochBuffer;
+pub use self::asynchronous::AsyncOperator;
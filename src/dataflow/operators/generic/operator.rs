@@ -1,6 +1,8 @@
 
 //! Methods to construct generic streaming and blocking unary operators.
 
+use std::time::{Duration, Instant};
+
 use dataflow::channels::pushers::Tee;
 use dataflow::channels::pact::ParallelizationContract;
 
@@ -8,6 +10,7 @@ use dataflow::operators::generic::handles::{InputHandle, FrontieredInputHandle,
 use dataflow::operators::capability::Capability;
 
 use ::Data;
+use order::PartialOrder;
 
 use dataflow::{Stream, Scope};
 
@@ -103,6 +106,70 @@ pub trait Operator<G: Scope, D1: Data> {
              P: ParallelizationContract<G::Timestamp, D1>>
              (&self, pact: P, name: &str, init: impl IntoIterator<Item=G::Timestamp>, logic: L) -> Stream<G, D2>;
 
+    /// Creates a new dataflow operator like `unary_notify`, whose `logic` is also passed mutable
+    /// access to an explicit `state`.
+    ///
+    /// Because the state is not captured by `logic`, the same logic, for example a plain function,
+    /// can be reused across operators and dataflows with different states. The operator owns `state`;
+    /// to inspect or snapshot it from outside the operator, supply a shared state such as an
+    /// `Rc<RefCell<_>>`.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::collections::HashMap;
+    /// use timely::dataflow::operators::{ToStream, Inspect, Notificator};
+    /// use timely::dataflow::operators::generic::{Operator, InputHandle, OutputHandle};
+    /// use timely::dataflow::channels::pact::Pipeline;
+    /// use timely::dataflow::channels::pushers::Tee;
+    /// use timely::dataflow::channels::Bundle;
+    /// use timely::communication::Pull;
+    /// use timely::progress::nested::product::Product;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// type Time = Product<RootTimestamp, u64>;
+    ///
+    /// // sums records at each time.
+    /// #[derive(Default)]
+    /// struct Sums {
+    ///     sums: HashMap<Time, u64>,
+    /// }
+    ///
+    /// fn sum<P: Pull<Bundle<Time, u64>>>(
+    ///        state: &mut Sums,
+    ///        input: &mut InputHandle<Time, u64, P>,
+    ///        output: &mut OutputHandle<Time, u64, Tee<Time, u64>>,
+    ///        notificator: &mut Notificator<Time>) {
+    ///     input.for_each(|time, data| {
+    ///         *state.sums.entry(time.time().clone()).or_insert(0) += data.iter().sum::<u64>();
+    ///         notificator.notify_at(time.retain());
+    ///     });
+    ///     notificator.for_each(|time, _cnt, _not| {
+    ///         if let Some(sum) = state.sums.remove(time.time()) {
+    ///             output.session(&time).give(sum);
+    ///         }
+    ///     });
+    /// }
+    ///
+    /// timely::example(|scope| {
+    ///     (0u64..10).to_stream(scope)
+    ///         .unary_notify_with_state(Pipeline, "Sum", None, Sums::default(), sum)
+    ///         .inspect(|x| assert_eq!(*x, 45));
+    /// });
+    /// ```
+    fn unary_notify_with_state<D2: Data,
+            St: 'static,
+            L: FnMut(&mut St,
+                     &mut InputHandle<G::Timestamp, D1, P::Puller>,
+                     &mut OutputHandle<G::Timestamp, D2, Tee<G::Timestamp, D2>>,
+                     &mut Notificator<G::Timestamp>)+'static,
+             P: ParallelizationContract<G::Timestamp, D1>>
+             (&self, pact: P, name: &str, init: impl IntoIterator<Item=G::Timestamp>, state: St, mut logic: L) -> Stream<G, D2> {
+        let mut state = state;
+        self.unary_notify(pact, name, init, move |input, output, notificator| {
+            logic(&mut state, input, output, notificator)
+        })
+    }
+
     /// Creates a new dataflow operator that partitions its input stream by a parallelization
     /// strategy `pact`, and repeatedly invokes `logic`, the function returned by the function passed as `constructor`.
     /// `logic` can read from the input stream, and write to the output stream.
@@ -580,3 +647,121 @@ where
 
     stream
 }
+
+/// Creates a new data stream source for a scope, whose logic is invoked at most once per `period`.
+///
+/// The source is constructed as with `source`, but the returned method is only invoked once `period`
+/// has elapsed since its previous invocation (and on the first scheduling of the operator). This
+/// allows sources that poll external systems (files, network services, queues) to avoid issuing a
+/// request each time the worker steps. The operator is still scheduled with each step of the worker,
+/// and so a worker with no other work will continue to spin, but without invoking `logic`.
+///
+/// #Examples
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::generic::operator::source_every;
+///
+/// timely::example(|scope| {
+///
+///     source_every(scope, "Ticks", Duration::from_millis(1), |capability| {
+///         let mut cap = Some(capability);
+///         move |output| {
+///             let mut done = false;
+///             if let Some(cap) = cap.as_mut() {
+///                 let mut time = cap.time().clone();
+///                 output.session(&cap)
+///                       .give(cap.time().inner);
+///                 time.inner += 1;
+///                 *cap = cap.delayed(&time);
+///                 done = time.inner > 5;
+///             }
+///             if done { cap = None; }
+///         }
+///     })
+///     .inspect(|x| println!("tick: {:?}", x));
+/// });
+/// ```
+pub fn source_every<G: Scope, D, B, L>(scope: &G, name: &str, period: Duration, constructor: B) -> Stream<G, D>
+where
+    D: Data,
+    B: FnOnce(Capability<G::Timestamp>) -> L,
+    L: FnMut(&mut OutputHandle<G::Timestamp, D, Tee<G::Timestamp, D>>)+'static {
+
+    source(scope, name, move |capability| {
+        let mut logic = constructor(capability);
+        let mut last: Option<Instant> = None;
+        move |output| {
+            let now = Instant::now();
+            let due = last.map(|last| now.duration_since(last) >= period).unwrap_or(true);
+            if due {
+                last = Some(now);
+                logic(output);
+            }
+        }
+    })
+}
+
+/// Creates a new data stream source from an iterator of timestamped records.
+///
+/// Each time the operator is scheduled it pulls up to `batch_size` records from `iter`. After each
+/// non-empty batch, `watermark` is called with the time of the last record pulled, and should return
+/// a lower bound on the times of all subsequent records; the source's capability is downgraded to this
+/// time if it advances the capability. Records must be at times greater or equal to the most recently
+/// returned watermark, and the operator panics otherwise. Once the iterator is exhausted the source
+/// releases its capability.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::generic::operator::source_from_iterator;
+/// use timely::progress::timestamp::RootTimestamp;
+///
+/// timely::example(|scope| {
+///     let records = (0..100u64).map(|x| (RootTimestamp::new(x / 10), x));
+///     source_from_iterator(scope, records, |time| time.clone(), 16)
+///         .inspect_time(|time, x| assert_eq!(time.inner, x / 10));
+/// });
+/// ```
+pub fn source_from_iterator<G: Scope, D, I, W>(scope: &G, iter: I, mut watermark: W, batch_size: usize) -> Stream<G, D>
+where
+    D: Data,
+    I: IntoIterator<Item=(G::Timestamp, D)>,
+    I::IntoIter: 'static,
+    W: FnMut(&G::Timestamp)->G::Timestamp+'static {
+
+    assert!(batch_size > 0, "batch_size must be positive");
+
+    let mut iter = iter.into_iter();
+    source(scope, "SourceFromIterator", move |capability| {
+        let mut cap = Some(capability);
+        move |output| {
+            let mut done = false;
+            if let Some(cap) = cap.as_mut() {
+                let mut count = 0;
+                let mut last = None;
+                for (time, data) in iter.by_ref().take(batch_size) {
+                    if cap.time() == &time {
+                        output.session(&*cap).give(data);
+                    }
+                    else {
+                        output.session(&cap.delayed(&time)).give(data);
+                    }
+                    last = Some(time);
+                    count += 1;
+                }
+
+                if let Some(last) = last {
+                    let bound = watermark(&last);
+                    if cap.time().less_than(&bound) {
+                        cap.downgrade(&bound);
+                    }
+                }
+
+                done = count < batch_size;
+            }
+
+            if done { cap = None; }
+        }
+    })
+}
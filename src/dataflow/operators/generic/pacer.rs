@@ -0,0 +1,254 @@
+//! A token bucket operators can consult to pace their output.
+//!
+//! Operators are invoked repeatedly by the worker, and an operator holding a backlog of work may
+//! choose to emit only part of it in each invocation. A `Pacer` tracks a budget of abstract units
+//! (records, bytes, requests; the choice is up to the operator) which refills at a fixed rate up
+//! to a maximum burst size. Work withheld from one invocation should be stashed along with the
+//! capabilities needed to send it, as the operator will be invoked again in a subsequent step.
+
+use std::time::{Duration, Instant};
+
+/// A token bucket refilled at a fixed rate, from which operators draw permission to do work.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::operators::generic::{Operator, Pacer};
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// timely::example(|scope| {
+///     (0..100).to_stream(scope)
+///             .unary_frontier(Pipeline, "Paced", |_cap, _info| {
+///                 // release at most 1000 records per second, in bursts of at most 10.
+///                 let mut pacer = Pacer::new(1000, 10);
+///                 let mut stash = Vec::new();
+///                 move |input, output| {
+///                     input.for_each(|time, data| {
+///                         stash.push((time.retain(), data.replace(Vec::new())));
+///                     });
+///                     while let Some((cap, mut data)) = stash.pop() {
+///                         let granted = pacer.take(data.len());
+///                         let rest = data.split_off(granted);
+///                         output.session(&cap).give_vec(&mut data);
+///                         if !rest.is_empty() {
+///                             stash.push((cap, rest));
+///                             break;
+///                         }
+///                     }
+///                 }
+///             })
+///             .inspect(|x| println!("paced: {:?}", x));
+/// });
+/// ```
+#[derive(Debug)]
+pub struct Pacer {
+    /// Units added to the bucket per second.
+    rate: u64,
+    /// Maximum number of units the bucket may hold.
+    burst: u64,
+    /// Units currently available.
+    tokens: u64,
+    /// Time up to which refills have been accounted.
+    refilled: Instant,
+}
+
+impl Pacer {
+    /// Allocates a new full `Pacer` refilling at `rate` units per second, holding at most `burst` units.
+    ///
+    /// The `burst` argument must be at least one, otherwise no work could ever be granted.
+    pub fn new(rate: u64, burst: u64) -> Self {
+        assert!(burst > 0, "Pacer burst size must be positive");
+        Pacer {
+            rate,
+            burst,
+            tokens: burst,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Adds units to the bucket according to the time elapsed since the last refill.
+    ///
+    /// Only whole units are credited; the fractional remainder is retained by not advancing the
+    /// refill time past the last whole unit.
+    pub fn refill(&mut self) {
+        self.refill_at(Instant::now());
+    }
+
+    fn refill_at(&mut self, now: Instant) {
+        if self.tokens >= self.burst || self.rate == 0 {
+            // nothing to credit; restart the clock so that idle time is not banked.
+            self.refilled = now;
+        }
+        else if now > self.refilled {
+            let elapsed = now - self.refilled;
+            let nanos = elapsed.as_secs() as u128 * 1_000_000_000 + elapsed.subsec_nanos() as u128;
+            let earned = nanos * self.rate as u128 / 1_000_000_000;
+            if earned > 0 {
+                let earned = ::std::cmp::min(earned, (self.burst - self.tokens) as u128) as u64;
+                self.tokens += earned;
+                if self.tokens >= self.burst {
+                    self.refilled = now;
+                }
+                else {
+                    let spent = (earned as u128 * 1_000_000_000) / self.rate as u128;
+                    self.refilled += Duration::new((spent / 1_000_000_000) as u64, (spent % 1_000_000_000) as u32);
+                }
+            }
+        }
+    }
+
+    /// Reports the number of units currently available, after refilling.
+    pub fn available(&mut self) -> u64 {
+        self.refill();
+        self.tokens
+    }
+
+    /// Withdraws up to `wanted` units, returning the number actually granted.
+    ///
+    /// The result may be zero, in which case the caller should retain its work for a later invocation.
+    pub fn take(&mut self, wanted: usize) -> usize {
+        self.refill();
+        let granted = ::std::cmp::min(self.tokens, wanted as u64);
+        self.tokens -= granted;
+        granted as usize
+    }
+
+    /// Withdraws exactly `amount` units if they are available, returning whether they were withdrawn.
+    ///
+    /// This is appropriate for indivisible work, for example a request whose size in bytes is known.
+    /// Requests larger than the burst size can never succeed, and are permitted once the bucket is full.
+    pub fn try_take(&mut self, amount: u64) -> bool {
+        self.refill();
+        if amount <= self.tokens {
+            self.tokens -= amount;
+            true
+        }
+        else if amount > self.burst && self.tokens == self.burst {
+            self.tokens = 0;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Reports the time until `amount` units will be available, or `None` if they are available now.
+    ///
+    /// This can be used by sources to decide how long a worker may park before there is more to do.
+    pub fn delay_until(&mut self, amount: u64) -> Option<Duration> {
+        self.refill();
+        let amount = ::std::cmp::min(amount, self.burst);
+        if amount <= self.tokens {
+            None
+        }
+        else if self.rate == 0 {
+            Some(Duration::from_secs(u64::max_value()))
+        }
+        else {
+            let needed = (amount - self.tokens) as u128;
+            let nanos = (needed * 1_000_000_000 + self.rate as u128 - 1) / self.rate as u128;
+            Some(Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+    use super::Pacer;
+
+    /// An empty pacer whose refills are accounted up to `start`.
+    fn empty(rate: u64, burst: u64, start: Instant) -> Pacer {
+        let mut pacer = Pacer::new(rate, burst);
+        pacer.tokens = 0;
+        pacer.refilled = start;
+        pacer
+    }
+
+    #[test]
+    fn burst() {
+        // a zero rate, so that the bucket is not refilled while the test runs.
+        let mut pacer = Pacer::new(0, 10);
+        assert_eq!(pacer.take(25), 10);
+        assert_eq!(pacer.take(1), 0);
+        assert!(!pacer.try_take(1));
+    }
+
+    #[test]
+    fn refill_whole_units() {
+        let start = Instant::now();
+        let mut pacer = empty(1000, 10, start);
+        pacer.refill_at(start + Duration::from_micros(5_500));
+        assert_eq!(pacer.tokens, 5);
+        // the half unit is retained, and completes with the next half millisecond.
+        pacer.refill_at(start + Duration::from_micros(6_000));
+        assert_eq!(pacer.tokens, 6);
+    }
+
+    #[test]
+    fn refill_fractional_rate() {
+        let start = Instant::now();
+        let mut pacer = empty(3, 10, start);
+        pacer.refill_at(start + Duration::from_millis(500));
+        assert_eq!(pacer.tokens, 1);
+        assert_eq!(pacer.refilled, start + Duration::from_nanos(333_333_333));
+        pacer.refill_at(start + Duration::from_millis(700));
+        assert_eq!(pacer.tokens, 2);
+        pacer.refill_at(start + Duration::from_millis(999));
+        assert_eq!(pacer.tokens, 2);
+        pacer.refill_at(start + Duration::from_millis(1000));
+        assert_eq!(pacer.tokens, 3);
+    }
+
+    #[test]
+    fn refill_never_earlier() {
+        let start = Instant::now() + Duration::from_secs(1);
+        let mut pacer = empty(1000, 10, start);
+        pacer.refill_at(start - Duration::from_millis(5));
+        assert_eq!(pacer.tokens, 0);
+        assert_eq!(pacer.refilled, start);
+    }
+
+    #[test]
+    fn long_idle_gap() {
+        let start = Instant::now();
+        let mut pacer = empty(1000, 10, start);
+        let later = start + Duration::from_secs(3600);
+        pacer.refill_at(later);
+        assert_eq!(pacer.tokens, 10);
+        assert_eq!(pacer.refilled, later);
+        // idle time while full is not banked.
+        pacer.tokens = 0;
+        pacer.refill_at(later + Duration::from_millis(3));
+        assert_eq!(pacer.tokens, 3);
+    }
+
+    #[test]
+    fn zero_rate() {
+        let start = Instant::now();
+        let mut pacer = empty(0, 5, start);
+        let later = start + Duration::from_secs(1000);
+        pacer.refill_at(later);
+        assert_eq!(pacer.tokens, 0);
+        assert_eq!(pacer.refilled, later);
+        assert_eq!(pacer.delay_until(1), Some(Duration::from_secs(u64::max_value())));
+        assert_eq!(pacer.take(1), 0);
+    }
+
+    #[test]
+    fn oversized_requests() {
+        let mut pacer = Pacer::new(0, 5);
+        assert!(pacer.try_take(6));
+        assert_eq!(pacer.tokens, 0);
+        assert!(!pacer.try_take(6));
+        assert_eq!(pacer.delay_until(6), Some(Duration::from_secs(u64::max_value())));
+    }
+
+    #[test]
+    fn delay_rounds_up() {
+        // refills are accounted into the future, so that none are credited while the test runs.
+        let mut pacer = empty(3, 10, Instant::now() + Duration::from_secs(3600));
+        assert_eq!(pacer.delay_until(1), Some(Duration::from_nanos(333_333_334)));
+    }
+}
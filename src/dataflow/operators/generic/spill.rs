@@ -0,0 +1,205 @@
+//! A per-timestamp buffer for frontier-gated operators, which spills cold epochs to disk.
+//!
+//! Operators that hold records until their input frontier passes the records' time (windows,
+//! joins, sinks) may accumulate more data for an epoch than a worker's memory holds. An
+//! `EpochBuffer` stashes records by timestamp, accounting for the bytes buffered at each time. When
+//! the bytes held in memory exceed a budget, the buffer writes the epochs furthest from completion
+//! (those with the greatest times) to files, and reads them back once they are taken, typically
+//! when the frontier no longer permits their times.
+//!
+//! Records are measured and written using their `Abomonation` implementations. Spilled files are
+//! read back into buffers aligned to eight bytes, and are checked to have the length written before
+//! they are decoded; record types requiring greater alignment cannot be spilled. Spilled files are
+//! removed once read, and when the buffer is dropped.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use abomonation::{encode, decode, measure};
+
+use order::PartialOrder;
+use Data;
+
+/// Distinguishes the files of buffers in the same process.
+static BUFFERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Records buffered by timestamp, within a memory budget.
+///
+/// #Examples
+/// ```
+/// use std::collections::BTreeMap;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::operators::generic::{Operator, EpochBuffer};
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// timely::example(|scope| {
+///     (0..1000u64).to_stream(scope)
+///                 .unary_frontier(Pipeline, "Spilling", |_cap, _info| {
+///                     // hold at most 1KB in memory, spilling the rest to the temporary directory.
+///                     let mut buffer = EpochBuffer::new(::std::env::temp_dir(), 1 << 10);
+///                     let mut capabilities = BTreeMap::new();
+///                     move |input, output| {
+///                         input.for_each(|time, data| {
+///                             buffer.push(time.time().clone(), &mut data.replace(Vec::new())).expect("spill failed");
+///                             capabilities.entry(time.time().clone()).or_insert_with(|| time.retain());
+///                         });
+///                         for (time, mut data) in buffer.drain_ready(&input.frontier().frontier()).expect("restore failed") {
+///                             let capability = capabilities.remove(&time).expect("missing capability");
+///                             output.session(&capability).give_vec(&mut data);
+///                         }
+///                     }
+///                 })
+///                 .inspect(|x| println!("released: {:?}", x));
+/// });
+/// ```
+pub struct EpochBuffer<T: PartialOrder+Ord+Clone, D: Data> {
+    /// Records held in memory, and their sizes in bytes, by time.
+    resident: BTreeMap<T, (Vec<D>, usize)>,
+    /// Files holding spilled records, their sizes in bytes, and their encoded lengths, by time.
+    spilled: BTreeMap<T, Vec<(PathBuf, usize, usize)>>,
+    /// Bytes held in memory.
+    resident_bytes: usize,
+    /// Bytes held in files.
+    spilled_bytes: usize,
+    /// Bytes that may be held in memory before spilling.
+    budget: usize,
+    /// Directory in which spilled files are written.
+    directory: PathBuf,
+    /// Identifies this buffer's files.
+    identifier: usize,
+    /// Number of files written.
+    files: usize,
+}
+
+impl<T: PartialOrder+Ord+Clone, D: Data> EpochBuffer<T, D> {
+    /// Allocates a new empty buffer, holding at most `budget` bytes in memory and spilling to `directory`.
+    pub fn new<P: Into<PathBuf>>(directory: P, budget: usize) -> Self {
+        EpochBuffer {
+            resident: BTreeMap::new(),
+            spilled: BTreeMap::new(),
+            resident_bytes: 0,
+            spilled_bytes: 0,
+            budget,
+            directory: directory.into(),
+            identifier: BUFFERS.fetch_add(1, Ordering::SeqCst),
+            files: 0,
+        }
+    }
+
+    /// Moves the records of `data` into the buffer at `time`, spilling epochs if over budget.
+    pub fn push(&mut self, time: T, data: &mut Vec<D>) -> Result<()> {
+        let bytes = data.iter().map(|datum| measure(datum)).sum::<usize>();
+        let entry = self.resident.entry(time).or_insert_with(|| (Vec::new(), 0));
+        entry.0.extend(data.drain(..));
+        entry.1 += bytes;
+        self.resident_bytes += bytes;
+        self.spill()
+    }
+
+    /// Writes the resident epochs with the greatest times to files, until within budget.
+    ///
+    /// The epoch with the least time is never spilled, as it is the next to be taken. Returns an error
+    /// without spilling if records of type `D` require alignment greater than eight bytes.
+    pub fn spill(&mut self) -> Result<()> {
+        if self.resident_bytes > self.budget && ::std::mem::align_of::<D>() > ::std::mem::align_of::<u64>() {
+            return Err(Error::new(ErrorKind::InvalidInput, "record type is too strictly aligned to spill"));
+        }
+        while self.resident_bytes > self.budget && self.resident.len() > 1 {
+            let time = self.resident.keys().next_back().cloned().expect("resident epochs vanished");
+            let (data, bytes) = self.resident.remove(&time).expect("resident epoch vanished");
+            let path = self.directory.join(format!("epochs-{}-{}-{}.spill", ::std::process::id(), self.identifier, self.files));
+            self.files += 1;
+            let encoded = measure(&data);
+            {
+                let mut writer = BufWriter::new(File::create(&path)?);
+                unsafe { encode(&data, &mut writer)?; }
+                writer.flush()?;
+            }
+            self.resident_bytes -= bytes;
+            self.spilled_bytes += bytes;
+            self.spilled.entry(time).or_insert_with(Vec::new).push((path, bytes, encoded));
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the records buffered at `time`, reading any spilled records back.
+    pub fn take(&mut self, time: &T) -> Result<Vec<D>> {
+        let mut result = Vec::new();
+        if let Some(files) = self.spilled.remove(time) {
+            for (path, bytes, encoded) in files {
+                let mut file = File::open(&path)?;
+                if file.metadata()?.len() != encoded as u64 {
+                    return Err(Error::new(ErrorKind::InvalidData, "spill file has unexpected length"));
+                }
+                // decoding requires the bytes to be aligned for `Vec<D>`, which a `Vec<u8>` need not be.
+                let mut aligned = vec![0u64; (encoded + 7) / 8];
+                let contents = unsafe { ::std::slice::from_raw_parts_mut(aligned.as_mut_ptr() as *mut u8, encoded) };
+                file.read_exact(contents)?;
+                ::std::fs::remove_file(&path)?;
+                match unsafe { decode::<Vec<D>>(contents) } {
+                    Some((data, rest)) if rest.is_empty() => { result.extend(data.iter().cloned()); },
+                    _ => { return Err(Error::new(ErrorKind::InvalidData, "corrupt spill file")); },
+                }
+                self.spilled_bytes -= bytes;
+            }
+        }
+        if let Some((data, bytes)) = self.resident.remove(time) {
+            result.extend(data);
+            self.resident_bytes -= bytes;
+        }
+        Ok(result)
+    }
+
+    /// Removes and returns, in order of time, the epochs whose times `frontier` no longer permits.
+    pub fn drain_ready(&mut self, frontier: &[T]) -> Result<Vec<(T, Vec<D>)>> {
+        let mut times = self.resident.keys().chain(self.spilled.keys())
+                            .filter(|time| !frontier.iter().any(|f| f.less_equal(time)))
+                            .cloned()
+                            .collect::<Vec<_>>();
+        times.sort();
+        times.dedup();
+        let mut result = Vec::with_capacity(times.len());
+        for time in times {
+            let data = self.take(&time)?;
+            result.push((time, data));
+        }
+        Ok(result)
+    }
+
+    /// The bytes buffered at `time`, in memory or on disk.
+    pub fn bytes(&self, time: &T) -> usize {
+        let resident = self.resident.get(time).map(|x| x.1).unwrap_or(0);
+        let spilled = self.spilled.get(time).map(|files| files.iter().map(|x| x.1).sum()).unwrap_or(0);
+        resident + spilled
+    }
+
+    /// The bytes held in memory.
+    pub fn resident_bytes(&self) -> usize { self.resident_bytes }
+
+    /// The bytes held in spilled files.
+    pub fn spilled_bytes(&self) -> usize { self.spilled_bytes }
+
+    /// The times with buffered records, in order.
+    pub fn times(&self) -> Vec<T> {
+        let mut times = self.resident.keys().chain(self.spilled.keys()).cloned().collect::<Vec<_>>();
+        times.sort();
+        times.dedup();
+        times
+    }
+
+    /// Returns true if no records are buffered.
+    pub fn is_empty(&self) -> bool { self.resident.is_empty() && self.spilled.is_empty() }
+}
+
+impl<T: PartialOrder+Ord+Clone, D: Data> Drop for EpochBuffer<T, D> {
+    fn drop(&mut self) {
+        for files in self.spilled.values() {
+            for &(ref path, _, _) in files.iter() {
+                let _ = ::std::fs::remove_file(path);
+            }
+        }
+    }
+}
@@ -347,6 +347,91 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
     pub fn time(&self) -> &Product<RootTimestamp, T> {
         &self.now_at
     }
+
+    /// Starts a transactional batch of records for the current epoch.
+    ///
+    /// Records sent to the session are introduced only once the session commits, along with the
+    /// advance of the epoch; see `InputSession` for details.
+    pub fn session(&mut self) -> InputSession<T, D> {
+        InputSession {
+            handle: self,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// A transactional batch of records for an input.
+///
+/// An input session buffers the records sent to it, and introduces them to the dataflow along with
+/// the advance of the input's epoch when it commits. No worker step can intervene between the two,
+/// and so the dataflow observes either none of the batch or all of it with the epoch closed. A
+/// session dropped without committing, for example by a panic while the batch is assembled,
+/// discards its records and leaves the input unchanged.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{Input, Inspect, Probe};
+/// use timely::dataflow::operators::input::Handle;
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let mut input = Handle::new();
+///     let probe = worker.dataflow(|scope| {
+///         scope.input_from(&mut input)
+///              .inspect(|x: &u64| assert!(*x % 2 == 0))
+///              .probe()
+///     });
+///
+///     for round in 0..10 {
+///         let mut session = input.session();
+///         session.send(2 * round);
+///         session.send(2 * round + 2);
+///         session.commit(round + 1);
+///         worker.step_while(|| probe.less_than(input.time()));
+///     }
+///
+///     // an abandoned session introduces nothing.
+///     let mut session = input.session();
+///     session.send(1);
+///     drop(session);
+/// }).unwrap();
+/// ```
+pub struct InputSession<'a, T: Timestamp+'a, D: Data+'a> {
+    handle: &'a mut Handle<T, D>,
+    buffer: Vec<D>,
+}
+
+impl<'a, T: Timestamp+'a, D: Data+'a> InputSession<'a, T, D> {
+    /// Adds one record to the batch.
+    pub fn send(&mut self, data: D) {
+        self.buffer.push(data);
+    }
+
+    /// Adds a batch of records to the batch, leaving `buffer` empty.
+    pub fn send_batch(&mut self, buffer: &mut Vec<D>) {
+        self.buffer.extend(buffer.drain(..));
+    }
+
+    /// The number of records in the batch.
+    pub fn len(&self) -> usize { self.buffer.len() }
+
+    /// Returns true if the batch holds no records.
+    pub fn is_empty(&self) -> bool { self.buffer.is_empty() }
+
+    /// The epoch at which the batch will be introduced.
+    pub fn epoch(&self) -> &T { self.handle.epoch() }
+
+    /// Introduces the records of the batch at the current epoch, and advances the input to `next`.
+    ///
+    /// This method panics, introducing nothing, if `next` is not greater or equal to the current epoch.
+    pub fn commit(mut self, next: T) {
+        assert!(self.handle.epoch().less_equal(&next), "input session committed to an earlier epoch");
+        self.handle.send_batch(&mut self.buffer);
+        self.handle.advance_to(next);
+    }
+
+    /// Discards the records of the batch, leaving the input unchanged.
+    pub fn abort(self) { }
 }
 
 impl<T:Timestamp, D: Data> Drop for Handle<T, D> {
@@ -0,0 +1,180 @@
+//! Groups of input handles advanced together.
+//!
+//! The frontier of a dataflow with several inputs can advance no further than its least advanced
+//! input, and an input whose epoch is never advanced stalls the computation without any error. An
+//! `InputGroup` holds a set of named input handles, advances them together, and detects inputs whose
+//! epochs lag the others by more than a bound, naming them.
+
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+use Data;
+use order::PartialOrder;
+use progress::{Timestamp, PathSummary};
+use progress::timestamp::RootTimestamp;
+use progress::nested::product::Product;
+use dataflow::operators::input::Handle as InputHandle;
+
+/// The operations an `InputGroup` requires of its members, independent of their data types.
+trait Member<T> {
+    fn advance_to(&mut self, next: T);
+    fn epoch(&self) -> &T;
+}
+
+impl<T: Timestamp, D: Data> Member<T> for InputHandle<T, D> {
+    fn advance_to(&mut self, next: T) { InputHandle::advance_to(self, next) }
+    fn epoch(&self) -> &T { InputHandle::epoch(self) }
+}
+
+/// A set of named input handles whose epochs are advanced together.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{Input, Map, Concat, Inspect, Probe};
+/// use timely::dataflow::operators::input_group::InputGroup;
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let (numbers, names, probe) = worker.dataflow(|scope| {
+///         let (numbers, stream1) = scope.new_input::<u64>();
+///         let (names, stream2) = scope.new_input::<String>();
+///         let probe = stream1.map(|x| x.to_string())
+///                            .concat(&stream2)
+///                            .inspect(|x| println!("seen: {:?}", x))
+///                            .probe();
+///         (numbers, names, probe)
+///     });
+///
+///     // allow inputs to be at most one epoch apart.
+///     let mut group = InputGroup::new(1);
+///     let mut numbers = group.add("numbers", numbers);
+///     let mut names = group.add("names", names);
+///
+///     for round in 0..10 {
+///         numbers.send(round);
+///         names.send(format!("round {}", round));
+///         group.advance_to(round + 1);
+///         worker.step_while(|| probe.less_than(&numbers.time()));
+///     }
+///
+///     // a single input may advance, up to the bound.
+///     numbers.advance_to(11);
+///     assert!(group.lagging().is_empty());
+///     numbers.advance_to(12);
+///     assert_eq!(group.lagging(), vec!["names".to_owned()]);
+/// }).unwrap();
+/// ```
+pub struct InputGroup<T: Timestamp> {
+    members: Vec<(String, Weak<RefCell<Member<T>>>)>,
+    bound: T::Summary,
+}
+
+impl<T: Timestamp> InputGroup<T> {
+
+    /// Allocates a new empty group, whose members may lag one another by at most `bound`.
+    pub fn new(bound: T::Summary) -> Self {
+        InputGroup {
+            members: Vec::new(),
+            bound,
+        }
+    }
+
+    /// Adds `input` to the group under `name`, returning a handle through which it is used.
+    ///
+    /// The input is closed once the returned handle is closed or dropped, at which point it leaves
+    /// the group.
+    pub fn add<D: Data>(&mut self, name: &str, input: InputHandle<T, D>) -> GroupedInput<T, D> {
+        let input = Rc::new(RefCell::new(input));
+        let member: Rc<RefCell<Member<T>>> = input.clone();
+        self.members.push((name.to_owned(), Rc::downgrade(&member)));
+        GroupedInput { input }
+    }
+
+    /// Advances each open member whose epoch is less or equal to `next` to `next`.
+    ///
+    /// #Panics
+    ///
+    /// Panics, naming the lagging members, if after advancing some member's epoch lags another's by
+    /// more than the group's bound.
+    pub fn advance_to(&mut self, next: T) {
+        self.members.retain(|&(_, ref member)| member.upgrade().is_some());
+        for &(_, ref member) in self.members.iter() {
+            if let Some(member) = member.upgrade() {
+                let mut member = member.borrow_mut();
+                if member.epoch().less_equal(&next) {
+                    member.advance_to(next.clone());
+                }
+            }
+        }
+
+        let lagging = self.lagging();
+        assert!(lagging.is_empty(), "inputs {:?} lag other inputs by more than {:?}", lagging, self.bound);
+    }
+
+    /// Reports the names of open members whose epochs lag some other member's epoch by more than the bound.
+    ///
+    /// A member lags another if the other's epoch is not less or equal to the member's epoch advanced
+    /// by the bound.
+    pub fn lagging(&self) -> Vec<String> {
+        let mut epochs = Vec::new();
+        for &(ref name, ref member) in self.members.iter() {
+            if let Some(member) = member.upgrade() {
+                let epoch = member.borrow().epoch().clone();
+                epochs.push((name, epoch));
+            }
+        }
+
+        epochs.iter()
+              .filter(|&&(_, ref epoch)| {
+                  self.bound.results_in(epoch).map(|limit| {
+                      epochs.iter().any(|&(_, ref other)| !other.less_equal(&limit))
+                  })
+                  .unwrap_or(false)
+              })
+              .map(|&(name, _)| name.clone())
+              .collect()
+    }
+
+    /// The number of members that have not been closed.
+    pub fn len(&self) -> usize {
+        self.members.iter().filter(|&&(_, ref member)| member.upgrade().is_some()).count()
+    }
+}
+
+/// An input handle belonging to an `InputGroup`.
+pub struct GroupedInput<T: Timestamp, D: Data> {
+    input: Rc<RefCell<InputHandle<T, D>>>,
+}
+
+impl<T: Timestamp, D: Data> GroupedInput<T, D> {
+    /// Sends one record at the current epoch.
+    pub fn send(&mut self, data: D) {
+        self.input.borrow_mut().send(data);
+    }
+
+    /// Sends a batch of records at the current epoch.
+    pub fn send_batch(&mut self, buffer: &mut Vec<D>) {
+        self.input.borrow_mut().send_batch(buffer);
+    }
+
+    /// Advances this input alone to `next`.
+    ///
+    /// The group does not check the bound when a single input advances; the bound is checked when
+    /// the group next advances, or when `lagging` is called.
+    pub fn advance_to(&mut self, next: T) {
+        self.input.borrow_mut().advance_to(next);
+    }
+
+    /// Reports the current epoch.
+    pub fn epoch(&self) -> T {
+        self.input.borrow().epoch().clone()
+    }
+
+    /// Reports the current timestamp.
+    pub fn time(&self) -> Product<RootTimestamp, T> {
+        self.input.borrow().time().clone()
+    }
+
+    /// Closes the input, removing it from its group.
+    pub fn close(self) { }
+}
@@ -0,0 +1,114 @@
+//! Operators assigning timestamps to records, with policies for records that arrive late.
+//!
+//! Records often carry their own notion of time (an "event time"), which is used to place them
+//! in the timely dataflow stream by delaying them to the corresponding timestamp. This is only
+//! possible if the record arrives at a timestamp less or equal to its event time, as timely does
+//! not permit records to move backwards in time. Records that arrive too late for this are "late",
+//! and the operators in this module apply a `LatePolicy` to them rather than panicking.
+//!
+//! A record arriving at a timestamp is always at or beyond the frontier of its input, and so a
+//! record is late exactly when its event time is not greater or equal to the time it arrived at.
+
+use std::collections::HashMap;
+
+use Data;
+use order::PartialOrder;
+use dataflow::channels::pact::Pipeline;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+use dataflow::operators::generic::FrontierNotificator;
+
+/// Describes what to do with a record that arrives after its assigned time.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LatePolicy {
+    /// Discard late records.
+    Drop,
+    /// Send late records, at the time they arrived, to a separate output.
+    Divert,
+    /// Send late records at the time they arrived, rather than their assigned time.
+    Retime,
+}
+
+/// Delays records to their assigned times, applying a policy to records that arrive late.
+pub trait DelayLate<G: Scope, D: Data> {
+    /// Advances the timestamp of records using a supplied function, applying `policy` to any record
+    /// whose new timestamp is not greater or equal to its current timestamp.
+    ///
+    /// The method returns two streams: the first contains records at their assigned times (and, under
+    /// `LatePolicy::Retime`, late records at their current times), and the second contains late records
+    /// at their current times under `LatePolicy::Divert`, and is empty otherwise.
+    ///
+    /// #Examples
+    ///
+    /// The following example assigns each record its value as a timestamp, after the records have
+    /// already been placed at times 0 through 9. Records less than 5 are then late, and diverted.
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Delay, Inspect};
+    /// use timely::dataflow::operators::late::{DelayLate, LatePolicy};
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::example(|scope| {
+    ///     let (on_time, late) =
+    ///     (0..10).to_stream(scope)
+    ///            .delay(|x,_t| RootTimestamp::new(9 - *x))
+    ///            .delay_late(|x,_t| RootTimestamp::new(*x), LatePolicy::Divert);
+    ///
+    ///     on_time.inspect_time(|t, x| assert_eq!(t.inner, *x));
+    ///     late.inspect(|x| assert!(*x < 5));
+    /// });
+    /// ```
+    fn delay_late(&self, func: impl Fn(&D, &G::Timestamp)->G::Timestamp+'static, policy: LatePolicy) -> (Stream<G, D>, Stream<G, D>);
+}
+
+impl<G: Scope, D: Data> DelayLate<G, D> for Stream<G, D> {
+    fn delay_late(&self, func: impl Fn(&D, &G::Timestamp)->G::Timestamp+'static, policy: LatePolicy) -> (Stream<G, D>, Stream<G, D>) {
+
+        let mut builder = OperatorBuilder::new("DelayLate".to_owned(), self.scope());
+
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut output, stream) = builder.new_output();
+        let (mut late_output, late_stream) = builder.new_output();
+
+        builder.build(move |_| {
+
+            let mut notificator = FrontierNotificator::new();
+            let mut elements = HashMap::new();
+            let mut vector = Vec::new();
+            let mut late = Vec::new();
+
+            move |frontiers| {
+
+                let mut output_handle = output.activate();
+                let mut late_handle = late_output.activate();
+
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    for datum in vector.drain(..) {
+                        let new_time = func(&datum, &time);
+                        let new_time = if time.time().less_equal(&new_time) { new_time } else {
+                            match policy {
+                                LatePolicy::Drop => continue,
+                                LatePolicy::Divert => { late.push(datum); continue; },
+                                LatePolicy::Retime => time.time().clone(),
+                            }
+                        };
+                        elements.entry(new_time.clone())
+                                .or_insert_with(|| { notificator.notify_at(time.delayed(&new_time)); Vec::new() })
+                                .push(datum);
+                    }
+                    late_handle.session(&time).give_vec(&mut late);
+                });
+
+                // for each available notification, send corresponding set
+                notificator.for_each(&[&frontiers[0]], |time, _| {
+                    if let Some(mut data) = elements.remove(time.time()) {
+                        output_handle.session(&time).give_iterator(data.drain(..));
+                    }
+                });
+            }
+        });
+
+        (stream, late_stream)
+    }
+}
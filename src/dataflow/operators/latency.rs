@@ -0,0 +1,171 @@
+//! Stamping records with their origin time, and measuring their latency at sinks.
+//!
+//! A `Stamped<D>` record carries a `D` and, if it was selected when stamped, an `Origin` recording an
+//! identifier and the time at which it was stamped. Operators may transform the data with
+//! `map_stamped`, which retains the stamp, and `measure_latency` removes the stamps at a sink and
+//! logs the observed latencies as `TimelyEvent::Latency` events, which `Metrics` also collects.
+//!
+//! Stamp times are read from the clock used for log timestamps. This clock is not synchronized
+//! between processes, and so latencies of records stamped in one process and measured in another
+//! include the difference of their clocks.
+
+use Data;
+use communication::logging::get_precise_time_ns;
+use logging::{TimelyEvent, LatencyEvent};
+use dataflow::channels::pact::Pipeline;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::Operator;
+
+/// The identifier and origin time of a stamped record.
+#[derive(Abomonation, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Origin {
+    /// An identifier unique among the records stamped by a computation.
+    pub id: u64,
+    /// The log clock, in nanoseconds, at which the record was stamped.
+    pub time_ns: u64,
+}
+
+/// A record, and its origin if it was selected for latency measurement.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stamped<D> {
+    /// The origin, if the record was selected.
+    pub origin: Option<Origin>,
+    /// The record.
+    pub data: D,
+}
+
+/// Methods to stamp records for latency measurement.
+pub trait Stamp<G: Scope, D: Data> {
+    /// Stamps every record.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::ToStream;
+    /// use timely::dataflow::operators::latency::{Stamp, Latency};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .stamp()
+    ///            .map_stamped(|x| x + 1)
+    ///            .measure_latency("increment");
+    /// });
+    /// ```
+    fn stamp(&self) -> Stream<G, Stamped<D>> {
+        self.stamp_where(|_| true)
+    }
+    /// Stamps the records satisfying `predicate`; other records are wrapped without a stamp.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::latency::{Stamp, Latency};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .stamp_where(|x| x % 5 == 0)
+    ///            .inspect(|x| assert_eq!(x.origin.is_some(), x.data % 5 == 0))
+    ///            .measure_latency("sampled");
+    /// });
+    /// ```
+    fn stamp_where(&self, predicate: impl FnMut(&D)->bool+'static) -> Stream<G, Stamped<D>>;
+}
+
+impl<G: Scope, D: Data> Stamp<G, D> for Stream<G, D> {
+    fn stamp_where(&self, mut predicate: impl FnMut(&D)->bool+'static) -> Stream<G, Stamped<D>> {
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "Stamp", move |_,_| {
+            // identifiers are interleaved across workers, so that they are unique.
+            let peers = self.scope().peers() as u64;
+            let mut next = self.scope().index() as u64;
+            move |input, output| {
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    let time_ns = get_precise_time_ns();
+                    output.session(&time).give_iterator(vector.drain(..).map(|data| {
+                        let origin = if predicate(&data) {
+                            let id = next;
+                            next += peers;
+                            Some(Origin { id, time_ns })
+                        }
+                        else { None };
+                        Stamped { origin, data }
+                    }));
+                });
+            }
+        })
+    }
+}
+
+/// Methods on streams of stamped records.
+pub trait Latency<G: Scope, D: Data> {
+    /// Transforms the data of each record, retaining its stamp.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::latency::{Stamp, Latency};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .stamp()
+    ///            .map_stamped(|x| x * 2)
+    ///            .inspect(|x| assert!(x.origin.is_some() && x.data % 2 == 0));
+    /// });
+    /// ```
+    fn map_stamped<D2: Data>(&self, logic: impl FnMut(D)->D2+'static) -> Stream<G, Stamped<D2>>;
+    /// Removes the stamps, logging the latencies of stamped records under `name`.
+    ///
+    /// Each batch containing stamped records logs one `LatencyEvent`, summarizing the latencies
+    /// observed in the batch.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::latency::{Stamp, Latency};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .stamp()
+    ///            .measure_latency("identity")
+    ///            .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn measure_latency(&self, name: &str) -> Stream<G, D>;
+}
+
+impl<G: Scope, D: Data> Latency<G, D> for Stream<G, Stamped<D>> {
+    fn map_stamped<D2: Data>(&self, mut logic: impl FnMut(D)->D2+'static) -> Stream<G, Stamped<D2>> {
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "MapStamped", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                output.session(&time).give_iterator(vector.drain(..).map(|x| Stamped { origin: x.origin, data: logic(x.data) }));
+            });
+        })
+    }
+
+    fn measure_latency(&self, name: &str) -> Stream<G, D> {
+        let name = name.to_owned();
+        let logging = self.scope().logging();
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "MeasureLatency", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let now = get_precise_time_ns();
+                let mut event = LatencyEvent { name: name.clone(), count: 0, sum_ns: 0, max_ns: 0 };
+                for record in vector.iter() {
+                    if let Some(origin) = record.origin {
+                        let latency = now.saturating_sub(origin.time_ns);
+                        event.count += 1;
+                        event.sum_ns += latency;
+                        if event.max_ns < latency { event.max_ns = latency; }
+                    }
+                }
+                if event.count > 0 {
+                    logging.when_enabled(|l| l.log(TimelyEvent::Latency(event)));
+                }
+                output.session(&time).give_iterator(vector.drain(..).map(|x| x.data));
+            });
+        })
+    }
+}
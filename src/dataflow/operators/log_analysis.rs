@@ -0,0 +1,245 @@
+//! Standard analyses of replayed timely logging streams.
+//!
+//! The `LogAnalysis` trait applies to streams of timely logging events, typically obtained by
+//! replaying the captured logs of a computation with `Replay`, and computes the quantities most
+//! often extracted from them: the fraction of time each operator is scheduled, the rates at which
+//! channels carry messages and records, and how long operators wait between schedulings.
+//!
+//! Each analysis exchanges events by the index of the worker that logged them, and processes the
+//! events of each logical time once it is complete, in order of their log timestamps. Windowed
+//! analyses report a window once an event of the same worker is logged after its end, and so the
+//! final partial window of each worker is not reported.
+//!
+//! #Examples
+//! ```
+//! use std::rc::Rc;
+//! use std::sync::{Mutex, mpsc};
+//! use timely::dataflow::operators::{ToStream, Inspect, Capture, Replay};
+//! use timely::dataflow::operators::capture::{Event, EventLink, EventPusher};
+//! use timely::dataflow::operators::log_analysis::LogAnalysis;
+//! use timely::logging::LoggerConfig;
+//!
+//! // capture the logs of a computation.
+//! let (send, recv) = mpsc::channel();
+//! let send = Mutex::new(send);
+//! let config = LoggerConfig::timely_only(move |_setup| send.lock().unwrap().clone());
+//!
+//! timely::execute_logging(timely::Configuration::Thread, config, |worker| {
+//!     worker.dataflow::<u64,_,_>(|scope| {
+//!         (0..1000).to_stream(scope)
+//!                  .inspect(|_| { });
+//!     });
+//! }).unwrap();
+//!
+//! // analyse the replayed logs.
+//! let events = recv.try_iter().collect::<Vec<_>>();
+//! let utilization = timely::example(move |scope| {
+//!     let mut link = Rc::new(EventLink::new());
+//!     let replay = link.clone();
+//!     for event in events.iter().cloned() {
+//!         link.push(event);
+//!     }
+//!     Some(replay).replay_into(scope)
+//!                 .operator_utilization(1_000_000)
+//!                 .capture()
+//! });
+//!
+//! for event in utilization.try_iter() {
+//!     if let Event::Messages(_time, records) = event {
+//!         for record in records {
+//!             assert!(record.fraction <= 1.0);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use dataflow::channels::pact::Exchange;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::Operator;
+use logging::{TimelySetup, TimelyEvent, StartStop};
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+
+/// The fraction of a window of log time during which an operator was scheduled.
+#[derive(Abomonation, Debug, Clone, PartialEq)]
+pub struct Utilization {
+    /// The worker scheduling the operator.
+    pub worker: usize,
+    /// The operator identifier.
+    pub operator: usize,
+    /// The start of the window, in log nanoseconds.
+    pub window_ns: u64,
+    /// The time the operator was scheduled within the window, in nanoseconds.
+    pub busy_ns: u64,
+    /// The fraction of the window the operator was scheduled.
+    pub fraction: f64,
+}
+
+/// The traffic sent on a channel by a worker within a window of log time.
+#[derive(Abomonation, Debug, Clone, PartialEq)]
+pub struct ChannelRate {
+    /// The sending worker.
+    pub worker: usize,
+    /// The channel identifier.
+    pub channel: usize,
+    /// The start of the window, in log nanoseconds.
+    pub window_ns: u64,
+    /// The number of messages sent within the window.
+    pub messages: u64,
+    /// The number of records sent within the window.
+    pub records: u64,
+    /// The rate of messages, per second.
+    pub messages_per_sec: f64,
+    /// The rate of records, per second.
+    pub records_per_sec: f64,
+}
+
+/// The wait of an operator before a scheduling in which it performed work.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleWait {
+    /// The worker scheduling the operator.
+    pub worker: usize,
+    /// The operator identifier.
+    pub operator: usize,
+    /// The log time at which the scheduling started.
+    pub start_ns: u64,
+    /// The time since the previous scheduling of the operator ended, in nanoseconds.
+    pub wait_ns: u64,
+}
+
+/// Analyses of streams of timely logging events.
+pub trait LogAnalysis<G: Scope<Timestamp=Product<RootTimestamp, u64>>> {
+    /// Reports the fraction of each window of `window_ns` log nanoseconds during which each
+    /// operator was scheduled, for windows in which the operator was scheduled.
+    fn operator_utilization(&self, window_ns: u64) -> Stream<G, Utilization>;
+    /// Reports the messages and records sent by each worker on each channel within each window of
+    /// `window_ns` log nanoseconds, and their rates, for windows in which the channel was used.
+    fn channel_rates(&self, window_ns: u64) -> Stream<G, ChannelRate>;
+    /// Reports, for each scheduling in which an operator performed work, the time since its
+    /// previous scheduling ended.
+    ///
+    /// The wait bounds how long the work may have waited to be scheduled.
+    fn schedule_waits(&self) -> Stream<G, ScheduleWait>;
+}
+
+impl<G: Scope<Timestamp=Product<RootTimestamp, u64>>> LogAnalysis<G> for Stream<G, (u64, TimelySetup, TimelyEvent)> {
+
+    fn operator_utilization(&self, window_ns: u64) -> Stream<G, Utilization> {
+        assert!(window_ns > 0, "window must be non-empty");
+        // per worker: the current window, busy time per operator within it, and open schedulings.
+        let mut windows = HashMap::<usize, (u64, HashMap<usize, u64>, HashMap<usize, u64>)>::new();
+        in_order(self, "OperatorUtilization", move |ts, setup, event, output| {
+            let &mut (ref mut window, ref mut busy, ref mut started) = windows.entry(setup.index).or_insert_with(|| (ts - ts % window_ns, HashMap::new(), HashMap::new()));
+            // close windows ending at or before `ts`, apportioning open schedulings.
+            while *window + window_ns <= ts {
+                let end = *window + window_ns;
+                for (&operator, start) in started.iter_mut() {
+                    *busy.entry(operator).or_insert(0) += end - *start;
+                    *start = end;
+                }
+                for (operator, busy_ns) in busy.drain() {
+                    let fraction = busy_ns as f64 / window_ns as f64;
+                    output.push(Utilization { worker: setup.index, operator, window_ns: *window, busy_ns, fraction });
+                }
+                // skip windows in which nothing was scheduled.
+                *window = if started.is_empty() { ts - ts % window_ns } else { end };
+            }
+            if let TimelyEvent::Schedule(ref schedule) = *event {
+                match schedule.start_stop {
+                    StartStop::Start => { started.insert(schedule.id, ts); },
+                    StartStop::Stop { .. } => {
+                        if let Some(start) = started.remove(&schedule.id) {
+                            *busy.entry(schedule.id).or_insert(0) += ts - start;
+                        }
+                    },
+                }
+            }
+        })
+    }
+
+    fn channel_rates(&self, window_ns: u64) -> Stream<G, ChannelRate> {
+        assert!(window_ns > 0, "window must be non-empty");
+        // per worker: the current window, and messages and records per channel within it.
+        let mut windows = HashMap::<usize, (u64, HashMap<usize, (u64, u64)>)>::new();
+        in_order(self, "ChannelRates", move |ts, setup, event, output| {
+            let &mut (ref mut window, ref mut counts) = windows.entry(setup.index).or_insert_with(|| (ts - ts % window_ns, HashMap::new()));
+            if *window + window_ns <= ts {
+                let seconds = window_ns as f64 / 1_000_000_000.0;
+                for (channel, (messages, records)) in counts.drain() {
+                    output.push(ChannelRate {
+                        worker: setup.index,
+                        channel,
+                        window_ns: *window,
+                        messages,
+                        records,
+                        messages_per_sec: messages as f64 / seconds,
+                        records_per_sec: records as f64 / seconds,
+                    });
+                }
+                *window = ts - ts % window_ns;
+            }
+            if let TimelyEvent::Messages(ref messages) = *event {
+                if messages.is_send {
+                    let counts = counts.entry(messages.channel).or_insert((0, 0));
+                    counts.0 += 1;
+                    counts.1 += messages.length as u64;
+                }
+            }
+        })
+    }
+
+    fn schedule_waits(&self) -> Stream<G, ScheduleWait> {
+        // per worker and operator: the end of the previous scheduling, and the start of the current.
+        let mut previous = HashMap::<(usize, usize), (Option<u64>, u64)>::new();
+        in_order(self, "ScheduleWaits", move |ts, setup, event, output| {
+            if let TimelyEvent::Schedule(ref schedule) = *event {
+                let state = previous.entry((setup.index, schedule.id)).or_insert((None, 0));
+                match schedule.start_stop {
+                    StartStop::Start => { state.1 = ts; },
+                    StartStop::Stop { activity } => {
+                        if activity {
+                            if let Some(stopped) = state.0 {
+                                output.push(ScheduleWait { worker: setup.index, operator: schedule.id, start_ns: state.1, wait_ns: state.1.saturating_sub(stopped) });
+                            }
+                        }
+                        state.0 = Some(ts);
+                    },
+                }
+            }
+        })
+    }
+}
+
+/// Applies `logic` to the events of each worker in order of their log timestamps, producing records
+/// at the logical time of the events that produced them.
+fn in_order<G, R, L>(stream: &Stream<G, (u64, TimelySetup, TimelyEvent)>, name: &str, mut logic: L) -> Stream<G, R>
+where
+    G: Scope<Timestamp=Product<RootTimestamp, u64>>,
+    R: ::Data,
+    L: FnMut(u64, &TimelySetup, &TimelyEvent, &mut Vec<R>)+'static,
+{
+    let mut stash = HashMap::new();
+    let mut vector = Vec::new();
+    let mut results = Vec::new();
+    stream.unary_notify(Exchange::new(|x: &(u64, TimelySetup, TimelyEvent)| (x.1).index as u64), name, vec![], move |input, output, notificator| {
+        input.for_each(|time, data| {
+            data.swap(&mut vector);
+            stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+            notificator.notify_at(time.retain());
+        });
+        // notifications are delivered in order of their times.
+        notificator.for_each(|time, _count, _notificator| {
+            if let Some(mut events) = stash.remove(time.time()) {
+                events.sort_by_key(|x: &(u64, TimelySetup, TimelyEvent)| x.0);
+                for (ts, setup, event) in events {
+                    logic(ts, &setup, &event, &mut results);
+                }
+                if !results.is_empty() {
+                    output.session(&time).give_vec(&mut results);
+                }
+            }
+        });
+    })
+}
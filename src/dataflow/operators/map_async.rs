@@ -0,0 +1,162 @@
+//! Applies blocking functions to records on a pool of threads, without stalling the worker.
+//!
+//! A worker runs all of its operators on one thread, and an operator that blocks (on a DNS lookup, or a
+//! database query) stalls every dataflow the worker hosts. The `map_async` operator instead ships each
+//! record to a pool of threads owned by the operator, keeps a capability for the record's time while
+//! it is in flight, and produces the result at the same time once the function returns. The threads
+//! exit once the operator is dropped.
+//!
+//! Results of `map_async` are produced in order of completion, and so records of the same time may be
+//! reordered. The `map_async_ordered` operator numbers the records of each time as they arrive, and
+//! produces their results in that order, holding results back until those of earlier records are
+//! produced.
+//!
+//! A panic in the function is caught on the pool thread, which continues with other records, and is
+//! resumed with its original payload on the worker thread, failing the operator as a panic in its own
+//! logic would.
+
+use std::collections::{BTreeMap, HashMap};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::channel;
+
+use Data;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::Capability;
+use dataflow::operators::generic::Operator;
+use dataflow::operators::offload::{Offload, Completion};
+
+/// Extension trait for `Stream`.
+pub trait MapAsync<G: Scope, D: Data+Send> {
+    /// Applies `logic` to each record on one of `pool_size` threads, producing the results at the
+    /// times of their records, in order of completion.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::map_async::MapAsync;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10u64).to_stream(scope)
+    ///               .map_async(4, |x| {
+    ///                   // stands in for a blocking call to a remote service.
+    ///                   ::std::thread::sleep(Duration::from_millis(10));
+    ///                   x + 1
+    ///               })
+    ///               .inspect(|x| println!("looked up: {:?}", x));
+    /// });
+    /// ```
+    fn map_async<R: Data+Send, L: Fn(D)->R+Send+Sync+'static>(&self, pool_size: usize, logic: L) -> Stream<G, R>;
+
+    /// As `map_async`, but producing the results of each time in the order their records arrived.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::map_async::MapAsync;
+    ///
+    /// timely::example(|scope| {
+    ///     let mut expected = 0;
+    ///     (0..10u64).to_stream(scope)
+    ///               .map_async_ordered(4, |x| {
+    ///                   // later records complete sooner, but are produced in order.
+    ///                   ::std::thread::sleep(Duration::from_millis(20 - 2 * x));
+    ///                   x
+    ///               })
+    ///               .inspect(move |x| { assert_eq!(*x, expected); expected += 1; });
+    /// });
+    /// ```
+    fn map_async_ordered<R: Data+Send, L: Fn(D)->R+Send+Sync+'static>(&self, pool_size: usize, logic: L) -> Stream<G, R>;
+}
+
+impl<G: Scope, D: Data+Send> MapAsync<G, D> for Stream<G, D> {
+    fn map_async<R: Data+Send, L: Fn(D)->R+Send+Sync+'static>(&self, pool_size: usize, logic: L) -> Stream<G, R> {
+
+        assert!(pool_size > 0, "pool size must be positive");
+
+        let (sender, receiver) = channel::<(Vec<D>, Completion<R>)>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let logic = Arc::new(logic);
+
+        for index in 0 .. pool_size {
+            let receiver = receiver.clone();
+            let logic = logic.clone();
+            ::std::thread::Builder::new()
+                .name(format!("map_async thread {}", index))
+                .spawn(move || {
+                    loop {
+                        // the lock is released before the job runs, so that other threads may receive.
+                        let job = receiver.lock().ok().and_then(|receiver| receiver.recv().ok());
+                        match job {
+                            Some((records, completion)) => {
+                                let results = catch_unwind(AssertUnwindSafe(|| {
+                                    records.into_iter().map(|record| logic(record)).collect()
+                                }));
+                                match results {
+                                    Ok(results) => completion.complete(results),
+                                    Err(payload) => completion.panic(payload),
+                                }
+                            },
+                            None => break,
+                        }
+                    }
+                })
+                .expect("failed to spawn map_async thread");
+        }
+
+        // each record is a batch, so that the pool's threads share the records of a time.
+        self.offload("MapAsync", 1, move |records, completion| {
+            sender.send((records, completion)).expect("map_async threads have exited");
+        })
+    }
+
+    fn map_async_ordered<R: Data+Send, L: Fn(D)->R+Send+Sync+'static>(&self, pool_size: usize, logic: L) -> Stream<G, R> {
+
+        // number the records of each time in order of arrival.
+        let mut counts: HashMap<G::Timestamp, u64> = HashMap::new();
+        let numbered = self.unary_frontier(Pipeline, "Sequence", move |_capability, _info| move |input, output| {
+            input.for_each(|time, data| {
+                let count = counts.entry(time.time().clone()).or_insert(0);
+                let mut session = output.session(&time);
+                for record in data.replace(Vec::new()) {
+                    session.give((*count, record));
+                    *count += 1;
+                }
+            });
+            let frontier = input.frontier();
+            counts.retain(|time, _| frontier.less_equal(time));
+        });
+
+        let results = numbered.map_async(pool_size, move |(sequence, record)| (sequence, logic(record)));
+
+        // produce results of each time in sequence, holding back those that arrive early.
+        let mut pending: HashMap<G::Timestamp, (Capability<G::Timestamp>, u64, BTreeMap<u64, R>)> = HashMap::new();
+        results.unary_frontier(Pipeline, "Reorder", move |_capability, _info| move |input, output| {
+            input.for_each(|time, data| {
+                let key = time.time().clone();
+                let entry = pending.entry(key).or_insert_with(|| (time.retain(), 0, BTreeMap::new()));
+                entry.2.extend(data.replace(Vec::new()));
+                let mut session = output.session(&entry.0);
+                while let Some(result) = entry.2.remove(&entry.1) {
+                    session.give(result);
+                    entry.1 += 1;
+                }
+            });
+            // once the frontier passes a time, no held-back result can be preceded by another.
+            let frontier = input.frontier();
+            pending.retain(|time, &mut (ref capability, _, ref mut held)| {
+                if frontier.less_equal(time) { true }
+                else {
+                    let mut session = output.session(capability);
+                    for (_, result) in ::std::mem::replace(held, BTreeMap::new()) {
+                        session.give(result);
+                    }
+                    false
+                }
+            });
+        })
+    }
+}
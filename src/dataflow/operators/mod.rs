@@ -22,9 +22,11 @@ pub use self::delay::Delay;
 pub use self::exchange::Exchange;
 pub use self::broadcast::Broadcast;
 pub use self::probe::Probe;
-pub use self::to_stream::ToStream;
+pub use self::to_stream::{ToStream, ToStreamSplit};
 pub use self::capture::Capture;
+pub use self::collect::Collect;
 pub use self::branch::{Branch, BranchWhen};
+pub use self::zip::{Zip, Unzip};
 
 pub use self::generic::{Unary, Binary, Operator};
 pub use self::generic::{Notificator, FrontierNotificator};
@@ -34,27 +36,54 @@ pub use self::count::Accumulate;
 
 pub mod enterleave;
 pub mod input;
+pub mod input_group;
+pub mod bridge;
+pub mod ticker;
+#[cfg(feature = "external")]
+pub mod external;
 pub mod flow_controlled;
+pub mod latency;
+pub mod log_analysis;
+pub mod sink;
 pub mod unordered_input;
+pub mod watermark;
 pub mod feedback;
 pub mod concat;
 pub mod partition;
 pub mod map;
+pub mod fuse;
 pub mod inspect;
 pub mod filter;
 pub mod delay;
+pub mod late;
 pub mod exchange;
 pub mod broadcast;
 pub mod probe;
 pub mod to_stream;
 pub mod capture;
+pub mod export;
+pub mod publish;
+pub mod file;
+pub mod collect;
 pub mod branch;
+pub mod zip;
 
 pub mod aggregation;
+pub mod collective;
 pub mod generic;
 
 pub mod reclock;
 pub mod count;
+pub mod offload;
+pub mod map_async;
+pub mod catch;
+pub mod assertions;
+pub mod resample;
+pub mod dedup;
+pub mod sort;
+pub mod bloom;
+
+pub mod audit;
 
 // keep "mint" module-private
 mod capability;
@@ -0,0 +1,141 @@
+//! Hands batches of records to external executors, and emits their results once complete.
+//!
+//! Some stages of a computation run best outside the worker thread, for example on a GPU stream or
+//! a dedicated thread pool. The `offload` operator accumulates the records of each time into batches,
+//! submits each batch to a user-supplied executor along with a `Completion`, and keeps a capability
+//! for the batch's time until the executor completes it. The operator checks for completed batches
+//! each time it is scheduled, and emits their results at the batch's time, so the worker continues
+//! with other work while the executor runs.
+//!
+//! The executor may complete a batch from any thread. A `Completion` dropped without being completed
+//! abandons its batch, which then produces no results. An executor that panics may instead report the
+//! panic's payload, which the operator resumes on the worker thread when it next checks the batch.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use Data;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::Capability;
+use dataflow::operators::generic::Operator;
+
+/// The state of a submitted batch.
+enum State<R> {
+    Running,
+    Complete(Vec<R>),
+    Panicked(Box<Any+Send>),
+    Abandoned,
+}
+
+/// The means by which an executor reports the results of a batch.
+pub struct Completion<R> {
+    state: Arc<Mutex<State<R>>>,
+}
+
+impl<R> Completion<R> {
+    /// Allocates a completion and the shared state the operator polls.
+    fn new() -> (Self, Arc<Mutex<State<R>>>) {
+        let state = Arc::new(Mutex::new(State::Running));
+        (Completion { state: state.clone() }, state)
+    }
+
+    /// Reports the results of the batch.
+    pub fn complete(self, results: Vec<R>) {
+        *self.state.lock().expect("failed to lock completion") = State::Complete(results);
+    }
+
+    /// Reports that the batch failed with a panic, whose `payload` the operator resumes.
+    pub fn panic(self, payload: Box<Any+Send>) {
+        *self.state.lock().expect("failed to lock completion") = State::Panicked(payload);
+    }
+}
+
+impl<R> Drop for Completion<R> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let State::Running = *state {
+                *state = State::Abandoned;
+            }
+        }
+    }
+}
+
+/// Extension trait for `Stream`.
+pub trait Offload<G: Scope, D: Data> {
+    /// Submits batches of at most `batch_size` records of the same time to `submit`, and produces the
+    /// results each batch completes with at the batch's time.
+    ///
+    /// A batch is submitted once it holds `batch_size` records, or once the input frontier passes its
+    /// time, whichever comes first. Results are produced in order of completion.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::thread;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::offload::{Offload, Completion};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10u64).to_stream(scope)
+    ///               .offload("Square", 4, |batch: Vec<u64>, completion: Completion<u64>| {
+    ///                   // stands in for an asynchronous device or service.
+    ///                   thread::spawn(move || {
+    ///                       completion.complete(batch.into_iter().map(|x| x * x).collect());
+    ///                   });
+    ///               })
+    ///               .inspect(|x| println!("squared: {:?}", x));
+    /// });
+    /// ```
+    fn offload<R: Data, F: FnMut(Vec<D>, Completion<R>)+'static>(&self, name: &str, batch_size: usize, submit: F) -> Stream<G, R>;
+}
+
+impl<G: Scope, D: Data> Offload<G, D> for Stream<G, D> {
+    fn offload<R: Data, F: FnMut(Vec<D>, Completion<R>)+'static>(&self, name: &str, batch_size: usize, mut submit: F) -> Stream<G, R> {
+
+        assert!(batch_size > 0, "batch size must be positive");
+
+        // records awaiting submission, and batches awaiting completion, with their capabilities.
+        let mut stash: BTreeMap<G::Timestamp, (Capability<G::Timestamp>, Vec<D>)> = BTreeMap::new();
+        let mut running: Vec<(Capability<G::Timestamp>, Arc<Mutex<State<R>>>)> = Vec::new();
+
+        self.unary_frontier(Pipeline, name, move |_capability, _info| move |input, output| {
+
+            input.for_each(|time, data| {
+                let key = time.time().clone();
+                let entry = stash.entry(key).or_insert_with(|| (time.retain(), Vec::new()));
+                entry.1.extend(data.replace(Vec::new()));
+                while entry.1.len() >= batch_size {
+                    let rest = entry.1.split_off(batch_size);
+                    let batch = ::std::mem::replace(&mut entry.1, rest);
+                    let (completion, state) = Completion::new();
+                    submit(batch, completion);
+                    running.push((entry.0.clone(), state));
+                }
+            });
+
+            // submit partial batches at times the input frontier has passed.
+            let frontier = input.frontier();
+            let ready = stash.keys().filter(|time| !frontier.less_equal(time)).cloned().collect::<Vec<_>>();
+            for time in ready {
+                let (capability, batch) = stash.remove(&time).expect("stashed time vanished");
+                if !batch.is_empty() {
+                    let (completion, state) = Completion::new();
+                    submit(batch, completion);
+                    running.push((capability, state));
+                }
+            }
+
+            // emit the results of completed batches, and release their capabilities.
+            running.retain(|&(ref capability, ref state)| {
+                let mut state = state.lock().expect("failed to lock completion");
+                match ::std::mem::replace(&mut *state, State::Abandoned) {
+                    State::Running => { *state = State::Running; true },
+                    State::Complete(mut results) => { output.session(capability).give_vec(&mut results); false },
+                    State::Panicked(payload) => { drop(state); ::std::panic::resume_unwind(payload) },
+                    State::Abandoned => false,
+                }
+            });
+        })
+    }
+}
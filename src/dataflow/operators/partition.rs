@@ -52,7 +52,7 @@ impl<G: Scope, D: Data, D2: Data, F: Fn(D)->(u64, D2)+'static> Partition<G, D, D
 
         let operator = Operator::new(PullCounter::new(receiver), targets, route);
         let index = scope.add_operator(operator);
-        self.connect_to(Target { index, port: 0 }, sender, channel_id);
+        self.connect_to_via(Target { index, port: 0 }, sender, channel_id, "Pipeline", None);
 
         let mut results = Vec::new();
         for (output, registrar) in registrars.into_iter().enumerate() {
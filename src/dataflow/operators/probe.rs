@@ -2,9 +2,12 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::sync::{Arc, Mutex, Condvar};
+use std::time::{Duration, Instant, SystemTime};
 
+use order::PartialOrder;
 use progress::Timestamp;
-use progress::frontier::{AntichainRef, MutableAntichain};
+use progress::frontier::{Antichain, AntichainRef, MutableAntichain};
 use dataflow::channels::pushers::Counter as PushCounter;
 use dataflow::channels::pushers::buffer::Buffer as PushBuffer;
 use dataflow::channels::pact::Pipeline;
@@ -97,13 +100,28 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
         let mut output = PushBuffer::new(PushCounter::new(tee));
 
         let frontier = handle.frontier.clone();
+        let watchers = handle.watchers.clone();
+        let completions = handle.completions.clone();
         let mut started = false;
 
         let mut vector = Vec::new();
 
         builder.build(
             move |changes| {
-                frontier.borrow_mut().update_iter(changes[0].drain());
+                let mut frontier = frontier.borrow_mut();
+                let mut completions = completions.borrow_mut();
+                if let Some(ref mut completions) = *completions {
+                    completions.observe(changes[0].iter().filter(|&&(_, diff)| diff > 0).map(|&(ref time, _)| time.clone()));
+                }
+                frontier.update_iter(changes[0].drain());
+                if let Some(ref mut completions) = *completions {
+                    completions.complete(&frontier);
+                }
+                let mut watchers = watchers.borrow_mut();
+                watchers.started = true;
+                for watcher in watchers.list.iter() {
+                    watcher.publish(frontier.frontier());
+                }
             },
             move |consumed, internal, produced| {
 
@@ -138,7 +156,9 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
 
 /// Reports information about progress at the probe.
 pub struct Handle<T:Timestamp> {
-    frontier: Rc<RefCell<MutableAntichain<T>>>
+    frontier: Rc<RefCell<MutableAntichain<T>>>,
+    watchers: Rc<RefCell<Watchers<T>>>,
+    completions: Rc<RefCell<Option<Completions<T>>>>,
 }
 
 impl<T: Timestamp> Handle<T> {
@@ -149,7 +169,13 @@ impl<T: Timestamp> Handle<T> {
     /// returns true iff the frontier is empty.
     #[inline] pub fn done(&self) -> bool { self.frontier.borrow().is_empty() }
     /// Allocates a new handle.
-    #[inline] pub fn new() -> Self { Handle { frontier: Rc::new(RefCell::new(MutableAntichain::new())) } }
+    #[inline] pub fn new() -> Self {
+        Handle {
+            frontier: Rc::new(RefCell::new(MutableAntichain::new())),
+            watchers: Rc::new(RefCell::new(Watchers { started: false, list: Vec::new() })),
+            completions: Rc::new(RefCell::new(None)),
+        }
+    }
 
     /// Invokes a method on the frontier, returning its result.
     ///
@@ -168,16 +194,387 @@ impl<T: Timestamp> Handle<T> {
     pub fn with_frontier<R, F: FnMut(AntichainRef<T>)->R>(&self, mut function: F) -> R {
         function(self.frontier.borrow().frontier())
     }
+
+    /// Creates a `Watcher` reporting the frontier of the probe to other threads.
+    ///
+    /// The watcher is updated as the worker steps, and other threads may block on it until a time
+    /// is complete. Any number of watchers may be created, and each may be cloned.
+    ///
+    /// #Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{Input, Probe};
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, probe) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input::<u64>();
+    ///         (input, stream.probe())
+    ///     });
+    ///
+    ///     let watcher = probe.watch();
+    ///     let waiter = ::std::thread::spawn(move || {
+    ///         watcher.wait_until(&RootTimestamp::new(4), Duration::from_secs(60))
+    ///     });
+    ///
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///
+    ///     assert!(waiter.join().unwrap());
+    /// }).unwrap();
+    /// ```
+    pub fn watch(&self) -> Watcher<T> {
+        let mut watchers = self.watchers.borrow_mut();
+        let state = Arc::new(WatchState {
+            frontier: Mutex::new(None),
+            condvar: Condvar::new(),
+        });
+        if watchers.started {
+            state.publish(self.frontier.borrow().frontier());
+        }
+        watchers.list.push(state.clone());
+        Watcher { state }
+    }
+
+    /// Starts recording when times complete at the probe, and how long each took to complete.
+    ///
+    /// A time is first seen when the probe is first informed of data or capabilities at the time,
+    /// and is complete once the frontier contains no element less or equal to it. The latency of a
+    /// time is the duration between the two, and is recorded in a histogram; the wall-clock time
+    /// of each completion is retained until drained by `drain_completions`, and so applications
+    /// that track completions should drain them regularly.
+    ///
+    /// Times first seen before tracking started are not recorded.
+    ///
+    /// #Examples
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{Input, Probe};
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, probe) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input::<u64>();
+    ///         (input, stream.probe())
+    ///     });
+    ///
+    ///     probe.track_completions();
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///
+    ///     let completions = probe.drain_completions();
+    ///     assert!(completions.len() > 0);
+    ///     let histogram = probe.completion_histogram().unwrap();
+    ///     assert_eq!(histogram.count(), completions.len() as u64);
+    ///     println!("median latency: {:?}", histogram.quantile(0.5));
+    /// }).unwrap();
+    /// ```
+    pub fn track_completions(&self) {
+        let mut completions = self.completions.borrow_mut();
+        if completions.is_none() {
+            *completions = Some(Completions { pending: Vec::new(), completed: Vec::new(), histogram: Histogram::new() });
+        }
+    }
+
+    /// Removes and returns the recorded completions, as the time, the wall-clock time at which it
+    /// completed, and its latency, in order of completion.
+    pub fn drain_completions(&self) -> Vec<(T, SystemTime, Duration)> {
+        self.completions.borrow_mut()
+            .as_mut()
+            .map(|completions| ::std::mem::replace(&mut completions.completed, Vec::new()))
+            .unwrap_or(Vec::new())
+    }
+
+    /// A copy of the histogram of completion latencies, if completions are tracked.
+    pub fn completion_histogram(&self) -> Option<Histogram> {
+        self.completions.borrow().as_ref().map(|completions| completions.histogram.clone())
+    }
 }
 
 impl<T: Timestamp> Clone for Handle<T> {
     fn clone(&self) -> Self {
         Handle {
-            frontier: self.frontier.clone()
+            frontier: self.frontier.clone(),
+            watchers: self.watchers.clone(),
+            completions: self.completions.clone(),
         }
     }
 }
 
+/// Reports the combined progress of several probes.
+///
+/// The frontier of a multi-probe is the least of the frontiers of its probes: a time is complete
+/// at the multi-probe only once it is complete at each of its probes.
+///
+/// #Examples
+///
+/// ```
+/// use timely::dataflow::operators::{Input, Map, Probe};
+/// use timely::dataflow::operators::probe::MultiProbe;
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let mut probes = MultiProbe::new();
+///     let mut input = worker.dataflow(|scope| {
+///         let (input, stream) = scope.new_input::<u64>();
+///         stream.map(|x| x + 1).probe_with(&mut probes.handle());
+///         stream.map(|x| x * 2).probe_with(&mut probes.handle());
+///         input
+///     });
+///
+///     for round in 0..10 {
+///         input.send(round);
+///         input.advance_to(round + 1);
+///         worker.step_until(&probes, input.time());
+///         assert!(!probes.less_than(input.time()));
+///     }
+/// }).unwrap();
+/// ```
+pub struct MultiProbe<T: Timestamp> {
+    handles: Vec<Handle<T>>,
+}
+
+impl<T: Timestamp> MultiProbe<T> {
+    /// Allocates a multi-probe without probes, whose frontier is empty.
+    pub fn new() -> Self {
+        MultiProbe { handles: Vec::new() }
+    }
+
+    /// Allocates a new probe handle included in the multi-probe, for use with `probe_with`.
+    pub fn handle(&mut self) -> Handle<T> {
+        let handle = Handle::new();
+        self.handles.push(handle.clone());
+        handle
+    }
+
+    /// Includes an existing probe handle in the multi-probe.
+    pub fn add(&mut self, handle: &Handle<T>) {
+        self.handles.push(handle.clone());
+    }
+
+    /// The probe handles included in the multi-probe.
+    pub fn handles(&self) -> &[Handle<T>] { &self.handles[..] }
+
+    /// returns true iff the frontier of some probe is strictly less than `time`.
+    #[inline] pub fn less_than(&self, time: &T) -> bool { self.handles.iter().any(|handle| handle.less_than(time)) }
+    /// returns true iff the frontier of some probe is less than or equal to `time`.
+    #[inline] pub fn less_equal(&self, time: &T) -> bool { self.handles.iter().any(|handle| handle.less_equal(time)) }
+    /// returns true iff the frontiers of all probes are empty.
+    #[inline] pub fn done(&self) -> bool { self.handles.iter().all(|handle| handle.done()) }
+
+    /// The combined frontier of the probes: the minimal elements of their frontiers.
+    pub fn frontier(&self) -> Antichain<T> {
+        let mut frontier = Antichain::new();
+        for handle in self.handles.iter() {
+            handle.with_frontier(|elements| {
+                for time in elements.iter() {
+                    frontier.insert(time.clone());
+                }
+            });
+        }
+        frontier
+    }
+}
+
+impl<T: Timestamp> Clone for MultiProbe<T> {
+    fn clone(&self) -> Self {
+        MultiProbe { handles: self.handles.clone() }
+    }
+}
+
+impl<T: Timestamp> Default for MultiProbe<T> {
+    fn default() -> Self { Self::new() }
+}
+
+/// Completion tracking state of a probe handle.
+struct Completions<T> {
+    /// Times seen but not yet complete, and the instants at which they were first seen.
+    pending: Vec<(T, Instant)>,
+    /// Completions not yet drained.
+    completed: Vec<(T, SystemTime, Duration)>,
+    histogram: Histogram,
+}
+
+impl<T: Timestamp> Completions<T> {
+    /// Records the first sighting of times not already pending.
+    fn observe<I: Iterator<Item=T>>(&mut self, times: I) {
+        let now = Instant::now();
+        for time in times {
+            if !self.pending.iter().any(|&(ref t, _)| t == &time) {
+                self.pending.push((time, now));
+            }
+        }
+    }
+    /// Completes pending times that `frontier` has passed.
+    fn complete(&mut self, frontier: &MutableAntichain<T>) {
+        let now = Instant::now();
+        let wall = SystemTime::now();
+        let mut index = 0;
+        while index < self.pending.len() {
+            if frontier.less_equal(&self.pending[index].0) {
+                index += 1;
+            }
+            else {
+                let (time, seen) = self.pending.remove(index);
+                let latency = now.duration_since(seen);
+                self.histogram.record(latency);
+                self.completed.push((time, wall, latency));
+            }
+        }
+    }
+}
+
+/// A histogram of durations, in buckets of powers of two nanoseconds.
+///
+/// #Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::operators::probe::Histogram;
+///
+/// let mut histogram = Histogram::new();
+/// histogram.record(Duration::from_millis(1));
+/// histogram.record(Duration::from_millis(3));
+/// histogram.record(Duration::from_millis(100));
+///
+/// assert_eq!(histogram.count(), 3);
+/// assert!(histogram.quantile(0.5).unwrap() >= Duration::from_millis(3));
+/// assert!(histogram.quantile(1.0).unwrap() >= Duration::from_millis(100));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    /// Bucket `i` counts durations of less than `2^i` nanoseconds, and not less than `2^(i-1)`.
+    buckets: Vec<u64>,
+}
+
+impl Histogram {
+    /// Allocates a new empty histogram.
+    pub fn new() -> Self {
+        Histogram { buckets: vec![0; 65] }
+    }
+    /// Records a duration.
+    pub fn record(&mut self, duration: Duration) {
+        let nanos = duration.as_secs().saturating_mul(1_000_000_000).saturating_add(duration.subsec_nanos() as u64);
+        self.buckets[64 - nanos.leading_zeros() as usize] += 1;
+    }
+    /// The number of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+    /// The non-empty buckets, as the exclusive upper bound of each bucket and its count.
+    ///
+    /// The upper bound of the last bucket is `u64::max_value()` nanoseconds, which it includes.
+    pub fn buckets<'a>(&'a self) -> impl Iterator<Item=(Duration, u64)>+'a {
+        self.buckets.iter().enumerate().filter(|&(_, &count)| count > 0).map(|(index, &count)| (Self::bound(index), count))
+    }
+    /// An upper bound on the `q` quantile of the recorded durations, or `None` if there are none.
+    ///
+    /// The bound is the upper bound of the bucket containing the quantile, and so is at most twice
+    /// the quantile.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 { return None; }
+        let rank = ((q.max(0.0).min(1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &bucket) in self.buckets.iter().enumerate() {
+            seen += bucket;
+            if seen >= rank { return Some(Self::bound(index)); }
+        }
+        None
+    }
+    /// Adds the counts of `other` to this histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += *count;
+        }
+    }
+    fn bound(index: usize) -> Duration {
+        let nanos = if index < 64 { 1u64 << index } else { u64::max_value() };
+        Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self { Self::new() }
+}
+
+/// Watchers registered with a probe handle.
+struct Watchers<T> {
+    /// True once the probe has received its first frontier update.
+    started: bool,
+    list: Vec<Arc<WatchState<T>>>,
+}
+
+/// Frontier state shared between a probe and its watchers.
+struct WatchState<T> {
+    /// The most recently published frontier, or `None` if none has been published yet.
+    frontier: Mutex<Option<Vec<T>>>,
+    condvar: Condvar,
+}
+
+impl<T: Clone> WatchState<T> {
+    fn publish(&self, frontier: AntichainRef<T>) {
+        *self.frontier.lock().expect("watcher mutex poisoned") = Some(frontier.to_vec());
+        self.condvar.notify_all();
+    }
+}
+
+/// Reports the frontier of a probe to other threads.
+///
+/// A watcher is created by `Handle::watch`, and is updated as the probe's worker steps. If the
+/// worker stops stepping, the watcher stops changing, and so waiting methods take a timeout.
+pub struct Watcher<T> {
+    state: Arc<WatchState<T>>,
+}
+
+impl<T> Clone for Watcher<T> {
+    fn clone(&self) -> Self {
+        Watcher { state: self.state.clone() }
+    }
+}
+
+impl<T: Timestamp> Watcher<T> {
+
+    /// Blocks until `time` is complete at the probe, or `timeout` elapses.
+    ///
+    /// A time is complete once the frontier contains no element less or equal to it. Returns
+    /// true if the time is complete, and false if the timeout elapsed first.
+    pub fn wait_until(&self, time: &T, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut frontier = self.state.frontier.lock().expect("watcher mutex poisoned");
+        loop {
+            let complete = frontier.as_ref().map(|f| !f.iter().any(|t| t.less_equal(time))).unwrap_or(false);
+            if complete { return true; }
+            let now = Instant::now();
+            if now >= deadline { return false; }
+            frontier = self.state.condvar.wait_timeout(frontier, deadline - now).expect("watcher mutex poisoned").0;
+        }
+    }
+
+    /// Returns true iff the frontier is less than or equal to `time`.
+    ///
+    /// Before the probe's first frontier update this method returns true.
+    pub fn less_equal(&self, time: &T) -> bool {
+        self.state.frontier.lock().expect("watcher mutex poisoned")
+            .as_ref()
+            .map(|f| f.iter().any(|t| t.less_equal(time)))
+            .unwrap_or(true)
+    }
+
+    /// Returns a copy of the most recently published frontier, or `None` before the first update.
+    pub fn frontier(&self) -> Option<Vec<T>> {
+        self.state.frontier.lock().expect("watcher mutex poisoned").clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
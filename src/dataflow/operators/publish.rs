@@ -0,0 +1,143 @@
+//! Named streams, published by one dataflow and subscribed to by others of the same worker.
+//!
+//! A dataflow may publish a stream under a name, and dataflows constructed later by the same worker
+//! may subscribe to it by that name, each receiving the stream's frontier and all data published
+//! after it subscribes. The publications of a worker are kept in its `Resources`, and so any scope of
+//! the worker may publish and subscribe. Unlike `Export`, a publication may have any number of
+//! subscribers, including none, and data published while there are no subscribers are discarded.
+//!
+//! Subscribing dataflows must use the same timestamp and data types as the publishing dataflow.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::any::Any;
+use std::collections::HashMap;
+
+use Data;
+use progress::Timestamp;
+use progress::ChangeBatch;
+use progress::frontier::MutableAntichain;
+use dataflow::{Stream, Scope};
+use dataflow::operators::capture::{Capture, Event, EventPusher, EventLink, Replay};
+
+/// The publications of a worker, registered in its resources.
+#[derive(Default)]
+struct Publications {
+    map: RefCell<HashMap<String, Rc<Any>>>,
+}
+
+/// The frontier of a published stream, and links to its subscribers.
+struct Publication<T: Timestamp, D: Data> {
+    frontier: MutableAntichain<T>,
+    subscribers: Vec<Rc<EventLink<T, D>>>,
+}
+
+impl<T: Timestamp, D: Data> Publication<T, D> {
+    fn subscribe(&mut self) -> Rc<EventLink<T, D>> {
+        let mut link = Rc::new(EventLink::new());
+        let head = link.clone();
+        // bring the subscriber from the default frontier up to the current frontier.
+        let mut changes = ChangeBatch::new_from(Default::default(), -1);
+        changes.extend(self.frontier.frontier().iter().map(|x| (x.clone(), 1)));
+        if !changes.is_empty() {
+            link.push(Event::Progress(changes.into_inner()));
+        }
+        self.subscribers.push(link);
+        head
+    }
+}
+
+/// Presents the events of a published stream to its subscribers.
+struct Publisher<T: Timestamp, D: Data> {
+    publication: Rc<RefCell<Publication<T, D>>>,
+}
+
+impl<T: Timestamp, D: Data> EventPusher<T, D> for Publisher<T, D> {
+    fn push(&mut self, event: Event<T, D>) {
+        let mut publication = self.publication.borrow_mut();
+        if let Event::Progress(ref updates) = event {
+            publication.frontier.update_iter(updates.iter().cloned());
+        }
+        for subscriber in publication.subscribers.iter_mut() {
+            subscriber.push(event.clone());
+        }
+    }
+}
+
+/// Methods to publish a stream to other dataflows of the same worker.
+pub trait Publish<G: Scope, D: Data> {
+    /// Publishes the stream under `name`, replacing any publication of the same name.
+    ///
+    /// Replacing a publication does not affect its existing subscribers.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{Input, Inspect, Probe};
+    /// use timely::dataflow::operators::publish::{Publish, Subscribe};
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let mut input = worker.dataflow::<u64,_,_>(|scope| {
+    ///         let (input, stream) = scope.new_input::<String>();
+    ///         stream.publish("queries");
+    ///         input
+    ///     });
+    ///
+    ///     // a dataflow constructed later subscribes to the queries.
+    ///     let probe = worker.dataflow(|scope| {
+    ///         scope.subscribe::<String>("queries")
+    ///              .expect("queries not published")
+    ///              .inspect(|x| println!("query: {:?}", x))
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send("hello".to_owned());
+    ///     input.advance_to(1);
+    ///     worker.step_while(|| probe.less_than(input.time()));
+    /// }).unwrap();
+    /// ```
+    fn publish(&self, name: &str);
+}
+
+impl<G: Scope, D: Data> Publish<G, D> for Stream<G, D> {
+    fn publish(&self, name: &str) {
+        let publication = Rc::new(RefCell::new(Publication {
+            frontier: MutableAntichain::new_bottom(Default::default()),
+            subscribers: Vec::new(),
+        }));
+        self.capture_into(Publisher { publication: publication.clone() });
+        let resources = self.scope().resources();
+        if !resources.contains::<Publications>() {
+            resources.insert(Publications::default());
+        }
+        let publications = resources.get::<Publications>().expect("publications not registered");
+        publications.map.borrow_mut().insert(name.to_owned(), publication);
+    }
+}
+
+/// Methods to subscribe to the streams published by other dataflows of the same worker.
+pub trait Subscribe<G: Scope> {
+    /// Subscribes to the stream published under `name`, if any.
+    ///
+    /// #Panics
+    ///
+    /// Panics if the stream was published with a different timestamp or data type.
+    fn subscribe<D: Data>(&mut self, name: &str) -> Option<Stream<G, D>>;
+}
+
+impl<G: Scope> Subscribe<G> for G {
+    fn subscribe<D: Data>(&mut self, name: &str) -> Option<Stream<G, D>> {
+        let publication = self.resources()
+            .get::<Publications>()
+            .and_then(|publications| publications.map.borrow().get(name).cloned());
+
+        publication.map(|publication| {
+            let publication = publication
+                .downcast::<RefCell<Publication<G::Timestamp, D>>>()
+                .ok()
+                .unwrap_or_else(|| panic!("stream {:?} published with a different type", name));
+            let link = publication.borrow_mut().subscribe();
+            Some(link).replay_into(self)
+        })
+    }
+}
@@ -0,0 +1,168 @@
+//! Resamples keyed time series to one value per series per fixed-width interval of time.
+//!
+//! Observations of a series arrive at irregular times, and at varying rates for different series.
+//! Many consumers instead want one value per series for each interval of time, and to know when a
+//! series produced no observation in an interval. The operators in this module divide the times of
+//! an epoch-timestamped stream into intervals of a fixed width, and once the input frontier passes
+//! the end of an interval, produce a record for each series observed so far: its last observation in
+//! the interval, or if it had none a value filled in from its last observation before the interval,
+//! or an explicit marker that it has no data.
+//!
+//! The record for an interval is produced at the time of the interval's start. Intervals are produced
+//! from the first interval with an observation, and up to the interval of the last observation once
+//! the input is complete.
+
+use std::hash::Hash;
+use std::collections::{BTreeMap, HashMap};
+
+use ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Exchange;
+use dataflow::operators::generic::Operator;
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+
+/// Methods to resample streams of keyed observations.
+pub trait Resample<G: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData> {
+    /// Produces `(key, start, value)` for each series `key` and each interval of `width` times
+    /// beginning at `start`, where `value` is the last observation of the series in the interval,
+    /// or `None` if the series had no observation in the interval.
+    ///
+    /// Records are routed to workers by `hash` applied to their keys.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Delay, Map, Inspect};
+    /// use timely::dataflow::operators::resample::Resample;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::example(|scope| {
+    ///     // observations of series 7 at times 1, 2, and 25.
+    ///     vec![(1, (7u64, 1.5)), (2, (7, 2.0)), (25, (7, 4.0))]
+    ///         .to_stream(scope)
+    ///         .delay(|&(time, _), _| RootTimestamp::new(time))
+    ///         .map(|(_, observation)| observation)
+    ///         .resample(10, |key| *key)
+    ///         .inspect(|x| println!("resampled: {:?}", x));
+    ///     // produces (7, 0, Some(2.0)), (7, 10, None), and (7, 20, Some(4.0)).
+    /// });
+    /// ```
+    fn resample<H: Fn(&K)->u64+'static>(&self, width: u64, hash: H) -> Stream<G, (K, u64, Option<V>)>;
+
+    /// As `resample`, but for intervals without an observation of a series, produces the value
+    /// `fill` returns when applied to the key, the interval's start, and the series' last value.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Delay, Map, Inspect};
+    /// use timely::dataflow::operators::resample::Resample;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::example(|scope| {
+    ///     vec![(1, (7u64, 1.5)), (2, (7, 2.0)), (25, (7, 4.0))]
+    ///         .to_stream(scope)
+    ///         .delay(|&(time, _), _| RootTimestamp::new(time))
+    ///         .map(|(_, observation)| observation)
+    ///         // carries the last observation forward.
+    ///         .resample_with(10, |_key, _start, last| Some(*last), |key| *key)
+    ///         .inspect(|x| assert!(x.2.is_some()));
+    /// });
+    /// ```
+    fn resample_with<F, H>(&self, width: u64, fill: F, hash: H) -> Stream<G, (K, u64, Option<V>)>
+    where
+        F: Fn(&K, u64, &V)->Option<V>+'static,
+        H: Fn(&K)->u64+'static;
+}
+
+impl<G, K, V> Resample<G, K, V> for Stream<G, (K, V)>
+where
+    G: Scope<Timestamp=Product<RootTimestamp, u64>>,
+    K: ExchangeData+Hash+Eq,
+    V: ExchangeData,
+{
+    fn resample<H: Fn(&K)->u64+'static>(&self, width: u64, hash: H) -> Stream<G, (K, u64, Option<V>)> {
+        self.resample_with(width, |_, _, _| None, hash)
+    }
+
+    fn resample_with<F, H>(&self, width: u64, fill: F, hash: H) -> Stream<G, (K, u64, Option<V>)>
+    where
+        F: Fn(&K, u64, &V)->Option<V>+'static,
+        H: Fn(&K)->u64+'static {
+
+        assert!(width > 0, "interval width must be positive");
+
+        // the last observation of each series in each open interval, with its time.
+        let mut pending: BTreeMap<u64, HashMap<K, (u64, V)>> = BTreeMap::new();
+        // the last value of each series observed in a closed interval.
+        let mut series: HashMap<K, V> = HashMap::new();
+        // the start of the next interval to produce, once any observation has been seen.
+        let mut next: Option<u64> = None;
+        let mut vector = Vec::new();
+
+        self.unary_frontier(Exchange::new(move |x: &(K, V)| hash(&x.0)), "Resample", move |capability, _info| {
+
+            let mut capability = Some(capability);
+
+            move |input, output| {
+
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    let inner = time.time().inner;
+                    let start = inner - inner % width;
+                    let interval = pending.entry(start).or_insert_with(HashMap::new);
+                    for (key, value) in vector.drain(..) {
+                        let replace = interval.get(&key).map(|&(time, _)| time <= inner).unwrap_or(true);
+                        if replace {
+                            interval.insert(key, (inner, value));
+                        }
+                    }
+                    if next.map(|next| start < next).unwrap_or(true) {
+                        next = Some(start);
+                    }
+                });
+
+                // intervals starting before `limit` are complete.
+                let frontier = input.frontier().frontier();
+                let limit = match frontier.iter().map(|time| time.inner).min() {
+                    Some(inner) => inner - inner % width,
+                    None => pending.keys().next_back().map(|start| start + width).or(next).unwrap_or(0),
+                };
+
+                if let Some(ref mut capability) = capability {
+                    while let Some(start) = next.filter(|start| *start < limit) {
+                        // skip intervals preceding any series' first observation.
+                        if series.is_empty() {
+                            let first = pending.keys().next().cloned().unwrap_or(limit);
+                            if start < first {
+                                next = Some(first);
+                                continue;
+                            }
+                        }
+                        capability.downgrade(&RootTimestamp::new(start));
+                        let mut session = output.session(capability);
+                        let mut observed = pending.remove(&start).unwrap_or_else(HashMap::new);
+                        for (key, (_, value)) in observed.iter() {
+                            series.insert(key.clone(), value.clone());
+                        }
+                        for (key, last) in series.iter() {
+                            let value = match observed.remove(key) {
+                                Some((_, value)) => Some(value),
+                                None => fill(key, start, last),
+                            };
+                            session.give((key.clone(), start, value));
+                        }
+                        next = Some(start + width);
+                    }
+                }
+
+                if frontier.is_empty() {
+                    capability = None;
+                }
+                else if let Some(ref mut capability) = capability {
+                    let start = next.map(|next| ::std::cmp::min(next, limit)).unwrap_or(limit);
+                    capability.downgrade(&RootTimestamp::new(start));
+                }
+            }
+        })
+    }
+}
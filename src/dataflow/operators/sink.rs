@@ -0,0 +1,69 @@
+//! Side effects applied to the data of complete times.
+
+use std::collections::HashMap;
+
+use Data;
+use dataflow::channels::pact::Pipeline;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::Operator;
+
+/// Methods to apply side effects to the data of complete times.
+pub trait Sink<G: Scope, D: Data> {
+    /// Invokes `action` once for each time at which data arrive, with all the data at that time,
+    /// once the time is complete.
+    ///
+    /// A time is complete once the input frontier has passed it, and so no further data can arrive at
+    /// the time. Times complete together are presented in increasing order. This operator is suitable
+    /// for side effects, such as writes to external systems, that must only happen once the data for a
+    /// time are final, and it holds no capabilities, as it produces no output.
+    ///
+    /// Data are buffered until their time completes, and each worker sees only the data it receives.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use timely::dataflow::operators::{ToStream, Exchange};
+    /// use timely::dataflow::operators::sink::Sink;
+    ///
+    /// let written = Arc::new(Mutex::new(Vec::new()));
+    /// let written2 = written.clone();
+    ///
+    /// timely::example(move |scope| {
+    ///     let written = written2.clone();
+    ///     (0..10).to_stream(scope)
+    ///            .exchange(|x| *x)
+    ///            .sink_at_frontier(move |time, mut data| {
+    ///                data.sort();
+    ///                written.lock().unwrap().push((time.inner, data));
+    ///            });
+    /// });
+    ///
+    /// assert_eq!(*written.lock().unwrap(), vec![(0, (0..10).collect::<Vec<_>>())]);
+    /// ```
+    fn sink_at_frontier(&self, action: impl FnMut(&G::Timestamp, Vec<D>)+'static);
+}
+
+impl<G: Scope, D: Data> Sink<G, D> for Stream<G, D> {
+    fn sink_at_frontier(&self, mut action: impl FnMut(&G::Timestamp, Vec<D>)+'static) {
+        let mut stash = HashMap::new();
+        let mut vector = Vec::new();
+        let mut ready = Vec::new();
+        self.sink(Pipeline, "SinkAtFrontier", move |input| {
+
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+            });
+
+            // extract and sort times the frontier has passed.
+            let frontier = input.frontier();
+            ready.extend(stash.keys().filter(|time| !frontier.less_equal(time)).cloned());
+            ready.sort();
+            for time in ready.drain(..) {
+                if let Some(data) = stash.remove(&time) {
+                    action(&time, data);
+                }
+            }
+        });
+    }
+}
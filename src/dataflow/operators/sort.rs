@@ -0,0 +1,214 @@
+//! Sorts the records of each timestamp before producing them.
+//!
+//! Some sinks require their input in order, for example those writing sorted runs for a later merge.
+//! The operators in this module buffer the records of each timestamp in an `EpochBuffer`, and once
+//! the input frontier passes a timestamp, produce its records sorted by a comparison function.
+//! Records are sorted at each worker, and so each worker produces a sorted run for each timestamp.
+//!
+//! The `sort_global` operator instead produces the records of each timestamp in a total order across
+//! workers: each worker sorts its records and broadcasts a sample of them, the samples determine
+//! splitters dividing the records into one range per worker, and each worker receives and sorts the
+//! records of its range. The records of each worker then precede those of workers with greater indices.
+
+use std::rc::Rc;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use {Data, ExchangeData};
+use dataflow::{Stream, Scope};
+use dataflow::channels::pact::Pipeline;
+use dataflow::operators::{Broadcast, Exchange, Map};
+use dataflow::operators::generic::{Operator, EpochBuffer};
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+/// Methods to sort the records of each timestamp.
+pub trait SortWithinEpoch<G: Scope, D: Data> {
+    /// Produces the records of each timestamp once the timestamp is complete, sorted by `cmp`.
+    ///
+    /// The sort is stable, and so records that compare equal are produced in the order they arrived.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::sort::SortWithinEpoch;
+    ///
+    /// timely::example(|scope| {
+    ///     let mut last = None;
+    ///     vec![3, 1, 4, 1, 5, 9, 2, 6].to_stream(scope)
+    ///         .sort_within_epoch(|x, y| x.cmp(y))
+    ///         .inspect(move |x| { assert!(last <= Some(*x)); last = Some(*x); });
+    /// });
+    /// ```
+    fn sort_within_epoch<F: Fn(&D, &D)->Ordering+'static>(&self, cmp: F) -> Stream<G, D>;
+
+    /// As `sort_within_epoch`, but holding at most `budget` bytes of buffered records in memory and
+    /// spilling the records of later timestamps to files in `directory`.
+    ///
+    /// The records of a timestamp are read back into memory to be sorted once it is complete.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::sort::SortWithinEpoch;
+    ///
+    /// timely::example(|scope| {
+    ///     (0..1000u64).rev().to_stream(scope)
+    ///         .sort_within_epoch_spilling(|x, y| x.cmp(y), ::std::env::temp_dir(), 1 << 10)
+    ///         .inspect(|x| println!("sorted: {:?}", x));
+    /// });
+    /// ```
+    fn sort_within_epoch_spilling<F, P>(&self, cmp: F, directory: P, budget: usize) -> Stream<G, D>
+    where
+        F: Fn(&D, &D)->Ordering+'static,
+        P: Into<PathBuf>;
+}
+
+impl<G: Scope, D: Data> SortWithinEpoch<G, D> for Stream<G, D> {
+    fn sort_within_epoch<F: Fn(&D, &D)->Ordering+'static>(&self, cmp: F) -> Stream<G, D> {
+        // a buffer that never exceeds its budget never spills, so the directory is not used.
+        self.sort_within_epoch_spilling(cmp, ::std::env::temp_dir(), usize::max_value())
+    }
+
+    fn sort_within_epoch_spilling<F, P>(&self, cmp: F, directory: P, budget: usize) -> Stream<G, D>
+    where
+        F: Fn(&D, &D)->Ordering+'static,
+        P: Into<PathBuf> {
+
+        let mut buffer = EpochBuffer::new(directory, budget);
+        let mut capabilities = BTreeMap::new();
+        let mut vector = Vec::new();
+
+        self.unary_frontier(Pipeline, "SortWithinEpoch", move |_capability, _info| move |input, output| {
+
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                buffer.push(time.time().clone(), &mut vector).expect("failed to spill records");
+                capabilities.entry(time.time().clone()).or_insert_with(|| time.retain());
+            });
+
+            for (time, mut data) in buffer.drain_ready(&input.frontier().frontier()).expect("failed to restore records") {
+                let capability = capabilities.remove(&time).expect("missing capability");
+                data.sort_by(|x, y| cmp(x, y));
+                output.session(&capability).give_vec(&mut data);
+            }
+        })
+    }
+}
+
+/// Methods to sort the records of each timestamp across workers.
+pub trait SortGlobal<G: Scope, D: ExchangeData> {
+    /// Produces the records of each timestamp once the timestamp is complete, sorted by `cmp` at each
+    /// worker, and such that each record at a worker is less or equal to each record at workers with
+    /// greater indices.
+    ///
+    /// Each worker contributes `samples` evenly spaced records of its sorted records to choose the
+    /// splitters between workers' ranges; more samples balance the ranges more evenly. All records
+    /// comparing equal to each other are produced by the same worker.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::sort::SortGlobal;
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     let index = worker.index();
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         let mut last = None;
+    ///         (0..100u64).map(move |x| (x * 37 + index as u64) % 100)
+    ///                    .to_stream(scope)
+    ///                    .sort_global(|x, y| x.cmp(y), 16)
+    ///                    .inspect(move |x| { assert!(last <= Some(*x)); last = Some(*x); });
+    ///     });
+    /// }).unwrap();
+    /// ```
+    fn sort_global<F: Fn(&D, &D)->Ordering+'static>(&self, cmp: F, samples: usize) -> Stream<G, D>;
+}
+
+impl<G: Scope, D: ExchangeData> SortGlobal<G, D> for Stream<G, D> {
+    fn sort_global<F: Fn(&D, &D)->Ordering+'static>(&self, cmp: F, samples: usize) -> Stream<G, D> {
+
+        let peers = self.scope().peers();
+        let cmp = Rc::new(cmp);
+
+        // sort the records of each time locally, and sample the sorted records.
+        let mut builder = OperatorBuilder::new("SortGlobalSample".to_owned(), self.scope());
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut sorted_output, sorted) = builder.new_output();
+        let (mut sample_output, sampled) = builder.new_output();
+
+        let local_cmp = cmp.clone();
+        builder.build(move |_| {
+
+            let mut stash: HashMap<G::Timestamp, Vec<D>> = HashMap::new();
+            let mut capabilities = BTreeMap::new();
+
+            move |frontiers| {
+
+                let mut sorted_handle = sorted_output.activate();
+                let mut sample_handle = sample_output.activate();
+
+                input.for_each(|time, data| {
+                    stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(data.replace(Vec::new()));
+                    capabilities.entry(time.time().clone()).or_insert_with(|| time.retain());
+                });
+
+                let frontier = frontiers[0].frontier();
+                let ready = capabilities.keys().filter(|time| !frontier.less_equal(time)).cloned().collect::<Vec<_>>();
+                for time in ready {
+                    let capability = capabilities.remove(&time).expect("missing capability");
+                    let mut records = stash.remove(&time).unwrap_or_else(Vec::new);
+                    records.sort_by(|x, y| local_cmp(x, y));
+                    let count = records.len();
+                    if count > 0 {
+                        let mut session = sample_handle.session(&capability);
+                        for index in 0 .. samples {
+                            session.give(records[(index * count) / samples].clone());
+                        }
+                    }
+                    sorted_handle.session(&capability).give_vec(&mut records);
+                }
+            }
+        });
+
+        // route each record to the worker whose range contains it, once all samples are known.
+        let route_cmp = cmp.clone();
+        let routed = sorted.binary_frontier(&sampled.broadcast(), Pipeline, Pipeline, "SortGlobalRoute", move |_capability, _info| {
+
+            let mut records = BTreeMap::new();
+            let mut samples: HashMap<G::Timestamp, Vec<D>> = HashMap::new();
+
+            move |input1, input2, output| {
+
+                input1.for_each(|time, data| {
+                    let entry = records.entry(time.time().clone()).or_insert_with(|| (time.retain(), Vec::new()));
+                    entry.1.extend(data.replace(Vec::new()));
+                });
+                input2.for_each(|time, data| {
+                    samples.entry(time.time().clone()).or_insert_with(Vec::new).extend(data.replace(Vec::new()));
+                });
+
+                let frontier1 = input1.frontier();
+                let frontier2 = input2.frontier();
+                let ready = records.keys().filter(|time| !frontier1.less_equal(time) && !frontier2.less_equal(time)).cloned().collect::<Vec<_>>();
+                for time in ready {
+                    let (capability, data) = records.remove(&time).expect("missing records");
+                    let mut sample = samples.remove(&time).unwrap_or_else(Vec::new);
+                    sample.sort_by(|x, y| route_cmp(x, y));
+                    let splitters = (1 .. peers).filter_map(|index| sample.get((index * sample.len()) / peers)).collect::<Vec<_>>();
+                    let mut session = output.session(&capability);
+                    for record in data {
+                        let target = splitters.iter().take_while(|splitter| route_cmp(*splitter, &record) != Ordering::Greater).count();
+                        session.give((target as u64, record));
+                    }
+                }
+                samples.retain(|time, _| frontier2.less_equal(time) || records.contains_key(time));
+            }
+        });
+
+        // merge the sorted runs each worker receives for its range.
+        routed.exchange(|x| x.0)
+              .map(|(_, record)| record)
+              .sort_within_epoch(move |x, y| cmp(x, y))
+    }
+}
@@ -0,0 +1,114 @@
+//! An input handle that advances its epoch automatically.
+//!
+//! Many streaming applications only need their inputs to advance regularly, so that operators see
+//! frontier progress, rather than at application-defined boundaries. A `Ticker` wraps an input handle
+//! and advances its epoch by one each time a number of records have been sent, or each time an
+//! interval has elapsed, whichever comes first.
+
+use std::time::{Duration, Instant};
+
+use Data;
+use communication::Allocate;
+use dataflow::scopes::Root;
+use dataflow::operators::input::Handle as InputHandle;
+
+/// An input handle advancing its epoch every `records` records or every `interval`.
+pub struct Ticker<D: Data> {
+    input: InputHandle<u64, D>,
+    records: Option<usize>,
+    interval: Option<Duration>,
+    /// Records sent since the epoch last advanced.
+    sent: usize,
+    /// The instant the epoch last advanced.
+    advanced: Instant,
+}
+
+impl<D: Data> Ticker<D> {
+
+    /// Wraps `input`, advancing its epoch once `records` records have been sent at the epoch, or
+    /// once `interval` has elapsed since the epoch last advanced.
+    ///
+    /// Either bound may be `None`, in which case it never advances the epoch.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{Input, Inspect, Probe};
+    /// use timely::dataflow::operators::ticker::Ticker;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (input, probe) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input();
+    ///         let probe = stream.inspect(|x: &u64| println!("received: {:?}", x)).probe();
+    ///         (input, probe)
+    ///     });
+    ///
+    ///     let mut ticker = Ticker::new(input, Some(10), Some(Duration::from_millis(100)));
+    ///     for record in 0..100 {
+    ///         ticker.send(record);
+    ///         ticker.step(worker);
+    ///     }
+    ///
+    ///     assert!(*ticker.epoch() >= 10);
+    ///     ticker.close();
+    ///     worker.step_while(|| !probe.done());
+    /// }).unwrap();
+    /// ```
+    pub fn new(input: InputHandle<u64, D>, records: Option<usize>, interval: Option<Duration>) -> Self {
+        Ticker {
+            input,
+            records,
+            interval,
+            sent: 0,
+            advanced: Instant::now(),
+        }
+    }
+
+    /// Sends one record at the current epoch, advancing the epoch if `records` records have been sent.
+    pub fn send(&mut self, data: D) {
+        self.input.send(data);
+        self.sent += 1;
+        if self.records.map(|records| self.sent >= records).unwrap_or(false) {
+            self.advance();
+        }
+    }
+
+    /// Advances the epoch if `interval` has elapsed since the epoch last advanced.
+    pub fn tick(&mut self) {
+        if self.interval.map(|interval| self.advanced.elapsed() >= interval).unwrap_or(false) {
+            self.advance();
+        }
+    }
+
+    /// Advances the epoch if the interval has elapsed, and then steps `worker`.
+    ///
+    /// Returns the result of the worker's step.
+    pub fn step<A: Allocate>(&mut self, worker: &mut Root<A>) -> bool {
+        self.tick();
+        worker.step()
+    }
+
+    /// Advances the epoch by one, resetting the record count and interval.
+    pub fn advance(&mut self) {
+        let next = *self.input.epoch() + 1;
+        self.input.advance_to(next);
+        self.sent = 0;
+        self.advanced = Instant::now();
+    }
+
+    /// Reports the current epoch.
+    pub fn epoch(&self) -> &u64 {
+        self.input.epoch()
+    }
+
+    /// Provides access to the wrapped input handle, for example to send batches of records.
+    ///
+    /// Records sent through the handle directly do not count towards `records`.
+    pub fn handle(&mut self) -> &mut InputHandle<u64, D> {
+        &mut self.input
+    }
+
+    /// Closes the input, consuming the ticker.
+    pub fn close(self) { }
+}
@@ -1,5 +1,8 @@
 //! Conversion to the `Stream` type from iterators.
 
+use std::iter::{Skip, StepBy};
+use std::ops::Range;
+
 use progress::Timestamp;
 
 use Data;
@@ -51,3 +54,135 @@ impl<T: Timestamp, I: IntoIterator+'static> ToStream<T, I::Item> for I where I::
         })
     }
 }
+
+/// A source of records that can be divided into disjoint parts, one for each worker.
+///
+/// The parts of a source, for any number of parts, together contain each of its records exactly once.
+///
+/// Ranges of integers and vectors split into contiguous parts without enumerating their records, and
+/// any other source may be split by position using `Strided`. A set of files is split by splitting
+/// the vector of their paths, each worker then reading the files of its part.
+///
+/// #Examples
+///
+/// ```
+/// use std::io::{BufRead, BufReader};
+/// use std::fs::File;
+/// use timely::dataflow::operators::{ToStreamSplit, Map, Inspect};
+///
+/// let path = ::std::env::temp_dir().join("timely_split_example.txt");
+/// ::std::fs::write(&path, "hello\nworld\n").unwrap();
+///
+/// timely::example(move |scope| {
+///     vec![path.clone()].to_stream_split(scope)
+///                       .flat_map(|path| BufReader::new(File::open(path).unwrap()).lines().map(|line| line.unwrap()))
+///                       .inspect(|line| println!("read: {:?}", line));
+/// });
+/// ```
+pub trait Split {
+    /// The records of the source.
+    type Item;
+    /// One part of the source.
+    type Part: IntoIterator<Item=Self::Item>;
+    /// The part `index` of `parts` parts.
+    fn split(self, index: usize, parts: usize) -> Self::Part;
+}
+
+/// The bounds of the part `index` of `parts` near-equal contiguous parts of `length` elements.
+fn part_bounds(length: u64, index: usize, parts: usize) -> (u64, u64) {
+    let (index, parts) = (index as u64, parts as u64);
+    let (chunk, remainder) = (length / parts, length % parts);
+    let lower = index * chunk + ::std::cmp::min(index, remainder);
+    let upper = lower + chunk + if index < remainder { 1 } else { 0 };
+    (lower, upper)
+}
+
+macro_rules! implement_split {
+    ($($index_type:ty: $unsigned_type:ty,)*) => (
+        $(
+            impl Split for Range<$index_type> {
+                type Item = $index_type;
+                type Part = Range<$index_type>;
+                fn split(self, index: usize, parts: usize) -> Range<$index_type> {
+                    if self.start >= self.end { return self; }
+                    let length = (self.end as $unsigned_type).wrapping_sub(self.start as $unsigned_type) as u64;
+                    let (lower, upper) = part_bounds(length, index, parts);
+                    let start = (self.start as $unsigned_type).wrapping_add(lower as $unsigned_type) as $index_type;
+                    let end = (self.start as $unsigned_type).wrapping_add(upper as $unsigned_type) as $index_type;
+                    start .. end
+                }
+            }
+        )*
+    )
+}
+
+implement_split!(u8: u8, u16: u16, u32: u32, u64: u64, usize: usize, i8: u8, i16: u16, i32: u32, i64: u64, isize: usize,);
+
+impl<T> Split for Vec<T> {
+    type Item = T;
+    type Part = Vec<T>;
+    fn split(mut self, index: usize, parts: usize) -> Vec<T> {
+        let (lower, upper) = part_bounds(self.len() as u64, index, parts);
+        self.truncate(upper as usize);
+        self.split_off(lower as usize)
+    }
+}
+
+/// Splits any source by position, the part `index` of `parts` holding the records at positions
+/// congruent to `index` modulo `parts`.
+///
+/// Each worker enumerates the whole source to produce its part, and so this suits sources that are
+/// cheap to enumerate, but whose records are expensive to process.
+///
+/// #Examples
+///
+/// ```
+/// use timely::dataflow::operators::{ToStreamSplit, Inspect};
+/// use timely::dataflow::operators::to_stream::Strided;
+///
+/// timely::execute(timely::Configuration::Process(2), |worker| {
+///     worker.dataflow::<u64,_,_>(|scope| {
+///         Strided("timely".chars()).to_stream_split(scope)
+///                                  .inspect(|x| println!("produced: {:?}", x));
+///     });
+/// }).unwrap();
+/// ```
+pub struct Strided<I>(pub I);
+
+impl<I: IntoIterator> Split for Strided<I> {
+    type Item = I::Item;
+    type Part = StepBy<Skip<I::IntoIter>>;
+    fn split(self, index: usize, parts: usize) -> Self::Part {
+        self.0.into_iter().skip(index).step_by(parts)
+    }
+}
+
+/// Converts to a timely `Stream` whose records are divided among the workers.
+pub trait ToStreamSplit<T: Timestamp, D: Data> {
+    /// Converts to a timely `Stream`, in which each worker produces its part of the source.
+    ///
+    /// Each worker should call this method with the same source. Unlike `to_stream`, with which
+    /// each worker produces all of its records, the records are produced once in total.
+    ///
+    /// #Examples
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{ToStreamSplit, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Process(3), |worker| {
+    ///     let index = worker.index();
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..100u64).to_stream_split(scope)
+    ///                    .inspect(move |x| println!("worker {} produced: {:?}", index, x));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    fn to_stream_split<S: Scope<Timestamp=T>>(self, scope: &mut S) -> Stream<S, D>;
+}
+
+impl<T: Timestamp, I: Split+'static> ToStreamSplit<T, I::Item> for I where I::Item: Data, I::Part: 'static {
+    fn to_stream_split<S: Scope<Timestamp=T>>(self, scope: &mut S) -> Stream<S, I::Item> {
+        let part = self.split(scope.index(), scope.peers());
+        part.to_stream(scope)
+    }
+}
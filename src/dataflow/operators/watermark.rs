@@ -0,0 +1,172 @@
+//! Create new `Streams` whose progress is driven by externally supplied watermarks.
+//!
+//! Many external sources are partitioned, and each partition reports a watermark: a promise that it
+//! will produce no further records at times not greater or equal to the watermark. The handle in this
+//! module accepts records and watermarks for each partition, and maintains the capabilities of an
+//! `UnorderedInput` so that the stream's frontier is the combination of the partition watermarks.
+
+use Data;
+use order::PartialOrder;
+use progress::Timestamp;
+use progress::frontier::Antichain;
+use dataflow::{Stream, Scope};
+use dataflow::operators::CapabilitySet;
+use dataflow::operators::unordered_input::{UnorderedInput, UnorderedHandle};
+
+/// Create a new `Stream` and `WatermarkHandle` through which to supply input.
+pub trait WatermarkInput<G: Scope> {
+    /// Creates an input stream fed by `partitions` partitions, each of which reports its own watermark.
+    ///
+    /// Each partition's watermark starts at the default timestamp. The stream's frontier is the set of
+    /// minimal watermarks among partitions that have not been closed, and once all partitions are
+    /// closed (or the handle is dropped) the stream is complete.
+    ///
+    /// #Examples
+    ///
+    /// ```
+    /// use timely::dataflow::operators::{Inspect, Probe};
+    /// use timely::dataflow::operators::watermark::WatermarkInput;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, probe) = worker.dataflow::<u64,_,_>(|scope| {
+    ///         let (input, stream) = scope.new_watermark_input(2);
+    ///         let probe = stream.inspect_time(|t, x: &u64| println!("{:?}: {:?}", t, x))
+    ///                           .probe();
+    ///         (input, probe)
+    ///     });
+    ///
+    ///     input.send(0, RootTimestamp::new(3), 0).unwrap();
+    ///     input.send(1, RootTimestamp::new(1), 1).unwrap();
+    ///
+    ///     // the frontier is held back by the slower partition.
+    ///     input.advance(0, RootTimestamp::new(5));
+    ///     input.advance(1, RootTimestamp::new(2));
+    ///     worker.step_while(|| probe.less_than(&RootTimestamp::new(2)));
+    ///     assert!(!probe.less_than(&RootTimestamp::new(2)));
+    ///     assert!(probe.less_than(&RootTimestamp::new(3)));
+    ///
+    ///     input.close(1);
+    ///     worker.step_while(|| probe.less_than(&RootTimestamp::new(5)));
+    /// }).unwrap();
+    /// ```
+    fn new_watermark_input<D: Data>(&mut self, partitions: usize) -> (WatermarkHandle<G::Timestamp, D>, Stream<G, D>);
+}
+
+impl<G: Scope> WatermarkInput<G> for G {
+    fn new_watermark_input<D: Data>(&mut self, partitions: usize) -> (WatermarkHandle<G::Timestamp, D>, Stream<G, D>) {
+        let ((handle, capability), stream) = self.new_unordered_input();
+        let mut capabilities = CapabilitySet::new();
+        if partitions > 0 {
+            capabilities.insert(capability);
+        }
+        let watermarks = (0 .. partitions).map(|_| Some(Default::default())).collect();
+        let handle = WatermarkHandle {
+            handle,
+            capabilities,
+            watermarks,
+        };
+        (handle, stream)
+    }
+}
+
+/// A handle to a watermarked input `Stream`, used to introduce data and per-partition watermarks.
+pub struct WatermarkHandle<T: Timestamp, D: Data> {
+    handle: UnorderedHandle<T, D>,
+    capabilities: CapabilitySet<T>,
+    /// The current watermark of each partition, or `None` if the partition is closed.
+    watermarks: Vec<Option<T>>,
+}
+
+impl<T: Timestamp, D: Data> WatermarkHandle<T, D> {
+
+    /// Sends a record from `partition` at `time`.
+    ///
+    /// If `time` is not greater or equal to the partition's watermark, or the partition is closed,
+    /// the record cannot be sent and is returned as an error.
+    pub fn send(&mut self, partition: usize, time: T, data: D) -> Result<(), D> {
+        if self.accepts(partition, &time) {
+            let capability = self.capabilities.delayed(&time);
+            self.handle.session(capability).give(data);
+            Ok(())
+        }
+        else {
+            Err(data)
+        }
+    }
+
+    /// Sends a batch of records from `partition` at `time`, leaving `data` empty.
+    ///
+    /// If `time` is not greater or equal to the partition's watermark, or the partition is closed,
+    /// the records cannot be sent and are left in `data`, and the method returns false.
+    pub fn send_batch(&mut self, partition: usize, time: T, data: &mut Vec<D>) -> bool {
+        if self.accepts(partition, &time) {
+            let capability = self.capabilities.delayed(&time);
+            self.handle.session(capability).give_content(data);
+            data.clear();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Advances the watermark of `partition` to `watermark`.
+    ///
+    /// Watermarks that do not advance the partition's current watermark are ignored, as are
+    /// watermarks for closed partitions. The frontier of the stream advances once the watermarks
+    /// of all open partitions have advanced.
+    pub fn advance(&mut self, partition: usize, watermark: T) {
+        let advanced = match self.watermarks[partition] {
+            Some(ref mut current) if current.less_than(&watermark) => { *current = watermark; true },
+            _ => false,
+        };
+        if advanced {
+            self.update_capabilities();
+        }
+    }
+
+    /// Closes `partition`, indicating that it will produce no further records.
+    pub fn close(&mut self, partition: usize) {
+        if self.watermarks[partition].take().is_some() {
+            self.update_capabilities();
+        }
+    }
+
+    /// Reports the watermark of `partition`, or `None` if the partition is closed.
+    pub fn watermark(&self, partition: usize) -> Option<&T> {
+        self.watermarks[partition].as_ref()
+    }
+
+    /// Reports the combined watermark of all open partitions, which is the frontier of the stream.
+    pub fn frontier(&self) -> Antichain<T> {
+        let mut frontier = Antichain::new();
+        for watermark in self.watermarks.iter() {
+            if let Some(ref watermark) = *watermark {
+                frontier.insert(watermark.clone());
+            }
+        }
+        frontier
+    }
+
+    /// Reports the number of partitions, open or closed.
+    pub fn partitions(&self) -> usize {
+        self.watermarks.len()
+    }
+
+    fn accepts(&self, partition: usize, time: &T) -> bool {
+        self.watermarks[partition].as_ref().map(|w| w.less_equal(time)).unwrap_or(false)
+    }
+
+    // downgrades held capabilities to the combined watermark.
+    fn update_capabilities(&mut self) {
+        let frontier = self.frontier();
+        if frontier.elements().is_empty() {
+            self.capabilities = CapabilitySet::new();
+        }
+        else {
+            self.capabilities.downgrade(frontier.elements());
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Operators that pair up records of two streams, and split streams of pairs.
+
+use std::collections::{HashMap, VecDeque};
+
+use Data;
+use dataflow::channels::pact::Pipeline;
+use dataflow::{Stream, Scope};
+use dataflow::operators::generic::operator::Operator;
+use dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+/// Pairs up the records of two streams.
+pub trait Zip<G: Scope, D1: Data> {
+    /// Pairs the i-th record of `self` at each time with the i-th record of `other` at the same time.
+    ///
+    /// Records are paired in the order they arrive at each worker, and pairs are produced as soon as
+    /// both records are available. If one stream has more records than the other at some time, the
+    /// surplus records are discarded once the time is complete, as with `Iterator::zip`.
+    ///
+    /// The pairing is local to each worker, and the order of arrival is only meaningful if both
+    /// streams are produced by the same worker in a known order, for example when `other` was
+    /// derived from `self` by record-at-a-time operators without exchanging data.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Zip, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     let inputs = (0..5).to_stream(scope);
+    ///     let results = inputs.map(|x| x * x);
+    ///     inputs.zip(&results)
+    ///           .capture()
+    /// });
+    ///
+    /// let extracted = captured.extract();
+    /// assert_eq!(extracted, vec![(RootTimestamp::new(0), vec![(0,0),(1,1),(2,4),(3,9),(4,16)])]);
+    /// ```
+    fn zip<D2: Data>(&self, other: &Stream<G, D2>) -> Stream<G, (D1, D2)>;
+}
+
+impl<G: Scope, D1: Data> Zip<G, D1> for Stream<G, D1> {
+    fn zip<D2: Data>(&self, other: &Stream<G, D2>) -> Stream<G, (D1, D2)> {
+
+        let mut queues = HashMap::new();
+        let mut vector1 = Vec::new();
+        let mut vector2 = Vec::new();
+
+        self.binary_notify(other, Pipeline, Pipeline, "Zip", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|time, data| {
+                data.swap(&mut vector1);
+                {
+                    let queues = queues.entry(time.time().clone()).or_insert_with(|| (VecDeque::new(), VecDeque::new()));
+                    queues.0.extend(vector1.drain(..));
+                    let mut session = output.session(&time);
+                    while !queues.0.is_empty() && !queues.1.is_empty() {
+                        session.give((queues.0.pop_front().unwrap(), queues.1.pop_front().unwrap()));
+                    }
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            input2.for_each(|time, data| {
+                data.swap(&mut vector2);
+                {
+                    let queues = queues.entry(time.time().clone()).or_insert_with(|| (VecDeque::new(), VecDeque::new()));
+                    queues.1.extend(vector2.drain(..));
+                    let mut session = output.session(&time);
+                    while !queues.0.is_empty() && !queues.1.is_empty() {
+                        session.give((queues.0.pop_front().unwrap(), queues.1.pop_front().unwrap()));
+                    }
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            // complete times can no longer form pairs; discard any surplus.
+            notificator.for_each(|time,_,_| {
+                queues.remove(time.time());
+            });
+        })
+    }
+}
+
+/// Splits a stream of pairs into two streams.
+pub trait Unzip<G: Scope, D1: Data, D2: Data> {
+    /// Splits a stream of pairs into a stream of first elements and a stream of second elements.
+    ///
+    /// Both output streams have exactly as many records as the input stream at each time.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Unzip, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     let (evens, odds) = (0..10).to_stream(scope)
+    ///                                .map(|x| (2 * x, 2 * x + 1))
+    ///                                .unzip();
+    ///
+    ///     evens.inspect(|x| assert!(x % 2 == 0));
+    ///     odds.inspect(|x| assert!(x % 2 == 1));
+    /// });
+    /// ```
+    fn unzip(&self) -> (Stream<G, D1>, Stream<G, D2>);
+}
+
+impl<G: Scope, D1: Data, D2: Data> Unzip<G, D1, D2> for Stream<G, (D1, D2)> {
+    fn unzip(&self) -> (Stream<G, D1>, Stream<G, D2>) {
+
+        let mut builder = OperatorBuilder::new("Unzip".to_owned(), self.scope());
+        builder.set_notify(false);
+
+        let mut input = builder.new_input(self, Pipeline);
+        let (mut output1, stream1) = builder.new_output();
+        let (mut output2, stream2) = builder.new_output();
+
+        builder.build(move |_| {
+            let mut vector = Vec::new();
+            move |_frontiers| {
+                let mut output1_handle = output1.activate();
+                let mut output2_handle = output2.activate();
+
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    let mut out1 = output1_handle.session(&time);
+                    let mut out2 = output2_handle.session(&time);
+                    for (datum1, datum2) in vector.drain(..) {
+                        out1.give(datum1);
+                        out2.give(datum2);
+                    }
+                });
+            }
+        });
+
+        (stream1, stream2)
+    }
+}
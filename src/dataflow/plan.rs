@@ -0,0 +1,173 @@
+//! Dataflows described by serializable plans, and rendered using registered operator constructors.
+//!
+//! A `Plan` describes a dataflow over streams of a single record type as a sequence of `Node`s, each
+//! naming an operator, the streams it consumes, how its inputs should be partitioned among workers,
+//! and string arguments for the operator. Plans contain no closures, and can be serialized (using
+//! `abomonation`) and shipped between processes. A `Registry` maps operator names to constructors,
+//! and renders a plan into a scope by invoking the named constructors in sequence.
+//!
+//! Plans describe operators within a single scope. Constructors may themselves build nested scopes,
+//! and so iterative computations are expressed by registering a constructor for the iteration.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+use ::ExchangeData;
+use dataflow::{Stream, Scope};
+use dataflow::operators::Exchange;
+
+/// How the records of a stream are partitioned among workers before they reach an operator.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub enum Pact {
+    /// Records remain at the worker that produced them.
+    Pipeline,
+    /// Records are routed to workers by their hash.
+    Exchange,
+}
+
+/// An operator in a plan.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// The name under which the operator's constructor is registered.
+    pub operator: String,
+    /// Identifiers of the streams the operator consumes.
+    pub inputs: Vec<usize>,
+    /// The partitioning applied to each input.
+    pub pact: Pact,
+    /// Arguments passed to the operator's constructor.
+    pub args: Vec<String>,
+}
+
+/// A serializable description of a dataflow.
+///
+/// Streams are identified by integers: the first `inputs` identifiers name streams supplied when
+/// the plan is rendered, and each node produces one stream whose identifier is `inputs` plus the
+/// node's position in `nodes`. Nodes may only consume streams with smaller identifiers.
+#[derive(Abomonation, Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// The number of streams supplied to the plan.
+    pub inputs: usize,
+    /// The operators of the plan, in order of construction.
+    pub nodes: Vec<Node>,
+    /// Identifiers of the streams returned once the plan is rendered.
+    pub outputs: Vec<usize>,
+}
+
+impl Plan {
+    /// Allocates a new plan consuming `inputs` streams, with no operators or outputs.
+    pub fn new(inputs: usize) -> Self {
+        Plan {
+            inputs,
+            nodes: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Adds an operator to the plan, returning the identifier of the stream it produces.
+    pub fn add(&mut self, operator: &str, inputs: Vec<usize>, pact: Pact, args: Vec<String>) -> usize {
+        self.nodes.push(Node {
+            operator: operator.to_owned(),
+            inputs,
+            pact,
+            args,
+        });
+        self.inputs + self.nodes.len() - 1
+    }
+
+    /// Indicates that the stream with identifier `stream` should be returned once rendered.
+    pub fn output(&mut self, stream: usize) {
+        self.outputs.push(stream);
+    }
+}
+
+/// A constructor for an operator named in a plan.
+pub type Constructor<G, D> = Box<Fn(&[Stream<G, D>], &[String])->Result<Stream<G, D>, String>>;
+
+/// Operator constructors by name, used to render plans.
+pub struct Registry<G: Scope, D: ExchangeData+Hash> {
+    constructors: HashMap<String, Constructor<G, D>>,
+}
+
+impl<G: Scope, D: ExchangeData+Hash> Registry<G, D> {
+
+    /// Allocates a new empty registry.
+    pub fn new() -> Self {
+        Registry { constructors: HashMap::new() }
+    }
+
+    /// Registers `constructor` under `name`, replacing any constructor previously registered under it.
+    ///
+    /// The constructor is called with the operator's input streams (already partitioned according to
+    /// the node's pact) and arguments, and may report an error, for example for malformed arguments.
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where F: Fn(&[Stream<G, D>], &[String])->Result<Stream<G, D>, String>+'static {
+        self.constructors.insert(name.to_owned(), Box::new(constructor));
+    }
+
+    /// Renders `plan` with the supplied input streams, returning the plan's output streams.
+    ///
+    /// An error is returned if the number of inputs does not match the plan, if a node names an
+    /// unregistered operator or a stream not yet defined, or if a constructor reports an error.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Filter, Concat, Inspect};
+    /// use timely::dataflow::plan::{Plan, Pact, Registry};
+    ///
+    /// // a plan, which could be built in another process and shipped here.
+    /// let mut plan = Plan::new(1);
+    /// let scaled = plan.add("scale", vec![0], Pact::Pipeline, vec!["10".to_owned()]);
+    /// let evens = plan.add("evens", vec![0], Pact::Exchange, vec![]);
+    /// let both = plan.add("concat", vec![scaled, evens], Pact::Pipeline, vec![]);
+    /// plan.output(both);
+    ///
+    /// timely::example(move |scope| {
+    ///
+    ///     let mut registry = Registry::new();
+    ///     registry.register("scale", |inputs, args| {
+    ///         let factor = args[0].parse::<u64>().map_err(|e| e.to_string())?;
+    ///         Ok(inputs[0].map(move |x| x * factor))
+    ///     });
+    ///     registry.register("evens", |inputs, _args| Ok(inputs[0].filter(|x| x % 2 == 0)));
+    ///     registry.register("concat", |inputs, _args| Ok(inputs[0].concat(&inputs[1])));
+    ///
+    ///     let input = (0..10u64).to_stream(scope);
+    ///     let outputs = registry.render(&plan, &[input]).unwrap();
+    ///     outputs[0].inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    pub fn render(&self, plan: &Plan, inputs: &[Stream<G, D>]) -> Result<Vec<Stream<G, D>>, String> {
+
+        if inputs.len() != plan.inputs {
+            return Err(format!("plan expects {} inputs, but {} were supplied", plan.inputs, inputs.len()));
+        }
+
+        let mut streams = inputs.to_vec();
+        for node in plan.nodes.iter() {
+
+            let constructor = self.constructors.get(&node.operator)
+                                  .ok_or_else(|| format!("operator not registered: {:?}", node.operator))?;
+
+            let mut node_inputs = Vec::with_capacity(node.inputs.len());
+            for &input in node.inputs.iter() {
+                let stream = streams.get(input)
+                                    .ok_or_else(|| format!("operator {:?} consumes undefined stream {}", node.operator, input))?;
+                node_inputs.push(match node.pact {
+                    Pact::Pipeline => stream.clone(),
+                    Pact::Exchange => stream.exchange(|record| {
+                        let mut hasher = DefaultHasher::new();
+                        record.hash(&mut hasher);
+                        hasher.finish()
+                    }),
+                });
+            }
+
+            streams.push(constructor(&node_inputs[..], &node.args[..])?);
+        }
+
+        plan.outputs.iter().map(|&output| {
+            streams.get(output).cloned().ok_or_else(|| format!("plan output names undefined stream {}", output))
+        }).collect()
+    }
+}
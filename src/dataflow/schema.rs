@@ -0,0 +1,178 @@
+//! Runtime-checked record types, for connecting streams whose types are known only at runtime.
+//!
+//! Dataflows assembled at runtime, for example from plans or by bridging streams between dataflows,
+//! may need to pass streams between code that does not share their types at compile time. A
+//! `TypeRegistry` names record types, and records for each how to encode and decode batches of its
+//! records as bytes. A `DynStream` is a stream whose record type has been erased, which the registry
+//! restores with a checked downcast, reporting the names of both types when they differ.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use abomonation::{encode, decode};
+
+use ::ExchangeData;
+use dataflow::{Stream, Scope};
+use progress::nested::subgraph::Source;
+
+/// How a registered type is named, encoded, and decoded.
+struct TypeEntry {
+    name: String,
+    encode: fn(&Any) -> Option<Vec<u8>>,
+    decode: fn(&[u8]) -> Option<Box<Any>>,
+}
+
+fn encode_records<D: ExchangeData>(records: &Any) -> Option<Vec<u8>> {
+    records.downcast_ref::<Vec<D>>().map(|records| {
+        let mut bytes = Vec::new();
+        unsafe { encode(records, &mut bytes).expect("encoding to a Vec failed"); }
+        bytes
+    })
+}
+
+fn decode_records<D: ExchangeData>(bytes: &[u8]) -> Option<Box<Any>> {
+    if ::std::mem::align_of::<D>() > ::std::mem::align_of::<u64>() {
+        return None;
+    }
+    // decoding requires the bytes to be aligned for `Vec<D>`, which the caller's bytes need not be.
+    let mut aligned = vec![0u64; (bytes.len() + 7) / 8];
+    let contents = unsafe { ::std::slice::from_raw_parts_mut(aligned.as_mut_ptr() as *mut u8, bytes.len()) };
+    contents.copy_from_slice(bytes);
+    match unsafe { decode::<Vec<D>>(contents) } {
+        Some((records, rest)) if rest.is_empty() => Some(Box::new(records.clone()) as Box<Any>),
+        _ => None,
+    }
+}
+
+/// A stream whose record type has been erased.
+pub struct DynStream<G: Scope> {
+    name: Source,
+    scope: G,
+    type_id: TypeId,
+    ports: Box<Any>,
+}
+
+impl<G: Scope> DynStream<G> {
+    /// The identifier of the stream's record type.
+    pub fn type_id(&self) -> TypeId { self.type_id }
+    /// The name of the stream's source operator.
+    pub fn name(&self) -> &Source { &self.name }
+}
+
+/// Record types registered by name, with their encodings.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::schema::TypeRegistry;
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<u64>("u64");
+/// registry.register::<String>("string");
+///
+/// timely::example(move |scope| {
+///     let erased = registry.erase((0..10u64).to_stream(scope)).unwrap();
+///     assert!(registry.downcast::<_, String>(&erased).is_err());
+///     registry.downcast::<_, u64>(&erased)
+///             .unwrap()
+///             .inspect(|x| println!("seen: {:?}", x));
+/// });
+///
+/// // batches may be moved as bytes, and decoded by the name of their type.
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<u64>("u64");
+/// let mut bytes = registry.encode("u64", &vec![1u64, 2, 3]).unwrap();
+/// assert_eq!(registry.decode::<u64>("u64", &bytes[..]), Ok(vec![1, 2, 3]));
+/// assert!(registry.decode::<u64>("u64", &bytes[.. bytes.len() - 1]).is_err());
+/// bytes.push(0);
+/// assert!(registry.decode::<u64>("u64", &bytes[..]).is_err());
+/// ```
+pub struct TypeRegistry {
+    entries: HashMap<TypeId, TypeEntry>,
+    names: HashMap<String, TypeId>,
+}
+
+impl TypeRegistry {
+
+    /// Allocates a new empty registry.
+    pub fn new() -> Self {
+        TypeRegistry { entries: HashMap::new(), names: HashMap::new() }
+    }
+
+    /// Registers the record type `D` under `name`, replacing any registration of either.
+    pub fn register<D: ExchangeData>(&mut self, name: &str) {
+        let type_id = TypeId::of::<D>();
+        if let Some(entry) = self.entries.remove(&type_id) {
+            self.names.remove(&entry.name);
+        }
+        if let Some(previous) = self.names.remove(name) {
+            self.entries.remove(&previous);
+        }
+        self.names.insert(name.to_owned(), type_id);
+        self.entries.insert(type_id, TypeEntry {
+            name: name.to_owned(),
+            encode: encode_records::<D>,
+            decode: decode_records::<D>,
+        });
+    }
+
+    /// The name under which the type with identifier `type_id` is registered, if any.
+    pub fn name_of(&self, type_id: TypeId) -> Option<&str> {
+        self.entries.get(&type_id).map(|entry| &entry.name[..])
+    }
+
+    /// The identifier of the type registered under `name`, if any.
+    pub fn type_id(&self, name: &str) -> Option<TypeId> {
+        self.names.get(name).cloned()
+    }
+
+    /// Describes a type for error messages.
+    fn describe(&self, type_id: TypeId) -> String {
+        match self.name_of(type_id) {
+            Some(name) => format!("{:?}", name),
+            None => "an unregistered type".to_owned(),
+        }
+    }
+
+    /// Erases the record type of `stream`, which must be registered.
+    pub fn erase<G: Scope, D: ExchangeData>(&self, stream: Stream<G, D>) -> Result<DynStream<G>, String> {
+        let type_id = TypeId::of::<D>();
+        if !self.entries.contains_key(&type_id) {
+            return Err("cannot erase a stream of an unregistered type".to_owned());
+        }
+        Ok(DynStream {
+            name: *stream.name(),
+            scope: stream.scope(),
+            type_id,
+            ports: Box::new(stream.ports().clone()),
+        })
+    }
+
+    /// Restores the stream `stream`, if its records have type `D`.
+    pub fn downcast<G: Scope, D: ExchangeData>(&self, stream: &DynStream<G>) -> Result<Stream<G, D>, String> {
+        if stream.type_id != TypeId::of::<D>() {
+            return Err(format!("stream carries {}, but {} was requested", self.describe(stream.type_id), self.describe(TypeId::of::<D>())));
+        }
+        let ports = stream.ports.downcast_ref().cloned().expect("erased stream has inconsistent type");
+        Ok(Stream::new(stream.name, ports, stream.scope.clone()))
+    }
+
+    /// Encodes `records`, a `Vec` of the records of the type registered under `name`.
+    pub fn encode(&self, name: &str, records: &Any) -> Result<Vec<u8>, String> {
+        let type_id = self.type_id(name).ok_or_else(|| format!("type not registered: {:?}", name))?;
+        (self.entries[&type_id].encode)(records).ok_or_else(|| format!("records are not a Vec of {:?}", name))
+    }
+
+    /// Decodes `bytes` into records of type `D`, which must be registered under `name`.
+    ///
+    /// The bytes are copied into a buffer aligned to eight bytes before they are decoded, and must hold
+    /// exactly one encoded batch; record types requiring greater alignment cannot be decoded.
+    pub fn decode<D: ExchangeData>(&self, name: &str, bytes: &[u8]) -> Result<Vec<D>, String> {
+        let type_id = self.type_id(name).ok_or_else(|| format!("type not registered: {:?}", name))?;
+        if type_id != TypeId::of::<D>() {
+            return Err(format!("bytes encode {:?}, but {} was requested", name, self.describe(TypeId::of::<D>())));
+        }
+        let records = (self.entries[&type_id].decode)(bytes).ok_or_else(|| format!("bytes do not encode records of {:?}", name))?;
+        Ok(*records.downcast::<Vec<D>>().expect("decoded records have inconsistent type"))
+    }
+}
@@ -1,14 +1,20 @@
 //! A child dataflow scope, used to build nested dataflow scopes.
 
 use std::cell::RefCell;
+use std::time::Duration;
 
 use progress::{Timestamp, Operate, SubgraphBuilder};
 use progress::nested::{Source, Target};
 use progress::nested::product::Product;
-use communication::{Allocate, Data, Push, Pull};
+use progress::snoop::{Snooper, EpochHooks};
+use progress::nested::reachability::Summary;
+use communication::{Allocate, Data, Push, Pull, WorkerIdentity};
 use logging::Logger;
+use timer::Timer;
+use resources::Resources;
+use progress::broadcast::ProgressMode;
 
-use super::{ScopeParent, Scope};
+use super::{ScopeParent, Scope, Root};
 
 /// A `Child` wraps a `Subgraph` and a parent `G: Scope`. It manages the addition
 /// of `Operate`s to a subgraph, and the connection of edges between them.
@@ -28,6 +34,87 @@ impl<'a, G: ScopeParent, T: Timestamp> Child<'a, G, T> {
     pub fn index(&self) -> usize { self.parent.index() }
     /// The total number of workers in the computation.
     pub fn peers(&self) -> usize { self.parent.peers() }
+
+    /// Sets the execution-time budget of operators subsequently added to this scope, and of scopes
+    /// nested within it, other than those with budgets of their own.
+    ///
+    /// Each invocation of an operator that exceeds its budget is logged as a `BudgetEvent`, with
+    /// the name and address of the operator. A nested scope is itself an operator of this scope, and
+    /// its invocations include those of its operators.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     scope.set_operator_budget(Some(Duration::from_millis(10)));
+    ///     (0..10u64).to_stream(scope)
+    ///               .map(|x| { ::std::thread::sleep(Duration::from_millis(20)); x })
+    ///               .inspect(|x| println!("slowly: {:?}", x));
+    /// });
+    /// ```
+    pub fn set_operator_budget(&self, budget: Option<Duration>) {
+        self.subgraph.borrow_mut().set_budget(budget);
+    }
+
+    /// Subscribes to changes in the frontiers at the operators of this scope.
+    ///
+    /// The frontiers reflect the progress of all workers, and the returned `Snooper` may be moved to
+    /// another thread, allowing an external controller to track the completion of times throughout
+    /// the scope without probing each stream.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{Input, Inspect};
+    /// use timely::progress::timestamp::RootTimestamp;
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, mut snooper) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input::<u64>();
+    ///         stream.inspect(|x| println!("{:?}", x));
+    ///         (input, scope.snoop())
+    ///     });
+    ///
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         while { snooper.pull(); !snooper.complete(&RootTimestamp::new(round)) } {
+    ///             worker.step();
+    ///         }
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn snoop(&self) -> Snooper<Product<G::Timestamp, T>> {
+        self.subgraph.borrow_mut().snoop()
+    }
+
+    /// Summarizes the paths between the ports of operators constructed so far in this scope.
+    ///
+    /// Operators are identified by their index within the scope (as reported by `OperatorInfo`),
+    /// and the summary can be used to determine whether and at what times data at one port could
+    /// arrive at another.
+    pub fn reachability(&self) -> Summary<Product<G::Timestamp, T>> {
+        self.subgraph.borrow().summarize()
+    }
+}
+
+impl<'a, A: Allocate, T: Timestamp> Child<'a, Root<A>, T> {
+    /// Subscribes to the completion of times by this dataflow.
+    ///
+    /// Hooks registered with the result are invoked by the worker as this dataflow completes their
+    /// times; see `EpochHooks` for an example.
+    pub fn epoch_hooks(&self) -> EpochHooks<T> {
+        self.parent.epoch_hooks(self.snoop())
+    }
+
+    /// The index of this dataflow within its worker, by which it may be cancelled.
+    ///
+    /// See `Root::cancel_dataflow` for an example.
+    pub fn dataflow_index(&self) -> usize {
+        self.subgraph.borrow().path[1]
+    }
 }
 
 impl<'a, G: ScopeParent, T: Timestamp> ScopeParent for Child<'a, G, T> {
@@ -36,6 +123,12 @@ impl<'a, G: ScopeParent, T: Timestamp> ScopeParent for Child<'a, G, T> {
     fn new_identifier(&mut self) -> usize {
         self.parent.new_identifier()
     }
+
+    fn timer(&self) -> Timer { self.parent.timer() }
+
+    fn resources(&self) -> Resources { self.parent.resources() }
+
+    fn progress_mode(&self) -> ProgressMode { self.parent.progress_mode() }
 }
 
 impl<'a, G: ScopeParent, T: Timestamp> Scope for Child<'a, G, T> {
@@ -65,7 +158,11 @@ impl<'a, G: ScopeParent, T: Timestamp> Scope for Child<'a, G, T> {
         let index = self.subgraph.borrow_mut().allocate_child_id();
         let path = self.subgraph.borrow().path.clone();
 
-        let subscope = RefCell::new(SubgraphBuilder::new_from(index, path, self.logging().clone()));
+        let mut subscope = SubgraphBuilder::new_from(index, path, self.logging().clone());
+        subscope.set_progress_mode(self.progress_mode());
+        subscope.set_budget(self.subgraph.borrow().budget());
+        subscope.set_unfinished(self.subgraph.borrow().unfinished());
+        let subscope = RefCell::new(subscope);
         let result = {
             let mut builder = Child {
                 subgraph: &subscope,
@@ -91,6 +188,7 @@ use communication::Message;
 impl<'a, G: ScopeParent, T: Timestamp> Allocate for Child<'a, G, T> {
     fn index(&self) -> usize { self.parent.index() }
     fn peers(&self) -> usize { self.parent.peers() }
+    fn identity(&self) -> WorkerIdentity { self.parent.identity() }
     fn allocate<D: Data>(&mut self) -> (Vec<Box<Push<Message<D>>>>, Box<Pull<Message<D>>>, Option<usize>) {
         self.parent.allocate()
     }
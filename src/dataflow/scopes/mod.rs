@@ -3,6 +3,9 @@
 use progress::{Timestamp, Operate};
 use progress::nested::{Source, Target};
 use logging::Logger;
+use timer::Timer;
+use resources::Resources;
+use progress::broadcast::ProgressMode;
 use communication::Allocate;
 
 pub mod root;
@@ -18,6 +21,15 @@ pub trait ScopeParent: Allocate+Clone {
 
     /// Allocates a new locally unique identifier.
     fn new_identifier(&mut self) -> usize;
+
+    /// Obtains the worker's timer, whose clock is sampled once per step.
+    fn timer(&self) -> Timer;
+
+    /// Obtains the worker's registry of shared resources.
+    fn resources(&self) -> Resources;
+
+    /// How dataflows constructed by the worker exchange progress updates.
+    fn progress_mode(&self) -> ProgressMode;
 }
 
 /// The fundamental operations required to add and connect operators in a timely dataflow graph.
@@ -58,6 +70,11 @@ pub trait Scope: ScopeParent {
     /// Commonly used to create new timely dataflow subgraphs, either creating new input streams
     /// and the input handle, or ingressing data streams and returning the egresses stream.
     ///
+    /// The child scope extends the timestamps of this scope with a `T`, which may be any type
+    /// implementing `Timestamp`, with its own `PathSummary`: integer iteration counters, pairs of
+    /// timestamps, `Saturating` counters, or types defined by the application, such as an
+    /// enumeration of the phases of a computation.
+    ///
     /// # Examples
     /// ```
     /// use timely::dataflow::Scope;
@@ -74,6 +91,51 @@ pub trait Scope: ScopeParent {
     ///     });
     /// });
     /// ```
+    ///
+    /// A scope whose timestamps are the phases of a computation, defined by the application.
+    ///
+    /// ```
+    /// use timely::PartialOrder;
+    /// use timely::dataflow::Scope;
+    /// use timely::dataflow::operators::{ToStream, Enter, Leave, Inspect};
+    /// use timely::progress::timestamp::{Timestamp, PathSummary};
+    ///
+    /// #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+    /// enum Phase { Load, Compute, Report }
+    ///
+    /// impl Default for Phase { fn default() -> Self { Phase::Load } }
+    /// impl PartialOrder for Phase { fn less_equal(&self, other: &Self) -> bool { self <= other } }
+    /// impl timely::Abomonation for Phase { }
+    /// impl Timestamp for Phase { type Summary = Steps; }
+    ///
+    /// // advances a phase by some number of steps.
+    /// #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+    /// struct Steps(u8);
+    ///
+    /// impl PartialOrder for Steps { fn less_equal(&self, other: &Self) -> bool { self.0 <= other.0 } }
+    /// impl PathSummary<Phase> for Steps {
+    ///     fn results_in(&self, src: &Phase) -> Option<Phase> {
+    ///         match *src as u8 + self.0 {
+    ///             0 => Some(Phase::Load),
+    ///             1 => Some(Phase::Compute),
+    ///             2 => Some(Phase::Report),
+    ///             _ => None,
+    ///         }
+    ///     }
+    ///     fn followed_by(&self, other: &Steps) -> Option<Steps> {
+    ///         if self.0 + other.0 <= 2 { Some(Steps(self.0 + other.0)) } else { None }
+    ///     }
+    /// }
+    ///
+    /// timely::example(|scope| {
+    ///     let stream = (0..10).to_stream(scope);
+    ///     scope.scoped::<Phase,_,_>(|inner| {
+    ///         stream.enter(inner)
+    ///               .inspect_batch(|t, xs| println!("{:?}: {:?}", t.inner, xs))
+    ///               .leave()
+    ///     });
+    /// });
+    /// ```
     fn scoped<T: Timestamp, R, F:FnOnce(&mut Child<Self, T>)->R>(&mut self, func: F) -> R;
 
     /// Obtains the logger associated with this scope.
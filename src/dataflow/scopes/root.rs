@@ -2,13 +2,26 @@
 
 use std::rc::Rc;
 use std::sync::Arc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::any::Any;
+use std::time::Duration;
 
+use order::PartialOrder;
 use progress::timestamp::RootTimestamp;
-use progress::{Timestamp, Operate, SubgraphBuilder};
+use progress::nested::product::Product;
+use progress::{Timestamp, PathSummary, Operate, SubgraphBuilder};
+use progress::nested::subgraph::UnfinishedPolicy;
+use progress::broadcast::ProgressMode;
+use progress::snoop::{Snooper, EpochHooks};
+use dataflow::operators::input::Handle as InputHandle;
+use dataflow::operators::probe::Handle as ProbeHandle;
+use dataflow::operators::probe::MultiProbe;
 use logging::Logger;
-use communication::{Allocate, Data, Push, Pull};
+use timer::Timer;
+use config::WorkerConfig;
+use resources::Resources;
+use dataflow::channels::registry::ChannelRegistry;
+use communication::{Allocate, Data, Push, Pull, WorkerIdentity};
 
 use super::{ScopeParent, Child};
 
@@ -20,17 +33,45 @@ pub struct Root<A: Allocate> {
     dataflows: Rc<RefCell<Vec<Wrapper>>>,
     dataflow_counter: Rc<RefCell<usize>>,
     logging: Arc<Fn(::logging::TimelySetup)->Logger+Sync+Send>,
+    timer: Timer,
+    config: WorkerConfig,
+    resources: Resources,
+    progress_mode: Rc<Cell<ProgressMode>>,
+    epoch_hooks: Rc<RefCell<Vec<Box<FnMut()->bool>>>>,
 }
 
 impl<A: Allocate> Root<A> {
     /// Allocates a new `Root` bound to a channel allocator.
     pub fn new(c: A, logging: Arc<Fn(::logging::TimelySetup)->Logger+Sync+Send>) -> Root<A> {
+        Root::new_with_config(c, logging, WorkerConfig::new())
+    }
+
+    /// Allocates a new `Root` bound to a channel allocator, with application-specific configuration.
+    ///
+    /// If the configuration contains the key `capability-audit`, the capabilities of the worker's
+    /// thread are audited, as described in the `audit` module. The key `operator-budget-us` sets the
+    /// execution-time budget, in microseconds, of the operators of each dataflow, as described by
+    /// `Child::set_operator_budget`. The key `unfinished-dataflows` determines what happens to
+    /// dataflows dropped before completing, as described by `UnfinishedPolicy::from_config`.
+    pub fn new_with_config(c: A, logging: Arc<Fn(::logging::TimelySetup)->Logger+Sync+Send>, config: WorkerConfig) -> Root<A> {
+        if config.contains("capability-audit") {
+            ::dataflow::operators::audit::enable();
+        }
         Root {
             allocator: Rc::new(RefCell::new(c)),
             identifiers: Rc::new(RefCell::new(0)),
             dataflows: Rc::new(RefCell::new(Vec::new())),
             dataflow_counter: Rc::new(RefCell::new(0)),
             logging,
+            timer: Timer::new(),
+            progress_mode: Rc::new(Cell::new(ProgressMode::from_config(&config))),
+            epoch_hooks: Rc::new(RefCell::new(Vec::new())),
+            config,
+            resources: {
+                let resources = Resources::new();
+                resources.insert(ChannelRegistry::new());
+                resources
+            },
         }
     }
 
@@ -38,8 +79,17 @@ impl<A: Allocate> Root<A> {
     ///
     /// A step gives each dataflow operator a chance to run, and is the
     /// main way to ensure that a computation proceeds.
+    ///
+    /// If another worker of the computation, in this or another process, has failed, the step does no
+    /// work and returns false, and the worker should stop; `peer_failed` reports this. The methods that
+    /// step the worker repeatedly, such as `step_while`, stop once a peer has failed.
     pub fn step(&mut self) -> bool {
 
+        if self.peer_failed() {
+            return false;
+        }
+
+        self.timer.advance();
         self.allocator.borrow_mut().pre_work();
 
         let mut active = false;
@@ -51,21 +101,162 @@ impl<A: Allocate> Root<A> {
         // discard completed dataflows.
         self.dataflows.borrow_mut().retain(|dataflow| dataflow.active());
 
+        // invoke epoch hooks, retaining those that remain live and any registered meanwhile.
+        let polls = ::std::mem::replace(&mut *self.epoch_hooks.borrow_mut(), Vec::new());
+        let mut retained = polls.into_iter().filter_map(|mut poll| if poll() { Some(poll) } else { None }).collect::<Vec<_>>();
+        retained.extend(self.epoch_hooks.borrow_mut().drain(..));
+        *self.epoch_hooks.borrow_mut() = retained;
+
         // TODO(andreal) do we want to flush logs here?
 
         self.allocator.borrow_mut().post_work();
 
         active
     }
-    /// Calls `self.step()` as long as `func` evaluates to true.
+    /// Calls `self.step()` as long as `func` evaluates to true, or until a peer worker has failed.
     pub fn step_while<F: FnMut()->bool>(&mut self, mut func: F) {
-        while func() { self.step(); }
+        while !self.peer_failed() && func() { self.step(); }
+    }
+
+    /// Returns true iff another worker of the computation, in this or another process, has failed.
+    ///
+    /// Loops that step the worker until some condition holds should also stop once this is true, as
+    /// the failed worker may never contribute what the condition awaits.
+    pub fn peer_failed(&self) -> bool { ::communication::peer_failed() }
+
+    /// Steps the worker until no probe of `probes` has a frontier strictly less than `time`.
+    ///
+    /// See `MultiProbe` for an example.
+    pub fn step_until<T: Timestamp>(&mut self, probes: &MultiProbe<T>, time: &T) {
+        self.step_while(|| probes.less_than(time));
+    }
+
+    /// Steps the worker while `probe` lags more than `lag` behind `epoch`.
+    ///
+    /// The probe lags behind `epoch` if some element of its frontier, advanced by `lag`, is not
+    /// greater or equal to `epoch`. With `lag` equal to `Default::default()` this is the familiar
+    /// `step_while(|| probe.less_than(input.time()))` loop, which prevents input from racing ahead
+    /// of the computation. Larger values of `lag` permit that many epochs to be in flight at once,
+    /// trading latency for throughput.
+    ///
+    /// An element of the frontier that `lag` would advance beyond the greatest representable time
+    /// does not lag: the advanced time would exceed every time, `epoch` included. Treating it as
+    /// lagging instead would step the worker until the element itself passed `epoch`, which near the
+    /// greatest time may never happen.
+    pub fn step_while_lagging<T: Timestamp>(&mut self, probe: &ProbeHandle<Product<RootTimestamp, T>>, epoch: &T, lag: &T::Summary) {
+        self.step_while(|| probe.with_frontier(|frontier| {
+            frontier.iter().any(|time| {
+                lag.results_in(&time.inner)
+                   .map(|bound| !epoch.less_equal(&bound))
+                   // an overflowing bound exceeds `epoch`, and so does not lag.
+                   .unwrap_or(false)
+            })
+        }));
+    }
+
+    /// Advances `input` to `next`, and then steps the worker until `probe` is within `lag` of `next`.
+    ///
+    /// This encapsulates the throttled ingestion loop: no more than `lag` epochs of input may be
+    /// outstanding at `probe` when this method returns, which bounds the amount of buffered work.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::operators::{Input, Probe, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Thread, |worker| {
+    ///
+    ///     let (mut input, probe) = worker.dataflow(|scope| {
+    ///         let (input, stream) = scope.new_input();
+    ///         let probe = stream.inspect(|x: &u64| println!("hello {:?}", x))
+    ///                           .probe();
+    ///         (input, probe)
+    ///     });
+    ///
+    ///     // allow at most two rounds to be outstanding.
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         worker.advance_input(&mut input, round + 1, &probe, &2);
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn advance_input<T: Timestamp, D: ::Data>(&mut self, input: &mut InputHandle<T, D>, next: T, probe: &ProbeHandle<Product<RootTimestamp, T>>, lag: &T::Summary) {
+        input.advance_to(next);
+        self.step_while_lagging(probe, input.epoch(), lag);
     }
 
     /// The index of the worker out of its peers.
     pub fn index(&self) -> usize { self.allocator.borrow().index() }
     /// The total number of peer workers.
     pub fn peers(&self) -> usize { self.allocator.borrow().peers() }
+    /// The position of the worker among its process and the computation.
+    ///
+    /// #Examples
+    /// ```
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     let identity = worker.identity();
+    ///     assert_eq!(identity.index, worker.index());
+    ///     assert_eq!(identity.threads, 2);
+    ///     println!("{} started", identity);
+    /// }).unwrap();
+    /// ```
+    pub fn identity(&self) -> WorkerIdentity { self.allocator.borrow().identity() }
+
+    /// Application-specific configuration, shared by the workers of this process.
+    pub fn config(&self) -> &WorkerConfig { &self.config }
+
+    /// Registers `value` as the worker's resource of type `T`, returning any previous such resource.
+    ///
+    /// The resource is available to this worker and to each of its scopes, through `resources()`.
+    pub fn insert_resource<T: 'static>(&mut self, value: T) -> Option<Rc<T>> { self.resources.insert(value) }
+    /// The worker's resource of type `T`, if any.
+    pub fn get_resource<T: 'static>(&self) -> Option<Rc<T>> { self.resources.get::<T>() }
+
+    /// The registry of channels connected by this worker.
+    ///
+    /// The registry maps the identifiers of channels, and of the communication channels allocated
+    /// for them, to the dataflow edges they implement. If the registry has been removed from the
+    /// worker's resources, a new empty registry is registered and returned.
+    pub fn channels(&self) -> Rc<ChannelRegistry> {
+        match self.resources.get::<ChannelRegistry>() {
+            Some(registry) => registry,
+            None => {
+                self.resources.insert(ChannelRegistry::new());
+                self.resources.get::<ChannelRegistry>().expect("registry just inserted")
+            }
+        }
+    }
+
+    /// Subscribes to the completion of times by the dataflow observed by `snooper`.
+    ///
+    /// The returned `EpochHooks` is polled after each step of the worker, invoking its hooks as
+    /// their times complete. Within a dataflow, `Child::epoch_hooks` subscribes to that dataflow.
+    pub fn epoch_hooks<T: Timestamp>(&self, snooper: Snooper<Product<RootTimestamp, T>>) -> EpochHooks<T> {
+        let hooks = EpochHooks::new(snooper);
+        let polled = hooks.clone();
+        self.epoch_hooks.borrow_mut().push(Box::new(move || polled.poll()));
+        hooks
+    }
+
+    /// Sets how the dataflows constructed subsequently exchange progress updates.
+    ///
+    /// The mode is initially read from the worker's configuration, as described by
+    /// `ProgressMode::from_config`. All workers must use the same mode for each dataflow.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::progress::broadcast::ProgressMode;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Process(4), |worker| {
+    ///     // aggregate progress updates within pairs of workers.
+    ///     worker.set_progress_mode(ProgressMode::Hierarchical(2));
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    pub fn set_progress_mode(&mut self, mode: ProgressMode) { self.progress_mode.set(mode); }
 
     /// Construct a new dataflow.
     pub fn dataflow<T: Timestamp, R, F:FnOnce(&mut Child<Self, T>)->R>(&mut self, func: F) -> R {
@@ -85,7 +276,12 @@ impl<A: Allocate> Root<A> {
         let logging = (self.logging)(::logging::TimelySetup {
             index: self.index(),
         });
-        let subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone());
+        logging.when_enabled(|l| l.log(::logging::TimelyEvent::Clock(::logging::ClockEvent::now())));
+        let mut subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone());
+        subscope.set_progress_mode(self.progress_mode.get());
+        let unfinished = Rc::new(Cell::new(UnfinishedPolicy::from_config(&self.config)));
+        subscope.set_unfinished(unfinished.clone());
+        subscope.set_budget(self.config.parse::<u64>("operator-budget-us").and_then(|budget| budget.ok()).map(Duration::from_micros));
         let subscope = RefCell::new(subscope);
 
         let result = {
@@ -105,7 +301,8 @@ impl<A: Allocate> Root<A> {
         operator.set_external_summary(Vec::new(), &mut []);
 
         let wrapper = Wrapper {
-            _index: dataflow_index,
+            index: dataflow_index,
+            unfinished,
             operate: Some(Box::new(operator)),
             resources: Some(Box::new(resources)),
         };
@@ -115,6 +312,63 @@ impl<A: Allocate> Root<A> {
 
     }
 
+    /// The indices of the dataflows this worker has not yet completed or cancelled, in order of construction.
+    pub fn dataflow_indices(&self) -> Vec<usize> {
+        self.dataflows.borrow().iter().map(|dataflow| dataflow.index).collect()
+    }
+
+    /// Cancels the dataflow with index `index`, returning false if there is no such active dataflow.
+    ///
+    /// The dataflow's operators are dropped immediately, rather than once its frontiers complete: they
+    /// are no longer scheduled, its progress state is discarded, and messages in flight to it or sent
+    /// to it subsequently are dropped rather than delivered. Its resources are released after its
+    /// operators. The index of a dataflow is reported by `Child::dataflow_index` as it is constructed.
+    ///
+    /// A dataflow exchanging data between workers must be cancelled by all of them, as workers that
+    /// continue it will wait indefinitely for progress from those that have cancelled it.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     let mut input = InputHandle::new();
+    ///     let index = worker.dataflow::<u64,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x: &u64| *x)
+    ///              .inspect(|x| println!("seen: {:?}", x));
+    ///         scope.dataflow_index()
+    ///     });
+    ///     input.send(0);
+    ///     input.advance_to(1);
+    ///     worker.step();
+    ///
+    ///     // the input remains open, but the dataflow is abandoned.
+    ///     assert!(worker.cancel_dataflow(index));
+    ///     assert!(worker.dataflow_indices().is_empty());
+    ///     input.send(1);
+    ///     assert!(!worker.step());
+    /// }).unwrap();
+    /// ```
+    pub fn cancel_dataflow(&mut self, index: usize) -> bool {
+        // remove the dataflow before dropping it, as its drop may inspect the worker's dataflows.
+        let cancelled = {
+            let mut dataflows = self.dataflows.borrow_mut();
+            let position = dataflows.iter().position(|dataflow| dataflow.index == index);
+            position.map(|position| dataflows.remove(position))
+        };
+        // a cancelled dataflow is deliberately unfinished, and peers may still send to its channels.
+        if let Some(dataflow) = cancelled {
+            dataflow.unfinished.set(UnfinishedPolicy::Ignore);
+            ::communication::allocator::process::cancelling(|| drop(dataflow));
+            true
+        }
+        else {
+            false
+        }
+    }
+
     // sane way to get new dataflow identifiers; used to be self.dataflows.len(). =/
     fn allocate_dataflow_index(&mut self) -> usize {
         *self.dataflow_counter.borrow_mut() += 1;
@@ -129,6 +383,12 @@ impl<A: Allocate> ScopeParent for Root<A> {
         *self.identifiers.borrow_mut() += 1;
         *self.identifiers.borrow() - 1
     }
+
+    fn timer(&self) -> Timer { self.timer.clone() }
+
+    fn resources(&self) -> Resources { self.resources.clone() }
+
+    fn progress_mode(&self) -> ProgressMode { self.progress_mode.get() }
 }
 
 use communication::Message;
@@ -136,8 +396,13 @@ use communication::Message;
 impl<A: Allocate> Allocate for Root<A> {
     fn index(&self) -> usize { self.allocator.borrow().index() }
     fn peers(&self) -> usize { self.allocator.borrow().peers() }
+    fn identity(&self) -> WorkerIdentity { self.allocator.borrow().identity() }
     fn allocate<D: Data>(&mut self) -> (Vec<Box<Push<Message<D>>>>, Box<Pull<Message<D>>>, Option<usize>) {
-        self.allocator.borrow_mut().allocate()
+        let (pushers, puller, comm_channel) = self.allocator.borrow_mut().allocate();
+        if let Some(registry) = self.resources.get::<ChannelRegistry>() {
+            registry.note_allocation(comm_channel);
+        }
+        (pushers, puller, comm_channel)
     }
 }
 
@@ -149,12 +414,18 @@ impl<A: Allocate> Clone for Root<A> {
             dataflows: self.dataflows.clone(),
             dataflow_counter: self.dataflow_counter.clone(),
             logging: self.logging.clone(),
+            timer: self.timer.clone(),
+            config: self.config.clone(),
+            resources: self.resources.clone(),
+            progress_mode: self.progress_mode.clone(),
+            epoch_hooks: self.epoch_hooks.clone(),
         }
     }
 }
 
 struct Wrapper {
-    _index: usize,
+    index: usize,
+    unfinished: Rc<Cell<UnfinishedPolicy>>,
     operate: Option<Box<Operate<RootTimestamp>>>,
     resources: Option<Box<Any>>,
 }
@@ -174,7 +445,7 @@ impl Wrapper {
 
 impl Drop for Wrapper {
     fn drop(&mut self) {
-        // println!("dropping dataflow {:?}", self._index);
+        // println!("dropping dataflow {:?}", self.index);
         // ensure drop order
         self.operate = None;
         self.resources = None;
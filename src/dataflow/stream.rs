@@ -10,6 +10,7 @@ use communication::Push;
 use dataflow::Scope;
 use dataflow::channels::pushers::tee::TeeHelper;
 use dataflow::channels::Bundle;
+use dataflow::channels::registry::{ChannelRecord, ChannelRegistry};
 
 // use dataflow::scopes::root::loggers::CHANNELS_Q;
 
@@ -33,15 +34,38 @@ impl<S: Scope, D> Stream<S, D> {
     /// The destination is described both by a `Target`, for progress tracking information, and a `P: Push` where the
     /// records should actually be sent. The identifier is unique to the edge and is used only for logging purposes.
     pub fn connect_to<P: Push<Bundle<S::Timestamp, D>>+'static>(&self, target: Target, pusher: P, identifier: usize) {
+        self.connect_to_via(target, pusher, identifier, "Direct", None);
+    }
 
-        let logging = self.scope().logging();
-        logging.when_enabled(|l| l.log(::logging::TimelyEvent::Channels(::logging::ChannelsEvent {
+    /// Connects the stream to a destination, through a named parallelization contract.
+    ///
+    /// As `connect_to`, but also records the name of the contract and the communication channel it
+    /// allocated, if any, in the worker's `ChannelRegistry` and in the logged `ChannelsEvent`.
+    pub fn connect_to_via<P: Push<Bundle<S::Timestamp, D>>+'static>(&self, target: Target, pusher: P, identifier: usize, pact: &str, comm_channel: Option<usize>) {
+
+        let record = ChannelRecord {
             id: identifier,
+            comm_channel,
             scope_addr: self.scope.addr(),
             source: (self.name.index, self.name.port),
             target: (target.index, target.port),
+            pact: pact.to_owned(),
+        };
+
+        let logging = self.scope().logging();
+        logging.when_enabled(|l| l.log(::logging::TimelyEvent::Channels(::logging::ChannelsEvent {
+            id: record.id,
+            scope_addr: record.scope_addr.clone(),
+            source: record.source,
+            target: record.target,
+            comm_channel: record.comm_channel,
+            pact: record.pact.clone(),
         })));
 
+        if let Some(registry) = self.scope.resources().get::<ChannelRegistry>() {
+            registry.register(record);
+        }
+
         self.scope.add_edge(self.name, target);
         self.ports.add_pusher(pusher);
     }
@@ -53,4 +77,6 @@ impl<S: Scope, D> Stream<S, D> {
     pub fn name(&self) -> &Source { &self.name }
     /// The scope immediately containing the stream.
     pub fn scope(&self) -> S { self.scope.clone() }
+    /// The rendezvous point at which recipients of the stream's records are registered.
+    pub fn ports(&self) -> &TeeHelper<S::Timestamp, D> { &self.ports }
 }
@@ -1,8 +1,10 @@
 //! Starts a timely dataflow execution from configuration information and per-worker logic.
 
-use communication::{initialize, Configuration, Allocator, WorkerGuards};
+use communication::{initialize, initialize_scoped, Configuration, Allocator, WorkerGuards};
+use communication::allocator::Thread;
 use dataflow::scopes::{Root, Child};
 use logging::LoggerConfig;
+use config::WorkerConfig;
 
 /// Executes a single-threaded timely dataflow computation.
 ///
@@ -66,6 +68,40 @@ where T: Send+'static,
           .expect("Unable to retrieve result!")
 }
 
+/// Constructs a single worker on the current thread, which the caller drives by calling `step`.
+///
+/// Unlike `execute` with `Configuration::Thread`, this method spawns no threads, and so it suits
+/// platforms without threads, such as `wasm32-unknown-unknown`, and hosts that must interleave the
+/// computation with other work, such as an event loop calling `step` from a callback. Logging is
+/// disabled, and a panic in the computation propagates to the caller of `step`.
+///
+/// #Examples
+/// ```rust
+/// use timely::dataflow::operators::{Input, Inspect, Probe};
+///
+/// let mut worker = timely::thread_worker();
+///
+/// let (mut input, probe) = worker.dataflow(|scope| {
+///     let (input, stream) = scope.new_input();
+///     let probe = stream.inspect(|x: &u64| println!("seen: {:?}", x))
+///                       .probe();
+///     (input, probe)
+/// });
+///
+/// // the host decides when the computation runs.
+/// for round in 0..10 {
+///     input.send(round);
+///     input.advance_to(round + 1);
+///     while probe.less_than(input.time()) {
+///         worker.step();
+///     }
+/// }
+/// ```
+pub fn thread_worker() -> Root<Allocator> {
+    let logging_config: LoggerConfig = Default::default();
+    Root::new(Allocator::Thread(Thread), logging_config.timely_logging)
+}
+
 /// Executes a timely dataflow from a configuration and per-communicator logic.
 ///
 /// The `execute` method takes a `Configuration` and spins up some number of
@@ -144,10 +180,154 @@ where T:Send+'static,
 /// }).unwrap();
 /// ```
 pub fn execute_logging<T, F>(config: Configuration, logging_config: LoggerConfig, func: F) -> Result<WorkerGuards<T>,String>
+where T:Send+'static,
+      F: Fn(&mut Root<Allocator>)->T+Send+Sync+'static {
+    execute_configured_logging(config, WorkerConfig::new(), logging_config, func)
+}
+
+/// Executes a timely dataflow from a configuration and per-communicator logic, exposing metrics.
+///
+/// The workers' operator and channel metrics are served in the Prometheus text format over HTTP at
+/// `addr`, one endpoint for the process, which serves for the life of the process. Use `Metrics::serve`
+/// and `execute_logging` directly to shut the endpoint down. Refer to [`execute`](fn.execute.html)
+/// for more details.
+///
+/// This function is only available with the `prometheus` feature.
+///
+/// ```rust
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// // serve metrics on an arbitrary port of the local host.
+/// timely::execute_with_metrics(timely::Configuration::Process(3), "127.0.0.1:0", |worker| {
+///     worker.dataflow::<(),_,_>(|scope| {
+///         (0..10).to_stream(scope)
+///                .inspect(|x| println!("seen: {:?}", x));
+///     })
+/// }).unwrap();
+/// ```
+#[cfg(feature = "prometheus")]
+pub fn execute_with_metrics<A, T, F>(config: Configuration, addr: A, func: F) -> Result<WorkerGuards<T>,String>
+where A: ::std::net::ToSocketAddrs,
+      T:Send+'static,
+      F: Fn(&mut Root<Allocator>)->T+Send+Sync+'static {
+    let metrics = ::metrics::Metrics::new();
+    try!(metrics.serve(addr).map_err(|error| format!("failed to serve metrics: {}", error)));
+    execute_logging(config, metrics.logger_config(), func)
+}
+
+/// Executes a timely dataflow from a configuration, application-specific configuration, and
+/// per-communicator logic.
+///
+/// Refer to [`execute`](fn.execute.html) for more details. This function additionally makes
+/// `worker_config` available to each worker through `worker.config()`.
+///
+/// #Examples
+/// ```rust
+/// use timely::config::WorkerConfig;
+///
+/// let config = WorkerConfig::new().set("greeting", "hello");
+///
+/// timely::execute_configured(timely::Configuration::Process(2), config, |worker| {
+///     println!("{}, worker {}", worker.config().get("greeting").unwrap(), worker.index());
+/// }).unwrap();
+/// ```
+pub fn execute_configured<T, F>(config: Configuration, worker_config: WorkerConfig, func: F) -> Result<WorkerGuards<T>,String>
+where T:Send+'static,
+      F: Fn(&mut Root<Allocator>)->T+Send+Sync+'static {
+    execute_configured_logging(config, worker_config, LoggerConfig::default_with_env(), func)
+}
+
+/// Executes a timely dataflow from a configuration, application-specific configuration, logging
+/// configuration, and per-communicator logic.
+///
+/// Refer to [`execute_configured`](fn.execute_configured.html) for more details.
+pub fn execute_configured_logging<T, F>(config: Configuration, worker_config: WorkerConfig, logging_config: LoggerConfig, func: F) -> Result<WorkerGuards<T>,String>
 where T:Send+'static,
       F: Fn(&mut Root<Allocator>)->T+Send+Sync+'static {
     let timely_logging = logging_config.timely_logging.clone();
     initialize(config, logging_config.communication_logging.clone(), move |allocator| {
+        let mut root = Root::new_with_config(allocator, timely_logging.clone(), worker_config.clone());
+        let result = func(&mut root);
+        while root.step() { }
+        result
+    })
+}
+
+/// Executes a timely dataflow, constructing thread-local resources for each worker before its logic.
+///
+/// The closure `build` runs on each worker thread, and constructs resources that need not be `Send`,
+/// for example `Rc`-based caches or handles to foreign libraries bound to a thread. Its result is
+/// moved into `func`, the logic of the worker, on the same thread. Only the closures themselves
+/// must be `Send+Sync`, as they are shared among the worker threads.
+///
+/// Refer to [`execute`](fn.execute.html) for more details.
+///
+/// #Examples
+/// ```rust
+/// use std::rc::Rc;
+/// use std::cell::RefCell;
+/// use std::collections::HashMap;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// timely::execute_with(timely::Configuration::Process(2),
+///     // a per-worker cache, which could not be shared among workers.
+///     |_worker| Rc::new(RefCell::new(HashMap::<u64, u64>::new())),
+///     |worker, cache| {
+///         let index = worker.index();
+///         worker.dataflow::<(),_,_>(move |scope| {
+///             (0..10).to_stream(scope)
+///                    .inspect(move |x| { cache.borrow_mut().insert(*x, *x * 2); })
+///                    .inspect(move |x| println!("worker {} cached: {:?}", index, x));
+///         })
+///     }
+/// ).unwrap();
+/// ```
+pub fn execute_with<T, L, B, F>(config: Configuration, build: B, func: F) -> Result<WorkerGuards<T>,String>
+where T:Send+'static,
+      B: Fn(&mut Root<Allocator>)->L+Send+Sync+'static,
+      F: Fn(&mut Root<Allocator>, L)->T+Send+Sync+'static {
+    execute(config, move |root| {
+        let local = build(root);
+        func(root, local)
+    })
+}
+
+/// Executes a timely dataflow from a configuration and per-communicator logic, borrowing from the caller.
+///
+/// Unlike `execute`, this method blocks until all local workers have completed, and returns their
+/// results directly. As the workers cannot outlive the call, the closure and its results may borrow
+/// from the caller's stack, for example to share read-only configuration or a large lookup table
+/// among workers without wrapping it in an `Arc`. A worker that panics is reported as an error.
+///
+/// Operator logic is retained by the dataflow and must still be `'static`; borrowed data may be used
+/// while constructing the dataflow and while driving it from the worker closure.
+///
+/// #Examples
+/// ```rust
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// // a lookup table shared by reference among workers.
+/// let names = vec!["zero", "one", "two", "three"];
+///
+/// let results = timely::execute_scoped(timely::Configuration::Process(3), |worker| {
+///     worker.dataflow::<(),_,_>(|scope| {
+///         names.iter()
+///              .map(|name| name.len())
+///              .collect::<Vec<_>>()
+///              .to_stream(scope)
+///              .inspect(|x| println!("seen: {:?}", x));
+///     });
+///     names[worker.index()]
+/// }).unwrap();
+///
+/// assert_eq!(results.into_iter().map(|x| x.unwrap()).collect::<Vec<_>>(), vec!["zero", "one", "two"]);
+/// ```
+pub fn execute_scoped<'a, T, F>(config: Configuration, func: F) -> Result<Vec<Result<T,String>>,String>
+where T:Send+'a,
+      F: Fn(&mut Root<Allocator>)->T+Send+Sync+'a {
+    let logging_config = LoggerConfig::default_with_env();
+    let timely_logging = logging_config.timely_logging.clone();
+    initialize_scoped(config, logging_config.communication_logging.clone(), move |allocator| {
         let mut root = Root::new(allocator, timely_logging.clone());
         let result = func(&mut root);
         while root.step() { }
@@ -177,6 +357,12 @@ where T:Send+'static,
 /// If not specified, `localhost` will be used, with port numbers increasing from 2101 (chosen
 /// arbitrarily).
 ///
+/// `-o, --ordered`: within a single process, deliver the messages available to each worker in order
+/// of their source worker, for more reproducible executions.
+///
+/// Other arguments, and all arguments following `--`, are made available to workers through
+/// `worker.config()` as a [`WorkerConfig`](config/struct.WorkerConfig.html).
+///
 /// #Examples
 ///
 /// ```rust
@@ -203,6 +389,9 @@ where T:Send+'static,
 /// host2:port
 /// host3:port
 /// ```
+///
+/// This function requires the `arg_parse` feature.
+#[cfg(feature = "arg_parse")]
 pub fn execute_from_args<I, T, F>(iter: I, func: F) -> Result<WorkerGuards<T>,String>
     where I: Iterator<Item=String>,
           T:Send+'static,
@@ -232,10 +421,14 @@ pub fn execute_from_args<I, T, F>(iter: I, func: F) -> Result<WorkerGuards<T>,St
 ///     })
 /// }).unwrap();
 /// ```
+///
+/// This function requires the `arg_parse` feature.
+#[cfg(feature = "arg_parse")]
 pub fn execute_from_args_logging<I, T, F>(iter: I, logging_config: LoggerConfig, func: F) -> Result<WorkerGuards<T>,String>
     where I: Iterator<Item=String>,
           T:Send+'static,
           F: Fn(&mut Root<Allocator>)->T+Send+Sync+'static, {
-    execute_logging(try!(Configuration::from_args(iter)), logging_config, func)
+    let (config, free) = try!(Configuration::from_args_with_free(iter));
+    execute_configured_logging(config, WorkerConfig::from_args(free), logging_config, func)
 }
 
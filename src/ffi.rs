@@ -0,0 +1,296 @@
+//! A C-compatible interface for embedding timely dataflow in other languages.
+//!
+//! The interface offers a fixed menu of dataflow patterns over `u64` records and `u64` epochs. A
+//! `Pipeline` is a sequence of stages, each a map or a filter implemented by a C function pointer,
+//! or an exchange of records between workers. Starting a pipeline spawns workers that each run the
+//! pipeline, and returns a `Computation` into which records are fed, whose epochs are advanced, and
+//! from which the output records are polled, each with its epoch.
+//!
+//! Function pointers are called by the worker threads, with the context pointer supplied alongside
+//! them, and so they must be safe to call concurrently from several threads.
+//!
+//! This module is only available with the `ffi` feature, and a library for linking is obtained by
+//! building the crate as a `staticlib` or `cdylib`.
+//!
+//! #Examples
+//! ```
+//! use std::os::raw::c_void;
+//! use std::ptr;
+//! use timely::ffi::*;
+//!
+//! extern "C" fn double(x: u64, _context: *mut c_void) -> u64 { 2 * x }
+//! extern "C" fn small(x: u64, _context: *mut c_void) -> bool { x < 10 }
+//!
+//! unsafe {
+//!     let pipeline = timely_pipeline_new();
+//!     timely_pipeline_map(pipeline, double, ptr::null_mut());
+//!     timely_pipeline_exchange(pipeline);
+//!     timely_pipeline_filter(pipeline, small, ptr::null_mut());
+//!
+//!     let computation = timely_start(pipeline, 2);
+//!     timely_pipeline_free(pipeline);
+//!     assert!(!computation.is_null());
+//!
+//!     for round in 0 .. 10 {
+//!         timely_send(computation, round);
+//!     }
+//!     timely_advance(computation, 1);
+//!     while timely_frontier(computation) < 1 { }
+//!
+//!     let mut results = Vec::new();
+//!     let (mut time, mut record) = (0, 0);
+//!     while timely_poll(computation, &mut time, &mut record) {
+//!         results.push((time, record));
+//!     }
+//!     results.sort();
+//!     assert_eq!(results, vec![(0, 0), (0, 2), (0, 4), (0, 6), (0, 8)]);
+//!
+//!     assert!(timely_finish(computation));
+//! }
+//! ```
+
+use std::os::raw::c_void;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver, TryRecvError};
+
+use communication::WorkerGuards;
+use dataflow::{InputHandle, ProbeHandle};
+use dataflow::operators::{Input, Map, Filter, Exchange, Inspect, Probe};
+
+/// A C function mapping a record to a record, called with its context pointer.
+pub type MapFunction = extern "C" fn(u64, *mut c_void) -> u64;
+/// A C function reporting whether a record should be retained, called with its context pointer.
+pub type FilterFunction = extern "C" fn(u64, *mut c_void) -> bool;
+
+/// A function pointer and the context pointer it is called with.
+#[derive(Clone, Copy)]
+struct Callback<F> {
+    function: F,
+    context: *mut c_void,
+}
+
+// The caller promises that functions and their contexts may be used from any thread.
+unsafe impl<F> Send for Callback<F> { }
+unsafe impl<F> Sync for Callback<F> { }
+
+#[derive(Clone, Copy)]
+enum Stage {
+    Map(Callback<MapFunction>),
+    Filter(Callback<FilterFunction>),
+    Exchange,
+}
+
+/// A description of a dataflow, as a sequence of stages.
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+enum Command {
+    Send(u64),
+    Advance(u64),
+}
+
+/// A running computation.
+pub struct Computation {
+    commands: Vec<Sender<Command>>,
+    next: usize,
+    epoch: u64,
+    output: Receiver<(u64, u64)>,
+    frontiers: Arc<Vec<AtomicU64>>,
+    guards: WorkerGuards<()>,
+}
+
+/// Allocates a new empty pipeline, which must be freed with `timely_pipeline_free`.
+#[no_mangle]
+pub extern "C" fn timely_pipeline_new() -> *mut Pipeline {
+    Box::into_raw(Box::new(Pipeline { stages: Vec::new() }))
+}
+
+/// Appends a stage applying `function` to each record.
+///
+/// #Safety
+///
+/// `pipeline` must have been returned by `timely_pipeline_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_pipeline_map(pipeline: *mut Pipeline, function: MapFunction, context: *mut c_void) {
+    (*pipeline).stages.push(Stage::Map(Callback { function, context }));
+}
+
+/// Appends a stage retaining the records for which `function` returns true.
+///
+/// #Safety
+///
+/// `pipeline` must have been returned by `timely_pipeline_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_pipeline_filter(pipeline: *mut Pipeline, function: FilterFunction, context: *mut c_void) {
+    (*pipeline).stages.push(Stage::Filter(Callback { function, context }));
+}
+
+/// Appends a stage routing each record to the worker indicated by its value.
+///
+/// #Safety
+///
+/// `pipeline` must have been returned by `timely_pipeline_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_pipeline_exchange(pipeline: *mut Pipeline) {
+    (*pipeline).stages.push(Stage::Exchange);
+}
+
+/// Frees a pipeline. Computations started from the pipeline are unaffected.
+///
+/// #Safety
+///
+/// `pipeline` must have been returned by `timely_pipeline_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_pipeline_free(pipeline: *mut Pipeline) {
+    drop(Box::from_raw(pipeline));
+}
+
+/// Starts `workers` worker threads running `pipeline`, returning null if they could not be started.
+///
+/// The computation must be finished with `timely_finish`.
+///
+/// #Safety
+///
+/// `pipeline` must have been returned by `timely_pipeline_new` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_start(pipeline: *const Pipeline, workers: usize) -> *mut Computation {
+
+    let stages = (*pipeline).stages.clone();
+    let workers = if workers == 0 { 1 } else { workers };
+
+    let mut commands = Vec::new();
+    let mut receivers = Vec::new();
+    for _ in 0 .. workers {
+        let (send, recv) = channel();
+        commands.push(send);
+        receivers.push(Mutex::new(Some(recv)));
+    }
+    let (output_send, output) = channel();
+    let output_send = Mutex::new(output_send);
+    let frontiers = Arc::new((0 .. workers).map(|_| AtomicU64::new(0)).collect::<Vec<_>>());
+    let worker_frontiers = frontiers.clone();
+
+    let guards = ::execute(::Configuration::Process(workers), move |worker| {
+
+        let index = worker.index();
+        let commands = receivers[index].lock().unwrap().take().expect("worker started twice");
+        let output = output_send.lock().unwrap().clone();
+
+        let mut input = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        worker.dataflow(|scope| {
+            let mut stream = scope.input_from(&mut input);
+            for stage in stages.iter() {
+                stream = match *stage {
+                    Stage::Map(callback) => stream.map(move |x| (callback.function)(x, callback.context)),
+                    Stage::Filter(callback) => stream.filter(move |x| (callback.function)(*x, callback.context)),
+                    Stage::Exchange => stream.exchange(|x| *x),
+                };
+            }
+            stream.inspect_batch(move |time, data| {
+                for &record in data.iter() {
+                    // the receiver may have been dropped; there is nobody to report to.
+                    let _ = output.send((time.inner, record));
+                }
+            })
+            .probe_with(&mut probe);
+        });
+
+        let mut input = Some(input);
+        while !probe.done() {
+            loop {
+                match commands.try_recv() {
+                    Ok(Command::Send(record)) => { if let Some(ref mut input) = input { input.send(record); } },
+                    Ok(Command::Advance(epoch)) => { if let Some(ref mut input) = input { input.advance_to(epoch); } },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => { input = None; break; },
+                }
+            }
+            worker.step();
+            let frontier = probe.with_frontier(|frontier| frontier.iter().map(|time| time.inner).min());
+            worker_frontiers[index].store(frontier.unwrap_or(u64::max_value()), Ordering::SeqCst);
+        }
+    });
+
+    match guards {
+        Ok(guards) => Box::into_raw(Box::new(Computation { commands, next: 0, epoch: 0, output, frontiers, guards })),
+        Err(_) => ::std::ptr::null_mut(),
+    }
+}
+
+/// Introduces `record` at the current epoch, at some worker.
+///
+/// #Safety
+///
+/// `computation` must have been returned by `timely_start` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn timely_send(computation: *mut Computation, record: u64) {
+    let computation = &mut *computation;
+    let index = computation.next % computation.commands.len();
+    computation.next += 1;
+    let _ = computation.commands[index].send(Command::Send(record));
+}
+
+/// Advances the current epoch to `epoch`, if it is greater than the current epoch.
+///
+/// #Safety
+///
+/// `computation` must have been returned by `timely_start` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn timely_advance(computation: *mut Computation, epoch: u64) {
+    let computation = &mut *computation;
+    if epoch > computation.epoch {
+        computation.epoch = epoch;
+        for commands in computation.commands.iter() {
+            let _ = commands.send(Command::Advance(epoch));
+        }
+    }
+}
+
+/// Reports the least epoch at which output may still be produced, or `u64::MAX` if none may be.
+///
+/// All output at earlier epochs may be polled once the frontier has passed them.
+///
+/// #Safety
+///
+/// `computation` must have been returned by `timely_start` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn timely_frontier(computation: *mut Computation) -> u64 {
+    (*computation).frontiers.iter().map(|frontier| frontier.load(Ordering::SeqCst)).min().unwrap_or(u64::max_value())
+}
+
+/// Polls for an output record, writing it and its epoch and returning true if one is available.
+///
+/// #Safety
+///
+/// `computation` must have been returned by `timely_start` and not yet finished, and `time` and
+/// `record` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn timely_poll(computation: *mut Computation, time: *mut u64, record: *mut u64) -> bool {
+    match (*computation).output.try_recv() {
+        Ok((t, r)) => {
+            *time = t;
+            *record = r;
+            true
+        },
+        Err(_) => false,
+    }
+}
+
+/// Closes the input, waits for the workers to complete, and frees the computation.
+///
+/// Output not yet polled is discarded. Returns true if no worker failed.
+///
+/// #Safety
+///
+/// `computation` must have been returned by `timely_start` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn timely_finish(computation: *mut Computation) -> bool {
+    let Computation { commands, output, guards, .. } = *Box::from_raw(computation);
+    // closes the input of each worker.
+    drop(commands);
+    drop(output);
+    guards.join().into_iter().all(|result| result.is_ok())
+}
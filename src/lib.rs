@@ -57,14 +57,26 @@
 
 #![forbid(missing_docs)]
 
-extern crate abomonation;
+/// Re-export of the `abomonation` crate, named by derived `Abomonation` implementations.
+pub extern crate abomonation;
 #[macro_use] extern crate abomonation_derive;
 extern crate timely_communication;
 extern crate time;
 extern crate bytes;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "backtrace")]
+extern crate backtrace;
 
-pub use execute::{execute, execute_logging, execute_from_args, execute_from_args_logging, example};
+pub use execute::{execute, execute_logging, execute_configured, execute_configured_logging, execute_scoped, execute_with};
+pub use execute::{example, thread_worker};
+#[cfg(feature = "arg_parse")]
+pub use execute::{execute_from_args, execute_from_args_logging};
+#[cfg(feature = "prometheus")]
+pub use execute::execute_with_metrics;
 pub use order::PartialOrder;
+pub use abomonation::Abomonation;
+pub use abomonation_derive::Abomonation;
 
 pub use timely_communication::Configuration;
 
@@ -75,11 +87,21 @@ pub mod communication {
 
 pub mod progress;
 pub mod dataflow;
+pub mod algorithms;
 pub mod synchronization;
 pub mod execute;
 pub mod order;
 
 pub mod logging;
+pub mod timer;
+pub mod config;
+pub mod resources;
+pub mod serialization;
+pub mod metrics;
+#[cfg(feature = "tracing")]
+pub mod tracer;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 /// A composite trait for types usable as data in timely dataflow.
 ///
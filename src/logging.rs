@@ -3,6 +3,9 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::sync::Arc;
+use std::hash::Hash;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ::progress::change_batch::ChangeBatch;
 use ::progress::timestamp::RootTimestamp;
@@ -53,9 +56,39 @@ impl LoggerConfig {
         }
     }
 
+    /// Makes a new `LoggerConfig` logging timely events to `timely_subscription`, with communication
+    /// logging disabled.
+    pub fn timely_only<P: 'static, F: 'static>(timely_subscription: F) -> Self where
+        P: EventPusher<Product<RootTimestamp, u64>, LogMessage> + Send,
+        F: Fn(TimelySetup)->P+Send+Sync {
+
+        LoggerConfig {
+            timely_logging: Arc::new(move |events_setup: TimelySetup| {
+                let logger = RefCell::new(BatchLogger::new((timely_subscription)(events_setup)));
+                Rc::new(BufferingLogger::new(events_setup, Box::new(move |data| logger.borrow_mut().publish_batch(data))))
+            }),
+            communication_logging: Arc::new(|_setup| BufferingLogger::new_inactive()),
+        }
+    }
+
     /// Sets up the default loggers: disabled, unless either
     /// TIMELY_WORKER_LOG_ADDR or TIMELY_COMM_LOG_ADDR are environment
     /// variables set to the log destinations.
+    ///
+    /// Publishing logs to these destinations requires the `networking` feature; without it,
+    /// the loggers are always disabled.
+    #[cfg(not(feature = "networking"))]
+    pub fn default_with_env() -> LoggerConfig {
+        Default::default()
+    }
+
+    /// Sets up the default loggers: disabled, unless either
+    /// TIMELY_WORKER_LOG_ADDR or TIMELY_COMM_LOG_ADDR are environment
+    /// variables set to the log destinations.
+    ///
+    /// Publishing logs to these destinations requires the `networking` feature; without it,
+    /// the loggers are always disabled.
+    #[cfg(feature = "networking")]
     pub fn default_with_env() -> LoggerConfig {
 
         use std::sync::{Arc, Mutex};
@@ -235,6 +268,10 @@ pub struct ChannelsEvent {
     pub source: (usize, usize),
     /// Target descriptor, indicating operator index and input port.
     pub target: (usize, usize),
+    /// Communication channel identifier, for channels allocated by the communication layer.
+    pub comm_channel: Option<usize>,
+    /// The name of the channel's parallelization contract.
+    pub pact: String,
 }
 
 #[derive(Abomonation, Debug, Clone)]
@@ -360,6 +397,150 @@ pub struct InputEvent {
     pub start_stop: StartStop,
 }
 
+#[derive(Abomonation, Debug, Clone, Copy, PartialEq, Eq)]
+/// The correspondence between log timestamps and wall-clock time.
+///
+/// Log timestamps are read from a high resolution counter, rebased once per process to be roughly
+/// comparable to a unix timestamp. The rebasing is only as accurate as the wall clock at that moment,
+/// and the counter drifts from the wall clock thereafter, so timestamps from different processes need
+/// not agree. Each worker logs a `ClockEvent` when it constructs a dataflow, which relates the two.
+pub struct ClockEvent {
+    /// The log timestamp, in nanoseconds, at which the wall clock was read.
+    pub log_ns: u64,
+    /// The wall-clock time, in nanoseconds since the unix epoch.
+    pub wall_ns: u64,
+}
+
+impl ClockEvent {
+    /// Reads the log clock and the wall clock.
+    pub fn now() -> Self {
+        let wall = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+        ClockEvent {
+            log_ns: ::communication::logging::get_precise_time_ns(),
+            wall_ns: wall.as_secs() * 1_000_000_000 + wall.subsec_nanos() as u64,
+        }
+    }
+
+    /// The wall-clock time, in nanoseconds since the unix epoch, corresponding to log timestamp `ts`.
+    pub fn to_wall_ns(&self, ts: u64) -> u64 {
+        (ts as i64 + (self.wall_ns as i64 - self.log_ns as i64)) as u64
+    }
+
+    /// The wall-clock time corresponding to log timestamp `ts`.
+    pub fn to_system_time(&self, ts: u64) -> SystemTime {
+        let wall_ns = self.to_wall_ns(ts);
+        UNIX_EPOCH + Duration::new(wall_ns / 1_000_000_000, (wall_ns % 1_000_000_000) as u32)
+    }
+}
+
+#[derive(Abomonation, Debug, Clone)]
+/// Latencies of stamped records observed by a batch at a sink.
+pub struct LatencyEvent {
+    /// The name of the measurement.
+    pub name: String,
+    /// The number of stamped records observed.
+    pub count: u64,
+    /// The sum of their latencies, in nanoseconds.
+    pub sum_ns: u64,
+    /// The greatest of their latencies, in nanoseconds.
+    pub max_ns: u64,
+}
+
+#[derive(Abomonation, Debug, Clone)]
+/// A skewed distribution of records among the destinations of an exchange channel at a timestamp.
+pub struct SkewEvent {
+    /// The channel identifier.
+    pub channel: usize,
+    /// The number of records the worker sent on the channel at the timestamp.
+    pub records: u64,
+    /// The ratio of the records sent to the busiest destination to the mean sent to each destination.
+    pub ratio: f64,
+    /// The busiest destinations and the records sent to each, busiest first.
+    pub top: Vec<(usize, u64)>,
+}
+
+#[derive(Abomonation, Debug, Clone)]
+/// An operator invocation that took longer than the operator's execution-time budget.
+pub struct BudgetEvent {
+    /// Worker-unique identifier for the operator, linkable to the identifiers in `OperatesEvent`.
+    pub id: usize,
+    /// The address of the operator.
+    pub addr: Vec<usize>,
+    /// The name of the operator.
+    pub name: String,
+    /// The duration of the invocation, in nanoseconds.
+    pub elapsed_ns: u64,
+    /// The budget of the operator, in nanoseconds.
+    pub budget_ns: u64,
+}
+
+/// Aligns log streams from several clock domains onto a common wall-clock timeline.
+///
+/// A clock domain is identified by the setup of its log stream, for example a `TimelySetup` or a
+/// `CommsSetup`. Observing a `ClockEvent` for a domain records the relation between its log
+/// timestamps and wall-clock time; the most recent observation is used, so that drift is corrected
+/// as new observations arrive.
+///
+/// #Examples
+/// ```
+/// use timely::logging::{ClockDomains, ClockEvent, TimelySetup};
+///
+/// let mut domains = ClockDomains::new();
+/// let worker0 = TimelySetup { index: 0 };
+/// let worker1 = TimelySetup { index: 1 };
+///
+/// // the log clock of worker 1 runs 500ns ahead of that of worker 0.
+/// domains.observe(worker0, ClockEvent { log_ns: 1_000, wall_ns: 2_000 });
+/// domains.observe(worker1, ClockEvent { log_ns: 1_500, wall_ns: 2_000 });
+///
+/// assert_eq!(domains.align(&worker0, 1_100), Some(2_100));
+/// assert_eq!(domains.align(&worker1, 1_600), Some(2_100));
+/// assert_eq!(domains.align(&TimelySetup { index: 2 }, 0), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClockDomains<S: Hash+Eq> {
+    clocks: HashMap<S, ClockEvent>,
+}
+
+impl<S: Hash+Eq> ClockDomains<S> {
+    /// Allocates a new set of clock domains, with no observations.
+    pub fn new() -> Self {
+        ClockDomains { clocks: HashMap::new() }
+    }
+
+    /// Records the clock of the domain `setup`.
+    pub fn observe(&mut self, setup: S, clock: ClockEvent) {
+        self.clocks.insert(setup, clock);
+    }
+
+    /// The clock most recently observed for `setup`, if any.
+    pub fn clock(&self, setup: &S) -> Option<&ClockEvent> {
+        self.clocks.get(setup)
+    }
+
+    /// The wall-clock time, in nanoseconds since the unix epoch, of log timestamp `ts` of `setup`.
+    ///
+    /// Returns `None` if no clock has been observed for `setup`.
+    pub fn align(&self, setup: &S, ts: u64) -> Option<u64> {
+        self.clocks.get(setup).map(|clock| clock.to_wall_ns(ts))
+    }
+
+    /// Rewrites the timestamps of `logs` onto the common wall-clock timeline.
+    ///
+    /// Records of domains without an observed clock are left unchanged.
+    pub fn align_all<E>(&self, logs: &mut [(u64, S, E)]) {
+        for &mut (ref mut ts, ref setup, _) in logs.iter_mut() {
+            if let Some(aligned) = self.align(setup, *ts) {
+                *ts = aligned;
+            }
+        }
+    }
+}
+
+impl<S: Hash+Eq> Default for ClockDomains<S> {
+    fn default() -> Self { Self::new() }
+}
+
 #[derive(Debug, Clone, Abomonation)]
 /// An event in a timely worker
 pub enum TimelyEvent {
@@ -385,6 +566,14 @@ pub enum TimelyEvent {
     /*  9 */ CommChannels(CommChannelsEvent),
     /// Input event.
     /* 10 */ Input(InputEvent),
+    /// Log clock and wall-clock correspondence.
+    /* 11 */ Clock(ClockEvent),
+    /// Record latencies observed at a sink.
+    /* 12 */ Latency(LatencyEvent),
+    /// Skewed routing on an exchange channel.
+    /* 13 */ Skew(SkewEvent),
+    /// An operator invocation exceeding its budget.
+    /* 14 */ Budget(BudgetEvent),
 }
 
 impl From<OperatesEvent> for TimelyEvent {
@@ -430,3 +619,19 @@ impl From<CommChannelsEvent> for TimelyEvent {
 impl From<InputEvent> for TimelyEvent {
     fn from(v: InputEvent) -> TimelyEvent { TimelyEvent::Input(v) }
 }
+
+impl From<ClockEvent> for TimelyEvent {
+    fn from(v: ClockEvent) -> TimelyEvent { TimelyEvent::Clock(v) }
+}
+
+impl From<LatencyEvent> for TimelyEvent {
+    fn from(v: LatencyEvent) -> TimelyEvent { TimelyEvent::Latency(v) }
+}
+
+impl From<SkewEvent> for TimelyEvent {
+    fn from(v: SkewEvent) -> TimelyEvent { TimelyEvent::Skew(v) }
+}
+
+impl From<BudgetEvent> for TimelyEvent {
+    fn from(v: BudgetEvent) -> TimelyEvent { TimelyEvent::Budget(v) }
+}
@@ -0,0 +1,295 @@
+//! Worker, operator, and channel metrics, collected from timely's logging events.
+//!
+//! A `Metrics` instance accumulates counters from the logging events of the workers of a process:
+//! how often each operator was scheduled, how often it did work, and how long it ran; how many
+//! messages and records each channel carried; and the latencies measured by `measure_latency`. The
+//! counters are rendered in the Prometheus text exposition format by `render`. With the `prometheus`
+//! feature, `serve` exposes them on an HTTP endpoint until its `MetricsServer` is shut down, and
+//! `execute_with_metrics` runs a computation with one such endpoint per process.
+//!
+//! #Examples
+//! ```
+//! use timely::metrics::Metrics;
+//! use timely::dataflow::operators::{ToStream, Map, Inspect};
+//!
+//! let metrics = Metrics::new();
+//!
+//! timely::execute_logging(timely::Configuration::Process(2), metrics.logger_config(), |worker| {
+//!     worker.dataflow::<u64,_,_>(|scope| {
+//!         (0..10).to_stream(scope)
+//!                .map(|x| x + 1)
+//!                .inspect(|x| println!("seen: {:?}", x));
+//!     });
+//! }).unwrap();
+//!
+//! let text = metrics.render();
+//! assert!(text.contains("timely_operator_schedules_total{worker=\"0\""));
+//! assert!(text.contains("name=\"Map\""));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+use dataflow::operators::capture::{Event, EventPusher};
+use logging::{LoggerConfig, TimelySetup, TimelyEvent, StartStop};
+
+#[derive(Default)]
+struct OperatorMetrics {
+    name: String,
+    schedules: u64,
+    active: u64,
+    busy_ns: u64,
+    started: Option<u64>,
+}
+
+#[derive(Default)]
+struct ChannelMetrics {
+    messages_sent: u64,
+    records_sent: u64,
+    messages_received: u64,
+    records_received: u64,
+}
+
+#[derive(Default)]
+struct LatencyMetrics {
+    count: u64,
+    sum_ns: u64,
+    max_ns: u64,
+}
+
+#[derive(Default)]
+struct WorkerMetrics {
+    operators: HashMap<usize, OperatorMetrics>,
+    channels: HashMap<usize, ChannelMetrics>,
+    latencies: HashMap<String, LatencyMetrics>,
+    progress_sent: u64,
+    progress_received: u64,
+}
+
+impl WorkerMetrics {
+    fn observe(&mut self, ts: u64, event: TimelyEvent) {
+        match event {
+            TimelyEvent::Operates(operates) => {
+                self.operators.entry(operates.id).or_insert_with(Default::default).name = operates.name;
+            },
+            TimelyEvent::Schedule(schedule) => {
+                let operator = self.operators.entry(schedule.id).or_insert_with(Default::default);
+                match schedule.start_stop {
+                    StartStop::Start => { operator.started = Some(ts); },
+                    StartStop::Stop { activity } => {
+                        operator.schedules += 1;
+                        if activity { operator.active += 1; }
+                        if let Some(started) = operator.started.take() {
+                            operator.busy_ns += ts.saturating_sub(started);
+                        }
+                    },
+                }
+            },
+            TimelyEvent::Messages(messages) => {
+                let channel = self.channels.entry(messages.channel).or_insert_with(Default::default);
+                if messages.is_send {
+                    channel.messages_sent += 1;
+                    channel.records_sent += messages.length as u64;
+                }
+                else {
+                    channel.messages_received += 1;
+                    channel.records_received += messages.length as u64;
+                }
+            },
+            TimelyEvent::Progress(progress) => {
+                if progress.is_send { self.progress_sent += 1; } else { self.progress_received += 1; }
+            },
+            TimelyEvent::Latency(latency) => {
+                let metrics = self.latencies.entry(latency.name).or_insert_with(Default::default);
+                metrics.count += latency.count;
+                metrics.sum_ns += latency.sum_ns;
+                if metrics.max_ns < latency.max_ns { metrics.max_ns = latency.max_ns; }
+            },
+            _ => { },
+        }
+    }
+}
+
+/// Counters shared by the workers of a process, fed by their logging events.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    workers: Arc<Mutex<HashMap<usize, WorkerMetrics>>>,
+}
+
+impl Metrics {
+    /// Allocates a new set of counters, all zero.
+    pub fn new() -> Self { Default::default() }
+
+    /// A logging configuration feeding the timely events of each worker into these counters.
+    pub fn logger_config(&self) -> LoggerConfig {
+        let metrics = self.clone();
+        LoggerConfig::timely_only(move |_setup| MetricsPusher { metrics: metrics.clone() })
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+
+        let workers = self.workers.lock().expect("metrics lock poisoned");
+        let mut indices = workers.keys().cloned().collect::<Vec<_>>();
+        indices.sort();
+
+        let mut text = String::new();
+        {
+            let mut family = |name: &str, kind: &str, help: &str, samples: &mut Iterator<Item=(String, u64)>| {
+                let _ = writeln!(text, "# HELP {} {}", name, help);
+                let _ = writeln!(text, "# TYPE {} {}", name, kind);
+                for (labels, value) in samples {
+                    let _ = writeln!(text, "{}{{{}}} {}", name, labels, value);
+                }
+            };
+
+            let operators = |value: fn(&OperatorMetrics)->u64| {
+                let mut samples = Vec::new();
+                for index in indices.iter() {
+                    let mut ids = workers[index].operators.keys().cloned().collect::<Vec<_>>();
+                    ids.sort();
+                    for id in ids {
+                        let operator = &workers[index].operators[&id];
+                        samples.push((format!("worker=\"{}\",operator=\"{}\",name=\"{}\"", index, id, escape(&operator.name)), value(operator)));
+                    }
+                }
+                samples
+            };
+            family("timely_operator_schedules_total", "counter", "Number of times an operator was scheduled.", &mut operators(|o| o.schedules).into_iter());
+            family("timely_operator_active_schedules_total", "counter", "Number of times an operator performed work when scheduled.", &mut operators(|o| o.active).into_iter());
+            family("timely_operator_busy_nanoseconds_total", "counter", "Time an operator spent scheduled, in nanoseconds.", &mut operators(|o| o.busy_ns).into_iter());
+
+            let channels = |value: fn(&ChannelMetrics)->u64| {
+                let mut samples = Vec::new();
+                for index in indices.iter() {
+                    let mut ids = workers[index].channels.keys().cloned().collect::<Vec<_>>();
+                    ids.sort();
+                    for id in ids {
+                        samples.push((format!("worker=\"{}\",channel=\"{}\"", index, id), value(&workers[index].channels[&id])));
+                    }
+                }
+                samples
+            };
+            family("timely_channel_messages_sent_total", "counter", "Number of messages sent on a channel.", &mut channels(|c| c.messages_sent).into_iter());
+            family("timely_channel_records_sent_total", "counter", "Number of records sent on a channel.", &mut channels(|c| c.records_sent).into_iter());
+            family("timely_channel_messages_received_total", "counter", "Number of messages received on a channel.", &mut channels(|c| c.messages_received).into_iter());
+            family("timely_channel_records_received_total", "counter", "Number of records received on a channel.", &mut channels(|c| c.records_received).into_iter());
+
+            let progress = |value: fn(&WorkerMetrics)->u64| {
+                indices.iter().map(|index| (format!("worker=\"{}\"", index), value(&workers[index]))).collect::<Vec<_>>()
+            };
+            family("timely_worker_progress_sent_total", "counter", "Number of progress messages sent by a worker.", &mut progress(|w| w.progress_sent).into_iter());
+            family("timely_worker_progress_received_total", "counter", "Number of progress messages received by a worker.", &mut progress(|w| w.progress_received).into_iter());
+
+            let latencies = |value: fn(&LatencyMetrics)->u64| {
+                let mut samples = Vec::new();
+                for index in indices.iter() {
+                    let mut names = workers[index].latencies.keys().cloned().collect::<Vec<_>>();
+                    names.sort();
+                    for name in names {
+                        samples.push((format!("worker=\"{}\",name=\"{}\"", index, escape(&name)), value(&workers[index].latencies[&name])));
+                    }
+                }
+                samples
+            };
+            family("timely_latency_records_total", "counter", "Number of stamped records measured.", &mut latencies(|l| l.count).into_iter());
+            family("timely_latency_nanoseconds_total", "counter", "Sum of the latencies of stamped records, in nanoseconds.", &mut latencies(|l| l.sum_ns).into_iter());
+            family("timely_latency_max_nanoseconds", "gauge", "Greatest latency of a stamped record, in nanoseconds.", &mut latencies(|l| l.max_ns).into_iter());
+        }
+        text
+    }
+
+    /// Serves the rendered counters over HTTP at `addr`, on a new thread, returning a handle to the server.
+    ///
+    /// Every request, whatever its path, receives the current counters. The server runs until it is
+    /// shut down with `MetricsServer::shutdown`; dropping the handle leaves it running.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::metrics::Metrics;
+    ///
+    /// let server = Metrics::new().serve("127.0.0.1:0").unwrap();
+    /// println!("serving metrics at {}", server.address());
+    /// server.shutdown();
+    /// ```
+    #[cfg(feature = "prometheus")]
+    pub fn serve<A: ::std::net::ToSocketAddrs>(&self, addr: A) -> ::std::io::Result<MetricsServer> {
+
+        use std::io::{Read, Write};
+        use std::sync::atomic::Ordering;
+
+        let listener = ::std::net::TcpListener::bind(addr)?;
+        let address = listener.local_addr()?;
+        let stopped = Arc::new(::std::sync::atomic::AtomicBool::new(false));
+        let metrics = self.clone();
+        let stopped2 = stopped.clone();
+        let thread = ::std::thread::Builder::new()
+            .name("timely:metrics".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped2.load(Ordering::SeqCst) { break; }
+                    if let Ok(mut stream) = stream {
+                        let mut request = [0u8; 1024];
+                        let _ = stream.read(&mut request);
+                        let body = metrics.render();
+                        let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                    }
+                }
+            })?;
+        Ok(MetricsServer { address, stopped, thread })
+    }
+}
+
+/// A handle to an HTTP endpoint serving metrics, started by `Metrics::serve`.
+#[cfg(feature = "prometheus")]
+pub struct MetricsServer {
+    address: ::std::net::SocketAddr,
+    stopped: Arc<::std::sync::atomic::AtomicBool>,
+    thread: ::std::thread::JoinHandle<()>,
+}
+
+#[cfg(feature = "prometheus")]
+impl MetricsServer {
+    /// The address the server is bound to.
+    pub fn address(&self) -> ::std::net::SocketAddr { self.address }
+
+    /// Stops accepting requests, and waits for the serving thread to exit.
+    pub fn shutdown(self) {
+        self.stopped.store(true, ::std::sync::atomic::Ordering::SeqCst);
+        // wake the serving thread, which is blocked accepting connections.
+        let mut address = self.address;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address {
+                ::std::net::SocketAddr::V4(_) => ::std::net::Ipv4Addr::LOCALHOST.into(),
+                ::std::net::SocketAddr::V6(_) => ::std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        if ::std::net::TcpStream::connect(address).is_ok() {
+            let _ = self.thread.join();
+        }
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Feeds the logging events of a worker into shared counters.
+struct MetricsPusher {
+    metrics: Metrics,
+}
+
+impl EventPusher<Product<RootTimestamp, u64>, (u64, TimelySetup, TimelyEvent)> for MetricsPusher {
+    fn push(&mut self, event: Event<Product<RootTimestamp, u64>, (u64, TimelySetup, TimelyEvent)>) {
+        if let Event::Messages(_, data) = event {
+            let mut workers = self.metrics.workers.lock().expect("metrics lock poisoned");
+            for (ts, setup, event) in data {
+                workers.entry(setup.index).or_insert_with(Default::default).observe(ts, event);
+            }
+        }
+    }
+}
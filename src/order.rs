@@ -46,3 +46,112 @@ macro_rules! implement_total {
 
 implement_partial!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, (),);
 implement_total!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, (),);
+
+/// A partially ordered type in which each pair of elements has a least upper bound and a greatest
+/// lower bound.
+///
+/// #Examples
+/// ```
+/// use timely::order::Lattice;
+///
+/// assert_eq!((1, 4).join(&(3, 2)), (3, 4));
+/// assert_eq!((1, 4).meet(&(3, 2)), (1, 2));
+///
+/// let mut time = 5u32;
+/// time.meet_assign(&3);
+/// assert_eq!(time, 3);
+/// ```
+pub trait Lattice : PartialOrder {
+    /// The least upper bound of `self` and `other`.
+    fn join(&self, other: &Self) -> Self;
+    /// The greatest lower bound of `self` and `other`.
+    fn meet(&self, other: &Self) -> Self;
+    /// Updates `self` to its least upper bound with `other`.
+    #[inline]
+    fn join_assign(&mut self, other: &Self) where Self: Sized {
+        *self = self.join(other);
+    }
+    /// Updates `self` to its greatest lower bound with `other`.
+    #[inline]
+    fn meet_assign(&mut self, other: &Self) where Self: Sized {
+        *self = self.meet(other);
+    }
+}
+
+macro_rules! implement_lattice {
+    ($($index_type:ty,)*) => (
+        $(
+            impl Lattice for $index_type {
+                #[inline(always)] fn join(&self, other: &Self) -> Self { ::std::cmp::max(*self, *other) }
+                #[inline(always)] fn meet(&self, other: &Self) -> Self { ::std::cmp::min(*self, *other) }
+            }
+        )*
+    )
+}
+
+implement_lattice!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, (),);
+
+/// The least upper bound of `elements`, or `None` if there are no elements.
+///
+/// #Examples
+/// ```
+/// use timely::order::join_all;
+///
+/// assert_eq!(join_all(&[(1, 4), (3, 2), (2, 2)]), Some((3, 4)));
+/// assert_eq!(join_all::<(u32, u32), _>(&[]), None);
+/// ```
+pub fn join_all<'a, T: Lattice+Clone+'a, I: IntoIterator<Item=&'a T>>(elements: I) -> Option<T> {
+    let mut iter = elements.into_iter();
+    iter.next().map(|first| iter.fold(first.clone(), |join, element| join.join(element)))
+}
+
+/// The greatest lower bound of `elements`, or `None` if there are no elements.
+///
+/// #Examples
+/// ```
+/// use timely::order::meet_all;
+///
+/// assert_eq!(meet_all(&[(1, 4), (3, 2), (2, 2)]), Some((1, 2)));
+/// ```
+pub fn meet_all<'a, T: Lattice+Clone+'a, I: IntoIterator<Item=&'a T>>(elements: I) -> Option<T> {
+    let mut iter = elements.into_iter();
+    iter.next().map(|first| iter.fold(first.clone(), |meet, element| meet.meet(element)))
+}
+
+/// Returns true iff `element` lies in the closed interval from `lower` to `upper`.
+///
+/// #Examples
+/// ```
+/// use timely::order::in_interval;
+///
+/// assert!(in_interval(&(1, 1), &(1, 2), &(2, 2)));
+/// assert!(!in_interval(&(1, 1), &(0, 2), &(2, 2)));
+/// ```
+pub fn in_interval<T: PartialOrder>(lower: &T, element: &T, upper: &T) -> bool {
+    lower.less_equal(element) && element.less_equal(upper)
+}
+
+/// Pairs are partially ordered by the product order, in which one pair is less or equal to another
+/// exactly when each of its coordinates is less or equal to the corresponding coordinate.
+///
+/// #Examples
+/// ```
+/// use timely::PartialOrder;
+///
+/// assert!((1, 2).less_equal(&(1, 3)));
+/// assert!(!(1, 2).less_equal(&(2, 1)));
+/// assert!(!(2, 1).less_equal(&(1, 2)));
+/// ```
+impl<A: PartialOrder, B: PartialOrder> PartialOrder for (A, B) {
+    #[inline(always)]
+    fn less_equal(&self, other: &Self) -> bool {
+        self.0.less_equal(&other.0) && self.1.less_equal(&other.1)
+    }
+}
+
+impl<A: Lattice, B: Lattice> Lattice for (A, B) {
+    #[inline]
+    fn join(&self, other: &Self) -> Self { (self.0.join(&other.0), self.1.join(&other.1)) }
+    #[inline]
+    fn meet(&self, other: &Self) -> Self { (self.0.meet(&other.0), self.1.meet(&other.1)) }
+}
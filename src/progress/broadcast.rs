@@ -3,6 +3,7 @@
 use progress::{ChangeBatch, Timestamp};
 use communication::{Allocate, Message, Push, Pull};
 use logging::Logger;
+use config::WorkerConfig;
 
 /// A list of progress updates corresponding to `((child_scope, [in/out]_port, timestamp), delta)`
 pub type ProgressVec<T> = Vec<((usize, usize, T), i64)>;
@@ -10,6 +11,79 @@ pub type ProgressVec<T> = Vec<((usize, usize, T), i64)>;
 /// message and internal updates
 pub type ProgressMsg<T> = Message<(usize, usize, ProgressVec<T>, ProgressVec<T>)>;
 
+/// How workers exchange progress updates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProgressMode {
+    /// Each worker sends its updates to every worker.
+    AllToAll,
+    /// Workers are grouped into consecutive ranges of the indicated number of workers, typically the
+    /// workers of each process, and each group's first worker aggregates the updates of the group.
+    ///
+    /// Each worker sends its updates only to its group's aggregator, which consolidates the updates
+    /// it receives into one batch per exchange, sends the batch of its group's updates to the other
+    /// aggregators, and sends the batch of all updates back to the members of its group. This
+    /// reduces progress traffic between processes from quadratic in the number of workers to
+    /// quadratic in the number of processes, at the cost of a hop through the aggregator, which
+    /// only forwards updates when it is itself scheduled.
+    Hierarchical(usize),
+    /// The indicated worker aggregates the updates of all workers.
+    ///
+    /// Each worker sends its updates only to the authority, which consolidates the updates it
+    /// receives into one batch per exchange and sends it to all workers. This minimizes progress
+    /// traffic for very large clusters, at the cost of a hop through the authority, which may become
+    /// a bottleneck and only forwards updates when it is itself scheduled.
+    Authority(usize),
+}
+
+impl ProgressMode {
+    /// Reads the mode from a worker configuration.
+    ///
+    /// The key `progress-group`, with a number of workers as its value, selects `Hierarchical`
+    /// aggregation by groups of that many workers, and the key `progress-authority`, with a worker
+    /// index as its value, selects that worker as the `Authority`; otherwise the mode is `AllToAll`.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::config::WorkerConfig;
+    /// use timely::progress::broadcast::ProgressMode;
+    ///
+    /// let config = WorkerConfig::new().set("progress-group", "8");
+    /// assert_eq!(ProgressMode::from_config(&config), ProgressMode::Hierarchical(8));
+    /// let config = WorkerConfig::new().set("progress-authority", "0");
+    /// assert_eq!(ProgressMode::from_config(&config), ProgressMode::Authority(0));
+    /// assert_eq!(ProgressMode::from_config(&WorkerConfig::new()), ProgressMode::AllToAll);
+    /// ```
+    ///
+    /// #Panics
+    ///
+    /// Panics if the value of `progress-group` is not a positive number, if the value of
+    /// `progress-authority` is not a number, or if both keys are present.
+    pub fn from_config(config: &WorkerConfig) -> Self {
+        match (config.parse::<usize>("progress-group"), config.parse::<usize>("progress-authority")) {
+            (Some(_), Some(_)) => panic!("at most one of progress-group and progress-authority may be set"),
+            (Some(Ok(size)), None) if size > 0 => ProgressMode::Hierarchical(size),
+            (Some(_), None) => panic!("progress-group must be a positive number of workers"),
+            (None, Some(Ok(index))) => ProgressMode::Authority(index),
+            (None, Some(Err(_))) => panic!("progress-authority must be a worker index"),
+            (None, None) => ProgressMode::AllToAll,
+        }
+    }
+}
+
+impl Default for ProgressMode {
+    fn default() -> Self { ProgressMode::AllToAll }
+}
+
+/// The workers with which a worker exchanges updates when they are aggregated.
+struct Aggregation {
+    /// The worker aggregating this worker's updates, perhaps itself.
+    aggregator: usize,
+    /// If this worker is an aggregator, the workers whose updates it aggregates.
+    members: Vec<usize>,
+    /// If this worker is an aggregator, the other aggregators.
+    aggregators: Vec<usize>,
+}
+
 /// Manages broadcasting of progress updates to and receiving updates from workers.
 pub struct Progcaster<T:Timestamp> {
     to_push: Option<ProgressMsg<T>>,
@@ -23,6 +97,8 @@ pub struct Progcaster<T:Timestamp> {
     addr: Vec<usize>,
     /// Communication channel identifier
     comm_channel: Option<usize>,
+    /// How updates are aggregated, if they are.
+    aggregation: Option<Aggregation>,
 
     logging: Logger,
 }
@@ -30,6 +106,11 @@ pub struct Progcaster<T:Timestamp> {
 impl<T:Timestamp+Send> Progcaster<T> {
     /// Creates a new `Progcaster` using a channel from the supplied allocator.
     pub fn new<A: Allocate>(allocator: &mut A, path: &Vec<usize>, logging: Logger) -> Progcaster<T> {
+        Progcaster::new_with_mode(allocator, path, logging, ProgressMode::AllToAll)
+    }
+
+    /// Creates a new `Progcaster` exchanging updates as indicated by `mode`.
+    pub fn new_with_mode<A: Allocate>(allocator: &mut A, path: &Vec<usize>, logging: Logger, mode: ProgressMode) -> Progcaster<T> {
         let (pushers, puller, chan) = allocator.allocate();
         logging.when_enabled(|l| l.log(::logging::TimelyEvent::CommChannels(::logging::CommChannelsEvent {
             comm_channel: chan,
@@ -37,6 +118,27 @@ impl<T:Timestamp+Send> Progcaster<T> {
         })));
         let worker = allocator.index();
         let addr = path.clone();
+        let peers = allocator.peers();
+        let aggregation = match mode {
+            ProgressMode::AllToAll => None,
+            ProgressMode::Hierarchical(size) => {
+                assert!(size > 0, "progress groups must be non-empty");
+                let aggregator = worker - worker % size;
+                Some(Aggregation {
+                    aggregator,
+                    members: (aggregator + 1 .. ::std::cmp::min(aggregator + size, peers)).collect(),
+                    aggregators: (0 .. peers).filter(|&x| x % size == 0 && x != aggregator).collect(),
+                })
+            },
+            ProgressMode::Authority(authority) => {
+                assert!(authority < peers, "progress authority {} is not a worker", authority);
+                Some(Aggregation {
+                    aggregator: authority,
+                    members: (0 .. peers).filter(|&x| x != authority).collect(),
+                    aggregators: Vec::new(),
+                })
+            },
+        };
         Progcaster {
             to_push: None,
             pushers,
@@ -45,6 +147,7 @@ impl<T:Timestamp+Send> Progcaster<T> {
             counter: 0,
             addr,
             comm_channel: chan,
+            aggregation,
             logging,
         }
     }
@@ -56,7 +159,12 @@ impl<T:Timestamp+Send> Progcaster<T> {
         messages: &mut ChangeBatch<(usize, usize, T)>,
         internal: &mut ChangeBatch<(usize, usize, T)>)
     {
-        if self.pushers.len() > 1 {  // if the length is one, just return the updates...
+        if self.aggregation.is_some() {
+            if self.pushers.len() > 1 {
+                self.send_and_recv_aggregated(messages, internal);
+            }
+        }
+        else if self.pushers.len() > 1 {  // if the length is one, just return the updates...
             if !messages.is_empty() || !internal.is_empty() {
                 self.logging.when_enabled(|l| l.log(::logging::TimelyEvent::Progress(::logging::ProgressEvent {
                     is_send: true,
@@ -134,4 +242,120 @@ impl<T:Timestamp+Send> Progcaster<T> {
             }
         }
     }
+
+    /// Exchanges updates through aggregators.
+    fn send_and_recv_aggregated(
+        &mut self,
+        messages: &mut ChangeBatch<(usize, usize, T)>,
+        internal: &mut ChangeBatch<(usize, usize, T)>)
+    {
+        let aggregation = self.aggregation.take().expect("updates not aggregated");
+        if self.source != aggregation.aggregator {
+            // A member sends its updates to its aggregator, and receives all updates from it.
+            if !messages.is_empty() || !internal.is_empty() {
+                self.log_send();
+                self.push_to(aggregation.aggregator, messages, internal);
+                self.counter += 1;
+                messages.clear();
+                internal.clear();
+            }
+            self.recv(|_source, recv_messages, recv_internal| {
+                for &(ref update, delta) in recv_messages.iter() { messages.update(update.clone(), delta); }
+                for &(ref update, delta) in recv_internal.iter() { internal.update(update.clone(), delta); }
+            });
+        }
+        else {
+            // An aggregator consolidates its own updates with those received from its members and from
+            // other aggregators, and distributes them.
+            let mut local = (ChangeBatch::new(), ChangeBatch::new());
+            let mut remote = (ChangeBatch::new(), ChangeBatch::new());
+            messages.drain_into(&mut local.0);
+            internal.drain_into(&mut local.1);
+            self.recv(|source, recv_messages, recv_internal| {
+                let batch = if aggregation.aggregators.contains(&source) { &mut remote } else { &mut local };
+                for &(ref update, delta) in recv_messages.iter() { batch.0.update(update.clone(), delta); }
+                for &(ref update, delta) in recv_internal.iter() { batch.1.update(update.clone(), delta); }
+            });
+
+            // Other aggregators receive the updates of this aggregator's members.
+            let local_updates = !local.0.is_empty() || !local.1.is_empty();
+            if local_updates {
+                self.log_send();
+                for &aggregator in aggregation.aggregators.iter() {
+                    self.push_to(aggregator, &mut local.0, &mut local.1);
+                }
+            }
+
+            // Members receive all updates, including their own.
+            local.0.drain_into(&mut remote.0);
+            local.1.drain_into(&mut remote.1);
+            let remote_updates = !remote.0.is_empty() || !remote.1.is_empty();
+            if remote_updates {
+                if !local_updates { self.log_send(); }
+                for &member in aggregation.members.iter() {
+                    self.push_to(member, &mut remote.0, &mut remote.1);
+                }
+                remote.0.drain_into(messages);
+                remote.1.drain_into(internal);
+            }
+
+            if local_updates || remote_updates {
+                self.counter += 1;
+            }
+        }
+        self.aggregation = Some(aggregation);
+    }
+
+    /// Pushes `messages` and `internal` to worker `target`, re-using allocations if possible.
+    fn push_to(&mut self, target: usize, messages: &mut ChangeBatch<(usize, usize, T)>, internal: &mut ChangeBatch<(usize, usize, T)>) {
+        if let Some(tuple) = &mut self.to_push {
+            let tuple = tuple.as_mut();
+            tuple.0 = self.source;
+            tuple.1 = self.counter;
+            tuple.2.clear(); tuple.2.extend(messages.iter().cloned());
+            tuple.3.clear(); tuple.3.extend(internal.iter().cloned());
+        }
+        if self.to_push.is_none() {
+            self.to_push = Some(Message::from_typed((
+                self.source,
+                self.counter,
+                messages.iter().cloned().collect(),
+                internal.iter().cloned().collect(),
+            )));
+        }
+        self.pushers[target].push(&mut self.to_push);
+    }
+
+    /// Logs the sending of progress updates.
+    fn log_send(&self) {
+        self.logging.when_enabled(|l| l.log(::logging::TimelyEvent::Progress(::logging::ProgressEvent {
+            is_send: true,
+            source: self.source,
+            comm_channel: self.comm_channel,
+            seq_no: self.counter,
+            addr: self.addr.clone(),
+            messages: Vec::new(),
+            internal: Vec::new(),
+        })));
+    }
+
+    /// Receives and logs all available updates, presenting each to `logic`.
+    fn recv<F: FnMut(usize, &ProgressVec<T>, &ProgressVec<T>)>(&mut self, mut logic: F) {
+        while let Some(message) = self.puller.pull() {
+            let source = message.0;
+            let counter = message.1;
+            let comm_channel = self.comm_channel;
+            let addr = &self.addr;
+            self.logging.when_enabled(|l| l.log(::logging::TimelyEvent::Progress(::logging::ProgressEvent {
+                is_send: false,
+                source,
+                seq_no: counter,
+                comm_channel,
+                addr: addr.clone(),
+                messages: Vec::new(),
+                internal: Vec::new(),
+            })));
+            logic(source, &message.2, &message.3);
+        }
+    }
 }
@@ -1,24 +1,74 @@
-//! A collection of updates of the form `(T, i64)`.
+//! A collection of updates of the form `(T, D)`, where `D` defaults to `i64`.
 
-/// A collection of updates of the form `(T, i64)`.
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign};
+
+/// A type of counts accumulated by `ChangeBatch` and `MutableAntichain`.
+///
+/// The default value of the type must be its zero. By default counts are `i64`, but deployments
+/// tracking very many updates may opt into smaller types such as `i32`, provided the accumulated
+/// counts cannot overflow.
+pub trait Diff: Copy+Ord+Default+Debug+Add<Output=Self>+AddAssign+'static { }
+impl<D: Copy+Ord+Default+Debug+Add<Output=D>+AddAssign+'static> Diff for D { }
+
+/// A collection of updates of the form `(T, D)`.
 ///
-/// A `ChangeBatch` accumulates updates of the form `(T, i64)`, where it is capable of consolidating
-/// the representation and removing elements whose `i64` field accumulates to zero.
+/// A `ChangeBatch` accumulates updates of the form `(T, D)`, where it is capable of consolidating
+/// the representation and removing elements whose `D` field accumulates to zero.
 ///
 /// The implementation is designed to be as lazy as possible, simply appending to a list of updates
 /// until they are required. This means that several seemingly simple operations may be expensive, in
 /// that they may provoke a compaction. I've tried to prevent exposing methods that allow surprisingly
 /// expensive operations; all operations should take an amortized constant or logarithmic time.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct ChangeBatch<T> {
+pub struct ChangeBatch<T, D=i64> {
     // A list of updates to which we append.
-    updates: Vec<(T, i64)>,
+    updates: Vec<(T, D)>,
     // The length of the prefix of `self.updates` known to be compact.
     clean: usize,
 }
 
 impl<T:Ord> ChangeBatch<T> {
 
+    /// Allocates a new `ChangeBatch` with a single entry.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::ChangeBatch;
+    ///
+    /// let mut batch = ChangeBatch::<usize>::new_from(17, 1);
+    /// assert!(!batch.is_empty());
+    ///```
+    pub fn new_from(key: T, val: i64) -> ChangeBatch<T> {
+        let mut result = ChangeBatch::new();
+        result.update(key, val);
+        result
+    }
+}
+
+impl<T:Ord, D: Diff> Default for ChangeBatch<T, D> {
+    /// Allocates a new empty `ChangeBatch`, with counts of any `Diff` type.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::ChangeBatch;
+    ///
+    /// let mut batch = ChangeBatch::<usize, i32>::default();
+    /// batch.update(17, 1);
+    /// assert_eq!(batch.into_inner(), vec![(17, 1i32)]);
+    ///```
+    fn default() -> Self {
+        ChangeBatch {
+            updates: Vec::new(),
+            clean: 0,
+        }
+    }
+}
+
+impl<T:Ord, D: Diff> ChangeBatch<T, D> {
+
     /// Allocates a new empty `ChangeBatch`.
     ///
     /// #Examples
@@ -29,30 +79,31 @@ impl<T:Ord> ChangeBatch<T> {
     /// let mut batch = ChangeBatch::<usize>::new();
     /// assert!(batch.is_empty());
     ///```
-    pub fn new() -> ChangeBatch<T> {
+    pub fn new() -> Self {
         ChangeBatch {
             updates: Vec::new(),
             clean: 0
         }
     }
 
-    /// Allocates a new `ChangeBatch` with a single entry.
+    /// Allocates a new empty `ChangeBatch` with space for `capacity` updates before reallocating.
     ///
     /// #Examples
     ///
     ///```
     /// use timely::progress::ChangeBatch;
     ///
-    /// let mut batch = ChangeBatch::<usize>::new_from(17, 1);
-    /// assert!(!batch.is_empty());
+    /// let mut batch = ChangeBatch::<usize>::with_capacity(10);
+    /// assert!(batch.capacity() >= 10);
+    /// assert!(batch.is_empty());
     ///```
-    pub fn new_from(key: T, val: i64) -> ChangeBatch<T> {
-        let mut result = ChangeBatch::new();
-        result.update(key, val);
-        result
+    pub fn with_capacity(capacity: usize) -> Self {
+        ChangeBatch {
+            updates: Vec::with_capacity(capacity),
+            clean: 0,
+        }
     }
 
-
     /// Adds a new update, for `item` with `value`.
     ///
     /// This could be optimized to perform compaction when the number of "dirty" elements exceeds
@@ -70,7 +121,7 @@ impl<T:Ord> ChangeBatch<T> {
     /// assert!(!batch.is_empty());
     ///```
     #[inline]
-    pub fn update(&mut self, item: T, value: i64) {
+    pub fn update(&mut self, item: T, value: D) {
         self.updates.push((item, value));
     }
 
@@ -86,13 +137,13 @@ impl<T:Ord> ChangeBatch<T> {
     /// assert!(batch.is_empty());
     ///```
     #[inline]
-    pub fn extend<I: Iterator<Item=(T, i64)>>(&mut self, iterator: I) {
+    pub fn extend<I: Iterator<Item=(T, D)>>(&mut self, iterator: I) {
         for (key, val) in iterator {
             self.update(key, val);
         }
     }
 
-    /// Extracts the `Vec<(T, i64)>` from the map, consuming it.
+    /// Extracts the `Vec<(T, D)>` from the map, consuming it.
     ///
     /// #Examples
     ///
@@ -102,13 +153,17 @@ impl<T:Ord> ChangeBatch<T> {
     /// let batch = ChangeBatch::<usize>::new_from(17, 1);
     /// assert_eq!(batch.into_inner(), vec![(17, 1)]);
     ///```
-    pub fn into_inner(mut self) -> Vec<(T, i64)> {
+    pub fn into_inner(mut self) -> Vec<(T, D)> {
         self.compact();
         self.updates
     }
 
     /// Iterates over the contents of the map.
     ///
+    /// This method first compacts the set of updates, which is why it requires mutable access, so
+    /// that the iterator yields each item at most once and only items with non-zero values. The
+    /// order of the items is unspecified.
+    ///
     /// #Examples
     ///
     ///```
@@ -123,7 +178,7 @@ impl<T:Ord> ChangeBatch<T> {
     /// assert!(!batch.is_empty());
     ///```
     #[inline]
-    pub fn iter(&mut self) -> ::std::slice::Iter<(T, i64)> {
+    pub fn iter(&mut self) -> ::std::slice::Iter<(T, D)> {
         self.compact();
         self.updates.iter()
     }
@@ -147,7 +202,7 @@ impl<T:Ord> ChangeBatch<T> {
     /// assert!(batch.is_empty());
     ///```
     #[inline]
-    pub fn drain(&mut self) -> ::std::vec::Drain<(T, i64)> {
+    pub fn drain(&mut self) -> ::std::vec::Drain<(T, D)> {
         self.compact();
         self.clean = 0;
         self.updates.drain(..)
@@ -203,13 +258,13 @@ impl<T:Ord> ChangeBatch<T> {
         self.updates.sort_by(|x,y| x.0.cmp(&y.0));
     }
 
-
     /// Drains `self` into `other`.
     ///
     /// This method has similar a effect to calling `other.extend(self.drain())`, but has the
     /// opportunity to optimize this to a `::std::mem::swap(self, other)` when `other` is empty.
     /// As many uses of this method are to propagate updates, this optimization can be quite
-    /// handy.
+    /// handy. In that case the allocations of the two batches are exchanged, and `self` is left
+    /// with the (empty) allocation of `other`. Updates are not compacted as part of the move.
     ///
     /// #Examples
     ///
@@ -223,7 +278,7 @@ impl<T:Ord> ChangeBatch<T> {
     /// assert!(!batch2.is_empty());
     ///```
     #[inline]
-    pub fn drain_into(&mut self, other: &mut ChangeBatch<T>) where T: Clone {
+    pub fn drain_into(&mut self, other: &mut ChangeBatch<T, D>) where T: Clone {
         if other.updates.is_empty() {
             ::std::mem::swap(self, other);
         }
@@ -233,22 +288,65 @@ impl<T:Ord> ChangeBatch<T> {
         }
     }
 
+    /// The number of updates the batch can hold before reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.updates.capacity()
+    }
+
+    /// Reserves space for at least `additional` more updates before reallocating.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.updates.reserve(additional);
+    }
+
+    /// Compacts the updates and releases any excess capacity.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::ChangeBatch;
+    ///
+    /// let mut batch = ChangeBatch::<usize>::with_capacity(100);
+    /// batch.update(17, 1);
+    /// batch.update(17, 1);
+    /// batch.shrink_to_fit();
+    /// assert_eq!(batch.capacity(), 1);
+    ///```
+    pub fn shrink_to_fit(&mut self) {
+        self.compact();
+        self.updates.shrink_to_fit();
+    }
+
     /// Compact the internal representation.
     ///
     /// This method sort `self.updates` and consolidates elements with equal item, discarding
     /// any whose accumulation is zero. It is optimized to only do this if the number of dirty
     /// elements is non-zero.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::ChangeBatch;
+    ///
+    /// let mut batch = ChangeBatch::<usize>::new_from(17, 0);
+    /// batch.compact();
+    /// assert_eq!(batch.into_inner(), vec![]);
+    ///```
     #[inline]
-    fn compact(&mut self) {
-        if self.clean < self.updates.len() && self.updates.len() > 1 {
-            self.updates.sort_by(|x,y| x.0.cmp(&y.0));
-            for i in 0 .. self.updates.len() - 1 {
-                if self.updates[i].0 == self.updates[i+1].0 {
-                    self.updates[i+1].1 += self.updates[i].1;
-                    self.updates[i].1 = 0;
+    pub fn compact(&mut self) {
+        if self.clean < self.updates.len() {
+            if self.updates.len() > 1 {
+                self.updates.sort_by(|x,y| x.0.cmp(&y.0));
+                for i in 0 .. self.updates.len() - 1 {
+                    if self.updates[i].0 == self.updates[i+1].0 {
+                        let diff = self.updates[i].1;
+                        self.updates[i+1].1 += diff;
+                        self.updates[i].1 = D::default();
+                    }
                 }
             }
-            self.updates.retain(|x| x.1 != 0);
+            self.updates.retain(|x| x.1 != D::default());
         }
         self.clean = self.updates.len();
     }
@@ -1,7 +1,8 @@
 //! Tracks minimal sets of mutually incomparable elements of a partial order.
 
 // use progress::CountMap;
-use order::PartialOrder;
+use order::{PartialOrder, Lattice, meet_all, join_all};
+use progress::change_batch::Diff;
 
 /// A set of mutually incomparable elements.
 ///
@@ -11,7 +12,7 @@ use order::PartialOrder;
 /// no greater than any other element.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Antichain<T> {
-    elements: Vec<T>
+    elements: Elements<T>
 }
 
 impl<T: PartialOrder> Antichain<T> {
@@ -30,10 +31,10 @@ impl<T: PartialOrder> Antichain<T> {
     }
 
     /// Creates a new empty `Antichain`.
-    pub fn new() -> Antichain<T> { Antichain { elements: Vec::new() } }
+    pub fn new() -> Antichain<T> { Antichain { elements: Elements::new() } }
 
     /// Creates a new singleton `Antichain`.
-    pub fn from_elem(element: T) -> Antichain<T> { Antichain { elements: vec![element] } }
+    pub fn from_elem(element: T) -> Antichain<T> { Antichain { elements: Elements::from_elem(element) } }
 
     /// Clears the contents of the antichain.
     pub fn clear(&mut self) { self.elements.clear() }
@@ -61,8 +62,181 @@ impl<T: PartialOrder> Antichain<T> {
 
     /// Reveals the elements in the antichain.
     #[inline] pub fn elements(&self) -> &[T] { &self.elements[..] }
+
+    /// The greatest lower bound of the elements of the antichain, or `None` if it is empty.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::Antichain;
+    ///
+    /// let mut frontier = Antichain::new();
+    /// frontier.insert((1u64, 3u64));
+    /// frontier.insert((2u64, 2u64));
+    /// assert_eq!(frontier.meet(), Some((1, 2)));
+    /// assert_eq!(frontier.join(), Some((2, 3)));
+    ///```
+    pub fn meet(&self) -> Option<T> where T: Lattice+Clone { meet_all(self.elements()) }
+
+    /// The least upper bound of the elements of the antichain, or `None` if it is empty.
+    pub fn join(&self) -> Option<T> where T: Lattice+Clone { join_all(self.elements()) }
+
+    /// The greatest antichain less or equal to both `self` and `other`.
+    ///
+    /// Understood as frontiers, the result permits exactly the times permitted by either `self` or
+    /// `other`: its up-set is the union of their up-sets. An operator with two inputs may produce
+    /// output at times in the lower bound of its input frontiers.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::Antichain;
+    ///
+    /// let mut frontier1 = Antichain::new();
+    /// frontier1.insert((0u64, 2u64));
+    /// frontier1.insert((2u64, 0u64));
+    /// let frontier2 = Antichain::from_elem((1u64, 1u64));
+    ///
+    /// let lower = frontier1.lower_bound(&frontier2);
+    /// assert_eq!(lower.elements().len(), 3);
+    /// assert!(lower.less_equal(&(1, 1)));
+    ///
+    /// let lower = frontier1.lower_bound(&Antichain::from_elem((0, 0)));
+    /// assert_eq!(lower.elements(), &[(0, 0)]);
+    ///```
+    pub fn lower_bound(&self, other: &Antichain<T>) -> Antichain<T> where T: Clone {
+        let mut result = self.clone();
+        for element in other.elements() {
+            result.insert(element.clone());
+        }
+        result
+    }
+
+    /// The least antichain greater or equal to both `self` and `other`.
+    ///
+    /// Understood as frontiers, the result permits exactly the times permitted by both `self` and
+    /// `other`: its up-set is the intersection of their up-sets. The bound consists of the minimal
+    /// joins of pairs of elements, and so requires a lattice.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::Antichain;
+    ///
+    /// let mut frontier1 = Antichain::new();
+    /// frontier1.insert((0u64, 2u64));
+    /// frontier1.insert((2u64, 0u64));
+    /// let frontier2 = Antichain::from_elem((1u64, 1u64));
+    ///
+    /// let mut upper = frontier1.upper_bound(&frontier2);
+    /// upper.sort();
+    /// assert_eq!(upper.elements(), &[(1, 2), (2, 1)]);
+    ///
+    /// // the empty frontier permits no times.
+    /// assert!(frontier1.upper_bound(&Antichain::new()).elements().is_empty());
+    ///```
+    pub fn upper_bound(&self, other: &Antichain<T>) -> Antichain<T> where T: Lattice+Clone {
+        let mut result = Antichain::new();
+        for element1 in self.elements() {
+            for element2 in other.elements() {
+                result.insert(element1.join(element2));
+            }
+        }
+        result
+    }
+
+    /// Returns true if `time` is greater or equal to some element of `self`, and less or equal to some element of `upper`.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::Antichain;
+    ///
+    /// let lower = Antichain::from_elem(2u64);
+    /// let upper = Antichain::from_elem(5u64);
+    /// assert!(!lower.within(&1, &upper));
+    /// assert!(lower.within(&2, &upper));
+    /// assert!(lower.within(&5, &upper));
+    /// assert!(!lower.within(&6, &upper));
+    ///```
+    #[inline]
+    pub fn within(&self, time: &T, upper: &Antichain<T>) -> bool {
+        self.less_equal(time) && upper.elements().iter().any(|x| time.less_equal(x))
+    }
+}
+
+/// A list of elements, stored inline while it holds at most one element.
+///
+/// Most antichains, and most frontiers in particular, contain a single element. Storing that element
+/// inline avoids a heap allocation per antichain; lists of more elements spill to a `Vec`, which is
+/// retained (though emptied) by `clear`.
+#[derive(Clone, Debug)]
+enum Elements<T> {
+    Inline(Option<T>),
+    Heap(Vec<T>),
+}
+
+impl<T> Elements<T> {
+    fn new() -> Self { Elements::Inline(None) }
+    fn from_elem(element: T) -> Self { Elements::Inline(Some(element)) }
+
+    fn push(&mut self, element: T) {
+        let spilled = match *self {
+            Elements::Inline(ref mut slot @ None) => { *slot = Some(element); return; },
+            Elements::Inline(ref mut slot) => vec![slot.take().unwrap(), element],
+            Elements::Heap(ref mut vec) => { vec.push(element); return; },
+        };
+        *self = Elements::Heap(spilled);
+    }
+
+    fn retain<F: FnMut(&T)->bool>(&mut self, mut predicate: F) {
+        match *self {
+            Elements::Inline(ref mut slot) => {
+                if slot.as_ref().map(|x| !predicate(x)).unwrap_or(false) { *slot = None; }
+            },
+            Elements::Heap(ref mut vec) => vec.retain(predicate),
+        }
+    }
+
+    fn clear(&mut self) {
+        match *self {
+            Elements::Inline(ref mut slot) => { *slot = None; },
+            Elements::Heap(ref mut vec) => vec.clear(),
+        }
+    }
+}
+
+impl<T> Default for Elements<T> {
+    fn default() -> Self { Elements::new() }
+}
+
+impl<T> ::std::ops::Deref for Elements<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match *self {
+            Elements::Inline(Some(ref element)) => ::std::slice::from_ref(element),
+            Elements::Inline(None) => &[],
+            Elements::Heap(ref vec) => &vec[..],
+        }
+    }
+}
+
+impl<T> ::std::ops::DerefMut for Elements<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match *self {
+            Elements::Inline(Some(ref mut element)) => ::std::slice::from_mut(element),
+            Elements::Inline(None) => &mut [],
+            Elements::Heap(ref mut vec) => &mut vec[..],
+        }
+    }
 }
 
+impl<T: PartialEq> PartialEq for Elements<T> {
+    fn eq(&self, other: &Self) -> bool { self[..] == other[..] }
+}
+
+impl<T: Eq> Eq for Elements<T> { }
+
 /// An antichain based on a multiset whose elements frequencies can be updated.
 ///
 /// The `MutableAntichain` maintains frequencies for many elements of type `T`, and exposes the set
@@ -82,15 +256,40 @@ impl<T: PartialOrder> Antichain<T> {
 /// There is an `update_dirty` method for single updates that leave the `MutableAntichain` in a dirty state,
 /// but I strongly recommend against using them unless you must (on part of timely progress tracking seems
 /// to be greatly simplified by access to this)
+///
+/// Counts are `i64` by default, and may be any `Diff` type; antichains with other count types are
+/// created using `Default::default()`.
 #[derive(Clone, Debug, Default)]
-pub struct MutableAntichain<T: PartialOrder+Ord> {
+pub struct MutableAntichain<T: PartialOrder+Ord, D=i64> {
     dirty: usize,
-    updates: Vec<(T, i64)>,
-    frontier: Vec<T>,
-    frontier_temp: Vec<T>,
+    updates: Vec<(T, D)>,
+    frontier: Elements<T>,
+    frontier_temp: Elements<T>,
 }
 
 impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
+    /// Creates a new singleton `MutableAntichain`.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::{AntichainRef, MutableAntichain};
+    ///
+    /// let mut frontier = MutableAntichain::new_bottom(0u64);
+    /// assert!(frontier.frontier() == AntichainRef::new(&[0u64]));
+    ///```
+    #[inline]
+    pub fn new_bottom(bottom: T) -> MutableAntichain<T> {
+        MutableAntichain {
+            dirty: 0,
+            updates: vec![(bottom.clone(), 1)],
+            frontier: Elements::from_elem(bottom.clone()),
+            frontier_temp: Elements::new(),
+        }
+    }
+}
+
+impl<T: PartialOrder+Ord+Clone, D: Diff> MutableAntichain<T, D> {
     /// Creates a new empty `MutableAntichain`.
     ///
     /// #Examples
@@ -102,12 +301,12 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
     /// assert!(frontier.is_empty());
     ///```
     #[inline]
-    pub fn new() -> MutableAntichain<T> {
+    pub fn new() -> Self {
         MutableAntichain {
             dirty: 0,
             updates: Vec::new(),
-            frontier:  Vec::new(),
-            frontier_temp: Vec::new(),
+            frontier:  Elements::new(),
+            frontier_temp: Elements::new(),
         }
     }
 
@@ -132,7 +331,7 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
 
     /// This method deletes the contents. Unlike `clear` it records doing so.
     pub fn empty(&mut self) {
-        for index in 0 .. self.updates.len() { self.updates[index].1 = 0; }
+        for index in 0 .. self.updates.len() { self.updates[index].1 = D::default(); }
         self.dirty = self.updates.len();
     }
 
@@ -152,24 +351,40 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
         AntichainRef::new(&self.frontier)
     }
 
-    /// Creates a new singleton `MutableAntichain`.
+    /// The changes that move the antichain `other` to the frontier of `self`.
+    ///
+    /// The result contains `(time, -1)` for each element of `other` not in the frontier, and
+    /// `(time, 1)` for each element of the frontier not in `other`, sorted by time. Applying the
+    /// changes to a `MutableAntichain` whose frontier is `other`, for example one mirroring a
+    /// frontier last observed elsewhere, results in the frontier of `self`.
     ///
     /// #Examples
     ///
     ///```
-    /// use timely::progress::frontier::{AntichainRef, MutableAntichain};
+    /// use timely::progress::frontier::{Antichain, MutableAntichain};
     ///
-    /// let mut frontier = MutableAntichain::new_bottom(0u64);
-    /// assert!(frontier.frontier() == AntichainRef::new(&[0u64]));
+    /// let mut frontier = MutableAntichain::new_bottom(1u64);
+    /// let mut mirror = frontier.clone();
+    /// let observed = Antichain::from_elem(1u64);
+    ///
+    /// frontier.update_iter(vec![(1, -1), (3, 1)]);
+    /// let changes = frontier.changes_from(observed.elements());
+    /// assert_eq!(changes, vec![(1, -1), (3, 1)]);
+    ///
+    /// mirror.update_iter(changes);
+    /// assert!(mirror.frontier() == frontier.frontier());
     ///```
-    #[inline]
-    pub fn new_bottom(bottom: T) -> MutableAntichain<T> {
-        MutableAntichain {
-            dirty: 0,
-            updates: vec![(bottom.clone(), 1)],
-            frontier: vec![bottom.clone()],
-            frontier_temp: Vec::new(),
+    pub fn changes_from(&self, other: &[T]) -> Vec<(T, D)> where D: From<i8> {
+        debug_assert_eq!(self.dirty, 0);
+        let mut changes = Vec::new();
+        for time in other.iter().filter(|t| !self.frontier.contains(t)) {
+            changes.push((time.clone(), D::from(-1)));
+        }
+        for time in self.frontier.iter().filter(|t| !other.contains(t)) {
+            changes.push((time.clone(), D::from(1)));
         }
+        changes.sort();
+        changes
     }
 
     /// Returns true if there are no elements in the `MutableAntichain`.
@@ -231,7 +446,7 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
     /// akin to `update_iter`, perhaps with a `None` argument if you have no more data, as this method will
     /// tidy up the internal representation.
     #[inline]
-    pub fn update_dirty(&mut self, time: T, delta: i64) {
+    pub fn update_dirty(&mut self, time: T, delta: D) {
         self.updates.push((time, delta));
         self.dirty += 1;
     }
@@ -252,7 +467,7 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
     #[inline]
     pub fn update_iter<I>(&mut self, updates: I)
     where
-        I: IntoIterator<Item = (T, i64)>
+        I: IntoIterator<Item = (T, D)>
     {
         self.update_iter_and(updates, |_,_| { });
     }
@@ -280,7 +495,7 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
     #[inline]
     pub fn update_iter_and<I, A>(&mut self, updates: I, action: A)
     where
-        I: IntoIterator<Item = (T, i64)>,
+        I: IntoIterator<Item = (T, D)>,
         A: FnMut(&T, i64)
     {
         for (time, delta) in updates {
@@ -300,7 +515,7 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
 
             let beyond_frontier = self.frontier.iter().any(|f| f.less_than(time));
             let before_frontier = !self.frontier.iter().any(|f| f.less_equal(time));
-            rebuild_required = rebuild_required || !(beyond_frontier || (delta < 0 && before_frontier));
+            rebuild_required = rebuild_required || !(beyond_frontier || (delta < D::default() && before_frontier));
 
             self.dirty -= 1;
         }
@@ -323,16 +538,17 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
             self.updates.sort_by(|x,y| x.0.cmp(&y.0));
             for i in 0 .. self.updates.len() - 1 {
                 if self.updates[i].0 == self.updates[i+1].0 {
-                    self.updates[i+1].1 += self.updates[i].1;
-                    self.updates[i].1 = 0;
+                    let diff = self.updates[i].1;
+                    self.updates[i+1].1 += diff;
+                    self.updates[i].1 = D::default();
                 }
             }
-            self.updates.retain(|x| x.1 != 0);
+            self.updates.retain(|x| x.1 != D::default());
         }
 
         // build new frontier using strictly positive times.
         // as the times are sorted, we don't need to worry that we might displace frontier elements.
-        for time in self.updates.iter().filter(|x| x.1 > 0) {
+        for time in self.updates.iter().filter(|x| x.1 > D::default()) {
             if !self.frontier_temp.iter().any(|f| f.less_equal(&time.0)) {
                 self.frontier_temp.push(time.0.clone());
             }
@@ -354,12 +570,11 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
     }
 
     /// Reports the count for a queried time.
-    pub fn count_for(&self, query_time: &T) -> i64 {
+    pub fn count_for(&self, query_time: &T) -> D {
         self.updates
             .iter()
             .filter(|td| td.0.eq(query_time))
-            .map(|td| td.1)
-            .sum()
+            .fold(D::default(), |sum, td| sum + td.1)
     }
 }
 
@@ -452,6 +667,28 @@ impl<'a, T: 'a+PartialOrder> AntichainRef<'a, T> {
     pub fn to_vec(&self) -> Vec<T> where T: Clone {
         self.frontier.to_vec()
     }
+
+    /// The greatest lower bound of the elements of the frontier, or `None` if it is empty.
+    ///
+    /// #Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::AntichainRef;
+    ///
+    /// let frontier = AntichainRef::new(&[(1u64, 3u64), (2u64, 2u64)]);
+    /// assert_eq!(frontier.meet(), Some((1, 2)));
+    /// assert_eq!(frontier.join(), Some((2, 3)));
+    ///```
+    pub fn meet(&self) -> Option<T> where T: Lattice+Clone { meet_all(self.frontier) }
+
+    /// The least upper bound of the elements of the frontier, or `None` if it is empty.
+    pub fn join(&self) -> Option<T> where T: Lattice+Clone { join_all(self.frontier) }
+
+    /// Returns true if `time` is greater or equal to some element of `self`, and less or equal to some element of `upper`.
+    #[inline]
+    pub fn within(&self, time: &T, upper: &[T]) -> bool {
+        self.less_equal(time) && upper.iter().any(|x| time.less_equal(x))
+    }
 }
 
 impl<'a, T: PartialOrder> ::std::ops::Deref for AntichainRef<'a, T> {
@@ -3,7 +3,7 @@
 pub use self::operate::Operate;
 pub use self::nested::{Subgraph, SubgraphBuilder, Source, Target};
 pub use self::timestamp::{Timestamp, PathSummary};
-pub use self::change_batch::ChangeBatch;
+pub use self::change_batch::{ChangeBatch, Diff};
 pub use self::frontier::Antichain;
 
 pub mod change_batch;
@@ -12,3 +12,4 @@ pub mod nested;
 pub mod timestamp;
 pub mod operate;
 pub mod broadcast;
+pub mod snoop;
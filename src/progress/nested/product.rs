@@ -3,7 +3,7 @@
 // use std::cmp::Ordering;
 use std::fmt::{Formatter, Error, Debug};
 
-use ::order::{PartialOrder, TotalOrder};
+use ::order::{PartialOrder, TotalOrder, Lattice};
 use progress::Timestamp;
 use progress::nested::summary::Summary;
 
@@ -44,6 +44,13 @@ impl<TOuter: PartialOrder, TInner: PartialOrder> PartialOrder for Product<TOuter
         self.outer.less_equal(&other.outer) && self.inner.less_equal(&other.inner)
     }
 }
+impl<TOuter: Lattice, TInner: Lattice> Lattice for Product<TOuter, TInner> {
+    #[inline]
+    fn join(&self, other: &Self) -> Self { Product::new(self.outer.join(&other.outer), self.inner.join(&other.inner)) }
+    #[inline]
+    fn meet(&self, other: &Self) -> Self { Product::new(self.outer.meet(&other.outer), self.inner.meet(&other.inner)) }
+}
+
 // impl<TOuter: PartialOrd, TInner: PartialOrd> PartialOrd for Product<TOuter, TInner> {
 //     #[inline(always)]
 //     fn partial_cmp(&self, other: &Product<TOuter, TInner>) -> Option<Ordering> {
@@ -40,6 +40,8 @@
 //! assert_eq!(tracker.pushed_mut(2)[0].drain().collect::<Vec<_>>(), vec![(17, 1)]);
 //! ```
 
+use std::collections::BTreeMap;
+
 use progress::Timestamp;
 use progress::nested::{Source, Target};
 use progress::ChangeBatch;
@@ -106,6 +108,8 @@ pub struct Builder<T: Timestamp> {
     edges: Vec<Vec<Vec<Target>>>,
     /// Numbers of inputs and outputs for each node.
     shape: Vec<(usize, usize)>,
+    /// The summary of the current nodes and edges, if computed since they last changed.
+    summary: Option<Summary<T>>,
 }
 
 impl<T: Timestamp> Builder<T> {
@@ -116,6 +120,7 @@ impl<T: Timestamp> Builder<T> {
             nodes: Vec::new(),
             edges: Vec::new(),
             shape: Vec::new(),
+            summary: None,
         }
     }
 
@@ -134,6 +139,7 @@ impl<T: Timestamp> Builder<T> {
             self.shape.push((0, 0));
         }
 
+        self.summary = None;
         self.nodes[index] = summary;
         if self.edges[index].len() != outputs {
             self.edges[index] = vec![Vec::new(); outputs];
@@ -151,15 +157,73 @@ impl<T: Timestamp> Builder<T> {
         debug_assert!(source.port < self.shape[source.index].1);
         debug_assert!(target.port < self.shape[target.index].0);
 
+        self.summary = None;
         self.edges[source.index][source.port].push(target);
     }
 
+    /// Reports the targets lying on cycles along which timestamps need not strictly advance.
+    ///
+    /// Such a cycle is a serious liveness issue: a capability on the cycle prevents its own times
+    /// from becoming complete, and the frontiers of the operators on the cycle will never advance.
+    /// The result is sorted, and empty for valid graphs.
+    ///
+    /// #Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::subgraph::{Source, Target};
+    /// use timely::progress::nested::reachability::Builder;
+    ///
+    /// let mut builder = Builder::<usize>::new();
+    /// builder.add_node(0, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_edge(Source { index: 0, port: 0}, Target { index: 1, port: 0} );
+    /// builder.add_edge(Source { index: 1, port: 0}, Target { index: 0, port: 0} );
+    ///
+    /// let cycles = builder.unadvancing_cycles();
+    /// assert_eq!(cycles, vec![Target { index: 0, port: 0 }, Target { index: 1, port: 0 }]);
+    /// ```
+    pub fn unadvancing_cycles(&mut self) -> Vec<Target> {
+        let summary = self.summarize();
+        let mut cycles = Vec::new();
+        for index in 0 .. self.nodes.len() {
+            for input_port in 0 .. self.nodes[index].len() {
+                let this_target = Target { index, port: input_port };
+                let unadvancing = self.nodes[index][input_port].iter().enumerate().any(|(output_port, internal_summaries)| {
+                    internal_summaries.elements().iter().any(|internal_summary| {
+                        summary.source_target[index][output_port].iter().any(|&(ref target, ref summaries)| {
+                            target == &this_target && summaries.elements().iter().any(|path_summary| {
+                                internal_summary.followed_by(path_summary)
+                                                .map(|cycle| cycle.less_equal(&Default::default()))
+                                                .unwrap_or(false)
+                            })
+                        })
+                    })
+                });
+                if unadvancing {
+                    cycles.push(this_target);
+                }
+            }
+        }
+        cycles
+    }
+
     /// Compiles the current nodes and edges into immutable path summaries.
     ///
-    /// This method has the opportunity to perform some error checking that the path summaries
-    /// are valid, including references to undefined nodes and ports, as well as self-loops with
-    /// default summaries (a serious liveness issue).
+    /// This method does not check that the path summaries are valid; in particular, cycles along
+    /// which timestamps need not advance (a serious liveness issue) are reported separately by
+    /// `unadvancing_cycles`.
+    ///
+    /// The summary is cached until nodes or edges are next added, and so repeated calls are cheap.
     pub fn summarize(&mut self) -> Summary<T> {
+        if self.summary.is_none() {
+            self.summary = Some(self.compute_summary());
+        }
+        self.summary.clone().expect("summary just computed")
+    }
+
+    /// Computes path summaries from the current nodes and edges.
+    fn compute_summary(&self) -> Summary<T> {
 
         // We maintain a list of new ((source, target), path_summary) entries whose implications
         // have not yet been fully explored. While such entries exist, we consider the next and
@@ -222,21 +286,6 @@ impl<T: Timestamp> Builder<T> {
             }
         }
 
-        // Test for trivial summaries along self-loops.
-        #[cfg(debug_assertions)]
-        {
-            for node in 0 .. target_target.len() {
-                for port in 0 .. target_target[node].len() {
-                    let this_target = Target { index: node, port };
-                    for &(ref target, ref summary) in target_target[node][port].iter() {
-                        if target == &this_target && summary.less_equal(&Default::default()) {
-                            panic!("Default summary found along self-loop: {:?}", target);
-                        }
-                    }
-                }
-            }
-        }
-
         // Incorporate trivial self-loops, as changes at a target do apply to the target.
         for index in 0 .. self.nodes.len() {
             for input_port in 0 .. self.nodes[index].len() {
@@ -255,11 +304,199 @@ impl<T: Timestamp> Builder<T> {
     }
 }
 
+impl<T: Timestamp> Builder<T> {
+
+    /// Compiles path summaries to the targets selected by `observed`, collapsing straight-line pipelines.
+    ///
+    /// The result agrees with `summarize` restricted to observed targets. A straight-line pipeline is
+    /// a chain of nodes, each with one unobserved input and one output, in which each node's input is
+    /// connected only to the output of the previous node, and that output to nothing else. Paths are
+    /// explored through each chain as if it were a single node, whose summary composes those of its
+    /// members, and the summaries from the outputs of members are derived from that of the last. The
+    /// members retain their own ports, and so their identities for scheduling and logging.
+    ///
+    /// Wide dataflows with many stages that do not observe their frontiers spend much less time here
+    /// than in `summarize`, which explores every path through every stage.
+    ///
+    /// #Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::subgraph::{Source, Target};
+    /// use timely::progress::nested::reachability::Builder;
+    ///
+    /// // a pipeline of five nodes, the last of which increments and feeds back to the first.
+    /// let mut builder = Builder::<usize>::new();
+    /// for index in 0 .. 5 {
+    ///     builder.add_node(index, 1, 1, vec![vec![Antichain::from_elem(if index == 4 { 1 } else { 0 })]]);
+    /// }
+    /// for index in 0 .. 5 {
+    ///     builder.add_edge(Source { index, port: 0 }, Target { index: (index + 1) % 5, port: 0 });
+    /// }
+    ///
+    /// // only the first node observes its frontier.
+    /// let observed = |target: Target| target.index == 0;
+    /// let collapsed = builder.summarize_observed(observed);
+    /// let complete = builder.summarize();
+    ///
+    /// for index in 0 .. 5 {
+    ///     let source = Source { index, port: 0 };
+    ///     let target = Target { index: 0, port: 0 };
+    ///     assert_eq!(collapsed.source_results(source, &3, target), complete.source_results(source, &3, target));
+    ///     assert_eq!(collapsed.source_target[index][0].len(), 1);
+    /// }
+    /// ```
+    pub fn summarize_observed<F: Fn(Target)->bool>(&self, observed: F) -> Summary<T> {
+
+        let nodes = self.nodes.len();
+
+        // Count the edges arriving at each target.
+        let mut in_edges = self.shape.iter().map(|&(inputs, _)| vec![0; inputs]).collect::<Vec<_>>();
+        for outputs in self.edges.iter() {
+            for targets in outputs.iter() {
+                for target in targets.iter() {
+                    in_edges[target.index][target.port] += 1;
+                }
+            }
+        }
+
+        // A node may join a pipeline if it has one unobserved input and one output, and it continues
+        // the pipeline of a joinable node whose only edge leads to its only input, and only there.
+        let joinable = (0 .. nodes).map(|index| self.shape[index] == (1, 1) && !observed(Target { index, port: 0 })).collect::<Vec<_>>();
+        let mut next = vec![None; nodes];
+        let mut previous = vec![None; nodes];
+        for index in 0 .. nodes {
+            if joinable[index] && self.edges[index][0].len() == 1 {
+                let target = self.edges[index][0][0];
+                if target.index != index && joinable[target.index] && in_edges[target.index][0] == 1 {
+                    next[index] = Some(target.index);
+                    previous[target.index] = Some(index);
+                }
+            }
+        }
+
+        // Assemble pipelines from their first nodes; cycles of joinable nodes have none, and are
+        // explored as usual. For each member, record the composed summary of the members after it.
+        let mut pipelines = vec![None; nodes];  // first node: (last node, composed summary)
+        let mut interior = vec![None; nodes];   // interior node: (last node, summary of the rest)
+        for first in 0 .. nodes {
+            if next[first].is_some() && previous[first].is_none() {
+                let mut members = vec![first];
+                while let Some(node) = next[*members.last().unwrap()] {
+                    members.push(node);
+                }
+                let last = *members.last().unwrap();
+                let mut rest = Antichain::from_elem(Default::default());
+                for &member in members.iter().rev().skip(1) {
+                    let successor = next[member].unwrap();
+                    rest = compose::<T>(&self.nodes[successor][0][0], &rest);
+                    interior[member] = Some((last, rest.clone()));
+                }
+                pipelines[first] = Some((last, compose::<T>(&self.nodes[first][0][0], &rest)));
+            }
+        }
+
+        let mut work = ::std::collections::VecDeque::<((Source, Target), T::Summary)>::new();
+
+        // Initialize `work` with edges leaving nodes other than interior members of pipelines.
+        for index in 0 .. self.edges.len() {
+            if interior[index].is_none() {
+                for port in 0 .. self.edges[index].len() {
+                    for &target in &self.edges[index][port] {
+                        work.push_back(((Source { index, port }, target), Default::default()));
+                    }
+                }
+            }
+        }
+
+        let mut source_target: Vec<Vec<Vec<(Target, Antichain<T::Summary>)>>> = Vec::new();
+        let mut target_target: Vec<Vec<Vec<(Target, Antichain<T::Summary>)>>> = Vec::new();
+
+        for &(inputs, outputs) in self.shape.iter() {
+            source_target.push(vec![Vec::new(); outputs]);
+            target_target.push(vec![Vec::new(); inputs]);
+        }
+
+        // Establish source-target path summaries by fixed-point computation, crossing each pipeline
+        // in one step.
+        while let Some(((source, target), summary)) = work.pop_front() {
+            if add_summary(&mut source_target[source.index][source.port], target, summary.clone()) {
+                if let Some((last, ref composed)) = pipelines[target.index] {
+                    for internal_summary in composed.elements() {
+                        if let Some(new_summary) = summary.followed_by(internal_summary) {
+                            for &new_target in self.edges[last][0].iter() {
+                                work.push_back(((source, new_target), new_summary.clone()));
+                            }
+                        }
+                    }
+                }
+                else {
+                    for (new_source_port, internal_summaries) in self.nodes[target.index][target.port].iter().enumerate() {
+                        for internal_summary in internal_summaries.elements() {
+                            if let Some(new_summary) = summary.followed_by(internal_summary) {
+                                for &new_target in self.edges[target.index][new_source_port].iter() {
+                                    work.push_back(((source, new_target), new_summary.clone()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Retain only observed targets, which excludes the members of pipelines.
+        for sources in source_target.iter_mut() {
+            for targets in sources.iter_mut() {
+                targets.retain(|&(target, _)| observed(target));
+            }
+        }
+
+        // Derive the summaries from interior members of pipelines from those of their last members.
+        for index in 0 .. nodes {
+            if let Some((last, ref rest)) = interior[index] {
+                let mut derived = Vec::new();
+                for &(target, ref summaries) in source_target[last][0].iter() {
+                    for summary in compose::<T>(rest, summaries).elements() {
+                        add_summary(&mut derived, target, summary.clone());
+                    }
+                }
+                source_target[index][0] = derived;
+            }
+        }
+
+        // Extend source-target path summaries by one target'-source connection, as in `summarize`.
+        for index in 0 .. self.nodes.len() {
+            for input_port in 0 .. self.nodes[index].len() {
+                for (output_port, internal_summaries) in self.nodes[index][input_port].iter().enumerate() {
+                    for internal_summary in internal_summaries.elements() {
+                        for &(target, ref new_summaries) in source_target[index][output_port].iter() {
+                            for new_summary in new_summaries.elements() {
+                                if let Some(summary) = internal_summary.followed_by(new_summary) {
+                                    add_summary(&mut target_target[index][input_port], target, summary);
+                                }
+                            }
+                        }
+                    }
+                }
+                let target = Target { index, port: input_port };
+                if observed(target) {
+                    add_summary(&mut target_target[index][input_port], target, Default::default());
+                }
+            }
+        }
+
+        Summary {
+            source_target,
+            target_target,
+        }
+    }
+}
+
 /// A summary of minimal path summaries in a timely dataflow graph.
 ///
 /// A `Summary` instance records a compiled representation of path summaries along paths
 /// in a timely dataflow graph, mostly commonly constructed by a `reachability::Builder`.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Summary<T: Timestamp> {
 
     // TODO: As all of this information is static, we should be able to flatten it into
@@ -280,6 +517,121 @@ pub struct Summary<T: Timestamp> {
     pub target_target: Vec<Vec<Vec<(Target, Antichain<T::Summary>)>>>,
 }
 
+impl<T: Timestamp> Summary<T> {
+
+    /// Reports the least times at which a pointstamp at `source` at `time` could arrive at `target`.
+    ///
+    /// The result is empty if no path connects `source` to `target`, or if every path advances
+    /// `time` beyond the range of the timestamp type.
+    ///
+    /// #Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::subgraph::{Source, Target};
+    /// use timely::progress::nested::reachability::Builder;
+    ///
+    /// let mut builder = Builder::<usize>::new();
+    /// builder.add_node(0, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(1)]]);
+    /// builder.add_node(2, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_edge(Source { index: 0, port: 0}, Target { index: 1, port: 0} );
+    /// builder.add_edge(Source { index: 1, port: 0}, Target { index: 0, port: 0} );
+    ///
+    /// let summary = builder.summarize();
+    /// let source = Source { index: 0, port: 0 };
+    /// assert_eq!(summary.source_results(source, &3, Target { index: 0, port: 0 }).elements(), &[4]);
+    /// assert!(summary.source_results(source, &3, Target { index: 2, port: 0 }).elements().is_empty());
+    /// ```
+    pub fn source_results(&self, source: Source, time: &T, target: Target) -> Antichain<T> {
+        results(&self.source_target[source.index][source.port], time, target)
+    }
+
+    /// Reports the least times at which a pointstamp at `origin` at `time` could arrive at `target`.
+    ///
+    /// Each target reaches itself with the default summary, and so the result for `target` equal to
+    /// `origin` contains `time`.
+    pub fn target_results(&self, origin: Target, time: &T, target: Target) -> Antichain<T> {
+        results(&self.target_target[origin.index][origin.port], time, target)
+    }
+
+    /// The path summaries from `origin` to `target`, if any path connects them.
+    pub fn summaries(&self, origin: Location, target: Target) -> Option<&Antichain<T::Summary>> {
+        let reachable = match origin {
+            Location::Source(source) => &self.source_target[source.index][source.port],
+            Location::Target(origin) => &self.target_target[origin.index][origin.port],
+        };
+        reachable.iter().find(|x| x.0 == target).map(|x| &x.1)
+    }
+
+    /// The path summaries between all connected pairs of locations, for diagnostics.
+    ///
+    /// #Examples
+    ///
+    /// ```rust
+    /// use timely::progress::frontier::Antichain;
+    /// use timely::progress::nested::subgraph::{Source, Target};
+    /// use timely::progress::nested::reachability::{Builder, Location};
+    ///
+    /// let mut builder = Builder::<usize>::new();
+    /// builder.add_node(0, 1, 1, vec![vec![Antichain::from_elem(1)]]);
+    /// builder.add_node(1, 1, 1, vec![vec![Antichain::from_elem(0)]]);
+    /// builder.add_edge(Source { index: 0, port: 0}, Target { index: 1, port: 0} );
+    ///
+    /// let summary = builder.summarize();
+    /// let matrix = summary.matrix();
+    /// let origin = Location::Target(Target { index: 0, port: 0 });
+    /// let target = Target { index: 1, port: 0 };
+    /// assert_eq!(matrix[&(origin, target)].elements(), &[1]);
+    /// assert_eq!(summary.summaries(origin, target), Some(&matrix[&(origin, target)]));
+    /// for ((origin, target), summaries) in matrix.iter() {
+    ///     println!("{:?} -> {:?}: {:?}", origin, target, summaries.elements());
+    /// }
+    /// ```
+    pub fn matrix(&self) -> BTreeMap<(Location, Target), Antichain<T::Summary>> {
+        let mut matrix = BTreeMap::new();
+        for (index, ports) in self.source_target.iter().enumerate() {
+            for (port, reachable) in ports.iter().enumerate() {
+                for &(target, ref summaries) in reachable.iter() {
+                    matrix.insert((Location::Source(Source { index, port }), target), summaries.clone());
+                }
+            }
+        }
+        for (index, ports) in self.target_target.iter().enumerate() {
+            for (port, reachable) in ports.iter().enumerate() {
+                for &(target, ref summaries) in reachable.iter() {
+                    matrix.insert((Location::Target(Target { index, port }), target), summaries.clone());
+                }
+            }
+        }
+        matrix
+    }
+}
+
+/// A port of a node, from which path summaries lead to targets.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Location {
+    /// An output port.
+    Source(Source),
+    /// An input port.
+    Target(Target),
+}
+
+/// Applies the summaries in `reachable` leading to `target` to `time`.
+fn results<T: Timestamp>(reachable: &[(Target, Antichain<T::Summary>)], time: &T, target: Target) -> Antichain<T> {
+    let mut results = Antichain::new();
+    for &(ref reached, ref summaries) in reachable.iter() {
+        if reached == &target {
+            for summary in summaries.elements() {
+                if let Some(result) = summary.results_in(time) {
+                    results.insert(result);
+                }
+            }
+        }
+    }
+    results
+}
+
 /// An interactive tracker of propagated reachability information.
 ///
 /// A `Tracker` tracks, for a fixed graph topology, the consequences of
@@ -394,6 +746,32 @@ impl<T:Timestamp> Tracker<T> {
         self.targets.iter_mut().any(|x| x.iter_mut().any(|y| !y.is_empty()))
     }
 
+    /// Releases the state of a node that will neither hold nor receive pointstamps again.
+    ///
+    /// The node's frontiers and buffers are replaced by empty allocations, and paths to and from the
+    /// node are removed, so that propagation no longer visits it. The node's ports remain valid, and
+    /// may still be inspected, but any updates at them are ignored by other nodes.
+    pub fn retire(&mut self, index: usize) {
+
+        debug_assert!(self.sources[index].iter().all(|x| x.is_empty()));
+        debug_assert!(self.targets[index].iter().all(|x| x.is_empty()));
+
+        for summaries in self.source_target.iter_mut() {
+            for port in summaries.iter_mut() { port.retain(|&(t, _)| t.index != index); }
+        }
+        for summaries in self.target_target.iter_mut() {
+            for port in summaries.iter_mut() { port.retain(|&(t, _)| t.index != index); }
+        }
+
+        let outputs = self.sources[index].len();
+        let inputs = self.targets[index].len();
+        self.source_target[index] = vec![Vec::new(); outputs];
+        self.target_target[index] = vec![Vec::new(); inputs];
+        self.sources[index] = vec![MutableAntichain::new(); outputs];
+        self.targets[index] = vec![MutableAntichain::new(); inputs];
+        self.pusheds[index] = vec![ChangeBatch::new(); inputs];
+    }
+
     /// Allocate a new `Tracker` using the shape from `summaries`.
     pub fn allocate_from(summary: Summary<T>) -> Self {
 
@@ -492,4 +870,17 @@ fn add_summary<S: PartialOrder+Eq>(vector: &mut Vec<(Target, Antichain<S>)>, tar
     }
     vector.push((target, Antichain::from_elem(summary)));
     true
-}
\ No newline at end of file
+}
+
+/// Composes the path summaries of `first` with those of `second`, which follow them.
+fn compose<T: Timestamp>(first: &Antichain<T::Summary>, second: &Antichain<T::Summary>) -> Antichain<T::Summary> {
+    let mut result = Antichain::new();
+    for summary1 in first.elements() {
+        for summary2 in second.elements() {
+            if let Some(summary) = summary1.followed_by(summary2) {
+                result.insert(summary);
+            }
+        }
+    }
+    result
+}
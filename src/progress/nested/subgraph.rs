@@ -1,18 +1,22 @@
 //! Implements `Operate` for a scoped collection of child operators.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::default::Default;
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant};
 
 use communication::Allocate;
 
 use logging::Logger;
+use config::WorkerConfig;
 
 use progress::frontier::{MutableAntichain, Antichain};
 use progress::{Timestamp, Operate};
 
 use progress::ChangeBatch;
-use progress::broadcast::Progcaster;
+use progress::broadcast::{Progcaster, ProgressMode};
+use progress::snoop::{Snooper, SnoopMsg};
 use progress::nested::summary::Summary::{Local, Outer};
 use progress::nested::product::Product;
 use progress::nested::reachability;
@@ -72,6 +76,21 @@ pub struct SubgraphBuilder<TOuter: Timestamp, TInner: Timestamp> {
     // expressed capabilities, used to filter changes against.
     output_capabilities: Vec<MutableAntichain<TOuter>>,
 
+    // subscribers to changes in the frontiers of targets.
+    snoopers: Vec<Sender<SnoopMsg<Product<TOuter, TInner>>>>,
+
+    // how the subgraph exchanges progress updates with other workers.
+    progress_mode: ProgressMode,
+
+    // the budget of children without their own.
+    budget: Option<Duration>,
+
+    // what to do if the subgraph is dropped before completing, shared with nested subgraphs.
+    unfinished: Rc<Cell<UnfinishedPolicy>>,
+
+    // the most recent summary, and the numbers of children, edges, inputs, and outputs it reflects.
+    summary: RefCell<Option<((usize, usize, usize, usize), reachability::Summary<Product<TOuter, TInner>>)>>,
+
     /// Logging handle
     logging: Logger,
 }
@@ -91,6 +110,31 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
         Source { index: self.index, port: self.output_capabilities.len() - 1 }
     }
 
+    /// Sets how the subgraph exchanges progress updates with other workers.
+    pub fn set_progress_mode(&mut self, mode: ProgressMode) {
+        self.progress_mode = mode;
+    }
+
+    /// Sets the execution-time budget of children added subsequently without budgets of their own.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+    }
+
+    /// The execution-time budget of children added subsequently without budgets of their own.
+    pub fn budget(&self) -> Option<Duration> { self.budget }
+
+    /// Sets what the subgraph does if dropped before completing.
+    ///
+    /// The policy is shared, so that it may be changed after the subgraph is built, for example to
+    /// ignore a dataflow that is deliberately cancelled. Nested subgraphs should share the policy of
+    /// their parent.
+    pub fn set_unfinished(&mut self, policy: Rc<Cell<UnfinishedPolicy>>) {
+        self.unfinished = policy;
+    }
+
+    /// The shared policy applied if the subgraph is dropped before completing.
+    pub fn unfinished(&self) -> Rc<Cell<UnfinishedPolicy>> { self.unfinished.clone() }
+
     /// Introduces a dependence from the source to the target.
     ///
     /// This method does not effect data movement, but rather reveals to the progress tracking infrastructure
@@ -99,6 +143,55 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
         self.edge_stash.push((source, target));
     }
 
+    /// Summarizes the paths between ports of the subgraph, as constructed so far.
+    ///
+    /// The summary describes, for each output (`Source`) and input (`Target`) port of each child,
+    /// the minimal actions a timestamp undergoes along paths to each reachable input port. Child
+    /// zero stands for the outside world, and its inputs and outputs are the subgraph's outputs
+    /// and inputs, respectively; paths leaving the subgraph are not continued outside of it.
+    ///
+    /// As the subgraph only grows, the summary is cached until children, edges, inputs, or outputs
+    /// are added.
+    pub fn summarize(&self) -> reachability::Summary<Product<TOuter, TInner>> {
+        let shape = (self.children.len(), self.edge_stash.len(), self.input_messages.len(), self.output_capabilities.len());
+        let mut cached = self.summary.borrow_mut();
+        if cached.as_ref().map(|x| x.0 != shape).unwrap_or(true) {
+            *cached = Some((shape, self.topology().summarize()));
+        }
+        cached.as_ref().expect("summary just computed").1.clone()
+    }
+
+    /// Assembles a reachability builder from the children and edges of the subgraph.
+    fn topology(&self) -> reachability::Builder<Product<TOuter, TInner>> {
+
+        let inputs = self.input_messages.len();
+        let outputs = self.output_capabilities.len();
+
+        let mut builder = reachability::Builder::new();
+
+        // Child 0 has `inputs` outputs and `outputs` inputs, not yet connected.
+        builder.add_node(0, outputs, inputs, vec![vec![Antichain::new(); inputs]; outputs]);
+        for child in self.children.iter().skip(1) {
+            builder.add_node(child.index, child.inputs, child.outputs, child.gis_summary.clone());
+        }
+
+        for &(source, target) in self.edge_stash.iter() {
+            builder.add_edge(source, target);
+        }
+
+        builder
+    }
+
+    /// Subscribes to changes in the frontiers of the subgraph's targets.
+    ///
+    /// The returned `Snooper` receives the initial frontiers once the subgraph is built, and
+    /// subsequent changes after each step of the subgraph.
+    pub fn snoop(&mut self) -> Snooper<Product<TOuter, TInner>> {
+        let (sender, receiver) = channel();
+        self.snoopers.push(sender);
+        Snooper::new(receiver)
+    }
+
     /// Creates a new Subgraph from a channel allocator and "descriptive" indices.
     pub fn new_from(index: usize, mut path: Vec<usize>, logging: Logger) -> SubgraphBuilder<TOuter, TInner> {
         path.push(index);
@@ -117,6 +210,12 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
             input_messages:      Default::default(),
             output_capabilities: Default::default(),
 
+            snoopers:            Vec::new(),
+            progress_mode:       ProgressMode::AllToAll,
+            budget:              None,
+            unfinished:          Rc::new(Cell::new(UnfinishedPolicy::Ignore)),
+            summary:             RefCell::new(None),
+
             logging,
         }
     }
@@ -138,7 +237,10 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
                 name: child.name().to_owned(),
             })));
         }
-        self.children.push(PerOperatorState::new(child, index, self.path.clone(), identifier, self.logging.clone()))
+        let budget = child.budget().or(self.budget);
+        let mut state = PerOperatorState::new(child, index, self.path.clone(), identifier, self.logging.clone());
+        state.budget = budget;
+        self.children.push(state)
     }
 
     /// Now that initialization is complete, actually build a subgraph.
@@ -152,25 +254,28 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
         self.children.sort_by(|x,y| x.index.cmp(&y.index));
         assert!(self.children.iter().enumerate().all(|(i,x)| i == x.index));
 
-        let inputs = self.input_messages.len();
-        let outputs = self.output_capabilities.len();
-
-        let mut builder = reachability::Builder::new();
-
-        // Child 0 has `inputs` outputs and `outputs` inputs, not yet connected.
-        builder.add_node(0, outputs, inputs, vec![vec![Antichain::new(); inputs]; outputs]);
-        for (index, child) in self.children.iter().enumerate().skip(1) {
-            builder.add_node(index, child.inputs, child.outputs, child.gis_summary.clone());
+        let mut builder = self.topology();
+
+        // fail fast on cycles that would freeze the frontiers of their operators.
+        let cycles = builder.unadvancing_cycles();
+        if !cycles.is_empty() {
+            let mut operators = cycles.iter().map(|target| target.index).collect::<Vec<_>>();
+            operators.dedup();
+            let described = operators.iter().map(|&index| {
+                let mut addr = self.path.clone();
+                addr.push(index);
+                format!("{}{:?}", self.children[index].name, addr)
+            }).collect::<Vec<_>>();
+            panic!("{}{:?} contains cycles along which timestamps need not advance, through operators: {}", self.name, self.path, described.join(", "));
         }
 
         for (source, target) in self.edge_stash {
             self.children[source.index].edges[source.port].push(target);
-            builder.add_edge(source, target);
         }
 
         let tracker = reachability::Tracker::allocate_from(builder.summarize());
 
-        let progcaster = Progcaster::new(allocator, &self.path, self.logging.clone());
+        let progcaster = Progcaster::new_with_mode(allocator, &self.path, self.logging.clone(), self.progress_mode);
 
         Subgraph {
             name: self.name,
@@ -190,10 +295,58 @@ impl<TOuter: Timestamp, TInner: Timestamp> SubgraphBuilder<TOuter, TInner> {
 
             pointstamp_builder: builder,
             pointstamp_tracker: tracker,
+
+            snoopers: self.snoopers,
+            snoop_buffer: Vec::new(),
+
+            unfinished: self.unfinished,
+        }
+    }
+}
+
+/// What a subgraph does if it is dropped before completing.
+///
+/// A dataflow dropped with messages outstanding, or with capabilities held (for example, by inputs
+/// that were never closed), silently loses the data it would have produced. This commonly happens
+/// when a worker exits before its dataflows complete, and may hide errors in tests.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnfinishedPolicy {
+    /// Drop the subgraph silently.
+    Ignore,
+    /// Describe the outstanding messages and held capabilities on standard error.
+    Report,
+    /// Panic with a description of the outstanding messages and held capabilities.
+    Panic,
+}
+
+impl UnfinishedPolicy {
+    /// Reads the policy from the value of the key `unfinished-dataflows` of a worker configuration.
+    ///
+    /// The value `report` selects `Report` and the value `panic` selects `Panic`; otherwise, and if the
+    /// key is absent, the policy is `Ignore`.
+    ///
+    /// #Examples
+    /// ```
+    /// use timely::config::WorkerConfig;
+    /// use timely::progress::nested::subgraph::UnfinishedPolicy;
+    ///
+    /// let config = WorkerConfig::new().set("unfinished-dataflows", "panic");
+    /// assert_eq!(UnfinishedPolicy::from_config(&config), UnfinishedPolicy::Panic);
+    /// assert_eq!(UnfinishedPolicy::from_config(&WorkerConfig::new()), UnfinishedPolicy::Ignore);
+    /// ```
+    pub fn from_config(config: &WorkerConfig) -> Self {
+        match config.get("unfinished-dataflows") {
+            Some("report") => UnfinishedPolicy::Report,
+            Some("panic") => UnfinishedPolicy::Panic,
+            _ => UnfinishedPolicy::Ignore,
         }
     }
 }
 
+impl Default for UnfinishedPolicy {
+    fn default() -> Self { UnfinishedPolicy::Ignore }
+}
+
 
 /// A dataflow subgraph.
 ///
@@ -228,6 +381,54 @@ pub struct Subgraph<TOuter:Timestamp, TInner:Timestamp> {
 
     // channel / whatever used to communicate pointstamp updates to peers.
     progcaster: Progcaster<Product<TOuter, TInner>>,
+
+    // subscribers to changes in the frontiers of targets, and changes yet to be reported to them.
+    snoopers: Vec<Sender<SnoopMsg<Product<TOuter, TInner>>>>,
+    snoop_buffer: SnoopMsg<Product<TOuter, TInner>>,
+
+    // what to do if dropped before completing.
+    unfinished: Rc<Cell<UnfinishedPolicy>>,
+}
+
+impl<TOuter: Timestamp, TInner: Timestamp> Subgraph<TOuter, TInner> {
+    /// Describes the messages outstanding at, and capabilities held by, the children of the subgraph.
+    fn describe_unfinished(&mut self) -> Vec<String> {
+        self.pointstamp_tracker.propagate_all();
+        let mut described = Vec::new();
+        for index in 1 .. self.children.len() {
+            let (targets, sources, _) = self.pointstamp_tracker.node_state(index);
+            let child = &self.children[index];
+            for (port, target) in targets.iter().enumerate() {
+                if !target.is_empty() {
+                    described.push(format!("{}{:?} input {} has messages outstanding at {:?}", child.name, child.addr, port, target.frontier().to_vec()));
+                }
+            }
+            for (port, source) in sources.iter().enumerate() {
+                if !source.is_empty() {
+                    described.push(format!("{}{:?} output {} holds capabilities at {:?}", child.name, child.addr, port, source.frontier().to_vec()));
+                }
+            }
+        }
+        described
+    }
+}
+
+impl<TOuter: Timestamp, TInner: Timestamp> Drop for Subgraph<TOuter, TInner> {
+    fn drop(&mut self) {
+        let policy = self.unfinished.get();
+        if policy != UnfinishedPolicy::Ignore {
+            let described = self.describe_unfinished();
+            if !described.is_empty() {
+                let message = format!("{}{:?} dropped before completing:\n  {}", self.name, self.path, described.join("\n  "));
+                if policy == UnfinishedPolicy::Panic && !::std::thread::panicking() {
+                    panic!("{}", message);
+                }
+                else {
+                    eprintln!("{}", message);
+                }
+            }
+        }
+    }
 }
 
 
@@ -275,7 +476,8 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
         // done with the pointstamps, so we should clean up.
         self.pointstamp_tracker.clear();
 
-        let summary = self.pointstamp_builder.summarize();
+        // Only paths to the subgraph's outputs are of interest.
+        let summary = self.pointstamp_builder.summarize_observed(|target| target.index == 0);
 
         // Summarize the scope internals by looking for source_target_summaries from child 0
         // sources to child 0 targets. These summaries are only in terms of the outer timestamp.
@@ -327,13 +529,11 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
         // zero. This prevents the subgraph from reporting the external world's capabilities back as capabilities
         // held by the subgraph. We also remove summaries to nodes that do not require progress information.
         // self.pointstamp_builder.add_node(0, self.outputs, self.inputs, new_summary);
-        let mut pointstamp_summaries = self.pointstamp_builder.summarize();
+        // Summaries to nodes that do not require progress information are never produced, and chains of
+        // such nodes are explored as single nodes.
+        let mut pointstamp_summaries = self.pointstamp_builder.summarize_observed(|target| self.children[target.index].notify);
         for summaries in pointstamp_summaries.target_target[0].iter_mut() { summaries.retain(|&(t, _)| t.index > 0); }
         for summaries in pointstamp_summaries.source_target[0].iter_mut() { summaries.retain(|&(t, _)| t.index > 0); }
-        for child in 0 .. self.children.len() {
-            for summaries in pointstamp_summaries.target_target[child].iter_mut() { summaries.retain(|&(t,_)| self.children[t.index].notify); }
-            for summaries in pointstamp_summaries.source_target[child].iter_mut() { summaries.retain(|&(t,_)| self.children[t.index].notify); }
-        }
 
         // Allocate the pointstamp tracker using the finalized topology.
         self.pointstamp_tracker = reachability::Tracker::allocate_from(pointstamp_summaries.clone());
@@ -378,9 +578,14 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
             }
 
             let child_index = child.index;
+            if !self.snoopers.is_empty() {
+                snoop_record(&mut self.snoop_buffer, child_index, self.pointstamp_tracker.pushed_mut(child_index));
+            }
             child.set_external_summary(summary, self.pointstamp_tracker.pushed_mut(child_index));
         }
 
+        snoop_report(&mut self.snoopers, &mut self.snoop_buffer);
+
         // clean up after ourselves.
         assert!(self.pointstamp_tracker.is_empty());
     }
@@ -539,6 +744,12 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
 
             let (targets, sources, pushed) = self.pointstamp_tracker.node_state(index);
 
+            if !self.snoopers.is_empty() {
+                snoop_record(&mut self.snoop_buffer, index, pushed);
+            }
+
+            let was_open = child.operator.is_some();
+
             let child_active = child.exchange_progress(
                 pushed,
                 targets,
@@ -546,10 +757,22 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
                 message_buffer,
                 internal_buffer);
 
+            // A child shut down in this call will never again hold or receive pointstamps, and
+            // its progress state can be released. This is most important for completed nested
+            // scopes, whose internal progress state is dropped along with the child itself.
+            if was_open && child.operator.is_none() {
+                child.release();
+                self.pointstamp_tracker.retire(index);
+            }
+
             any_child_active = any_child_active || child_active;
         }
 
         // Step 6. Child zero's frontier information are reported as capabilities via `internal`.
+        if !self.snoopers.is_empty() {
+            snoop_record(&mut self.snoop_buffer, 0, self.pointstamp_tracker.pushed_mut(0));
+            snoop_report(&mut self.snoopers, &mut self.snoop_buffer);
+        }
         for (output, pointstamps) in self.pointstamp_tracker.pushed_mut(0).iter_mut().enumerate() {
             let iterator = pointstamps.drain().map(|(time, diff)| (time.outer, diff));
             self.output_capabilities[output].update_iter_and(iterator, |t, v| {
@@ -567,11 +790,27 @@ impl<TOuter: Timestamp, TInner: Timestamp> Operate<TOuter> for Subgraph<TOuter,
     }
 }
 
+/// Records the changes in `pushed` as changes to the frontiers of the inputs of child `index`.
+fn snoop_record<T: Timestamp>(buffer: &mut SnoopMsg<T>, index: usize, pushed: &mut [ChangeBatch<T>]) {
+    for (port, changes) in pushed.iter_mut().enumerate() {
+        for &(ref time, diff) in changes.iter() {
+            buffer.push((Target { index, port }, time.clone(), diff));
+        }
+    }
+}
+
+/// Sends `buffer` to each of `snoopers`, discarding those whose receivers have been dropped.
+fn snoop_report<T: Timestamp>(snoopers: &mut Vec<Sender<SnoopMsg<T>>>, buffer: &mut SnoopMsg<T>) {
+    if !buffer.is_empty() {
+        snoopers.retain(|snooper| snooper.send(buffer.clone()).is_ok());
+        buffer.clear();
+    }
+}
 
 struct PerOperatorState<T: Timestamp> {
 
     name: String,       // name of the operator
-    // addr: Vec<usize>,   // address of the operator
+    addr: Vec<usize>,   // address of the operator
     index: usize,       // index of the operator within its parent scope
     id: usize,          // worker-unique identifier
 
@@ -598,11 +837,26 @@ struct PerOperatorState<T: Timestamp> {
     gis_capabilities: Vec<ChangeBatch<T>>,
     gis_summary: Vec<Vec<Antichain<T::Summary>>>,   // cached result from get_internal_summary.
 
+    budget: Option<Duration>,   // expected maximum duration of an invocation, if any.
+
     logging: Logger,
 }
 
 impl<T: Timestamp> PerOperatorState<T> {
 
+    // releases buffers and summaries of a shut down operator, retaining its shape.
+    fn release(&mut self) {
+        debug_assert!(self.operator.is_none());
+        self.edges = vec![Vec::new(); self.outputs];
+        self.external = vec![Default::default(); self.inputs];
+        self.external_buffer = vec![ChangeBatch::new(); self.inputs];
+        self.consumed_buffer = vec![ChangeBatch::new(); self.inputs];
+        self.internal_buffer = vec![ChangeBatch::new(); self.outputs];
+        self.produced_buffer = vec![ChangeBatch::new(); self.outputs];
+        self.gis_capabilities = Vec::new();
+        self.gis_summary = Vec::new();
+    }
+
     fn add_input(&mut self) {
         self.inputs += 1;
         self.external.push(Default::default());
@@ -620,7 +874,7 @@ impl<T: Timestamp> PerOperatorState<T> {
         path.push(0);
         PerOperatorState {
             name:       "External".to_owned(),
-            // addr:       path,
+            addr:       path,
             operator:      None,
             index:      0,
             id:         usize::max_value(),
@@ -638,6 +892,8 @@ impl<T: Timestamp> PerOperatorState<T> {
             internal_buffer: Vec::new(),
             produced_buffer: Vec::new(),
 
+            budget:     None,
+
             logging,
 
             gis_capabilities: Vec::new(),
@@ -645,7 +901,9 @@ impl<T: Timestamp> PerOperatorState<T> {
         }
     }
 
-    pub fn new(mut scope: Box<Operate<T>>, index: usize, mut _path: Vec<usize>, identifier: usize, logging: Logger) -> PerOperatorState<T> {
+    pub fn new(mut scope: Box<Operate<T>>, index: usize, mut path: Vec<usize>, identifier: usize, logging: Logger) -> PerOperatorState<T> {
+
+        path.push(index);
 
         let local = scope.local();
         let inputs = scope.inputs();
@@ -659,6 +917,7 @@ impl<T: Timestamp> PerOperatorState<T> {
 
         PerOperatorState {
             name:               scope.name(),
+            addr:               path,
             operator:           Some(scope),
             index,
             id:                 identifier,
@@ -678,6 +937,8 @@ impl<T: Timestamp> PerOperatorState<T> {
             internal_buffer:    vec![ChangeBatch::new(); outputs],
             produced_buffer:    vec![ChangeBatch::new(); outputs],
 
+            budget:             None,
+
             logging,
 
             gis_capabilities,
@@ -803,6 +1064,8 @@ impl<T: Timestamp> PerOperatorState<T> {
                 debug_assert!(self.internal_buffer.iter_mut().all(|cm| cm.is_empty()));
                 debug_assert!(self.produced_buffer.iter_mut().all(|cm| cm.is_empty()));
 
+                let started = self.budget.map(|_| Instant::now());
+
                 let internal_activity =
                     operator.pull_internal_progress(
                         &mut self.consumed_buffer[..],
@@ -810,6 +1073,20 @@ impl<T: Timestamp> PerOperatorState<T> {
                         &mut self.produced_buffer[..],
                     );
 
+                if let (Some(budget), Some(started)) = (self.budget, started) {
+                    let elapsed = started.elapsed();
+                    if elapsed > budget {
+                        let (addr, name) = (&self.addr, &self.name);  // avoid capturing `self` in logging closures.
+                        self.logging.when_enabled(|l| l.log(::logging::TimelyEvent::Budget(::logging::BudgetEvent {
+                            id: self_id,
+                            addr: addr.clone(),
+                            name: name.clone(),
+                            elapsed_ns: elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64,
+                            budget_ns: budget.as_secs() * 1_000_000_000 + budget.subsec_nanos() as u64,
+                        })));
+                    }
+                }
+
                 // Scan reported changes, propagate as appropriate.
                 let mut did_work = false;
                 for output in 0 .. self.outputs {
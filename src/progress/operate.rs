@@ -1,6 +1,7 @@
 //! Methods which describe an operators topology, and the progress it makes.
 
 use std::default::Default;
+use std::time::Duration;
 
 use progress::{Timestamp, ChangeBatch, Antichain};
 
@@ -98,4 +99,10 @@ pub trait Operate<T: Timestamp> {
 
     /// Indicates of whether the operator requires `push_external_progress` information or not.
     fn notify_me(&self) -> bool { true }
+
+    /// The time an invocation of `pull_internal_progress` is expected to take at most, if any.
+    ///
+    /// Invocations exceeding the budget are logged as `BudgetEvent`s by the containing scope. If the
+    /// operator has no budget, that of its scope applies.
+    fn budget(&self) -> Option<Duration> { None }
 }
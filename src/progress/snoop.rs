@@ -0,0 +1,187 @@
+//! Read-only subscriptions to the progress information of a scope.
+//!
+//! A scope tracks the frontier at each input of each of its operators, aggregated across all workers.
+//! A `Snooper` receives the changes to these frontiers from one worker, and so can follow the progress
+//! of the whole computation in the scope, for example to determine when an epoch has completed at every
+//! operator, without adding probes to the dataflow. Frontier information is reported for operators that
+//! request notifications, and for the outputs of the scope itself (those targets with index zero).
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::mpsc::Receiver;
+
+use progress::Timestamp;
+use progress::frontier::MutableAntichain;
+use progress::nested::Target;
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+
+/// Changes to the frontiers of a scope's targets, reported by a scope after each step.
+pub type SnoopMsg<T> = Vec<(Target, T, i64)>;
+
+/// Receives and accumulates the frontier changes reported by a scope.
+///
+/// The snooper holds only a channel receiver and accumulated frontiers, and may be sent to another
+/// thread once created.
+pub struct Snooper<T: Timestamp> {
+    receiver: Receiver<SnoopMsg<T>>,
+    frontiers: BTreeMap<Target, MutableAntichain<T>>,
+}
+
+impl<T: Timestamp> Snooper<T> {
+
+    /// Allocates a new snooper from a receiver of frontier changes.
+    pub fn new(receiver: Receiver<SnoopMsg<T>>) -> Self {
+        Snooper {
+            receiver,
+            frontiers: BTreeMap::new(),
+        }
+    }
+
+    /// Applies all received frontier changes, returning false once the scope has been dropped.
+    pub fn pull(&mut self) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(changes) => {
+                    for (target, time, diff) in changes {
+                        self.frontiers.entry(target).or_insert_with(MutableAntichain::new).update_iter(Some((time, diff)));
+                    }
+                },
+                Err(::std::sync::mpsc::TryRecvError::Empty) => return true,
+                Err(::std::sync::mpsc::TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Reports the frontier at `target`, as of the most recently applied changes.
+    pub fn frontier(&self, target: Target) -> Vec<T> {
+        self.frontiers.get(&target).map(|f| f.frontier().to_vec()).unwrap_or(Vec::new())
+    }
+
+    /// Reports the targets for which frontier changes have been received, in sorted order.
+    pub fn targets(&self) -> Vec<Target> {
+        self.frontiers.keys().cloned().collect()
+    }
+
+    /// Returns true iff `time` is complete at every target, as of the most recently applied changes.
+    ///
+    /// A time is complete at a target once no element of the target's frontier is less or equal to it.
+    pub fn complete(&self, time: &T) -> bool {
+        self.frontiers.values().all(|f| !f.less_equal(time))
+    }
+
+    /// Returns true iff every frontier is empty, as of the most recently applied changes.
+    pub fn done(&self) -> bool {
+        self.frontiers.values().all(|f| f.is_empty())
+    }
+}
+
+/// Callbacks invoked as a dataflow completes times, for releasing resources keyed by epoch.
+///
+/// An `EpochHooks` follows the frontiers of a dataflow with a `Snooper`, and is polled by the worker
+/// after each step. A hook registered with `at` is invoked once the time is complete at every
+/// operator input of the dataflow, or once the dataflow has been dropped, whichever comes first.
+/// Application code holding caches or external state keyed by epoch may release it from a hook,
+/// rather than probing the dataflow and polling the probe.
+///
+/// #Examples
+/// ```
+/// use std::rc::Rc;
+/// use std::cell::RefCell;
+/// use timely::dataflow::operators::{Input, Inspect};
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     let (mut input, hooks) = worker.dataflow(|scope| {
+///         let (input, stream) = scope.new_input::<u64>();
+///         stream.inspect(|x| println!("{:?}", x));
+///         (input, scope.epoch_hooks())
+///     });
+///
+///     let collected = Rc::new(RefCell::new(Vec::new()));
+///     for round in 0..10 {
+///         let collected = collected.clone();
+///         hooks.at(round, move |time| collected.borrow_mut().push(*time));
+///         input.send(round);
+///         input.advance_to(round + 1);
+///         worker.step();
+///     }
+///
+///     input.close();
+///     while worker.step() { }
+///
+///     assert_eq!(*collected.borrow(), (0..10).collect::<Vec<_>>());
+///     assert_eq!(hooks.pending(), 0);
+/// }).unwrap();
+/// ```
+pub struct EpochHooks<T: Timestamp> {
+    state: Rc<RefCell<HookState<T>>>,
+}
+
+struct HookState<T: Timestamp> {
+    snooper: Snooper<Product<RootTimestamp, T>>,
+    hooks: Vec<(T, Box<FnMut(&T)>)>,
+    dropped: bool,
+}
+
+impl<T: Timestamp> EpochHooks<T> {
+
+    /// Allocates hooks driven by the frontier changes `snooper` receives from a dataflow.
+    pub fn new(snooper: Snooper<Product<RootTimestamp, T>>) -> Self {
+        EpochHooks {
+            state: Rc::new(RefCell::new(HookState {
+                snooper,
+                hooks: Vec::new(),
+                dropped: false,
+            }))
+        }
+    }
+
+    /// Registers `hook` to be invoked with `time` once the dataflow has completed `time`.
+    pub fn at<F: FnOnce(&T)+'static>(&self, time: T, hook: F) {
+        let mut hook = Some(hook);
+        self.state.borrow_mut().hooks.push((time, Box::new(move |time| (hook.take().expect("hook invoked twice"))(time))));
+    }
+
+    /// The number of hooks not yet invoked.
+    pub fn pending(&self) -> usize { self.state.borrow().hooks.len() }
+
+    /// Invokes the hooks of completed times, in order of registration.
+    ///
+    /// Returns false once no further hooks can be registered or invoked, which is once the dataflow
+    /// has been dropped, or the worker holds the only handle, and no hooks remain.
+    pub fn poll(&self) -> bool {
+        let ready = {
+            let mut state = self.state.borrow_mut();
+            if !state.dropped && !state.snooper.pull() {
+                state.dropped = true;
+            }
+            // the dataflow reports its initial frontiers once built; until then, nothing is complete.
+            let started = state.dropped || !state.snooper.targets().is_empty();
+            if started {
+                let (ready, pending): (Vec<_>, Vec<_>) = {
+                    let HookState { ref snooper, ref mut hooks, dropped } = *state;
+                    hooks.drain(..).partition(|&(ref time, _)| dropped || snooper.complete(&Product::new(RootTimestamp, time.clone())))
+                };
+                state.hooks = pending;
+                ready
+            }
+            else {
+                Vec::new()
+            }
+        };
+        // hooks are invoked without holding the state, so that they may register further hooks.
+        for (time, mut hook) in ready {
+            hook(&time);
+        }
+        let state = self.state.borrow();
+        !state.hooks.is_empty() || (!state.dropped && Rc::strong_count(&self.state) > 1)
+    }
+}
+
+impl<T: Timestamp> Clone for EpochHooks<T> {
+    fn clone(&self) -> Self {
+        EpochHooks { state: self.state.clone() }
+    }
+}
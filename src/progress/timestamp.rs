@@ -7,7 +7,7 @@ use std::fmt::Formatter;
 use std::fmt::Error;
 use std::hash::Hash;
 
-use order::PartialOrder;
+use order::{PartialOrder, TotalOrder, Lattice};
 use progress::nested::product::Product;
 
 use abomonation::Abomonation;
@@ -75,6 +75,10 @@ impl Debug for RootTimestamp {
 }
 
 impl PartialOrder for RootTimestamp { #[inline(always)] fn less_equal(&self, _other: &Self) -> bool { true } }
+impl Lattice for RootTimestamp {
+    #[inline(always)] fn join(&self, _other: &Self) -> Self { RootTimestamp }
+    #[inline(always)] fn meet(&self, _other: &Self) -> Self { RootTimestamp }
+}
 
 impl Abomonation for RootTimestamp { }
 impl RootTimestamp {
@@ -135,3 +139,79 @@ impl PathSummary<i32> for i32 {
     #[inline]
     fn followed_by(&self, other: &i32) -> Option<i32> { self.checked_add(*other) }
 }
+
+/// Pairs of timestamps, as for a scope indexed by two independent coordinates.
+///
+/// Pairs are ordered by the product order, and are summarized by pairs of summaries, each acting on
+/// its coordinate. A pair of timestamps may be used as the timestamp of a nested scope.
+///
+/// #Examples
+/// ```
+/// use timely::dataflow::Scope;
+/// use timely::dataflow::operators::{ToStream, Enter, Leave, Inspect};
+///
+/// timely::example(|scope| {
+///     let stream = (0..10).to_stream(scope);
+///     scope.scoped::<(u32, u32),_,_>(|inner| {
+///         stream.enter(inner)
+///               .inspect_batch(|t, xs| println!("{:?}: {:?}", t.inner, xs))
+///               .leave()
+///     });
+/// });
+/// ```
+impl<A: Timestamp, B: Timestamp> Timestamp for (A, B) { type Summary = (A::Summary, B::Summary); }
+impl<A, B, SA: PathSummary<A>, SB: PathSummary<B>> PathSummary<(A, B)> for (SA, SB) {
+    #[inline]
+    fn results_in(&self, src: &(A, B)) -> Option<(A, B)> {
+        self.0.results_in(&src.0).and_then(|a| self.1.results_in(&src.1).map(|b| (a, b)))
+    }
+    #[inline]
+    fn followed_by(&self, other: &(SA, SB)) -> Option<(SA, SB)> {
+        self.0.followed_by(&other.0).and_then(|a| self.1.followed_by(&other.1).map(|b| (a, b)))
+    }
+}
+
+/// An unsigned integer timestamp whose summaries saturate at its maximum value.
+///
+/// Where `u32` summaries discard timestamps they would advance past `u32::max_value()`, as the
+/// `feedback` operator does with their records, `Saturating<u32>` summaries advance them to the
+/// maximum value instead. This suits counters that should stop rather than overflow, for example
+/// counts of attempts. Records circulating in a loop whose summary saturates are not discarded,
+/// and so such a loop must stop circulating records itself, or its scope will not complete.
+///
+/// #Examples
+/// ```
+/// use timely::progress::timestamp::{PathSummary, Saturating};
+///
+/// let summary = Saturating(5u8);
+/// assert_eq!(summary.results_in(&Saturating(3u8)), Some(Saturating(8u8)));
+/// assert_eq!(summary.results_in(&Saturating(254u8)), Some(Saturating(255u8)));
+/// assert_eq!(summary.followed_by(&Saturating(255u8)), Some(Saturating(255u8)));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Saturating<T>(pub T);
+
+macro_rules! implement_saturating {
+    ($($index_type:ty,)*) => (
+        $(
+            impl PartialOrder for Saturating<$index_type> {
+                #[inline(always)] fn less_equal(&self, other: &Self) -> bool { self.0 <= other.0 }
+            }
+            impl TotalOrder for Saturating<$index_type> { }
+            impl Lattice for Saturating<$index_type> {
+                #[inline(always)] fn join(&self, other: &Self) -> Self { ::std::cmp::max(*self, *other) }
+                #[inline(always)] fn meet(&self, other: &Self) -> Self { ::std::cmp::min(*self, *other) }
+            }
+            impl Abomonation for Saturating<$index_type> { }
+            impl Timestamp for Saturating<$index_type> { type Summary = Saturating<$index_type>; }
+            impl PathSummary<Saturating<$index_type>> for Saturating<$index_type> {
+                #[inline]
+                fn results_in(&self, src: &Self) -> Option<Self> { Some(Saturating(self.0.saturating_add(src.0))) }
+                #[inline]
+                fn followed_by(&self, other: &Self) -> Option<Self> { Some(Saturating(self.0.saturating_add(other.0))) }
+            }
+        )*
+    )
+}
+
+implement_saturating!(u8, u16, u32, u64, usize,);
@@ -0,0 +1,75 @@
+//! A typed registry of resources shared by the operators of a worker.
+//!
+//! Operators constructed in different modules often need the same handles, for example a connection
+//! pool or a cache, and threading them through every closure is tedious. A worker's `Resources` holds
+//! at most one value of each type, which may be registered before dataflows are constructed and then
+//! retrieved by type from the worker or from any scope. Values are shared through `Rc`, and so remain
+//! local to the worker; workers that must share state should each register a handle to it.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A shared map from types to a value of that type.
+///
+/// #Examples
+/// ```
+/// use std::cell::RefCell;
+/// use timely::dataflow::ScopeParent;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// // a stand-in for some shared handle.
+/// struct Cache { hits: RefCell<usize> }
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///
+///     worker.insert_resource(Cache { hits: RefCell::new(0) });
+///
+///     worker.dataflow::<u64,_,_>(|scope| {
+///         let cache = scope.resources().get::<Cache>().expect("cache not registered");
+///         (0..10).to_stream(scope)
+///                .inspect(move |_x| *cache.hits.borrow_mut() += 1);
+///     });
+///
+///     let cache = worker.get_resource::<Cache>().unwrap();
+///     worker.step_while(|| *cache.hits.borrow() < 10);
+/// }).unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct Resources {
+    map: Rc<RefCell<HashMap<TypeId, Rc<Any>>>>,
+}
+
+impl Resources {
+    /// Allocates a new empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers `value`, returning the previously registered value of the same type, if any.
+    pub fn insert<T: 'static>(&self, value: T) -> Option<Rc<T>> {
+        self.map.borrow_mut()
+            .insert(TypeId::of::<T>(), Rc::new(value))
+            .map(|previous| previous.downcast::<T>().ok().expect("resource registered under wrong type"))
+    }
+
+    /// The registered value of type `T`, if any.
+    pub fn get<T: 'static>(&self) -> Option<Rc<T>> {
+        self.map.borrow()
+            .get(&TypeId::of::<T>())
+            .map(|value| value.clone().downcast::<T>().ok().expect("resource registered under wrong type"))
+    }
+
+    /// Unregisters and returns the value of type `T`, if any.
+    pub fn remove<T: 'static>(&self) -> Option<Rc<T>> {
+        self.map.borrow_mut()
+            .remove(&TypeId::of::<T>())
+            .map(|value| value.downcast::<T>().ok().expect("resource registered under wrong type"))
+    }
+
+    /// Returns true iff a value of type `T` is registered.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.borrow().contains_key(&TypeId::of::<T>())
+    }
+}
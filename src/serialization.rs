@@ -0,0 +1,92 @@
+//! Serializable stand-ins for common types that lack `Abomonation` implementations.
+//!
+//! Records exchanged between workers must implement `Abomonation`. Most user types can derive it,
+//! using the derive macro re-exported from timely, as long as their fields implement it as well. The
+//! types here wrap standard types whose implementations are missing, convert to and from them, and
+//! may be used as fields in their place. Fixed-size arrays longer than 32 elements lack implementations
+//! too, and are most easily held in a `Vec`.
+//!
+//! The derived implementations name the `abomonation` crate from the root of the deriving crate. A
+//! crate need not depend on `abomonation` itself, if it imports timely's re-export at its root.
+//!
+//! #Examples
+//! ```
+//! extern crate timely;
+//!
+//! // makes `::abomonation` available to the derived implementation.
+//! use timely::abomonation;
+//! use timely::Abomonation;
+//!
+//! use std::time::Duration;
+//! use timely::serialization::DurationData;
+//! use timely::dataflow::operators::{ToStream, Exchange, Map, Inspect};
+//!
+//! #[derive(Abomonation, Clone, Debug)]
+//! struct Request {
+//!     id: u64,
+//!     latency: DurationData,
+//! }
+//!
+//! fn main() {
+//!     timely::example(|scope| {
+//!         (0..10u64).map(|id| Request { id, latency: Duration::from_millis(id).into() })
+//!                   .to_stream(scope)
+//!                   .exchange(|request| request.id)
+//!                   .map(|request| Duration::from(request.latency))
+//!                   .inspect(|latency| println!("latency: {:?}", latency));
+//!     });
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A serializable `Duration`.
+#[derive(Abomonation, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct DurationData {
+    /// Whole seconds.
+    pub secs: u64,
+    /// Additional nanoseconds, less than one second.
+    pub nanos: u32,
+}
+
+impl From<Duration> for DurationData {
+    fn from(duration: Duration) -> Self {
+        DurationData {
+            secs: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+        }
+    }
+}
+
+impl From<DurationData> for Duration {
+    fn from(data: DurationData) -> Self {
+        Duration::new(data.secs, data.nanos)
+    }
+}
+
+/// A serializable `SystemTime`, as an offset from the unix epoch.
+#[derive(Abomonation, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SystemTimeData {
+    /// A time at or after the unix epoch.
+    After(DurationData),
+    /// A time before the unix epoch.
+    Before(DurationData),
+}
+
+impl From<SystemTime> for SystemTimeData {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => SystemTimeData::After(after.into()),
+            Err(error) => SystemTimeData::Before(error.duration().into()),
+        }
+    }
+}
+
+impl From<SystemTimeData> for SystemTime {
+    fn from(data: SystemTimeData) -> Self {
+        match data {
+            SystemTimeData::After(after) => UNIX_EPOCH + Duration::from(after),
+            SystemTimeData::Before(before) => UNIX_EPOCH - Duration::from(before),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! A per-worker clock, sampled once per step, and deadlines registered against it.
+//!
+//! Operators that need the current time, for example to rate limit their output or to expire state,
+//! can read it from the worker's `Timer` rather than calling `Instant::now()` for each record. The
+//! worker samples the clock at the start of each step, and all operators observe the same instant
+//! for the duration of the step. The clock is monotonic: it never reports an earlier instant than
+//! it has previously reported.
+//!
+//! Operators may also register deadlines with the timer, which each report when they have passed.
+//! As operators are scheduled in each step, registering a deadline does not itself cause an
+//! operator to be scheduled; rather the timer records the earliest outstanding deadline, so that
+//! a worker with no other work can determine how long it may sleep before it must step again.
+//!
+//! The clock is read through a function reporting the time elapsed since some origin, which by
+//! default measures the time since the timer was created. Platforms without a system clock, such as
+//! `wasm32-unknown-unknown`, may install a clock of their own with `set_clock`; their default clock
+//! never advances, and methods presenting times as an `Instant` are unavailable.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// A shared handle to a worker's clock.
+///
+/// #Examples
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::ScopeParent;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// timely::example(|scope| {
+///     let timer = scope.timer();
+///     let deadline = timer.deadline_after(Duration::from_millis(10));
+///     (0..10).to_stream(scope)
+///            .inspect(move |x| println!("seen {:?} at {:?}; deadline passed: {:?}", x, timer.elapsed(), deadline.passed()));
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Timer {
+    state: Rc<RefCell<TimerState>>,
+}
+
+struct TimerState {
+    /// The instant the timer was created.
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+    /// Reports the time elapsed since the timer was created.
+    clock: Box<Fn()->Duration>,
+    /// The elapsed time most recently sampled.
+    now: Duration,
+    /// Outstanding deadlines, as elapsed times, earliest first.
+    deadlines: BinaryHeap<Reverse<Duration>>,
+}
+
+impl Timer {
+
+    /// Allocates a new timer, sampling the clock.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Self {
+        let start = Instant::now();
+        Timer {
+            state: Rc::new(RefCell::new(TimerState {
+                start,
+                clock: Box::new(move || start.elapsed()),
+                now: Duration::from_millis(0),
+                deadlines: BinaryHeap::new(),
+            }))
+        }
+    }
+
+    /// Allocates a new timer, whose clock does not advance until one is installed with `set_clock`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn new() -> Self {
+        Timer {
+            state: Rc::new(RefCell::new(TimerState {
+                clock: Box::new(|| Duration::from_millis(0)),
+                now: Duration::from_millis(0),
+                deadlines: BinaryHeap::new(),
+            }))
+        }
+    }
+
+    /// Installs a clock, reporting the time elapsed since the timer was created.
+    ///
+    /// The clock is sampled from the next step onward. As the timer never reports an earlier time than
+    /// it has previously reported, the clock should start from the time most recently reported by
+    /// `elapsed`.
+    ///
+    /// #Examples
+    /// ```
+    /// use std::rc::Rc;
+    /// use std::cell::Cell;
+    /// use std::time::Duration;
+    /// use timely::timer::Timer;
+    ///
+    /// // a clock advanced by hand, as a browser might advance it from `performance.now()`.
+    /// let millis = Rc::new(Cell::new(0));
+    /// let clock = millis.clone();
+    ///
+    /// let timer = Timer::new();
+    /// timer.set_clock(move || Duration::from_millis(clock.get()));
+    /// let deadline = timer.deadline_after(Duration::from_millis(10));
+    ///
+    /// millis.set(20);
+    /// timer.advance();
+    /// assert_eq!(timer.elapsed(), Duration::from_millis(20));
+    /// assert!(deadline.passed());
+    /// ```
+    pub fn set_clock<F: Fn()->Duration+'static>(&self, clock: F) {
+        self.state.borrow_mut().clock = Box::new(clock);
+    }
+
+    /// Samples the clock, and retires any passed deadlines.
+    ///
+    /// This method is called by the worker at the start of each step, and need not be called by
+    /// operators.
+    pub fn advance(&self) {
+        let mut state = self.state.borrow_mut();
+        let sampled = (state.clock)();
+        if sampled > state.now {
+            state.now = sampled;
+        }
+        let now = state.now;
+        while state.deadlines.peek().map(|&Reverse(deadline)| deadline <= now).unwrap_or(false) {
+            state.deadlines.pop();
+        }
+    }
+
+    /// The instant sampled at the start of the current step.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    pub fn now(&self) -> Instant {
+        let state = self.state.borrow();
+        state.start + state.now
+    }
+
+    /// The time elapsed from the timer's creation until the start of the current step.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.state.borrow().now
+    }
+
+    /// Registers a deadline at `instant`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn deadline_at(&self, instant: Instant) -> Deadline {
+        let start = self.state.borrow().start;
+        let at = if instant > start { instant.duration_since(start) } else { Duration::from_millis(0) };
+        self.deadline_at_elapsed(at)
+    }
+
+    /// Registers a deadline once `duration` has elapsed from the start of the current step.
+    pub fn deadline_after(&self, duration: Duration) -> Deadline {
+        let at = self.elapsed() + duration;
+        self.deadline_at_elapsed(at)
+    }
+
+    /// Registers a deadline once the timer's elapsed time reaches `at`.
+    fn deadline_at_elapsed(&self, at: Duration) -> Deadline {
+        self.state.borrow_mut().deadlines.push(Reverse(at));
+        Deadline {
+            at,
+            timer: self.clone(),
+        }
+    }
+
+    /// The earliest deadline that had not passed at the start of the current step, if any.
+    ///
+    /// A worker with no other work may sleep until this instant without delaying any deadline.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let state = self.state.borrow();
+        state.deadlines.peek().map(|&Reverse(deadline)| state.start + deadline)
+    }
+
+    /// The time remaining until the earliest deadline that had not passed at the start of the
+    /// current step, if any.
+    pub fn until_next_deadline(&self) -> Option<Duration> {
+        let state = self.state.borrow();
+        state.deadlines.peek().map(|&Reverse(deadline)| if deadline > state.now { deadline - state.now } else { Duration::from_millis(0) })
+    }
+}
+
+/// A deadline registered with a `Timer`.
+#[derive(Clone)]
+pub struct Deadline {
+    at: Duration,
+    timer: Timer,
+}
+
+impl Deadline {
+    /// The instant of the deadline.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn instant(&self) -> Instant { self.timer.state.borrow().start + self.at }
+
+    /// Returns true iff the deadline had passed at the start of the current step.
+    pub fn passed(&self) -> bool { self.at <= self.timer.elapsed() }
+
+    /// The time remaining until the deadline, as of the start of the current step.
+    pub fn remaining(&self) -> Duration {
+        let now = self.timer.elapsed();
+        if self.at > now { self.at - now } else { Duration::from_millis(0) }
+    }
+}
@@ -0,0 +1,169 @@
+//! Forwarding of timely and communication logging events into the `tracing` ecosystem.
+//!
+//! The `Tracer` produces a `LoggerConfig` whose loggers forward events to the current `tracing`
+//! subscriber of the thread that flushes them, which for timely events is the worker thread. Each
+//! scheduling of an operator becomes an `operator` span, carrying the worker index, operator
+//! identifier and name, and whether the operator did any work. Other timely events, including
+//! message sends and receives, become events within the spans, as do communication events.
+//!
+//! Logging events are buffered before they are forwarded, and so spans are entered and exited some
+//! time after the scheduling they describe. Each span and event carries the original log timestamp
+//! in nanoseconds as the field `log_ns`, which should be used for timing.
+//!
+//! This module is only available with the `tracing` feature.
+//!
+//! #Examples
+//! ```
+//! use timely::tracer::Tracer;
+//! use timely::dataflow::operators::{ToStream, Inspect};
+//!
+//! // forward events of worker zero only.
+//! let logger_config = Tracer::new().workers(|index| index == 0).logger_config();
+//!
+//! timely::execute_logging(timely::Configuration::Process(2), logger_config, |worker| {
+//!     worker.dataflow::<u64,_,_>(|scope| {
+//!         (0..10).to_stream(scope)
+//!                .inspect(|x| println!("seen: {:?}", x));
+//!     });
+//! }).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ::tracing::{Level, Span};
+use ::tracing::field::Empty;
+
+use progress::nested::product::Product;
+use progress::timestamp::RootTimestamp;
+use dataflow::operators::capture::{Event, EventPusher};
+use logging::{LoggerConfig, TimelySetup, TimelyEvent, StartStop};
+use communication::logging::{BufferingLogger, CommsSetup, CommsEvent};
+
+/// A builder of logging configurations that forward events to `tracing`.
+#[derive(Clone)]
+pub struct Tracer {
+    workers: Arc<Fn(usize)->bool+Send+Sync>,
+    communication: bool,
+}
+
+impl Tracer {
+    /// Allocates a new `Tracer`, forwarding the timely events of all workers.
+    pub fn new() -> Self {
+        Tracer {
+            workers: Arc::new(|_| true),
+            communication: false,
+        }
+    }
+
+    /// Forwards timely events only of those workers whose indices satisfy `filter`.
+    pub fn workers<F: Fn(usize)->bool+Send+Sync+'static>(mut self, filter: F) -> Self {
+        self.workers = Arc::new(filter);
+        self
+    }
+
+    /// Forwards communication events as well, if `forward` is true.
+    pub fn communication(mut self, forward: bool) -> Self {
+        self.communication = forward;
+        self
+    }
+
+    /// A logging configuration forwarding events as configured.
+    pub fn logger_config(&self) -> LoggerConfig {
+        let workers = self.workers.clone();
+        let mut config = LoggerConfig::timely_only(move |setup: TimelySetup| {
+            TimelyTracer {
+                enabled: (workers)(setup.index),
+                names: HashMap::new(),
+                scheduled: HashMap::new(),
+            }
+        });
+        if self.communication {
+            let communication = LoggerConfig::new(|_setup: TimelySetup| Discard, |_setup: CommsSetup| CommsTracer);
+            config.communication_logging = communication.communication_logging;
+        }
+        else {
+            config.communication_logging = Arc::new(|_setup| BufferingLogger::new_inactive());
+        }
+        config
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Self { Self::new() }
+}
+
+/// Forwards the timely events of one worker.
+struct TimelyTracer {
+    enabled: bool,
+    names: HashMap<usize, String>,
+    scheduled: HashMap<usize, Span>,
+}
+
+impl EventPusher<Product<RootTimestamp, u64>, (u64, TimelySetup, TimelyEvent)> for TimelyTracer {
+    fn push(&mut self, event: Event<Product<RootTimestamp, u64>, (u64, TimelySetup, TimelyEvent)>) {
+        if !self.enabled { return; }
+        if let Event::Messages(_, data) = event {
+            for (ts, setup, event) in data {
+                match event {
+                    TimelyEvent::Operates(operates) => {
+                        ::tracing::event!(Level::DEBUG, worker = setup.index as u64, log_ns = ts, id = operates.id as u64, name = %operates.name, "operator created");
+                        self.names.insert(operates.id, operates.name);
+                    },
+                    TimelyEvent::Schedule(schedule) => {
+                        match schedule.start_stop {
+                            StartStop::Start => {
+                                let name = self.names.get(&schedule.id).map(|name| &name[..]).unwrap_or("");
+                                let span = ::tracing::span!(Level::TRACE, "operator", worker = setup.index as u64, log_ns = ts, id = schedule.id as u64, name = %name, activity = Empty, stop_ns = Empty);
+                                if let Some(id) = span.id() {
+                                    ::tracing::dispatcher::get_default(|dispatch| dispatch.enter(&id));
+                                }
+                                self.scheduled.insert(schedule.id, span);
+                            },
+                            StartStop::Stop { activity } => {
+                                if let Some(span) = self.scheduled.remove(&schedule.id) {
+                                    span.record("activity", &activity);
+                                    span.record("stop_ns", &ts);
+                                    if let Some(id) = span.id() {
+                                        ::tracing::dispatcher::get_default(|dispatch| dispatch.exit(&id));
+                                    }
+                                }
+                            },
+                        }
+                    },
+                    TimelyEvent::Messages(messages) => {
+                        ::tracing::event!(Level::TRACE, worker = setup.index as u64, log_ns = ts, is_send = messages.is_send, channel = messages.channel as u64,
+                                          source = messages.source as u64, target = messages.target as u64, length = messages.length as u64, "message");
+                    },
+                    TimelyEvent::Budget(budget) => {
+                        ::tracing::event!(Level::WARN, worker = setup.index as u64, log_ns = ts, id = budget.id as u64, addr = ?budget.addr, name = %budget.name,
+                                          elapsed_ns = budget.elapsed_ns, budget_ns = budget.budget_ns, "operator exceeded budget");
+                    },
+                    event => {
+                        ::tracing::event!(Level::TRACE, worker = setup.index as u64, log_ns = ts, event = ?event);
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Forwards the events of one communication thread.
+struct CommsTracer;
+
+impl EventPusher<Product<RootTimestamp, u64>, (u64, CommsSetup, CommsEvent)> for CommsTracer {
+    fn push(&mut self, event: Event<Product<RootTimestamp, u64>, (u64, CommsSetup, CommsEvent)>) {
+        if let Event::Messages(_, data) = event {
+            for (ts, setup, event) in data {
+                ::tracing::event!(Level::TRACE, process = setup.process as u64, sender = setup.sender, log_ns = ts, event = ?event);
+            }
+        }
+    }
+}
+
+/// Discards events; its loggers are replaced before use.
+struct Discard;
+
+impl<T, D> EventPusher<T, D> for Discard {
+    fn push(&mut self, _event: Event<T, D>) { }
+}
@@ -0,0 +1,187 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use timely::Configuration;
+use timely::algorithms::{Propagate, PartitionEdges};
+use timely::dataflow::operators::{Input, Map, Concat, Inspect, Probe};
+use timely::progress::timestamp::RootTimestamp;
+
+/// Runs `propagate` on `workers` workers, introducing at each epoch the edges and initial labels of
+/// `epochs` from worker zero, and returns the sorted `(epoch, vertex, label)` results of all workers.
+fn propagate_helper<F>(workers: usize, epochs: Vec<(Vec<(u64, u64)>, Vec<(u64, u64)>)>, step: F) -> Vec<(u64, u64, u64)>
+where F: Fn(&u64)->u64+Send+Sync+'static {
+    let step = ::std::sync::Arc::new(step);
+    let guards = timely::execute(Configuration::Process(workers), move |worker| {
+        let step = step.clone();
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+        let (mut edges, mut labels, probe) = worker.dataflow::<u64,_,_>(move |scope| {
+            let (edge_input, edges) = scope.new_input::<(u64, u64)>();
+            let (label_input, labels) = scope.new_input::<(u64, u64)>();
+            let (output, _convergence) = edges.propagate(&labels, move |label| step(label));
+            let probe = output
+                .inspect_batch(move |time, data| {
+                    for &(vertex, label) in data.iter() {
+                        results2.borrow_mut().push((time.inner, vertex, label));
+                    }
+                })
+                .probe();
+            (edge_input, label_input, probe)
+        });
+
+        for (epoch, &(ref epoch_edges, ref epoch_labels)) in epochs.iter().enumerate() {
+            if worker.index() == 0 {
+                for edge in epoch_edges.iter() { edges.send(*edge); }
+                for label in epoch_labels.iter() { labels.send(*label); }
+            }
+            edges.advance_to(epoch as u64 + 1);
+            labels.advance_to(epoch as u64 + 1);
+        }
+        edges.close();
+        labels.close();
+        while !probe.done() { worker.step(); }
+
+        let results = results.borrow().clone();
+        results
+    }).unwrap();
+
+    let mut results = guards.join().into_iter().flat_map(|result| result.unwrap()).collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+fn bfs_epochs() -> Vec<(Vec<(u64, u64)>, Vec<(u64, u64)>)> {
+    vec![
+        // a chain from zero, and an unreachable edge.
+        (vec![(0, 1), (1, 2), (2, 3), (5, 6)], vec![(0, 0)]),
+        // the same chain with a shortcut, rooted at one instead.
+        (vec![(0, 1), (1, 2), (2, 3), (1, 3)], vec![(1, 0)]),
+        // no edges, only a root.
+        (vec![], vec![(4, 0)]),
+    ]
+}
+
+fn bfs_expected() -> Vec<(u64, u64, u64)> {
+    vec![
+        (0, 0, 0), (0, 1, 1), (0, 2, 2), (0, 3, 3),
+        (1, 1, 0), (1, 2, 1), (1, 3, 1),
+        (2, 4, 0),
+    ]
+}
+
+#[test] fn bfs_1w() { assert_eq!(propagate_helper(1, bfs_epochs(), |hops| hops + 1), bfs_expected()); }
+#[test] fn bfs_3w() { assert_eq!(propagate_helper(3, bfs_epochs(), |hops| hops + 1), bfs_expected()); }
+
+fn components_epochs() -> Vec<(Vec<(u64, u64)>, Vec<(u64, u64)>)> {
+    let undirected = |edges: Vec<(u64, u64)>| edges.iter().cloned().chain(edges.iter().map(|&(x, y)| (y, x))).collect::<Vec<_>>();
+    let labels = |vertices: ::std::ops::Range<u64>| vertices.map(|x| (x, x)).collect::<Vec<_>>();
+    vec![
+        // components {0, 1, 2} and {3, 4}.
+        (undirected(vec![(2, 1), (1, 0), (3, 4)]), labels(0 .. 5)),
+        // a later epoch joining both components, labelled from scratch.
+        (undirected(vec![(2, 1), (1, 0), (3, 4), (4, 2)]), labels(0 .. 5)),
+    ]
+}
+
+fn components_expected() -> Vec<(u64, u64, u64)> {
+    vec![
+        (0, 0, 0), (0, 1, 0), (0, 2, 0), (0, 3, 3), (0, 4, 3),
+        (1, 0, 0), (1, 1, 0), (1, 2, 0), (1, 3, 0), (1, 4, 0),
+    ]
+}
+
+#[test] fn components_1w() { assert_eq!(propagate_helper(1, components_epochs(), |label| *label), components_expected()); }
+#[test] fn components_4w() { assert_eq!(propagate_helper(4, components_epochs(), |label| *label), components_expected()); }
+
+// Later epochs with lower labels must not affect earlier epochs, however the workers are scheduled.
+#[test]
+fn epochs_independent() {
+    let epochs = vec![
+        (vec![(0, 1), (1, 2)], vec![(0, 10)]),
+        (vec![(0, 1), (1, 2)], vec![(0, 1)]),
+    ];
+    let expected = vec![
+        (0, 0, 10), (0, 1, 11), (0, 2, 12),
+        (1, 0, 1), (1, 1, 2), (1, 2, 3),
+    ];
+    for workers in 1 .. 4 {
+        assert_eq!(propagate_helper(workers, epochs.clone(), |label| label + 1), expected);
+    }
+}
+
+#[test]
+fn convergence() {
+    timely::execute(Configuration::Process(2), |worker| {
+        let (mut input, convergence) = worker.dataflow::<u64,_,_>(|scope| {
+            let (input, edges) = scope.new_input::<(u64, u64)>();
+            let both = edges.map(|(x, y)| (y, x)).concat(&edges);
+            let labels = both.map(|(x, _)| (x, x));
+            let (_components, convergence) = both.propagate(&labels, |label| *label);
+            (input, convergence)
+        });
+
+        // a chain of vertices, whose least label needs many iterations to reach its end.
+        if worker.index() == 0 {
+            for vertex in 0 .. 20 { input.send((vertex, vertex + 1)); }
+        }
+        input.advance_to(1);
+
+        assert!(!convergence.converged(&RootTimestamp::new(0)));
+        assert_eq!(convergence.iteration(&RootTimestamp::new(0)), Some(0));
+
+        let mut iterations = 0;
+        while !convergence.converged(&RootTimestamp::new(0)) {
+            if let Some(iteration) = convergence.iteration(&RootTimestamp::new(0)) {
+                assert!(iteration >= iterations);
+                iterations = iteration;
+            }
+            worker.step();
+        }
+        assert!(iterations > 0);
+        assert_eq!(convergence.iteration(&RootTimestamp::new(0)), None);
+
+        // the open input holds back the next epoch, which therefore has not converged.
+        assert!(!convergence.converged(&RootTimestamp::new(1)));
+        input.close();
+        while !convergence.converged(&RootTimestamp::new(1)) { worker.step(); }
+    }).unwrap().join().into_iter().for_each(|result| result.unwrap());
+}
+
+#[test]
+fn partition_edges() {
+    let guards = timely::execute(Configuration::Process(3), |worker| {
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+        let (mut input, probe) = worker.dataflow::<u64,_,_>(move |scope| {
+            let (input, edges) = scope.new_input::<(u64, u64)>();
+            let probe = edges.partition_edges(10)
+                             .inspect(move |edge| results2.borrow_mut().push(*edge))
+                             .probe();
+            (input, probe)
+        });
+        if worker.index() == 0 {
+            for target in 1 .. 101 { input.send((0, target)); }
+            for source in 1 .. 10 { input.send((source, source + 1)); }
+        }
+        input.close();
+        while !probe.done() { worker.step(); }
+        let results = results.borrow().clone();
+        results
+    }).unwrap();
+
+    let results = guards.join().into_iter().map(|result| result.unwrap()).collect::<Vec<_>>();
+
+    // every edge is produced exactly once.
+    let mut edges = results.iter().flat_map(|edges| edges.iter().cloned()).collect::<Vec<_>>();
+    edges.sort();
+    let mut expected = (1 .. 101).map(|x| (0, x)).chain((1 .. 10).map(|x| (x, x + 1))).collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(edges, expected);
+
+    // the edges of the high-degree vertex are spread among the workers.
+    for edges in results.iter() {
+        assert!(edges.iter().any(|&(source, _)| source == 0));
+    }
+}
@@ -37,5 +37,8 @@ fn barrier_sync_helper(config: ::timely_communication::Configuration) {
             )
             .connect_loop(handle);
         });
-    }).unwrap(); // asserts error-free execution;
+    }).unwrap()
+      .join()
+      .into_iter()
+      .for_each(|result| { result.unwrap(); }); // asserts error-free execution;
 }
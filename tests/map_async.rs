@@ -0,0 +1,21 @@
+extern crate timely;
+
+use timely::Configuration;
+use timely::dataflow::operators::{ToStream, Probe};
+use timely::dataflow::operators::map_async::MapAsync;
+
+// A panic on a pool thread fails the worker with the panic's original message.
+#[test]
+fn map_async_panic() {
+    let results = timely::execute(Configuration::Thread, |worker| {
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            (0..10u64).to_stream(scope)
+                      .map_async(2, |x| { if x == 7 { panic!("lookup of {} failed", x); } x })
+                      .probe()
+        });
+        while !probe.done() { worker.step(); }
+    }).unwrap().join();
+
+    let error = results.into_iter().next().unwrap().unwrap_err();
+    assert!(error.contains("lookup of 7 failed"), "unexpected failure: {}", error);
+}
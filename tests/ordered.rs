@@ -0,0 +1,52 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::{Arc, Barrier};
+
+use timely::Configuration;
+use timely::dataflow::operators::{Input, Exchange, Inspect, Probe};
+
+/// Runs four workers that each send records to worker zero, and returns the records in the order
+/// worker zero received them.
+///
+/// The workers synchronize before and after each epoch, so that all of an epoch's records are
+/// available before worker zero pulls any of them.
+fn ordered_helper() -> Vec<u64> {
+    let barrier = Arc::new(Barrier::new(4));
+    let guards = timely::execute(Configuration::Ordered(4), move |worker| {
+        let index = worker.index() as u64;
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let received2 = received.clone();
+
+        let (mut input, probe) = worker.dataflow::<u64,_,_>(move |scope| {
+            let (input, stream) = scope.new_input::<u64>();
+            let probe = stream.exchange(|_| 0)
+                              .inspect(move |x| received2.borrow_mut().push(*x))
+                              .probe();
+            (input, probe)
+        });
+
+        for epoch in 0 .. 5u64 {
+            for x in 0 .. 5 { input.send(1000 * epoch + 100 * index + x); }
+            input.advance_to(epoch + 1);
+            barrier.wait();
+            worker.step_while(|| probe.less_than(input.time()));
+            barrier.wait();
+        }
+
+        let received = received.borrow().clone();
+        received
+    }).unwrap();
+
+    guards.join().into_iter().map(|result| result.unwrap()).flat_map(|received| received).collect()
+}
+
+// Each epoch's records arrive in order of their source worker, and so in increasing order.
+#[test]
+fn ordered_by_source() {
+    let expected = (0 .. 5u64).flat_map(|epoch| (0 .. 4u64).flat_map(move |index| (0 .. 5).map(move |x| 1000 * epoch + 100 * index + x))).collect::<Vec<_>>();
+    for _ in 0 .. 5 {
+        assert_eq!(ordered_helper(), expected);
+    }
+}
@@ -0,0 +1,238 @@
+extern crate timely;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use timely::Configuration;
+use timely::dataflow::Scope;
+use timely::dataflow::channels::pact::Exchange as ExchangePact;
+use timely::dataflow::operators::{Input, Enter, Leave, LoopVariable, ConnectLoop, Concat, Map, Filter, Exchange, Inspect, Probe};
+use timely::dataflow::operators::generic::operator::Operator;
+use timely::progress::broadcast::ProgressMode;
+use timely::progress::frontier::Antichain;
+use timely::progress::nested::reachability::{Builder, Tracker};
+use timely::progress::nested::subgraph::{Source, Target};
+
+/// Runs a dataflow on four workers exchanging progress updates by `mode`, and returns the sorted
+/// `(epoch, worker, records)` notifications of a counting operator, and the frontiers each worker's
+/// probe reached after each epoch.
+///
+/// The dataflow iterates records through a loop of operators that do not observe their frontiers,
+/// and concatenates the result with that of a scope fed by an input closed after two epochs, which
+/// shuts down while the rest of the dataflow continues.
+fn progress_helper(mode: ProgressMode) -> (Vec<(u64, usize, usize)>, Vec<Vec<Vec<u64>>>) {
+    let guards = timely::execute(Configuration::Process(4), move |worker| {
+        worker.set_progress_mode(mode);
+        let index = worker.index();
+        let notified = Rc::new(RefCell::new(Vec::new()));
+        let notified2 = notified.clone();
+
+        let (mut input, side, probe) = worker.dataflow::<u64,_,_>(move |scope| {
+            let (input, stream) = scope.new_input::<u64>();
+            let (side, side_stream) = scope.new_input::<u64>();
+
+            // each record circulates `x % 4` times.
+            let iterated = scope.scoped::<u64,_,_>(|inner| {
+                let (handle, cycle) = inner.loop_variable(100, 1);
+                let records = stream.enter(inner)
+                                    .map(|x| (x, x % 4))
+                                    .concat(&cycle)
+                                    .exchange(|&(x, _)| x);
+                records.filter(|&(_, rounds)| rounds > 0)
+                       .map(|(x, rounds)| (x, rounds - 1))
+                       .connect_loop(handle);
+                records.filter(|&(_, rounds)| rounds == 0)
+                       .map(|(x, _)| x)
+                       .leave()
+            });
+
+            let side = scope.scoped::<u64,_,_>(|inner| {
+                side_stream.enter(inner)
+                           .exchange(|x| *x)
+                           .map(|x| x + 1)
+                           .leave()
+            });
+
+            let mut counts = HashMap::new();
+            let probe = iterated.concat(&side)
+                .unary_notify(ExchangePact::new(|x: &u64| *x), "Count", Vec::new(), move |input, output, notificator| {
+                    input.for_each(|time, data| {
+                        *counts.entry(time.time().clone()).or_insert(0) += data.len();
+                        notificator.notify_at(time.retain());
+                    });
+                    notificator.for_each(|time, _count, _notificator| {
+                        if let Some(count) = counts.remove(time.time()) {
+                            output.session(&time).give((time.time().inner, count));
+                        }
+                    });
+                })
+                .inspect(move |&(epoch, count)| notified2.borrow_mut().push((epoch, index, count)))
+                .probe();
+
+            (input, side, probe)
+        });
+
+        let mut side = Some(side);
+        let mut frontiers = Vec::new();
+        for epoch in 0 .. 6u64 {
+            for x in 0 .. 10 { input.send(1000 * epoch + 10 * index as u64 + x); }
+            input.advance_to(epoch + 1);
+            if let Some(mut handle) = side.take() {
+                for x in 0 .. 3 { handle.send(1000 * epoch + 500 + 10 * index as u64 + x); }
+                if epoch < 1 {
+                    handle.advance_to(epoch + 1);
+                    side = Some(handle);
+                }
+                else {
+                    handle.close();
+                }
+            }
+            worker.step_while(|| probe.less_than(input.time()));
+            frontiers.push(probe.with_frontier(|frontier| frontier.iter().map(|time| time.inner).collect::<Vec<_>>()));
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        let notified = notified.borrow().clone();
+        (notified, frontiers)
+    }).unwrap();
+
+    let mut notified = Vec::new();
+    let mut frontiers = Vec::new();
+    for result in guards.join() {
+        let (worker_notified, worker_frontiers) = result.unwrap();
+        notified.extend(worker_notified);
+        frontiers.push(worker_frontiers);
+    }
+    notified.sort();
+    (notified, frontiers)
+}
+
+/// Asserts that each worker was notified of each epoch at most once, and only once all of the
+/// epoch's records had arrived, and that each probe reached each epoch exactly.
+fn check_progress(notified: &[(u64, usize, usize)], frontiers: &[Vec<Vec<u64>>]) {
+    for pair in notified.windows(2) {
+        assert!((pair[0].0, pair[0].1) != (pair[1].0, pair[1].1), "notified twice: {:?}", pair);
+    }
+    for epoch in 0 .. 6 {
+        let records = notified.iter().filter(|x| x.0 == epoch).map(|x| x.2).sum::<usize>();
+        assert_eq!(records, if epoch < 2 { 52 } else { 40 });
+    }
+    let expected = (1 .. 7).map(|epoch| vec![epoch]).collect::<Vec<_>>();
+    for worker_frontiers in frontiers.iter() {
+        assert_eq!(worker_frontiers, &expected);
+    }
+}
+
+#[test]
+fn all_to_all() {
+    let (notified, frontiers) = progress_helper(ProgressMode::AllToAll);
+    check_progress(&notified, &frontiers);
+}
+
+/// A graph of eight nodes, each with one output and all but node four with one input.
+///
+/// Node zero feeds a pipeline of nodes one, two, and three, and node seven, both of which feed the
+/// first input of node four. Node four feeds node zero, and a loop through nodes five and six back to
+/// its second input; node five increments timestamps.
+fn graph() -> Builder<usize> {
+    let mut builder = Builder::new();
+    for index in 0 .. 8 {
+        if index == 4 {
+            builder.add_node(index, 2, 1, vec![vec![Antichain::from_elem(0)], vec![Antichain::from_elem(0)]]);
+        }
+        else {
+            builder.add_node(index, 1, 1, vec![vec![Antichain::from_elem(if index == 5 { 1 } else { 0 })]]);
+        }
+    }
+    let edges = vec![(0, 1, 0), (1, 2, 0), (2, 3, 0), (3, 4, 0), (0, 7, 0), (7, 4, 0), (4, 0, 0), (4, 5, 0), (5, 6, 0), (6, 4, 1)];
+    for (source, target, port) in edges {
+        builder.add_edge(Source { index: source, port: 0 }, Target { index: target, port });
+    }
+    builder
+}
+
+/// The targets of the nodes of `graph`.
+fn targets() -> Vec<Target> {
+    (0 .. 8).map(|index| Target { index, port: 0 }).chain(Some(Target { index: 4, port: 1 })).collect()
+}
+
+/// Applies the updates of `round` at the ports of all nodes but `skip`, propagates them, and returns
+/// the consequences pushed to each of `targets`.
+fn apply_round(tracker: &mut Tracker<usize>, round: usize, skip: Option<usize>, targets: &[Target]) -> Vec<Vec<(usize, i64)>> {
+    let nodes = (0 .. 8).filter(|&index| Some(index) != skip).collect::<Vec<_>>();
+    let source = nodes[round % nodes.len()];
+    let target = nodes[(3 * round) % nodes.len()];
+    tracker.update_source(Source { index: source, port: 0 }, round, 1);
+    tracker.update_target(Target { index: target, port: 0 }, round + 1, 1);
+    if round >= 4 {
+        let source = nodes[(round - 4) % nodes.len()];
+        let target = nodes[(3 * (round - 4)) % nodes.len()];
+        tracker.update_source(Source { index: source, port: 0 }, round - 4, -1);
+        tracker.update_target(Target { index: target, port: 0 }, round - 3, -1);
+    }
+    tracker.propagate_all();
+    targets.iter().map(|target| tracker.pushed_mut(target.index)[target.port].drain().collect()).collect()
+}
+
+// Retiring a node without pointstamps leaves the consequences of updates elsewhere unchanged.
+#[test]
+fn tracker_retire() {
+    let targets = targets();
+    let mut retained = Tracker::allocate_from(graph().summarize());
+    let mut retired = Tracker::allocate_from(graph().summarize());
+    for index in 0 .. 8 {
+        assert_eq!(apply_round(&mut retained, index, None, &targets), apply_round(&mut retired, index, None, &targets));
+    }
+    // withdraw the outstanding pointstamps, and retire node seven, which held some of them.
+    for index in 4 .. 8 {
+        for tracker in vec![&mut retained, &mut retired] {
+            let source = index % 8;
+            let target = (3 * index) % 8;
+            tracker.update_source(Source { index: source, port: 0 }, index, -1);
+            tracker.update_target(Target { index: target, port: 0 }, index + 1, -1);
+            tracker.propagate_all();
+            for target in targets.iter() { tracker.pushed_mut(target.index)[target.port].clear(); }
+        }
+    }
+    assert!(!retired.tracking_anything());
+    retired.retire(7);
+    let others = targets.iter().cloned().filter(|target| target.index != 7).collect::<Vec<_>>();
+    for index in 0 .. 20 {
+        assert_eq!(apply_round(&mut retained, index, Some(7), &others), apply_round(&mut retired, index, Some(7), &others));
+    }
+}
+
+// Summarizing only the paths to observed targets pushes the same consequences to those targets.
+#[test]
+fn tracker_summarize_observed() {
+    let observed = targets().into_iter().filter(|target| target.index == 0 || target.index == 4).collect::<Vec<_>>();
+    let mut full = Tracker::allocate_from(graph().summarize());
+    let mut pruned = Tracker::allocate_from(graph().summarize_observed(|target| target.index == 0 || target.index == 4));
+    for index in 0 .. 20 {
+        assert_eq!(apply_round(&mut full, index, None, &observed), apply_round(&mut pruned, index, None, &observed));
+    }
+}
+
+// Groups of two workers evenly divide the four workers, and groups of three leave a smaller group.
+#[test]
+fn hierarchical() {
+    let expected = progress_helper(ProgressMode::AllToAll);
+    for &size in [2, 3].iter() {
+        let (notified, frontiers) = progress_helper(ProgressMode::Hierarchical(size));
+        check_progress(&notified, &frontiers);
+        assert_eq!((notified, frontiers), expected);
+    }
+}
+
+// The authority may be the first worker or the last.
+#[test]
+fn authority() {
+    let expected = progress_helper(ProgressMode::AllToAll);
+    for &worker in [0, 3].iter() {
+        let (notified, frontiers) = progress_helper(ProgressMode::Authority(worker));
+        check_progress(&notified, &frontiers);
+        assert_eq!((notified, frontiers), expected);
+    }
+}